@@ -168,8 +168,20 @@ impl Chunker {
       chunks
    }
 
-   async fn get_language(&self, path: &Path) -> Result<Option<Language>> {
-      self.0.get_language_for_path(path).await.inspect_err(|e| {
+   /// Detects the language of a file, resolving custom grammars the
+   /// chunker's grammar manager was created with.
+   pub fn detect_language(&self, path: &Path, content: &str) -> Option<&str> {
+      self.0.detect_language(path, content)
+   }
+
+   /// Whether `language` was simple-chunked because its grammar failed to
+   /// download or load.
+   pub async fn grammar_missing(&self, language: &str) -> bool {
+      self.0.has_failed(language).await
+   }
+
+   async fn get_language(&self, path: &Path, content: &str) -> Result<Option<Language>> {
+      self.0.get_language_for_path(path, content).await.inspect_err(|e| {
          tracing::warn!("failed to load language for {}: {}", path.display(), e);
       })
    }
@@ -234,7 +246,7 @@ impl Chunker {
       content: &Str,
       path: &Path,
    ) -> Result<Option<Vec<Chunk>>> {
-      let Some(language) = self.get_language(path).await? else {
+      let Some(language) = self.get_language(path, content.as_str()).await? else {
          return Ok(None);
       };
 
@@ -341,13 +353,17 @@ impl Chunker {
          }
 
          let node_text = content.slice(effective.start_byte()..effective.end_byte());
-         chunks.push(Chunk::new(
-            node_text,
-            effective.start_position().row,
-            effective.end_position().row,
-            Self::classify_node(&effective),
-            stack.as_ref(),
-         ));
+         let symbol = Self::get_node_name(&effective, content.as_str()).map(Str::copy_from_str);
+         chunks.push(
+            Chunk::new(
+               node_text,
+               effective.start_position().row,
+               effective.end_position().row,
+               Self::classify_node(&effective),
+               stack.as_ref(),
+            )
+            .with_symbol(symbol),
+         );
       }
 
       let mut cursor = effective.walk();
@@ -529,13 +545,16 @@ impl Chunker {
             content = Str::from_string(format!("{h}\n{content}"));
          }
 
-         sub_chunks.push(Chunk::new(
-            content,
-            chunk.start_line + i,
-            chunk.start_line + end,
-            chunk.chunk_type.unwrap_or(ChunkType::Other),
-            &chunk.context,
-         ));
+         sub_chunks.push(
+            Chunk::new(
+               content,
+               chunk.start_line + i,
+               chunk.start_line + end,
+               chunk.chunk_type.unwrap_or(ChunkType::Other),
+               &chunk.context,
+            )
+            .with_symbol(chunk.symbol.clone()),
+         );
 
          i += stride;
       }
@@ -591,6 +610,9 @@ impl Chunker {
          chunk.chunk_type.unwrap_or(ChunkType::Other),
          &chunk.context,
       )
+      .into_iter()
+      .map(|sc| sc.with_symbol(chunk.symbol.clone()))
+      .collect()
    }
 
    fn extract_header_line(text: &str) -> Option<&str> {
@@ -650,6 +672,25 @@ mod tests {
       );
    }
 
+   #[test]
+   fn split_chunks_keep_symbol() {
+      let content = Str::from_string((0..131).fold(String::new(), |mut s, i| {
+         let _ = writeln!(s, "line {i}");
+         s
+      }));
+      let chunk = Chunk::new(content, 0, 131, ChunkType::Function, &[])
+         .with_symbol(Some(Str::from_static("handle_request")));
+
+      let sub_chunks = Chunker::split_if_too_big(chunk);
+
+      assert!(sub_chunks.len() > 1);
+      assert!(
+         sub_chunks
+            .iter()
+            .all(|c| c.symbol.as_deref() == Some("handle_request"))
+      );
+   }
+
    #[test]
    fn split_if_too_big_keeps_trailing_lines() {
       let content = Str::from_string((0..131).fold(String::new(), |mut s, i| {
@@ -6,10 +6,10 @@ use console::style;
 use serde::Serialize;
 
 use crate::{
-   Result,
+   Result, config,
    error::Error,
    identity,
-   snapshot::SnapshotManager,
+   snapshot::{SnapshotManager, tombstone_ratio},
    store::LanceStore,
 };
 
@@ -21,11 +21,14 @@ struct AuditError {
 
 #[derive(Serialize)]
 struct AuditJson {
-   schema_version: u32,
-   store_id:       String,
-   snapshot_id:    Option<String>,
-   ok:             bool,
-   errors:         Vec<AuditError>,
+   schema_version:      u32,
+   store_id:            String,
+   snapshot_id:         Option<String>,
+   ok:                  bool,
+   tombstones:          u64,
+   tombstone_ratio:     f64,
+   max_tombstone_ratio: f64,
+   errors:              Vec<AuditError>,
 }
 
 /// Executes the audit command.
@@ -60,6 +63,10 @@ pub async fn execute(path: Option<PathBuf>, json: bool, store_id: Option<String>
    }
 
    let ok = errors.is_empty();
+   let tombstones: u64 = manifest.tombstones.iter().map(|t| t.count).sum();
+   let ratio = tombstone_ratio(&manifest);
+   let max_ratio = config::get().max_tombstone_ratio;
+   let ratio_exceeded = max_ratio > 0.0 && ratio > max_ratio;
 
    if json {
       let payload = AuditJson {
@@ -67,12 +74,24 @@ pub async fn execute(path: Option<PathBuf>, json: bool, store_id: Option<String>
          store_id: root_store_id,
          snapshot_id: Some(snapshot_id),
          ok,
+         tombstones,
+         // JSON has no infinity; report fully tombstoned snapshots as -1.
+         tombstone_ratio: if ratio.is_finite() { ratio } else { -1.0 },
+         max_tombstone_ratio: max_ratio,
          errors,
       };
       println!("{}", serde_json::to_string_pretty(&payload)?);
       return Ok(());
    }
 
+   let ratio_line =
+      format!("Tombstone ratio: {:.3} ({} tombstones, max {})", ratio, tombstones, max_ratio);
+   if ratio_exceeded {
+      println!("{}", style(format!("{ratio_line} — compaction overdue")).yellow());
+   } else {
+      println!("{ratio_line}");
+   }
+
    if ok {
       println!("{}", style("✓ Audit OK: manifest counts consistent").green());
       return Ok(());
@@ -1,53 +1,85 @@
 //! Store cleanup command.
 //!
 //! Removes both lance data and metadata for a store, ensuring a clean slate for
-//! re-indexing.
+//! re-indexing. Downloaded models and grammars are kept unless explicitly
+//! requested, since they are expensive to fetch again.
+
+use std::path::Path;
 
 use console::style;
 
-use crate::{Result, config, identity, reader_lock::ReaderLock};
+use crate::{
+   Result, config, identity,
+   reader_lock::ReaderLock,
+   util::{format_size, get_dir_size},
+};
 
-pub fn execute(store_id: Option<String>, all: bool) -> Result<()> {
-   if all {
-      return clean_all();
-   }
+/// Which on-disk state `clean` removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CleanScope {
+   /// Store data and metadata only (models and grammars are kept).
+   #[default]
+   Stores,
+   /// Store data plus downloaded models and grammars.
+   Everything,
+   /// Downloaded models and grammars only; index data is kept.
+   CachesOnly,
+}
 
-   let resolved_store_id = if let Some(id) = store_id {
-      id
-   } else {
-      let cwd = std::env::current_dir()?;
-      identity::resolve_index_identity(&cwd)?.store_id
-   };
+pub fn execute(store_id: Option<String>, all: bool, scope: CleanScope) -> Result<()> {
+   let mut freed = 0;
+
+   if scope != CleanScope::CachesOnly {
+      freed += if all {
+         clean_all()?
+      } else {
+         let resolved_store_id = if let Some(id) = store_id {
+            id
+         } else {
+            let cwd = std::env::current_dir()?;
+            identity::resolve_index_identity(&cwd)?.store_id
+         };
 
-   clean_store(&resolved_store_id)?;
+         let bytes = clean_store(&resolved_store_id)?;
+         println!("{}", style(format!("Cleaned store: {resolved_store_id}")).green());
+         bytes
+      };
+   }
+
+   if scope != CleanScope::Stores {
+      freed += clean_caches()?;
+   }
 
-   println!("{}", style(format!("Cleaned store: {resolved_store_id}")).green());
+   println!("Freed {}", format_size(freed));
    Ok(())
 }
 
-fn clean_store(store_id: &str) -> Result<()> {
+/// Removes a store's metadata and data directory, returning the bytes freed.
+fn clean_store(store_id: &str) -> Result<u64> {
    let _lock = ReaderLock::acquire_exclusive(store_id)?;
+   let mut freed = 0;
+
    // Delete metadata file
    let meta_path = config::meta_dir().join(format!("{store_id}.json"));
    if meta_path.exists() {
+      freed += std::fs::metadata(&meta_path).map_or(0, |m| m.len());
       std::fs::remove_file(&meta_path)?;
    }
 
    // Delete entire lance database directory (not just drop_table which leaves
    // fragments)
    let data_path = config::data_dir().join(store_id);
-   if data_path.exists() {
-      std::fs::remove_dir_all(&data_path)?;
-   }
+   freed += remove_dir_measured(&data_path)?;
 
-   Ok(())
+   Ok(freed)
 }
 
-fn clean_all() -> Result<()> {
+fn clean_all() -> Result<u64> {
    let meta_dir = config::meta_dir();
    let data_dir = config::data_dir();
 
    let mut cleaned = 0;
+   let mut freed = 0;
 
    // Clean stores found in meta directory
    if meta_dir.exists() {
@@ -59,7 +91,7 @@ fn clean_all() -> Result<()> {
          {
             let store_id = stem.to_string_lossy();
             println!("{}", style(format!("Cleaning: {store_id}")).dim());
-            clean_store(&store_id)?;
+            freed += clean_store(&store_id)?;
             cleaned += 1;
          }
       }
@@ -77,7 +109,7 @@ fn clean_all() -> Result<()> {
             let meta_path = meta_dir.join(format!("{store_id}.json"));
             if !meta_path.exists() {
                println!("{}", style(format!("Cleaning orphaned: {store_id}")).dim());
-               let _ = std::fs::remove_dir_all(&path);
+               freed += remove_dir_measured(&path).unwrap_or(0);
                cleaned += 1;
             }
          }
@@ -90,5 +122,28 @@ fn clean_all() -> Result<()> {
       println!("{}", style(format!("Cleaned {cleaned} store(s)")).green());
    }
 
-   Ok(())
+   Ok(freed)
+}
+
+/// Removes downloaded models and grammars, returning the bytes freed.
+fn clean_caches() -> Result<u64> {
+   let mut freed = 0;
+   for (label, dir) in [("models", config::model_dir()), ("grammars", config::grammar_dir())] {
+      let bytes = remove_dir_measured(dir)?;
+      if bytes > 0 {
+         println!("{}", style(format!("Removed {label}: {}", format_size(bytes))).dim());
+      }
+      freed += bytes;
+   }
+   Ok(freed)
+}
+
+/// Removes `path` if it exists, returning its size beforehand.
+fn remove_dir_measured(path: &Path) -> Result<u64> {
+   if !path.exists() {
+      return Ok(0);
+   }
+   let bytes = get_dir_size(path).unwrap_or(0);
+   std::fs::remove_dir_all(path)?;
+   Ok(bytes)
 }
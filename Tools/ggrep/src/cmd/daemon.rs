@@ -47,6 +47,57 @@ pub async fn connect_matching_daemon(path: &Path, store_id: &str) -> Result<usoc
    wait_for_daemon(store_id, &config_fingerprint).await
 }
 
+/// A handshaken daemon connection that can carry many requests.
+///
+/// The server loop serves any number of requests per connection, so batch and
+/// interactive callers connect once and reuse the stream. After an error
+/// (including a timeout) the connection state is unknown; drop the client and
+/// reconnect.
+pub struct DaemonClient {
+   stream: usock::Stream,
+   buffer: SocketBuffer,
+}
+
+impl DaemonClient {
+   /// Connects to a matching daemon (spawning one if needed) and completes the
+   /// handshake.
+   pub async fn connect(path: &Path, store_id: &str) -> Result<Self> {
+      let stream = connect_matching_daemon(path, store_id).await?;
+      Ok(Self { stream, buffer: SocketBuffer::new() })
+   }
+
+   /// Payload sizes of the last request sent and response received.
+   pub const fn last_exchange_bytes(&self) -> (usize, usize) {
+      (self.buffer.sent_bytes(), self.buffer.received_bytes())
+   }
+
+   /// Sends `request` and waits up to `timeout` for its response.
+   pub async fn request(
+      &mut self,
+      op: &'static str,
+      request: &Request,
+      timeout: Duration,
+   ) -> Result<Response> {
+      let Self { stream, buffer } = self;
+      match time::timeout(timeout, async {
+         buffer
+            .send_with_limit(stream, request, config::get().max_request_bytes)
+            .await?;
+         buffer
+            .recv_with_limit(stream, config::get().max_response_bytes)
+            .await
+      })
+      .await
+      {
+         Ok(result) => result,
+         Err(_) => Err(Error::Server {
+            op,
+            reason: format!("timeout waiting for daemon response ({}s)", timeout.as_secs()),
+         }),
+      }
+   }
+}
+
 /// Spawns a new daemon process in the background for the given path.
 pub fn spawn_daemon(path: &Path) -> Result<()> {
    let exe = std::env::current_exe()?;
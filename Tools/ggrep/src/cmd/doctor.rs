@@ -10,6 +10,7 @@ use hf_hub::Cache;
 
 use crate::{
    Result, config,
+   embed::{DummyEmbedder, Embedder},
    grammar::{GRAMMAR_URLS, GrammarManager},
    models,
    util::{format_size, get_dir_size},
@@ -72,6 +73,22 @@ pub fn execute() -> Result<()> {
       );
    }
 
+   let dummy_vars: Vec<_> = ["GGREP_DUMMY_EMBEDDER", "GGREP_TEST_EMBEDDER"]
+      .into_iter()
+      .filter(|var| std::env::var_os(var).is_some())
+      .collect();
+   if !dummy_vars.is_empty() {
+      let desc = DummyEmbedder::new(cfg.dense_dim).describe();
+      let verb = if dummy_vars.len() == 1 { "is" } else { "are" };
+      println!(
+         "{} Embedder: {} {}",
+         style("⚠").yellow(),
+         style(&desc).dim(),
+         style(format!("({} {verb} set; results will not be semantic)", dummy_vars.join(" and ")))
+            .yellow()
+      );
+   }
+
    println!();
 
    let grammar_manager = if let Ok(gm) = GrammarManager::with_auto_download(false) {
@@ -85,17 +102,23 @@ pub fn execute() -> Result<()> {
    if let Some(gm) = &grammar_manager {
       let available = gm.available_languages();
       let missing = gm.missing_languages();
+      let stale: Vec<_> = gm.stale_languages().collect();
 
       for (lang, _) in GRAMMAR_URLS {
          let exists = available.clone().any(|l| &l == lang);
+         let is_stale = stale.contains(lang);
 
-         let symbol = if exists {
+         let symbol = if is_stale {
+            style("⚠").yellow()
+         } else if exists {
             style("✓").green()
          } else {
             style("○").yellow()
          };
 
-         let status = if exists {
+         let status = if is_stale {
+            "installed, older release than pinned".to_string()
+         } else if exists {
             "installed".to_string()
          } else {
             "will download on first use".to_string()
@@ -117,6 +140,14 @@ pub fn execute() -> Result<()> {
             style("ℹ").cyan()
          );
       }
+      if !stale.is_empty() {
+         println!(
+            "{} {} cached grammar(s) no longer match the pinned release; run {}",
+            style("⚠").yellow(),
+            stale.len(),
+            style(format!("ggrep grammars fetch {}", stale.join(" "))).green()
+         );
+      }
    }
 
    if data.exists()
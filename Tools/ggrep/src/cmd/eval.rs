@@ -2,10 +2,13 @@
 //!
 //! Runs a suite of natural-language queries against an indexed repo and writes
 //! a JSON report with hit-rates + MRR for tuning recall/embedding behavior.
+//! The JSON report is the source of truth; `--html` renders the same report as
+//! a self-contained page for sharing.
 
 use std::{
    collections::{BTreeMap, HashSet},
    ffi::OsStr,
+   fmt::Write as _,
    io,
    path::{Path, PathBuf},
    sync::Arc,
@@ -14,6 +17,7 @@ use std::{
 
 use chrono::Utc;
 use console::style;
+use futures::{StreamExt, stream};
 use indicatif::{ProgressBar, ProgressStyle};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -26,10 +30,10 @@ use crate::{
    file::LocalFileSystem,
    identity,
    snapshot::{SnapshotManager, SnapshotView},
-   search::{SearchEngine, profile::bucket_for_path},
+   search::{SearchEngine, profile::bucket_for},
    store::LanceStore,
    sync::{SyncEngine, SyncResult},
-   types::{ChunkType, SearchMode},
+   types::{ChunkType, QueryOptions, SearchMode},
    version,
 };
 
@@ -115,10 +119,29 @@ struct EvalCase {
    #[serde(default)]
    expect_all_path_regex: Vec<String>,
 
+   /// Paths that must not appear in the hits; any match fails the case.
+   #[serde(default)]
+   expect_none_path_contains: Vec<String>,
+
+   #[serde(default)]
+   expect_none_path_regex: Vec<String>,
+
+   /// Lines that some hit in a matching path must cover.
+   #[serde(default)]
+   expect_line_in_path: Vec<ExpectedLine>,
+
    #[serde(default)]
    notes: Option<String>,
 }
 
+/// A line an eval hit must cover; `path` matches like
+/// `expect_*_path_contains` (case-insensitive substring).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExpectedLine {
+   path: String,
+   line: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct EvalReport {
    meta:    EvalMeta,
@@ -130,7 +153,14 @@ struct EvalReport {
 #[derive(Debug, Serialize, Deserialize)]
 struct EvalMeta {
    started_at_utc: String,
+   /// Median wall time of one pass over the suite, measured after warmup.
    elapsed_ms:     u128,
+   /// Wall time of each `--repeat` pass, in run order.
+   #[serde(default)]
+   run_ms:         Vec<u128>,
+   /// Wall time of the whole eval, including sync and warmup.
+   #[serde(default)]
+   total_ms:       Option<u128>,
    suite_path:     String,
    suite_version:  u32,
    store_id:       String,
@@ -142,17 +172,19 @@ struct EvalMeta {
 
 #[derive(Debug, Serialize, Deserialize)]
 struct EvalConfig {
-   dense_model:        String,
-   colbert_model:      String,
-   dense_dim:          usize,
-   colbert_dim:        usize,
-   dense_max_length:   usize,
-   colbert_max_length: usize,
-   query_prefix:       String,
-   doc_prefix:         String,
-   disable_gpu:        bool,
-   fast_mode:          bool,
-   low_impact:         bool,
+   dense_model:         String,
+   colbert_model:       String,
+   dense_dim:           usize,
+   colbert_dim:         usize,
+   dense_max_length:    usize,
+   colbert_max_length:  usize,
+   query_prefix:        String,
+   #[serde(default)]
+   mode_query_prefixes: BTreeMap<SearchMode, String>,
+   doc_prefix:          String,
+   disable_gpu:         bool,
+   fast_mode:           bool,
+   low_impact:          bool,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -161,8 +193,27 @@ struct EvalOverrides {
    per_file:        Option<usize>,
    mode:            Option<SearchMode>,
    no_rerank:       bool,
+   #[serde(default)]
+   vector_only:     bool,
    include_anchors: bool,
    no_sync:         bool,
+   #[serde(default = "default_repeat")]
+   repeat:          usize,
+   #[serde(default)]
+   shuffle_seed:    Option<u64>,
+   #[serde(default)]
+   warmup:          usize,
+   /// Cases evaluated concurrently.
+   #[serde(default = "default_jobs")]
+   jobs:            usize,
+}
+
+fn default_repeat() -> usize {
+   1
+}
+
+fn default_jobs() -> usize {
+   1
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -179,8 +230,12 @@ struct EvalSummary {
    passed:        usize,
    pass_rate:     f32,
    mean_mrr:      f32,
+   #[serde(default)]
+   mean_ndcg:     f32,
    mean_hit_rank: Option<f32>,
    by_mode:       BTreeMap<SearchMode, EvalModeSummary>,
+   #[serde(default, skip_serializing_if = "Vec::is_empty")]
+   unstable:      Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -189,6 +244,8 @@ struct EvalModeSummary {
    passed:    usize,
    pass_rate: f32,
    mean_mrr:  f32,
+   #[serde(default)]
+   mean_ndcg: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -202,9 +259,30 @@ struct EvalCaseReport {
    passed:         bool,
    first_hit_rank: Option<usize>,
    mrr:            f32,
+   /// nDCG@k over the case's expectations (see [`ndcg`]).
+   #[serde(default)]
+   ndcg:           f32,
    missing_all:    Vec<String>,
+   /// Hit paths matching an `expect_none_*` pattern.
+   #[serde(default)]
+   unexpected:     Vec<String>,
    notes:          Option<String>,
    hits:           Vec<EvalHit>,
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   stability:      Option<EvalStability>,
+}
+
+/// Variance of a case across `--repeat` runs (the case report itself reflects
+/// the first run).
+#[derive(Debug, Serialize, Deserialize)]
+struct EvalStability {
+   runs:        usize,
+   passed_runs: usize,
+   mrr_min:     f32,
+   mrr_max:     f32,
+   mrr_mean:    f32,
+   mrr_stddev:  f32,
+   unstable:    bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -215,21 +293,27 @@ struct EvalHit {
    score:      f32,
    match_pct:  Option<u8>,
    start_line: u32,
+   #[serde(default)]
+   num_lines:  u32,
    chunk_type: Option<String>,
    preview:    String,
 }
 
 #[derive(Debug)]
 struct CaseMatchers {
-   any_contains: Vec<String>,
-   all_contains: Vec<String>,
-   any_regex:    Vec<Regex>,
-   all_regex:    Vec<Regex>,
+   any_contains:  Vec<String>,
+   all_contains:  Vec<String>,
+   any_regex:     Vec<Regex>,
+   all_regex:     Vec<Regex>,
+   none_contains: Vec<String>,
+   none_regex:    Vec<Regex>,
+   lines:         Vec<ExpectedLine>,
 }
 
 pub async fn execute(
    suite_path: Option<PathBuf>,
    out_path: Option<PathBuf>,
+   html_path: Option<PathBuf>,
    path: Option<PathBuf>,
    only: Vec<String>,
    no_sync: bool,
@@ -237,13 +321,19 @@ pub async fn execute(
    per_file_override: Option<usize>,
    mode_override: Option<String>,
    no_rerank: bool,
+   vector_only: bool,
    include_anchors: bool,
    eval_store: bool,
    fail_under_pass_rate: Option<f32>,
    fail_under_mrr: Option<f32>,
+   fail_under_ndcg: Option<f32>,
    baseline: Option<PathBuf>,
    baseline_max_drop_pass_rate: Option<f32>,
    baseline_max_drop_mrr: Option<f32>,
+   repeat: usize,
+   shuffle: bool,
+   warmup: usize,
+   jobs: usize,
    store_id: Option<String>,
 ) -> Result<()> {
    let root = std::env::current_dir()?;
@@ -307,13 +397,22 @@ pub async fn execute(
       .transpose()
       .map_err(|m| io::Error::new(io::ErrorKind::InvalidInput, m))?;
 
+   let repeat = repeat.max(1);
+   let jobs = jobs.max(1);
+   let shuffle_seed = shuffle.then(|| uuid::Uuid::new_v4().as_u64_pair().0);
+
    let overrides = EvalOverrides {
       k: k_override,
       per_file: per_file_override,
       mode: mode_override,
       no_rerank,
+      vector_only,
       include_anchors,
       no_sync,
+      repeat,
+      shuffle_seed,
+      warmup,
+      jobs,
    };
 
    let resolved_out_path = resolve_out_path(out_path, &resolved_store_id);
@@ -381,10 +480,9 @@ pub async fn execute(
    );
    let snapshot_view = snapshot_manager.open_snapshot_view().await?;
 
-   let mut case_reports = Vec::with_capacity(suite.cases.len());
-   for (idx, case) in suite.cases.iter().enumerate() {
-      println!("{}", style(format!("[{}/{}] {}", idx + 1, suite.cases.len(), case.id)).cyan());
-      let report =
+   if warmup > 0 && !suite.cases.is_empty() {
+      println!("{}", style(format!("Warming up ({warmup} queries)...")).dim());
+      for case in suite.cases.iter().cycle().take(warmup) {
          evaluate_case(
             &engine,
             &snapshot_view,
@@ -395,45 +493,109 @@ pub async fn execute(
             overrides,
          )
          .await?;
-      println!(
-         "  {}  first_hit={}  mrr={:.3}",
-         if report.passed {
-            style("PASS").green().bold()
-         } else {
-            style("FAIL").red().bold()
-         },
-         report
-            .first_hit_rank
-            .map_or_else(|| "-".to_string(), |r| r.to_string()),
-         report.mrr
-      );
-      case_reports.push(report);
+      }
+   }
+
+   let mut run_ms = Vec::with_capacity(repeat);
+   let mut runs: Vec<Vec<EvalCaseReport>> = suite
+      .cases
+      .iter()
+      .map(|_| Vec::with_capacity(repeat))
+      .collect();
+   let mut rng_state = shuffle_seed.unwrap_or_default();
+   for run in 0..repeat {
+      let mut order: Vec<usize> = (0..suite.cases.len()).collect();
+      if shuffle_seed.is_some() {
+         shuffle_indices(&mut order, &mut rng_state);
+      }
+      if repeat > 1 {
+         println!("{}", style(format!("Run {}/{}", run + 1, repeat)).bold());
+      }
+      let pass_t0 = Instant::now();
+
+      // Cases finish out of order under `--jobs`; each prints its lines in
+      // one go when done and lands in its own slot of `runs`.
+      let (engine, snapshot_view) = (&engine, &snapshot_view);
+      let (store_id, search_path) = (resolved_store_id.as_str(), search_path.as_path());
+      let mut pending = stream::iter(order.iter().map(|&idx| {
+         let case = &suite.cases[idx];
+         let defaults = &suite.defaults;
+         async move {
+            let report = evaluate_case(
+               engine,
+               snapshot_view,
+               store_id,
+               search_path,
+               defaults,
+               case,
+               overrides,
+            )
+            .await;
+            (idx, report)
+         }
+      }))
+      .buffer_unordered(jobs);
+      let mut done = 0;
+      while let Some((idx, report)) = pending.next().await {
+         let report = report?;
+         done += 1;
+         println!(
+            "{}\n  {}  first_hit={}  mrr={:.3}  ndcg={:.3}",
+            style(format!("[{}/{}] {}", done, suite.cases.len(), report.id)).cyan(),
+            if report.passed {
+               style("PASS").green().bold()
+            } else {
+               style("FAIL").red().bold()
+            },
+            report
+               .first_hit_rank
+               .map_or_else(|| "-".to_string(), |r| r.to_string()),
+            report.mrr,
+            report.ndcg
+         );
+         runs[idx].push(report);
+      }
+      run_ms.push(pass_t0.elapsed().as_millis());
    }
 
-   let elapsed_ms = run_t0.elapsed().as_millis();
+   let case_reports: Vec<EvalCaseReport> = runs
+      .into_iter()
+      .filter_map(|case_runs| {
+         let stability = (case_runs.len() > 1).then(|| stability(&case_runs));
+         let mut report = case_runs.into_iter().next()?;
+         report.stability = stability;
+         Some(report)
+      })
+      .collect();
+
+   let elapsed_ms = median_ms(&run_ms);
+   let total_ms = run_t0.elapsed().as_millis();
 
    let cfg = config::get().clone();
    let report = EvalReport {
       meta:    EvalMeta {
          started_at_utc: started_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
          elapsed_ms,
+         run_ms,
+         total_ms: Some(total_ms),
          suite_path: resolved_suite_path.display().to_string(),
          suite_version: suite.version,
          store_id: resolved_store_id.clone(),
          root: search_path.display().to_string(),
          ggrep_version: version::version_string(),
          config: EvalConfig {
-            dense_model:        cfg.dense_model,
-            colbert_model:      cfg.colbert_model,
-            dense_dim:          cfg.dense_dim,
-            colbert_dim:        cfg.colbert_dim,
-            dense_max_length:   cfg.dense_max_length,
-            colbert_max_length: cfg.colbert_max_length,
-            query_prefix:       cfg.query_prefix,
-            doc_prefix:         cfg.doc_prefix,
-            disable_gpu:        cfg.disable_gpu,
-            fast_mode:          cfg.fast_mode,
-            low_impact:         cfg.low_impact,
+            dense_model:         cfg.dense_model,
+            colbert_model:       cfg.colbert_model,
+            dense_dim:           cfg.dense_dim,
+            colbert_dim:         cfg.colbert_dim,
+            dense_max_length:    cfg.dense_max_length,
+            colbert_max_length:  cfg.colbert_max_length,
+            query_prefix:        cfg.query_prefix,
+            mode_query_prefixes: cfg.mode_query_prefixes,
+            doc_prefix:          cfg.doc_prefix,
+            disable_gpu:         cfg.disable_gpu,
+            fast_mode:           cfg.fast_mode,
+            low_impact:          cfg.low_impact,
          },
          overrides,
       },
@@ -452,20 +614,37 @@ pub async fn execute(
       std::fs::create_dir_all(parent)?;
    }
    std::fs::write(&resolved_out_path, json)?;
+   if let Some(html_path) = &html_path {
+      if let Some(parent) = html_path.parent() {
+         std::fs::create_dir_all(parent)?;
+      }
+      std::fs::write(html_path, render_html(&report))?;
+   }
 
    println!();
    println!(
       "{}",
       style(format!(
-         "Summary: {}/{} passed ({:.1}%), mean_mrr={:.3}",
+         "Summary: {}/{} passed ({:.1}%), mean_mrr={:.3}, mean_ndcg={:.3}",
          report.summary.passed,
          report.summary.total,
          report.summary.pass_rate * 100.0,
-         report.summary.mean_mrr
+         report.summary.mean_mrr,
+         report.summary.mean_ndcg
       ))
       .bold()
    );
+   if !report.summary.unstable.is_empty() {
+      println!(
+         "{}",
+         style(format!("Unstable across {} runs: {}", repeat, report.summary.unstable.join(", ")))
+            .yellow()
+      );
+   }
    println!("Report: {}", style(resolved_out_path.display()).dim());
+   if let Some(html_path) = &html_path {
+      println!("HTML:   {}", style(html_path.display()).dim());
+   }
    println!("Build:  {}", style(version::GIT_HASH).dim());
 
    let mut min_pass_rate = fail_under_pass_rate;
@@ -521,9 +700,383 @@ pub async fn execute(
       );
    }
 
+   if let Some(threshold) = fail_under_ndcg
+      && report.summary.mean_ndcg < threshold
+   {
+      return Err(
+         io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+               "mean_ndcg {:.3} is below threshold {:.3}",
+               report.summary.mean_ndcg, threshold
+            ),
+         )
+         .into(),
+      );
+   }
+
    Ok(())
 }
 
+/// How a case changed between two eval reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CaseTransition {
+   StillPassing,
+   StillFailing,
+   Fixed,
+   Regressed,
+   Added,
+   Removed,
+}
+
+impl CaseTransition {
+   fn label(self) -> &'static str {
+      match self {
+         Self::StillPassing => "PASS",
+         Self::StillFailing => "FAIL",
+         Self::Fixed => "FAIL→PASS",
+         Self::Regressed => "PASS→FAIL",
+         Self::Added => "added",
+         Self::Removed => "removed",
+      }
+   }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct MetricDelta {
+   before: f32,
+   after:  f32,
+   delta:  f32,
+}
+
+impl MetricDelta {
+   fn new(before: f32, after: f32) -> Self {
+      Self { before, after, delta: after - before }
+   }
+}
+
+#[derive(Debug, Serialize)]
+struct EvalCaseDiff {
+   id:          String,
+   transition:  CaseTransition,
+   rank_before: Option<usize>,
+   rank_after:  Option<usize>,
+   /// `None` for cases present in only one report.
+   mrr:         Option<MetricDelta>,
+   ndcg:        Option<MetricDelta>,
+}
+
+impl EvalCaseDiff {
+   fn changed(&self) -> bool {
+      const EPSILON: f32 = 1e-6;
+
+      !matches!(self.transition, CaseTransition::StillPassing | CaseTransition::StillFailing)
+         || self.rank_before != self.rank_after
+         || self.mrr.is_some_and(|mrr| mrr.delta.abs() > EPSILON)
+         || self.ndcg.is_some_and(|ndcg| ndcg.delta.abs() > EPSILON)
+   }
+}
+
+#[derive(Debug, Serialize)]
+struct EvalDiffJson {
+   before:    String,
+   after:     String,
+   pass_rate: MetricDelta,
+   mean_mrr:  MetricDelta,
+   mean_ndcg: MetricDelta,
+   /// Cases that passed in `before` and fail in `after`.
+   regressed: Vec<String>,
+   cases:     Vec<EvalCaseDiff>,
+}
+
+/// Compares two eval reports case by case (`ggrep eval-diff`). Cases are
+/// matched by id; fails when any case went from PASS to FAIL.
+pub fn execute_diff(before_path: PathBuf, after_path: PathBuf, json: bool) -> Result<()> {
+   let load = |path: &Path| -> Result<EvalReport> {
+      let raw = std::fs::read_to_string(path)?;
+      Ok(serde_json::from_str(&raw)?)
+   };
+   let before = load(&before_path)?;
+   let after = load(&after_path)?;
+   let diff = diff_reports(&before, &after, &before_path, &after_path);
+
+   if json {
+      println!("{}", serde_json::to_string_pretty(&diff)?);
+   } else {
+      print_diff(&diff);
+   }
+
+   if !diff.regressed.is_empty() {
+      return Err(
+         io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+               "{} case(s) regressed from PASS to FAIL: {}",
+               diff.regressed.len(),
+               diff.regressed.join(", ")
+            ),
+         )
+         .into(),
+      );
+   }
+
+   Ok(())
+}
+
+fn diff_reports(
+   before: &EvalReport,
+   after: &EvalReport,
+   before_path: &Path,
+   after_path: &Path,
+) -> EvalDiffJson {
+   let before_cases: BTreeMap<&str, &EvalCaseReport> =
+      before.cases.iter().map(|c| (c.id.as_str(), c)).collect();
+   let after_ids: HashSet<&str> = after.cases.iter().map(|c| c.id.as_str()).collect();
+
+   let mut cases: Vec<EvalCaseDiff> = after
+      .cases
+      .iter()
+      .map(|case| match before_cases.get(case.id.as_str()) {
+         Some(prev) => EvalCaseDiff {
+            id:          case.id.clone(),
+            transition:  match (prev.passed, case.passed) {
+               (true, true) => CaseTransition::StillPassing,
+               (false, false) => CaseTransition::StillFailing,
+               (false, true) => CaseTransition::Fixed,
+               (true, false) => CaseTransition::Regressed,
+            },
+            rank_before: prev.first_hit_rank,
+            rank_after:  case.first_hit_rank,
+            mrr:         Some(MetricDelta::new(prev.mrr, case.mrr)),
+            ndcg:        Some(MetricDelta::new(prev.ndcg, case.ndcg)),
+         },
+         None => EvalCaseDiff {
+            id:          case.id.clone(),
+            transition:  CaseTransition::Added,
+            rank_before: None,
+            rank_after:  case.first_hit_rank,
+            mrr:         None,
+            ndcg:        None,
+         },
+      })
+      .collect();
+   cases.extend(
+      before
+         .cases
+         .iter()
+         .filter(|c| !after_ids.contains(c.id.as_str()))
+         .map(|c| EvalCaseDiff {
+            id:          c.id.clone(),
+            transition:  CaseTransition::Removed,
+            rank_before: c.first_hit_rank,
+            rank_after:  None,
+            mrr:         None,
+            ndcg:        None,
+         }),
+   );
+
+   let regressed = cases
+      .iter()
+      .filter(|c| c.transition == CaseTransition::Regressed)
+      .map(|c| c.id.clone())
+      .collect();
+
+   EvalDiffJson {
+      before: before_path.display().to_string(),
+      after: after_path.display().to_string(),
+      pass_rate: MetricDelta::new(before.summary.pass_rate, after.summary.pass_rate),
+      mean_mrr: MetricDelta::new(before.summary.mean_mrr, after.summary.mean_mrr),
+      mean_ndcg: MetricDelta::new(before.summary.mean_ndcg, after.summary.mean_ndcg),
+      regressed,
+      cases,
+   }
+}
+
+fn print_diff(diff: &EvalDiffJson) {
+   let metric = |name: &str, m: MetricDelta| {
+      format!("{name} {:.3} -> {:.3} ({:+.3})", m.before, m.after, m.delta)
+   };
+   let rank = |r: Option<usize>| r.map_or_else(|| "-".to_string(), |r| r.to_string());
+
+   println!("{}", style(format!("Before: {}", diff.before)).dim());
+   println!("{}", style(format!("After:  {}", diff.after)).dim());
+   let mut unchanged = 0;
+   for case in &diff.cases {
+      if !case.changed() {
+         unchanged += 1;
+         continue;
+      }
+      let label = format!("{:<9}", case.transition.label());
+      let label = match case.transition {
+         CaseTransition::Regressed | CaseTransition::StillFailing => style(label).red().bold(),
+         CaseTransition::Fixed | CaseTransition::StillPassing => style(label).green().bold(),
+         CaseTransition::Added | CaseTransition::Removed => style(label).dim(),
+      };
+      let mut line = format!(
+         "  {label}  {}  rank {} -> {}",
+         case.id,
+         rank(case.rank_before),
+         rank(case.rank_after)
+      );
+      if let Some(mrr) = case.mrr {
+         let _ = write!(line, "  {}", metric("mrr", mrr));
+      }
+      if let Some(ndcg) = case.ndcg {
+         let _ = write!(line, "  {}", metric("ndcg", ndcg));
+      }
+      println!("{line}");
+   }
+   if unchanged > 0 {
+      println!("{}", style(format!("  {unchanged} case(s) unchanged")).dim());
+   }
+
+   println!();
+   println!(
+      "{}",
+      style(format!(
+         "Summary: {}, {}, {}",
+         metric("pass_rate", diff.pass_rate),
+         metric("mean_mrr", diff.mean_mrr),
+         metric("mean_ndcg", diff.mean_ndcg)
+      ))
+      .bold()
+   );
+}
+
+const HTML_STYLE: &str = concat!(
+   "body{font-family:system-ui,sans-serif;margin:2rem;color:#222}",
+   "table{border-collapse:collapse;margin:1rem 0}",
+   "th,td{border:1px solid #ddd;padding:.3rem .6rem;text-align:left;vertical-align:top}",
+   "th{background:#f4f4f4}.dim{color:#777}summary{cursor:pointer}",
+   ".pass{color:#1a7f37;font-weight:600}.fail{color:#cf222e;font-weight:600}",
+   "pre{margin:.3rem 0;padding:.4rem;background:#f6f8fa;white-space:pre-wrap}",
+);
+
+/// Renders an eval report as a self-contained HTML page: summary metrics, a
+/// per-case table and expandable hit previews.
+fn render_html(report: &EvalReport) -> String {
+   let meta = &report.meta;
+   let summary = &report.summary;
+   let store = escape_html(&meta.store_id);
+   let mut out = String::new();
+   out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">");
+   let _ = writeln!(out, "<title>ggrep eval: {store}</title><style>{HTML_STYLE}</style>");
+   let _ = writeln!(out, "</head><body>\n<h1>ggrep eval: {store}</h1>");
+   let _ = writeln!(
+      out,
+      "<p class=\"dim\">{} &middot; ggrep {} &middot; suite {} (v{}) &middot; {} ms/run \
+       (median)</p>",
+      escape_html(&meta.started_at_utc),
+      escape_html(&meta.ggrep_version),
+      escape_html(&meta.suite_path),
+      meta.suite_version,
+      meta.elapsed_ms
+   );
+
+   out.push_str("<h2>Summary</h2>\n<table><tr><th>Passed</th><th>Pass rate</th>");
+   out.push_str("<th>Mean MRR</th><th>Mean nDCG</th><th>Mean hit rank</th></tr>\n");
+   let _ = writeln!(
+      out,
+      "<tr><td>{}/{}</td><td>{:.1}%</td><td>{:.3}</td><td>{:.3}</td><td>{}</td></tr></table>",
+      summary.passed,
+      summary.total,
+      summary.pass_rate * 100.0,
+      summary.mean_mrr,
+      summary.mean_ndcg,
+      summary
+         .mean_hit_rank
+         .map_or_else(|| "-".to_string(), |rank| format!("{rank:.2}"))
+   );
+   if !summary.by_mode.is_empty() {
+      out.push_str("<table><tr><th>Mode</th><th>Passed</th><th>Pass rate</th>");
+      out.push_str("<th>Mean MRR</th><th>Mean nDCG</th></tr>\n");
+      for (mode, stats) in &summary.by_mode {
+         let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}/{}</td><td>{:.1}%</td><td>{:.3}</td><td>{:.3}</td></tr>",
+            format!("{mode:?}").to_lowercase(),
+            stats.passed,
+            stats.total,
+            stats.pass_rate * 100.0,
+            stats.mean_mrr,
+            stats.mean_ndcg
+         );
+      }
+      out.push_str("</table>\n");
+   }
+   if !summary.unstable.is_empty() {
+      let _ = writeln!(
+         out,
+         "<p class=\"fail\">Unstable: {}</p>",
+         escape_html(&summary.unstable.join(", "))
+      );
+   }
+
+   out.push_str("<h2>Cases</h2>\n<table><tr><th>Case</th><th>Mode</th><th>Result</th>");
+   out.push_str("<th>First hit</th><th>MRR</th><th>nDCG</th><th>Hits</th></tr>\n");
+   for case in &report.cases {
+      let _ = write!(
+         out,
+         "<tr><td><strong>{}</strong><br><span class=\"dim\">{}</span>",
+         escape_html(&case.id),
+         escape_html(&case.query)
+      );
+      if !case.missing_all.is_empty() {
+         let _ = write!(out, "<br>missing: {}", escape_html(&case.missing_all.join(", ")));
+      }
+      if !case.unexpected.is_empty() {
+         let _ = write!(out, "<br>unexpected: {}", escape_html(&case.unexpected.join(", ")));
+      }
+      let (class, label) = if case.passed {
+         ("pass", "pass")
+      } else {
+         ("fail", "FAIL")
+      };
+      let rank = case
+         .first_hit_rank
+         .map_or_else(|| "-".to_string(), |rank| rank.to_string());
+      let _ = write!(
+         out,
+         "</td><td>{}</td><td class=\"{class}\">{label}</td><td>{rank}</td><td>{:.3}</td>",
+         format!("{:?}", case.mode).to_lowercase(),
+         case.mrr
+      );
+      let _ = write!(out, "<td>{:.3}</td>", case.ndcg);
+      let _ = write!(out, "<td><details><summary>{} hit(s)</summary>", case.hits.len());
+      for hit in &case.hits {
+         let _ = write!(
+            out,
+            "<div>#{} <code>{}:{}</code> <span class=\"dim\">{} &middot; {:.3}</span>",
+            hit.rank,
+            escape_html(&hit.path),
+            hit.start_line,
+            escape_html(&hit.bucket),
+            hit.score
+         );
+         let _ = write!(out, "<pre>{}</pre></div>", escape_html(&hit.preview));
+      }
+      out.push_str("</details></td></tr>\n");
+   }
+   out.push_str("</table>\n</body></html>\n");
+   out
+}
+
+fn escape_html(text: &str) -> String {
+   let mut escaped = String::with_capacity(text.len());
+   for c in text.chars() {
+      match c {
+         '&' => escaped.push_str("&amp;"),
+         '<' => escaped.push_str("&lt;"),
+         '>' => escaped.push_str("&gt;"),
+         '"' => escaped.push_str("&quot;"),
+         '\'' => escaped.push_str("&#39;"),
+         _ => escaped.push(c),
+      }
+   }
+   escaped
+}
+
 fn resolve_suite_path(search_root: &Path, suite_path: Option<PathBuf>) -> Result<PathBuf> {
    if let Some(p) = suite_path {
       if p.exists() {
@@ -578,8 +1131,11 @@ fn parse_mode(mode: &str) -> std::result::Result<SearchMode, String> {
       "implementation" | "impl" => Ok(SearchMode::Implementation),
       "planning" | "plan" => Ok(SearchMode::Planning),
       "debug" => Ok(SearchMode::Debug),
+      "tests" | "test" => Ok(SearchMode::Tests),
+      "paths" | "path" => Ok(SearchMode::Paths),
       other => Err(format!(
-         "invalid mode '{other}' (expected: balanced|discovery|implementation|planning|debug)"
+         "invalid mode '{other}' (expected: \
+          balanced|discovery|implementation|planning|debug|tests|paths)"
       )),
    }
 }
@@ -611,7 +1167,7 @@ async fn evaluate_case(
    let include_anchors = overrides.include_anchors || config::get().fast_mode;
 
    let response = engine
-      .search_with_mode(
+      .search_with_options(
          snapshot,
          store_id,
          &case.query,
@@ -621,6 +1177,7 @@ async fn evaluate_case(
          rerank,
          include_anchors,
          mode,
+         &QueryOptions { no_fts: overrides.vector_only, ..QueryOptions::default() },
       )
       .await?;
 
@@ -630,7 +1187,7 @@ async fn evaluate_case(
       .filter(|r| include_anchors || !r.is_anchor.unwrap_or(false))
       .enumerate()
       .map(|(idx, r)| {
-         let bucket = match bucket_for_path(&r.path) {
+         let bucket = match bucket_for(&r.path, r.language.as_deref()) {
             crate::search::profile::SearchBucket::Code => "code",
             crate::search::profile::SearchBucket::Docs => "docs",
             crate::search::profile::SearchBucket::Graph => "graph",
@@ -642,6 +1199,7 @@ async fn evaluate_case(
             score:      r.score,
             match_pct:  None,
             start_line: r.start_line,
+            num_lines:  r.num_lines,
             chunk_type: r
                .chunk_type
                .map(ChunkType::as_lowercase_str)
@@ -653,7 +1211,8 @@ async fn evaluate_case(
 
    apply_match_pcts(&mut hits);
 
-   let (passed, first_hit_rank, mrr, missing_all) = score_case(&hits, &matchers);
+   let (passed, first_hit_rank, mrr, missing_all, unexpected) = score_case(&hits, &matchers);
+   let ndcg = ndcg(&hits, &matchers, k);
 
    Ok(EvalCaseReport {
       id: case.id.clone(),
@@ -665,12 +1224,62 @@ async fn evaluate_case(
       passed,
       first_hit_rank,
       mrr,
+      ndcg,
       missing_all,
+      unexpected,
       notes: case.notes.clone(),
       hits,
+      stability: None,
    })
 }
 
+fn stability(runs: &[EvalCaseReport]) -> EvalStability {
+   const MRR_EPSILON: f32 = 1e-6;
+
+   let count = runs.len().max(1) as f32;
+   let passed_runs = runs.iter().filter(|r| r.passed).count();
+   let mrr_min = runs.iter().map(|r| r.mrr).fold(f32::INFINITY, f32::min);
+   let mrr_max = runs.iter().map(|r| r.mrr).fold(f32::NEG_INFINITY, f32::max);
+   let mrr_mean = runs.iter().map(|r| r.mrr).sum::<f32>() / count;
+   let variance = runs.iter().map(|r| (r.mrr - mrr_mean).powi(2)).sum::<f32>() / count;
+   let unstable = (passed_runs != 0 && passed_runs != runs.len())
+      || mrr_max - mrr_min > MRR_EPSILON
+      || runs
+         .windows(2)
+         .any(|w| w[0].first_hit_rank != w[1].first_hit_rank);
+
+   EvalStability {
+      runs: runs.len(),
+      passed_runs,
+      mrr_min,
+      mrr_max,
+      mrr_mean,
+      mrr_stddev: variance.sqrt(),
+      unstable,
+   }
+}
+
+/// Median of the per-pass timings; the upper middle value for an even count.
+fn median_ms(run_ms: &[u128]) -> u128 {
+   let mut sorted = run_ms.to_vec();
+   sorted.sort_unstable();
+   sorted.get(sorted.len() / 2).copied().unwrap_or_default()
+}
+
+/// Fisher-Yates shuffle driven by splitmix64, so a recorded seed reproduces
+/// the case order.
+fn shuffle_indices(indices: &mut [usize], state: &mut u64) {
+   for i in (1..indices.len()).rev() {
+      *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+      let mut z = *state;
+      z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+      z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+      z ^= z >> 31;
+      let j = (z % (i as u64 + 1)) as usize;
+      indices.swap(i, j);
+   }
+}
+
 fn apply_match_pcts(hits: &mut [EvalHit]) {
    if hits.is_empty() {
       return;
@@ -705,28 +1314,61 @@ fn build_matchers(case: &EvalCase) -> Result<CaseMatchers> {
       .iter()
       .map(|p| Regex::new(p))
       .collect::<std::result::Result<Vec<_>, _>>()?;
+   let none_contains: Vec<String> = case
+      .expect_none_path_contains
+      .iter()
+      .map(|s| s.to_ascii_lowercase())
+      .collect();
+   let none_regex = case
+      .expect_none_path_regex
+      .iter()
+      .map(|p| Regex::new(p))
+      .collect::<std::result::Result<Vec<_>, _>>()?;
+   let lines: Vec<ExpectedLine> = case
+      .expect_line_in_path
+      .iter()
+      .map(|expected| ExpectedLine {
+         path: expected.path.to_ascii_lowercase(),
+         line: expected.line,
+      })
+      .collect();
 
    if any_contains.is_empty()
       && all_contains.is_empty()
       && any_regex.is_empty()
       && all_regex.is_empty()
+      && none_contains.is_empty()
+      && none_regex.is_empty()
+      && lines.is_empty()
    {
       return Err(
          io::Error::new(
             io::ErrorKind::InvalidInput,
-            format!("case '{}' has no expectations (expect_any_* / expect_all_*)", case.id),
+            format!(
+               "case '{}' has no expectations (expect_any_* / expect_all_* / expect_none_* / \
+                expect_line_in_path)",
+               case.id
+            ),
          )
          .into(),
       );
    }
 
-   Ok(CaseMatchers { any_contains, all_contains, any_regex, all_regex })
+   Ok(CaseMatchers {
+      any_contains,
+      all_contains,
+      any_regex,
+      all_regex,
+      none_contains,
+      none_regex,
+      lines,
+   })
 }
 
 fn score_case(
    hits: &[EvalHit],
    matchers: &CaseMatchers,
-) -> (bool, Option<usize>, f32, Vec<String>) {
+) -> (bool, Option<usize>, f32, Vec<String>, Vec<String>) {
    let first_hit_rank = first_hit_rank(hits, matchers);
    let mrr = first_hit_rank.map_or(0.0, |r| 1.0 / r as f32);
 
@@ -736,9 +1378,56 @@ fn score_case(
       has_any_match(hits, &matchers.any_contains, &matchers.any_regex)
    };
 
-   let (all_ok, missing_all) = all_matches(hits, &matchers.all_contains, &matchers.all_regex);
+   let (all_ok, mut missing_all) = all_matches(hits, &matchers.all_contains, &matchers.all_regex);
+   for expected in &matchers.lines {
+      if !hits.iter().any(|hit| covers_line(hit, expected)) {
+         missing_all.push(format!("{}:{}", expected.path, expected.line));
+      }
+   }
+   let all_ok = all_ok && missing_all.is_empty();
+   let unexpected = unexpected_paths(hits, &matchers.none_contains, &matchers.none_regex);
+
+   (any_ok && all_ok && unexpected.is_empty(), first_hit_rank, mrr, missing_all, unexpected)
+}
+
+/// nDCG@k with binary gains. Each `expect_all_*` pattern and expected line
+/// is one relevant item, and the `expect_any_*` patterns together are one
+/// more, satisfied by any of them. An item is credited at the first hit
+/// matching it, and a hit credits at most one item, so the ideal ranking puts
+/// every item in the top ranks.
+fn ndcg(hits: &[EvalHit], matchers: &CaseMatchers, k: usize) -> f32 {
+   let mut items: Vec<Box<dyn Fn(&EvalHit) -> bool + '_>> = Vec::new();
+   if !matchers.any_contains.is_empty() || !matchers.any_regex.is_empty() {
+      items.push(Box::new(|hit: &EvalHit| {
+         has_any_match(std::slice::from_ref(hit), &matchers.any_contains, &matchers.any_regex)
+      }));
+   }
+   for p in &matchers.all_contains {
+      items.push(Box::new(move |hit: &EvalHit| hit.path.to_ascii_lowercase().contains(p)));
+   }
+   for re in &matchers.all_regex {
+      items.push(Box::new(move |hit: &EvalHit| re.is_match(&hit.path)));
+   }
+   for expected in &matchers.lines {
+      items.push(Box::new(move |hit: &EvalHit| covers_line(hit, expected)));
+   }
+
+   let discount = |rank: usize| 1.0 / (rank as f32 + 1.0).log2();
+   let mut credited = vec![false; items.len()];
+   let mut dcg = 0.0;
+   for hit in hits.iter().filter(|hit| hit.rank <= k) {
+      let matched = items
+         .iter()
+         .zip(&credited)
+         .position(|(item, &done)| !done && item(hit));
+      if let Some(idx) = matched {
+         credited[idx] = true;
+         dcg += discount(hit.rank);
+      }
+   }
 
-   (any_ok && all_ok, first_hit_rank, mrr, missing_all)
+   let ideal: f32 = (1..=items.len().min(k)).map(discount).sum();
+   if ideal > 0.0 { dcg / ideal } else { 0.0 }
 }
 
 fn first_hit_rank(hits: &[EvalHit], matchers: &CaseMatchers) -> Option<usize> {
@@ -756,10 +1445,29 @@ fn first_hit_rank(hits: &[EvalHit], matchers: &CaseMatchers) -> Option<usize> {
       if union_regex.clone().any(|re| re.is_match(&hit.path)) {
          return Some(hit.rank);
       }
+      if matchers
+         .lines
+         .iter()
+         .any(|expected| covers_line(hit, expected))
+      {
+         return Some(hit.rank);
+      }
    }
    None
 }
 
+/// Whether `hit` is in the expected path and its chunk spans the line.
+/// Expected lines are 1-based, chunk start lines 0-based and the span
+/// half-open: `[start_line, start_line + num_lines)`.
+fn covers_line(hit: &EvalHit, expected: &ExpectedLine) -> bool {
+   let Some(line) = expected.line.checked_sub(1) else {
+      return false;
+   };
+   hit.path.to_ascii_lowercase().contains(&expected.path)
+      && hit.start_line <= line
+      && line < hit.start_line.saturating_add(hit.num_lines)
+}
+
 fn has_any_match(hits: &[EvalHit], contains: &[String], regexes: &[Regex]) -> bool {
    for hit in hits {
       let path_lc = hit.path.to_ascii_lowercase();
@@ -773,6 +1481,20 @@ fn has_any_match(hits: &[EvalHit], contains: &[String], regexes: &[Regex]) -> bo
    false
 }
 
+/// Hit paths matching any forbidden pattern, in rank order without repeats.
+fn unexpected_paths(hits: &[EvalHit], contains: &[String], regexes: &[Regex]) -> Vec<String> {
+   let mut unexpected: Vec<String> = Vec::new();
+   for hit in hits {
+      let path_lc = hit.path.to_ascii_lowercase();
+      let forbidden = contains.iter().any(|p| path_lc.contains(p))
+         || regexes.iter().any(|re| re.is_match(&hit.path));
+      if forbidden && !unexpected.contains(&hit.path) {
+         unexpected.push(hit.path.clone());
+      }
+   }
+   unexpected
+}
+
 fn all_matches(hits: &[EvalHit], contains: &[String], regexes: &[Regex]) -> (bool, Vec<String>) {
    let mut missing = Vec::new();
 
@@ -819,6 +1541,11 @@ fn summarize(cases: &[EvalCaseReport]) -> EvalSummary {
    } else {
       cases.iter().map(|c| c.mrr).sum::<f32>() / total as f32
    };
+   let mean_ndcg = if total == 0 {
+      0.0
+   } else {
+      cases.iter().map(|c| c.ndcg).sum::<f32>() / total as f32
+   };
 
    let (hit_sum, hit_count) = cases.iter().fold((0usize, 0usize), |acc, c| {
       if let Some(r) = c.first_hit_rank {
@@ -853,16 +1580,28 @@ fn summarize(cases: &[EvalCaseReport]) -> EvalSummary {
          } else {
             mode_cases.iter().map(|c| c.mrr).sum::<f32>() / mode_total as f32
          };
+         let mode_mean_ndcg = if mode_total == 0 {
+            0.0
+         } else {
+            mode_cases.iter().map(|c| c.ndcg).sum::<f32>() / mode_total as f32
+         };
          (mode, EvalModeSummary {
             total:     mode_total,
             passed:    mode_passed,
             pass_rate: mode_pass_rate,
             mean_mrr:  mode_mean_mrr,
+            mean_ndcg: mode_mean_ndcg,
          })
       })
       .collect();
 
-   EvalSummary { total, passed, pass_rate, mean_mrr, mean_hit_rank, by_mode }
+   let unstable = cases
+      .iter()
+      .filter(|c| c.stability.as_ref().is_some_and(|s| s.unstable))
+      .map(|c| c.id.clone())
+      .collect();
+
+   EvalSummary { total, passed, pass_rate, mean_mrr, mean_ndcg, mean_hit_rank, by_mode, unstable }
 }
 
 fn normalize_path(path: &Path) -> String {
@@ -909,3 +1648,237 @@ fn preview(content: &str, max_chars: usize, max_lines: usize) -> String {
 
    out
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn hits(paths: &[&str]) -> Vec<EvalHit> {
+      paths
+         .iter()
+         .enumerate()
+         .map(|(idx, path)| EvalHit {
+            rank:       idx + 1,
+            path:       (*path).to_string(),
+            bucket:     "code".to_string(),
+            score:      1.0,
+            match_pct:  None,
+            start_line: 1,
+            num_lines:  10,
+            chunk_type: None,
+            preview:    String::new(),
+         })
+         .collect()
+   }
+
+   fn matchers(any: &[&str], all: &[&str]) -> CaseMatchers {
+      CaseMatchers {
+         any_contains:  any.iter().map(ToString::to_string).collect(),
+         all_contains:  all.iter().map(ToString::to_string).collect(),
+         any_regex:     vec![],
+         all_regex:     vec![],
+         none_contains: vec![],
+         none_regex:    vec![],
+         lines:         vec![],
+      }
+   }
+
+   #[test]
+   fn ndcg_credits_each_expectation_once() {
+      let m = matchers(&[], &["a.rs", "b.rs"]);
+      assert!((ndcg(&hits(&["a.rs", "b.rs", "c.rs"]), &m, 3) - 1.0).abs() < 1e-6);
+
+      // Both expectations found, but the second one ranks below a miss.
+      let late = ndcg(&hits(&["a.rs", "c.rs", "b.rs"]), &m, 3);
+      let expected = (1.0 + 0.5) / (1.0 + 1.0 / 3f32.log2());
+      assert!((late - expected).abs() < 1e-6, "{late}");
+
+      // A repeated match of the same expectation earns nothing.
+      let repeated = ndcg(&hits(&["a.rs", "a.rs"]), &m, 3);
+      assert!((repeated - 1.0 / (1.0 + 1.0 / 3f32.log2())).abs() < 1e-6, "{repeated}");
+
+      // Alternatives under `expect_any_*` count as one item.
+      let any = matchers(&["x.rs", "y.rs"], &[]);
+      assert!((ndcg(&hits(&["y.rs"]), &any, 3) - 1.0).abs() < 1e-6);
+      assert_eq!(ndcg(&hits(&["z.rs"]), &any, 3), 0.0);
+   }
+
+   #[test]
+   fn forbidden_paths_fail_the_case() {
+      let mut m = matchers(&["src/lib.rs"], &[]);
+      m.none_contains = vec!["fixtures/".to_string()];
+      m.none_regex = vec![Regex::new(r"_test\.rs$").unwrap()];
+
+      let clean = score_case(&hits(&["src/lib.rs", "src/main.rs"]), &m);
+      assert!(clean.0);
+      assert!(clean.4.is_empty());
+
+      let hits =
+         hits(&["src/lib.rs", "tests/fixtures/a.rs", "src/lib_test.rs", "tests/fixtures/a.rs"]);
+      let (passed, first_hit_rank, _, _, unexpected) = score_case(&hits, &m);
+      assert!(!passed);
+      assert_eq!(first_hit_rank, Some(1));
+      assert_eq!(unexpected, ["tests/fixtures/a.rs", "src/lib_test.rs"]);
+   }
+
+   /// A report with the given `(id, passed, first_hit_rank)` cases.
+   fn report(cases: &[(&str, bool, Option<usize>)]) -> EvalReport {
+      let passed = cases.iter().filter(|(_, passed, _)| *passed).count();
+      let cases: Vec<_> = cases
+         .iter()
+         .map(|(id, passed, rank)| {
+            serde_json::json!({
+               "id": id, "query": id, "mode": "balanced", "k": 10, "per_file": 1,
+               "rerank": true, "passed": passed, "first_hit_rank": rank,
+               "mrr": rank.map_or(0.0, |rank| 1.0 / rank as f32), "missing_all": [],
+               "notes": null, "hits": [],
+            })
+         })
+         .collect();
+      serde_json::from_value(serde_json::json!({
+         "meta": {
+            "started_at_utc": "2026-01-01T00:00:00Z", "elapsed_ms": 1, "suite_path": "suite.toml",
+            "suite_version": 1, "store_id": "s", "root": "/repo", "ggrep_version": "0",
+            "config": {
+               "dense_model": "d", "colbert_model": "c", "dense_dim": 8, "colbert_dim": 8,
+               "dense_max_length": 8, "colbert_max_length": 8, "query_prefix": "",
+               "doc_prefix": "", "disable_gpu": true, "fast_mode": false, "low_impact": false,
+            },
+            "overrides": {
+               "no_rerank": false, "include_anchors": false, "no_sync": true,
+            },
+         },
+         "sync": { "processed": 0, "indexed": 0, "skipped": 0, "deleted": 0 },
+         "summary": {
+            "total": cases.len(), "passed": passed,
+            "pass_rate": passed as f32 / cases.len() as f32, "mean_mrr": 0.5,
+            "mean_hit_rank": null, "by_mode": {},
+         },
+         "cases": cases,
+      }))
+      .unwrap()
+   }
+
+   #[test]
+   fn diff_reports_classifies_transitions() {
+      let before = report(&[
+         ("kept", true, Some(1)),
+         ("fixed", false, None),
+         ("broken", true, Some(2)),
+         ("still_failing", false, None),
+         ("dropped", true, Some(1)),
+      ]);
+      let after = report(&[
+         ("kept", true, Some(3)),
+         ("fixed", true, Some(1)),
+         ("broken", false, None),
+         ("still_failing", false, None),
+         ("new", true, Some(1)),
+      ]);
+      let diff = diff_reports(&before, &after, Path::new("before.json"), Path::new("after.json"));
+
+      let transitions: Vec<_> = diff
+         .cases
+         .iter()
+         .map(|case| (case.id.as_str(), case.transition))
+         .collect();
+      assert_eq!(transitions, [
+         ("kept", CaseTransition::StillPassing),
+         ("fixed", CaseTransition::Fixed),
+         ("broken", CaseTransition::Regressed),
+         ("still_failing", CaseTransition::StillFailing),
+         ("new", CaseTransition::Added),
+         ("dropped", CaseTransition::Removed),
+      ]);
+      assert_eq!(diff.regressed, ["broken"]);
+      let kept = &diff.cases[0];
+      assert_eq!((kept.rank_before, kept.rank_after), (Some(1), Some(3)));
+      assert!(kept.changed());
+      assert!(!diff.cases[3].changed());
+      assert!(diff.cases[4].mrr.is_none());
+   }
+
+   #[test]
+   fn execute_diff_fails_only_on_regressions() {
+      let dir = tempfile::TempDir::new().unwrap();
+      let write = |name: &str, report: &EvalReport| {
+         let path = dir.path().join(name);
+         std::fs::write(&path, serde_json::to_string(report).unwrap()).unwrap();
+         path
+      };
+      let before = write("before.json", &report(&[("a", true, Some(1)), ("b", false, None)]));
+      let fixed = write("fixed.json", &report(&[("a", true, Some(2)), ("b", true, Some(1))]));
+      let broken = write("broken.json", &report(&[("a", false, None), ("b", false, None)]));
+
+      execute_diff(before.clone(), fixed, true).expect("no regressions");
+      let err = execute_diff(before, broken, true).expect_err("a regressed");
+      assert!(
+         err.to_string()
+            .contains("1 case(s) regressed from PASS to FAIL: a"),
+         "{err}"
+      );
+   }
+
+   #[test]
+   fn expected_lines_must_be_covered() {
+      let mut m = matchers(&[], &[]);
+      m.lines = vec![ExpectedLine { path: "src/lib.rs".to_string(), line: 11 }];
+      let mut hits = hits(&["src/main.rs", "src/lib.rs"]);
+
+      let (passed, first_hit_rank, ..) = score_case(&hits, &m);
+      assert!(passed);
+      assert_eq!(first_hit_rank, Some(2));
+
+      // The hit spans 0-based lines 1..11, i.e. 1-based lines 2 through 11.
+      for (line, covered) in [(0, false), (1, false), (2, true), (12, false)] {
+         m.lines[0].line = line;
+         assert_eq!(score_case(&hits, &m).0, covered, "line {line}");
+      }
+
+      m.lines[0].line = 11;
+      hits[1].start_line = 20;
+      let (passed, first_hit_rank, _, missing_all, _) = score_case(&hits, &m);
+      assert!(!passed);
+      assert_eq!(first_hit_rank, None);
+      assert_eq!(missing_all, ["src/lib.rs:11"]);
+   }
+
+   #[test]
+   fn elapsed_is_the_median_pass() {
+      // A slow cold first pass does not move the median.
+      assert_eq!(median_ms(&[900, 40, 42]), 42);
+      assert_eq!(median_ms(&[40, 50]), 50);
+      assert_eq!(median_ms(&[]), 0);
+   }
+
+   #[test]
+   fn escape_html_escapes_markup_and_quotes() {
+      assert_eq!(
+         escape_html(r#"<a href="x">Tom & 'Jerry'</a>"#),
+         "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+      );
+      assert_eq!(escape_html("plain/path.rs"), "plain/path.rs");
+   }
+
+   #[test]
+   fn render_html_escapes_content_and_paths() {
+      let hostile = r#"<b>"x" & 'y'"#;
+      let mut report = report(&[(hostile, false, Some(1))]);
+      report.meta.store_id = hostile.to_string();
+      report.cases[0].missing_all = vec![hostile.to_string()];
+      report.cases[0].hits = hits(&[r#"src/<x>&"q'.rs"#]);
+      report.cases[0].hits[0].preview = "if a < b && c > \"d\" { 'e' }".to_string();
+
+      let html = render_html(&report);
+      let escaped = "&lt;b&gt;&quot;x&quot; &amp; &#39;y&#39;";
+      assert!(!html.contains(hostile), "{html}");
+      assert!(html.contains(&format!("<title>ggrep eval: {escaped}</title>")), "{html}");
+      assert!(html.contains(&format!("<strong>{escaped}</strong>")), "{html}");
+      assert!(html.contains(&format!("missing: {escaped}")), "{html}");
+      assert!(html.contains("<code>src/&lt;x&gt;&amp;&quot;q&#39;.rs:1</code>"), "{html}");
+      assert!(
+         html.contains("<pre>if a &lt; b &amp;&amp; c &gt; &quot;d&quot; { &#39;e&#39; }</pre>"),
+         "{html}"
+      );
+   }
+}
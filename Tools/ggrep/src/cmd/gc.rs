@@ -1,12 +1,13 @@
 //! Garbage collection command for stores and snapshots.
 
 use std::{
-   fs,
-   path::PathBuf,
+   fs::{self, OpenOptions},
+   path::{Path, PathBuf},
    time::{Duration, SystemTime},
 };
 
 use console::style;
+use fs4::FileExt;
 use serde::Serialize;
 use tokio::time;
 
@@ -16,12 +17,17 @@ use crate::{
    error::Error,
    identity,
    ipc::{self, Request, Response},
-   snapshot::{GcOptions, gc_snapshots},
+   lease,
+   snapshot::{GcOptions, StagingTxn, gc_snapshots, read_active_snapshot_id_at},
    store::LanceStore,
    usock,
    util::{format_size, get_dir_size},
 };
 
+/// Artifacts younger than this may belong to a download or sync still in
+/// flight and are never collected.
+const ARTIFACT_MIN_AGE: Duration = Duration::from_secs(600);
+
 #[derive(Serialize, Clone)]
 struct GcStoreInfo {
    store_id:    String,
@@ -38,6 +44,21 @@ struct GcJson {
    deleted:        Vec<GcStoreInfo>,
 }
 
+#[derive(Serialize, Clone)]
+struct GcArtifact {
+   kind:       &'static str,
+   path:       PathBuf,
+   size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct ArtifactGcJson {
+   schema_version: u32,
+   dry_run:        bool,
+   candidates:     Vec<GcArtifact>,
+   deleted:        Vec<GcArtifact>,
+}
+
 #[derive(Serialize)]
 struct SnapshotGcJson {
    schema_version:      u32,
@@ -53,6 +74,7 @@ struct SnapshotGcJson {
 
 pub async fn execute(
    stores: bool,
+   artifacts: bool,
    force: bool,
    json: bool,
    path: Option<PathBuf>,
@@ -61,6 +83,9 @@ pub async fn execute(
    if stores {
       return gc_stores(force, json);
    }
+   if artifacts {
+      return gc_artifacts(force, json);
+   }
 
    gc_snapshots_command(force, json, path, store_id).await
 }
@@ -150,6 +175,190 @@ fn gc_stores(force: bool, json: bool) -> Result<()> {
    Ok(())
 }
 
+fn gc_artifacts(force: bool, json: bool) -> Result<()> {
+   let now = SystemTime::now();
+   let mut candidates = Vec::new();
+   scan_grammar_artifacts(config::grammar_dir(), now, &mut candidates)?;
+   scan_data_artifacts(config::data_dir(), now, &mut candidates)?;
+
+   let deleted = if force {
+      delete_artifacts(&candidates)
+   } else {
+      Vec::new()
+   };
+
+   if json {
+      let payload = ArtifactGcJson { schema_version: 1, dry_run: !force, candidates, deleted };
+      println!("{}", serde_json::to_string_pretty(&payload)?);
+      return Ok(());
+   }
+
+   if candidates.is_empty() {
+      println!("{}", style("No stale artifacts found.").green());
+      return Ok(());
+   }
+
+   if !force {
+      println!(
+         "{}",
+         style(format!(
+            "{} stale artifact(s) found. Re-run with --force to delete:",
+            candidates.len()
+         ))
+         .yellow()
+      );
+      for candidate in &candidates {
+         println!(
+            "  {} {} ({})",
+            style(candidate.kind).bold(),
+            candidate.path.display(),
+            style(format_size(candidate.size_bytes)).dim()
+         );
+      }
+      return Ok(());
+   }
+
+   let freed: u64 = deleted.iter().map(|a| a.size_bytes).sum();
+   println!(
+      "{}",
+      style(format!("Deleted {} stale artifact(s), freed {}.", deleted.len(), format_size(freed)))
+         .green()
+   );
+   Ok(())
+}
+
+/// Collects interrupted grammar downloads (`*.tmp-<pid>`) and download locks
+/// nobody holds.
+fn scan_grammar_artifacts(
+   grammar_dir: &Path,
+   now: SystemTime,
+   candidates: &mut Vec<GcArtifact>,
+) -> Result<()> {
+   if !grammar_dir.exists() {
+      return Ok(());
+   }
+   for entry in fs::read_dir(grammar_dir)? {
+      let path = entry?.path();
+      if !path.is_file() || !is_old(&path, now) {
+         continue;
+      }
+      let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+         continue;
+      };
+      if name.contains(".tmp-") {
+         candidates.push(artifact("grammar_tmp", path));
+      } else if name.ends_with(".lock") && is_unlocked(&path) {
+         candidates.push(artifact("grammar_lock", path));
+      }
+   }
+   Ok(())
+}
+
+/// Collects index locks for stores that no longer exist, leftover lease temp
+/// files, and staging directories that neither the live writer lease nor a
+/// resumable transaction refers to.
+fn scan_data_artifacts(
+   data_dir: &Path,
+   now: SystemTime,
+   candidates: &mut Vec<GcArtifact>,
+) -> Result<()> {
+   if !data_dir.exists() {
+      return Ok(());
+   }
+   for entry in fs::read_dir(data_dir)? {
+      let path = entry?.path();
+      if path.is_file() {
+         let orphaned = path.extension().is_some_and(|e| e == "lock")
+            && !path.with_extension("").is_dir()
+            && is_old(&path, now)
+            && is_unlocked(&path);
+         if orphaned {
+            candidates.push(artifact("index_lock", path));
+         }
+         continue;
+      }
+      let Some(store_id) = path.file_name().and_then(|n| n.to_str()) else {
+         continue;
+      };
+
+      let locks_dir = path.join("locks");
+      if locks_dir.is_dir() {
+         for lock_entry in fs::read_dir(&locks_dir)? {
+            let lock_path = lock_entry?.path();
+            if lock_path.extension().is_some_and(|e| e == "tmp") && is_old(&lock_path, now) {
+               candidates.push(artifact("lease_tmp", lock_path));
+            }
+         }
+      }
+
+      let staging_dir = path.join("staging");
+      if !staging_dir.is_dir() {
+         continue;
+      }
+      let live_txn = lease::live_staging_txn_id(store_id);
+      let active_snapshot = read_active_snapshot_id_at(&path).ok().flatten();
+      for staging_entry in fs::read_dir(&staging_dir)? {
+         let staging_path = staging_entry?.path();
+         if !staging_path.is_dir() || !is_old(&staging_path, now) {
+            continue;
+         }
+         let txn_id = staging_path.file_name().and_then(|n| n.to_str());
+         if txn_id.is_some() && txn_id == live_txn.as_deref() {
+            continue;
+         }
+         let resumable = StagingTxn::load(&staging_path)
+            .is_some_and(|txn| txn.parent_snapshot_id == active_snapshot);
+         if !resumable {
+            candidates.push(artifact("staging", staging_path));
+         }
+      }
+   }
+   Ok(())
+}
+
+/// Deletes each candidate, returning the ones that were removed.
+fn delete_artifacts(candidates: &[GcArtifact]) -> Vec<GcArtifact> {
+   candidates
+      .iter()
+      .filter(|candidate| {
+         let removed = if candidate.path.is_dir() {
+            fs::remove_dir_all(&candidate.path)
+         } else {
+            fs::remove_file(&candidate.path)
+         };
+         removed.is_ok()
+      })
+      .cloned()
+      .collect()
+}
+
+fn artifact(kind: &'static str, path: PathBuf) -> GcArtifact {
+   let size_bytes = if path.is_dir() {
+      get_dir_size(&path).unwrap_or(0)
+   } else {
+      fs::metadata(&path).map_or(0, |m| m.len())
+   };
+   GcArtifact { kind, path, size_bytes }
+}
+
+fn is_old(path: &Path, now: SystemTime) -> bool {
+   fs::metadata(path)
+      .and_then(|m| m.modified())
+      .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() >= ARTIFACT_MIN_AGE)
+}
+
+/// Whether no process currently holds a lock on `path`.
+fn is_unlocked(path: &Path) -> bool {
+   let Ok(file) = OpenOptions::new().read(true).write(true).open(path) else {
+      return false;
+   };
+   let unlocked = file.try_lock_exclusive().is_ok();
+   if unlocked {
+      let _ = file.unlock();
+   }
+   unlocked
+}
+
 async fn gc_snapshots_command(
    force: bool,
    json: bool,
@@ -272,3 +481,95 @@ fn format_time_rfc3339(time: SystemTime) -> String {
    let dt: chrono::DateTime<chrono::Utc> = time.into();
    dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn touch(path: &Path) {
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, b"x").unwrap();
+   }
+
+   fn staging_txn(dir: &Path, parent_snapshot_id: Option<&str>) {
+      let txn = StagingTxn {
+         staging_txn_id:      "txn".to_string(),
+         snapshot_id:         "snap-2".to_string(),
+         segment_table:       "seg".to_string(),
+         parent_snapshot_id:  parent_snapshot_id.map(str::to_string),
+         config_fingerprint:  "fp".to_string(),
+         created_at:          "2026-01-01T00:00:00Z".to_string(),
+         chunking:            None,
+         max_chunks_per_file: None,
+      };
+      fs::create_dir_all(dir).unwrap();
+      fs::write(dir.join("txn.json"), serde_json::to_vec(&txn).unwrap()).unwrap();
+   }
+
+   #[test]
+   fn artifact_gc_deletes_orphans_and_keeps_live_files() {
+      let grammar_dir = tempfile::TempDir::new().unwrap();
+      let data_dir = tempfile::TempDir::new().unwrap();
+      let (grammars, data) = (grammar_dir.path(), data_dir.path());
+
+      let orphans = [
+         grammars.join("tree-sitter-go.wasm.tmp-4242"),
+         grammars.join("go.lock"),
+         data.join("gc-artifacts-gone.lock"),
+         data.join("gc-artifacts-live/locks/writer_lease.json.tmp"),
+         data.join("gc-artifacts-live/staging/abandoned"),
+         data.join("gc-artifacts-live/staging/stale-parent"),
+      ];
+      let live = [
+         grammars.join("tree-sitter-go.wasm"),
+         grammars.join("rust.lock"),
+         data.join("gc-artifacts-live.lock"),
+         data.join("gc-artifacts-live/locks/writer_lease.json"),
+         data.join("gc-artifacts-live/staging/resumable"),
+      ];
+      for path in orphans[..4].iter().chain(&live[..4]) {
+         touch(path);
+      }
+      touch(&data.join("gc-artifacts-live/staging/abandoned/rows"));
+      staging_txn(&orphans[5], Some("snap-0"));
+      staging_txn(&live[4], Some("snap-1"));
+      fs::write(data.join("gc-artifacts-live/ACTIVE_SNAPSHOT"), "snap-1\n").unwrap();
+      // A download in progress holds its lock.
+      let held = OpenOptions::new()
+         .read(true)
+         .write(true)
+         .open(&live[1])
+         .unwrap();
+      held.lock_exclusive().unwrap();
+
+      // Everything is past `ARTIFACT_MIN_AGE`.
+      let now = SystemTime::now() + ARTIFACT_MIN_AGE * 2;
+      let mut candidates = Vec::new();
+      scan_grammar_artifacts(grammars, now, &mut candidates).unwrap();
+      scan_data_artifacts(data, now, &mut candidates).unwrap();
+      let deleted = delete_artifacts(&candidates);
+
+      let mut kinds: Vec<_> = deleted.iter().map(|a| a.kind).collect();
+      kinds.sort_unstable();
+      assert_eq!(kinds, [
+         "grammar_lock",
+         "grammar_tmp",
+         "index_lock",
+         "lease_tmp",
+         "staging",
+         "staging"
+      ]);
+      for path in &orphans {
+         assert!(!path.exists(), "{} was kept", path.display());
+      }
+      for path in &live {
+         assert!(path.exists(), "{} was deleted", path.display());
+      }
+
+      // Recent artifacts may still be in use and are left alone.
+      touch(&orphans[0]);
+      let mut candidates = Vec::new();
+      scan_grammar_artifacts(grammars, SystemTime::now(), &mut candidates).unwrap();
+      assert!(candidates.is_empty());
+   }
+}
@@ -0,0 +1,348 @@
+//! Grammar cache management command.
+//!
+//! Lists the tree-sitter WASM grammars cached under the grammar directory,
+//! downloads a chosen subset ahead of time, and prunes cached grammars this
+//! build no longer knows about.
+
+use std::{
+   collections::BTreeSet,
+   fs,
+   path::{Path, PathBuf},
+};
+
+use console::style;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+   Result,
+   error::Error,
+   grammar::{GRAMMAR_URLS, GrammarManager},
+   util::format_size,
+};
+
+/// Which grammar operation to run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarsAction {
+   /// Show installed and missing grammars.
+   List,
+   /// Download the named grammars; `force` replaces cached copies.
+   Fetch { languages: Vec<String>, force: bool },
+   /// Remove cached grammars that are neither built in nor custom.
+   Prune,
+}
+
+/// Integrity of a cached grammar against its `.sha256` sidecar.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ChecksumStatus {
+   Verified,
+   Mismatch,
+   Unrecorded,
+}
+
+impl ChecksumStatus {
+   const fn label(self) -> &'static str {
+      match self {
+         Self::Verified => "checksum ok",
+         Self::Mismatch => "checksum mismatch",
+         Self::Unrecorded => "no checksum",
+      }
+   }
+}
+
+#[derive(Serialize)]
+struct GrammarInfoJson {
+   language:   String,
+   installed:  bool,
+   custom:     bool,
+   size_bytes: Option<u64>,
+   checksum:   Option<ChecksumStatus>,
+}
+
+#[derive(Serialize)]
+struct GrammarListJson {
+   schema_version: u32,
+   grammar_dir:    PathBuf,
+   available:      Vec<&'static str>,
+   missing:        Vec<&'static str>,
+   grammars:       Vec<GrammarInfoJson>,
+}
+
+#[derive(Serialize)]
+struct FetchFailureJson {
+   language: &'static str,
+   error:    String,
+}
+
+#[derive(Serialize)]
+struct FetchedGrammarJson {
+   language:   &'static str,
+   size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct GrammarFetchJson {
+   schema_version: u32,
+   fetched:        Vec<FetchedGrammarJson>,
+   failed:         Vec<FetchFailureJson>,
+}
+
+#[derive(Serialize)]
+struct PrunedGrammarJson {
+   language:   String,
+   path:       PathBuf,
+   size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct GrammarPruneJson {
+   schema_version: u32,
+   removed:        Vec<PrunedGrammarJson>,
+   freed_bytes:    u64,
+}
+
+pub async fn execute(action: GrammarsAction, json: bool) -> Result<()> {
+   match action {
+      GrammarsAction::List => list(json),
+      GrammarsAction::Fetch { languages, force } => fetch(&languages, force, json).await,
+      GrammarsAction::Prune => prune(json),
+   }
+}
+
+fn list(json: bool) -> Result<()> {
+   let manager = GrammarManager::with_auto_download(false)?;
+   let available: Vec<_> = manager.available_languages().collect();
+   let missing: Vec<_> = manager.missing_languages().collect();
+
+   let mut grammars: Vec<GrammarInfoJson> = GRAMMAR_URLS
+      .iter()
+      .map(|(lang, _)| grammar_info(lang, &manager.grammar_path(lang), false))
+      .collect();
+   grammars.extend(
+      manager
+         .custom_grammars()
+         .iter()
+         .map(|grammar| grammar_info(&grammar.language, &grammar.wasm_path, true)),
+   );
+
+   if json {
+      let payload = GrammarListJson {
+         schema_version: 1,
+         grammar_dir: manager.grammar_dir().to_path_buf(),
+         available,
+         missing,
+         grammars,
+      };
+      println!("{}", serde_json::to_string_pretty(&payload)?);
+      return Ok(());
+   }
+
+   println!(
+      "\n{} {}",
+      style(format!("{} of {} grammars installed", available.len(), GRAMMAR_URLS.len())).bold(),
+      style(format!("(in {})", manager.grammar_dir().display())).dim()
+   );
+   println!();
+   for grammar in &grammars {
+      let label = if grammar.custom {
+         format!("{} (custom)", grammar.language)
+      } else {
+         grammar.language.clone()
+      };
+      match (grammar.size_bytes, grammar.checksum) {
+         (Some(size), Some(checksum)) => {
+            let checksum = if checksum == ChecksumStatus::Mismatch {
+               style(checksum.label()).red()
+            } else {
+               style(checksum.label()).dim()
+            };
+            println!(
+               "  {} {:<14} {} • {}",
+               style("✓").green(),
+               label,
+               style(format_size(size)).dim(),
+               checksum
+            );
+         },
+         _ => println!("  {} {:<14} {}", style("○").yellow(), label, style("missing").dim()),
+      }
+   }
+   if !missing.is_empty() {
+      println!(
+         "\n{}",
+         style("Run `ggrep grammars fetch <lang>...` to download missing grammars.").dim()
+      );
+   }
+   Ok(())
+}
+
+fn grammar_info(language: &str, path: &Path, custom: bool) -> GrammarInfoJson {
+   let size_bytes = fs::metadata(path).ok().map(|meta| meta.len());
+   let checksum = size_bytes.map(|_| checksum_status(path));
+   GrammarInfoJson {
+      language: language.to_string(),
+      installed: size_bytes.is_some(),
+      custom,
+      size_bytes,
+      checksum,
+   }
+}
+
+fn checksum_status(path: &Path) -> ChecksumStatus {
+   let Ok(expected) = fs::read_to_string(path.with_extension("sha256")) else {
+      return ChecksumStatus::Unrecorded;
+   };
+   let expected = expected.trim();
+   if expected.is_empty() {
+      return ChecksumStatus::Unrecorded;
+   }
+   match fs::read(path) {
+      Ok(bytes) if hex::encode(Sha256::digest(&bytes)) == expected => ChecksumStatus::Verified,
+      _ => ChecksumStatus::Mismatch,
+   }
+}
+
+async fn fetch(languages: &[String], force: bool, json: bool) -> Result<()> {
+   let mut pairs = Vec::with_capacity(languages.len());
+   for language in languages {
+      let Some(pair) = GRAMMAR_URLS
+         .iter()
+         .find(|(lang, _)| lang.eq_ignore_ascii_case(language))
+      else {
+         let known: Vec<_> = GRAMMAR_URLS.iter().map(|(lang, _)| *lang).collect();
+         return Err(Error::Server {
+            op:     "grammars",
+            reason: format!(
+               "invalid_request: unknown grammar '{language}' (known: {})",
+               known.join(", ")
+            ),
+         });
+      };
+      if !pairs.contains(pair) {
+         pairs.push(*pair);
+      }
+   }
+
+   // An explicit fetch is a request to download, whatever the config says.
+   let manager = GrammarManager::with_auto_download(true)?;
+   let mut fetched = Vec::new();
+   let mut failed = Vec::new();
+   for pair in pairs {
+      let language = pair.0;
+      let result = if force {
+         manager.redownload_grammar(pair).await
+      } else {
+         manager.download_grammar(pair).await
+      };
+      match result {
+         Ok(_) => {
+            let size_bytes = fs::metadata(manager.grammar_path(language)).map_or(0, |m| m.len());
+            if !json {
+               println!(
+                  "{} {} {}",
+                  style("✓").green(),
+                  language,
+                  style(format_size(size_bytes)).dim()
+               );
+            }
+            fetched.push(FetchedGrammarJson { language, size_bytes });
+         },
+         Err(err) => {
+            if !json {
+               println!("{} {} {}", style("✗").red(), language, style(&err).dim());
+            }
+            failed.push(FetchFailureJson { language, error: err.to_string() });
+         },
+      }
+   }
+
+   let failures = failed.len();
+   if json {
+      let payload = GrammarFetchJson { schema_version: 1, fetched, failed };
+      println!("{}", serde_json::to_string_pretty(&payload)?);
+   }
+   if failures > 0 {
+      return Err(Error::Reported {
+         message:   format!("failed to fetch {failures} grammar(s)"),
+         exit_code: 1,
+      });
+   }
+   Ok(())
+}
+
+/// Every grammar ggrep can download is kept, including ones no extension
+/// maps to (e.g. `regex`, used for injections), as are custom grammars.
+fn is_prunable(language: &str, path: &Path, custom: &BTreeSet<&Path>) -> bool {
+   !GRAMMAR_URLS.iter().any(|(lang, _)| *lang == language) && !custom.contains(path)
+}
+
+fn prune(json: bool) -> Result<()> {
+   let manager = GrammarManager::with_auto_download(false)?;
+   let custom: BTreeSet<&Path> = manager
+      .custom_grammars()
+      .iter()
+      .map(|grammar| grammar.wasm_path.as_path())
+      .collect();
+
+   let mut removed = Vec::new();
+   for entry in fs::read_dir(manager.grammar_dir())? {
+      let path = entry?.path();
+      let Some(language) = path
+         .file_name()
+         .and_then(|name| name.to_str())
+         .and_then(|name| name.strip_prefix("tree-sitter-"))
+         .and_then(|name| name.strip_suffix(".wasm"))
+      else {
+         continue;
+      };
+      if !is_prunable(language, &path, &custom) {
+         continue;
+      }
+
+      let size_bytes = fs::metadata(&path).map_or(0, |m| m.len());
+      fs::remove_file(&path)?;
+      for sidecar in ["sha256", "source"] {
+         let sidecar = path.with_extension(sidecar);
+         if sidecar.exists() {
+            fs::remove_file(&sidecar)?;
+         }
+      }
+      removed.push(PrunedGrammarJson { language: language.to_string(), path, size_bytes });
+   }
+   removed.sort_by(|a, b| a.language.cmp(&b.language));
+   let freed_bytes = removed.iter().map(|grammar| grammar.size_bytes).sum();
+
+   if json {
+      let payload = GrammarPruneJson { schema_version: 1, removed, freed_bytes };
+      println!("{}", serde_json::to_string_pretty(&payload)?);
+      return Ok(());
+   }
+
+   if removed.is_empty() {
+      println!("No unused grammars to prune.");
+      return Ok(());
+   }
+   for grammar in &removed {
+      println!("{} {}", style("Removed").green(), grammar.language);
+   }
+   println!("Freed {}", format_size(freed_bytes));
+   Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn prune_keeps_built_in_and_custom_grammars() {
+      let custom_path = Path::new("/grammars/tree-sitter-acme.wasm");
+      let custom = BTreeSet::from([custom_path]);
+      let path = |lang: &str| PathBuf::from(format!("/grammars/tree-sitter-{lang}.wasm"));
+
+      assert!(!is_prunable("rust", &path("rust"), &custom));
+      assert!(!is_prunable("regex", &path("regex"), &custom));
+      assert!(!is_prunable("acme", custom_path, &custom));
+      assert!(is_prunable("cobol", &path("cobol"), &custom));
+   }
+}
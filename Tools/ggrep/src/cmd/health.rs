@@ -150,6 +150,8 @@ async fn collect_health_payload(path: &Path) -> Result<HealthJson> {
             Ok(manifest) => {
                row_count = Some(manifest.counts.chunks_indexed);
                segments_count = Some(manifest.segments.len());
+               let (severity, message) = grammar_check(&manifest);
+               push_check(&mut checks, &mut ok, "grammars_loaded", severity, message);
                tombstones_count = Some(manifest.tombstones.iter().map(|t| t.count).sum());
                if manifest.counts.chunks_indexed > 0 {
                   push_check(
@@ -289,6 +291,9 @@ async fn collect_health_payload(path: &Path) -> Result<HealthJson> {
       perf_compaction_check.1,
    );
 
+   let quarantine_check = quarantine_check(meta_store.as_ref());
+   push_check(&mut checks, &mut ok, "quarantined_files", quarantine_check.0, quarantine_check.1);
+
    if let Some(rows) = row_count {
       if let Some(meta) = meta_store.as_ref() {
          let files = meta.all_paths().count();
@@ -307,6 +312,22 @@ async fn collect_health_payload(path: &Path) -> Result<HealthJson> {
    Ok(HealthJson { schema_version: 1, store_id, active_snapshot_id, ok, checks })
 }
 
+/// Warns when the snapshot holds files chunked without their grammar.
+fn grammar_check(manifest: &SnapshotManifest) -> (Severity, String) {
+   let missing = manifest.grammar_missing_languages();
+   if missing.is_empty() {
+      return (Severity::Ok, "all languages chunked with their grammar".to_string());
+   }
+   (
+      Severity::Warn,
+      format!(
+         "grammar missing for {}; run `ggrep grammars fetch {}` and reindex",
+         missing.join(", "),
+         missing.join(" ")
+      ),
+   )
+}
+
 fn push_check(
    checks: &mut Vec<HealthCheck>,
    ok: &mut bool,
@@ -657,6 +678,27 @@ fn perf_compaction_check(meta: Option<&MetaStore>) -> (Severity, String) {
    )
 }
 
+/// Files skipped by every sync until they change because they kept failing
+/// to index.
+fn quarantine_check(meta: Option<&MetaStore>) -> (Severity, String) {
+   let Some(meta) = meta else {
+      return (Severity::Warn, "quarantine state unavailable".to_string());
+   };
+   let files: Vec<String> = meta
+      .quarantined()
+      .map(|(path, failure)| {
+         format!("{} ({}, failures={})", path.display(), failure.code, failure.failures)
+      })
+      .collect();
+   if files.is_empty() {
+      return (Severity::Ok, "no quarantined files".to_string());
+   }
+   (
+      Severity::Warn,
+      format!("{} file(s) quarantined until they change: {}", files.len(), files.join(", ")),
+   )
+}
+
 fn budget_check(label: &str, observed: Option<u64>, budget_ms: u64) -> (Severity, String) {
    if budget_ms == 0 {
       return (Severity::Ok, format!("{label} budget disabled"));
@@ -675,3 +717,30 @@ fn budget_check(label: &str, observed: Option<u64>, budget_ms: u64) -> (Severity
       format!("{label} ok ({}ms <= {}ms)", observed, budget_ms),
    )
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::snapshot::{GRAMMAR_MISSING_CODE, SnapshotError};
+
+   #[test]
+   fn grammar_check_warns_with_fetch_hint() {
+      let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/schemas/manifest.json");
+      let mut manifest = SnapshotManifest::load(&path).unwrap();
+      manifest.errors.clear();
+      assert_eq!(grammar_check(&manifest).0, Severity::Ok);
+
+      for language in ["rust", "go"] {
+         manifest.errors.push(SnapshotError {
+            code:     GRAMMAR_MISSING_CODE.to_string(),
+            message:  String::new(),
+            path_key: format!("main.{language}"),
+            language: Some(language.to_string()),
+         });
+      }
+      let (severity, message) = grammar_check(&manifest);
+      assert_eq!(severity, Severity::Warn);
+      assert!(message.starts_with("grammar missing for go, rust;"), "{message}");
+      assert!(message.contains("`ggrep grammars fetch go rust`"), "{message}");
+   }
+}
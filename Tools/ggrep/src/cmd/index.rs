@@ -4,12 +4,17 @@
 //! vector database. Supports dry-run mode and index reset operations.
 
 use std::{
+   collections::BTreeMap,
+   io::{self, Write},
    path::{Path, PathBuf},
    sync::Arc,
+   time::{Duration, Instant},
 };
 
 use console::style;
+use globset::GlobSet;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
 use walkdir::WalkDir;
 
 use crate::{
@@ -20,10 +25,90 @@ use crate::{
    identity,
    index_lock::IndexLock,
    meta::MetaStore,
+   snapshot::{ChunkingMode, SnapshotManager},
    store::LanceStore,
-   sync::{SyncEngine, SyncOptions, SyncProgressCallback},
+   sync::{self, SyncEngine, SyncOptions, SyncProgressCallback},
+   types::SyncProgress,
+   util::get_dir_size,
 };
 
+/// Machine-readable summary emitted by `ggrep index --stats-json`.
+#[derive(Serialize)]
+struct IndexStatsJson {
+   schema_version:     u32,
+   store_id:           String,
+   root:               String,
+   snapshot_id:        String,
+   degraded:           bool,
+   /// Languages chunked without their grammar (no structural chunk types).
+   grammar_missing:    Vec<String>,
+   files_indexed:      u64,
+   chunks_indexed:     u64,
+   chunks_by_language: BTreeMap<String, u64>,
+   /// Files skipped until they change because they failed to index.
+   quarantined:        Vec<QuarantinedFileJson>,
+   segments:           usize,
+   store_size_bytes:   u64,
+   elapsed_ms:         u64,
+}
+
+#[derive(Serialize)]
+struct QuarantinedFileJson {
+   path:         String,
+   failures:     u32,
+   code:         String,
+   message:      String,
+   last_failure: Option<String>,
+}
+
+/// How `ggrep index` reports sync progress.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum IndexProgress {
+   /// Interactive progress bar on the terminal.
+   #[default]
+   Bar,
+   /// One `SyncProgress` JSON object per line on stderr.
+   Json,
+}
+
+/// Minimum gap between `--progress json` lines; the final update always goes
+/// out.
+const JSON_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Emits throttled NDJSON progress lines, on stderr by default.
+struct JsonProgress<W: Write = io::Stderr> {
+   out:       W,
+   last_emit: Option<Instant>,
+}
+
+impl Default for JsonProgress {
+   fn default() -> Self {
+      Self::new(io::stderr())
+   }
+}
+
+impl<W: Write> JsonProgress<W> {
+   const fn new(out: W) -> Self {
+      Self { out, last_emit: None }
+   }
+}
+
+impl<W: Write + Send> SyncProgressCallback for JsonProgress<W> {
+   fn progress(&mut self, progress: SyncProgress) {
+      let done = progress.total > 0 && progress.processed >= progress.total;
+      let recent = self
+         .last_emit
+         .is_some_and(|at| at.elapsed() < JSON_PROGRESS_INTERVAL);
+      if recent && !done {
+         return;
+      }
+      self.last_emit = Some(Instant::now());
+      if serde_json::to_writer(&mut self.out, &progress).is_ok() {
+         let _ = writeln!(self.out);
+      }
+   }
+}
+
 /// Executes the index command to create or update a code index.
 pub async fn execute(
    path: Option<PathBuf>,
@@ -31,8 +116,16 @@ pub async fn execute(
    reset: bool,
    eval_store: bool,
    allow_degraded: bool,
+   quiet_errors: bool,
+   stats_json: bool,
+   progress: IndexProgress,
+   chunking: Option<ChunkingMode>,
+   max_chunks_per_file: Option<usize>,
+   only: Vec<String>,
+   include: Vec<String>,
    store_id: Option<String>,
 ) -> Result<()> {
+   let only = sync::only_matcher(&only)?;
    let cwd = std::env::current_dir()?.canonicalize()?;
    let requested = path.unwrap_or(cwd).canonicalize()?;
    let index_identity = identity::resolve_index_identity(&requested)?;
@@ -57,9 +150,11 @@ pub async fn execute(
    };
 
    if reset {
-      println!("{}", style(format!("Resetting index for store: {resolved_store_id}")).yellow());
+      // Notes go to stderr with the progress output, so `--json` keeps stdout
+      // a single JSON document.
+      eprintln!("{}", style(format!("Resetting index for store: {resolved_store_id}")).yellow());
       delete_store(&resolved_store_id, &index_path).await?;
-      println!("{}", style("Existing index removed. Re-indexing...").dim());
+      eprintln!("{}", style("Existing index removed. Re-indexing...").dim());
    }
 
    let spinner = ProgressBar::new_spinner();
@@ -71,37 +166,122 @@ pub async fn execute(
 
    if dry_run {
       spinner.set_message("Scanning files (dry run)...");
-      let file_count = scan_files(&index_path);
+      let file_count = scan_files(&index_path, only.as_ref());
       spinner.finish_with_message(format!("Dry run complete: would index {file_count} files"));
       println!("\nWould index files in: {}", index_path.display());
       println!("Store ID: {resolved_store_id}");
       return Ok(());
    }
 
-   let mut pb = ProgressBar::new(0);
-   pb.set_style(
-      ProgressStyle::default_bar()
-         .template("{spinner:.green} {msg} [{bar:40.cyan/blue}] {pos}/{len} ({percent}%)")
-         .unwrap()
-         .progress_chars("█▓░"),
-   );
-   pb.set_message("...");
-   pb.set_prefix("Indexing: ");
+   let index_start = Instant::now();
+   let result = match progress {
+      IndexProgress::Bar => {
+         let mut pb = ProgressBar::new(0);
+         pb.set_style(
+            ProgressStyle::default_bar()
+               .template("{spinner:.green} {msg} [{bar:40.cyan/blue}] {pos}/{len} ({percent}%)")
+               .unwrap()
+               .progress_chars("█▓░"),
+         );
+         pb.set_message("...");
+         pb.set_prefix("Indexing: ");
 
-   let result = index_files(&index_path, &resolved_store_id, &mut |u| {
-      pb.progress(u);
-      spinner.tick();
-      pb.tick();
-   }, allow_degraded)
-   .await?;
+         let result = index_files(
+            &index_path,
+            &resolved_store_id,
+            &mut |u| {
+               pb.progress(u);
+               spinner.tick();
+               pb.tick();
+            },
+            allow_degraded,
+            quiet_errors,
+            chunking,
+            max_chunks_per_file,
+            only,
+            include,
+         )
+         .await?;
+         pb.finish_with_message(format!("Indexing complete: {} files indexed", result.indexed));
+         result
+      },
+      IndexProgress::Json => {
+         index_files(
+            &index_path,
+            &resolved_store_id,
+            &mut JsonProgress::default(),
+            allow_degraded,
+            quiet_errors,
+            chunking,
+            max_chunks_per_file,
+            only,
+            include,
+         )
+         .await?
+      },
+   };
+
+   let meta_store = MetaStore::load(&resolved_store_id)?;
+   let quarantined: Vec<QuarantinedFileJson> = meta_store
+      .quarantined()
+      .map(|(path, failure)| QuarantinedFileJson {
+         path:         path.display().to_string(),
+         failures:     failure.failures,
+         code:         failure.code.clone(),
+         message:      failure.message.clone(),
+         last_failure: failure.last_failure.clone(),
+      })
+      .collect();
 
-   pb.finish_with_message(format!("Indexing complete: {} files indexed", result.indexed));
+   if stats_json {
+      let elapsed_ms = index_start.elapsed().as_millis() as u64;
+      let store = Arc::new(LanceStore::new()?);
+      let snapshot_manager = SnapshotManager::new(
+         Arc::clone(&store),
+         resolved_store_id.clone(),
+         index_identity.config_fingerprint.clone(),
+         index_identity.ignore_fingerprint.clone(),
+      );
+      let view = snapshot_manager.open_snapshot_view().await?;
+      let chunks_by_language = store.language_counts(&resolved_store_id, &view).await?;
+      let payload = IndexStatsJson {
+         schema_version: 1,
+         store_id: resolved_store_id.clone(),
+         root: index_path.display().to_string(),
+         snapshot_id: view.snapshot_id.clone(),
+         degraded: view.manifest.degraded,
+         grammar_missing: view
+            .manifest
+            .grammar_missing_languages()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+         files_indexed: view.manifest.counts.files_indexed,
+         chunks_indexed: view.manifest.counts.chunks_indexed,
+         chunks_by_language,
+         quarantined,
+         segments: view.segment_tables().len(),
+         store_size_bytes: get_dir_size(&store.store_path(&resolved_store_id)).unwrap_or(0),
+         elapsed_ms,
+      };
+      println!("{}", serde_json::to_string_pretty(&payload)?);
+      return Ok(());
+   }
 
    println!("\n{}", style("Index created successfully!").green().bold());
    println!("Store ID: {}", style(&resolved_store_id).cyan());
    println!("Path: {}", style(index_path.display()).dim());
    println!("Files indexed: {}", result.indexed);
    println!("Total chunks: {}", style(result.total_chunks.to_string()).bold());
+   if !quarantined.is_empty() {
+      println!(
+         "{}",
+         style(format!("Quarantined {} file(s) that failed to index:", quarantined.len())).yellow()
+      );
+      for file in &quarantined {
+         println!("  {} ({}, failures={})", file.path, file.code, file.failures);
+      }
+   }
 
    Ok(())
 }
@@ -116,13 +296,15 @@ async fn delete_store(store_id: &str, _index_path: &Path) -> Result<()> {
 
    let mut meta_store = MetaStore::load(store_id)?;
    meta_store.clear_all();
+   meta_store.clear_failures();
    meta_store.save()?;
 
    Ok(())
 }
 
-/// Scans the directory tree and counts indexable source files.
-fn scan_files(path: &Path) -> usize {
+/// Scans the directory tree and counts indexable source files, restricted to
+/// `only` when given.
+fn scan_files(path: &Path, only: Option<&GlobSet>) -> usize {
    let mut count = 0;
    if path.is_dir() {
       for entry in WalkDir::new(path)
@@ -131,6 +313,12 @@ fn scan_files(path: &Path) -> usize {
          .filter_map(|e| e.ok())
       {
          if entry.file_type().is_file()
+            && only.is_none_or(|only| {
+               entry
+                  .path()
+                  .strip_prefix(path)
+                  .is_ok_and(|rel| only.is_match(rel))
+            })
             && let Some(ext) = entry.path().extension()
          {
             let ext_str = ext.to_string_lossy();
@@ -156,8 +344,13 @@ async fn index_files(
    store_id: &str,
    callback: &mut dyn SyncProgressCallback,
    allow_degraded: bool,
+   quiet_errors: bool,
+   chunking: Option<ChunkingMode>,
+   max_chunks_per_file: Option<usize>,
+   only: Option<GlobSet>,
+   include: Vec<String>,
 ) -> Result<IndexResult> {
-   let file_system = LocalFileSystem::new();
+   let file_system = LocalFileSystem::with_extra_extensions(include);
    let embedder: Arc<dyn Embedder> = Arc::new(CandleEmbedder::new()?);
    let store: Arc<LanceStore> = Arc::new(LanceStore::new()?);
 
@@ -169,10 +362,51 @@ async fn index_files(
          path,
          None,
          false,
-         SyncOptions { allow_degraded, ..SyncOptions::default() },
+         SyncOptions {
+            allow_degraded,
+            quiet_errors,
+            chunking,
+            max_chunks_per_file,
+            only,
+            ..SyncOptions::default()
+         },
          callback,
       )
       .await?;
 
    Ok(IndexResult { indexed: result.indexed, total_chunks: result.indexed })
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn update(processed: usize, file: &str) -> SyncProgress {
+      SyncProgress { processed, indexed: processed, total: 3, current_file: Some(file.into()) }
+   }
+
+   #[test]
+   fn json_progress_writes_one_object_per_line() {
+      let mut progress = JsonProgress::new(Vec::new());
+      progress.progress(update(1, "src/a.rs"));
+      // Throttled: the next update lands within the interval.
+      progress.progress(update(2, "src/b.rs"));
+      // The final update always goes out.
+      progress.progress(update(3, "src/c.rs"));
+
+      let out = String::from_utf8(progress.out).expect("utf-8");
+      assert!(out.ends_with('\n'));
+      let lines: Vec<serde_json::Value> = out
+         .lines()
+         .map(|line| serde_json::from_str(line).expect("one JSON object per line"))
+         .collect();
+      assert_eq!(lines, [
+         serde_json::json!({
+            "processed": 1, "indexed": 1, "total": 3, "current_file": "src/a.rs"
+         }),
+         serde_json::json!({
+            "processed": 3, "indexed": 3, "total": 3, "current_file": "src/c.rs"
+         }),
+      ]);
+   }
+}
@@ -127,7 +127,7 @@ struct StoreInfo {
 }
 
 /// Formats a `SystemTime` as a human-readable "time ago" string.
-fn format_time_ago(time: SystemTime) -> String {
+pub(crate) fn format_time_ago(time: SystemTime) -> String {
    let now = SystemTime::now();
    let duration = now.duration_since(time).unwrap_or_default();
 
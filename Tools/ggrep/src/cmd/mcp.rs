@@ -20,7 +20,7 @@ use crate::{
    error::Error,
    file::{normalize_path, normalize_relative},
    identity,
-   types::SearchMode,
+   types::{QueryOptions, SearchMode},
 };
 
 /// Incoming JSON-RPC 2.0 request from an MCP client.
@@ -165,7 +165,7 @@ async fn handle_request(
                },
                "mode": {
                   "type": "string",
-                  "description": "Search mode: balanced|discovery|implementation|planning|debug (default: discovery)",
+                  "description": "Search mode: balanced|discovery|implementation|planning|debug|tests|paths (default: discovery)",
                   "default": "discovery"
                },
                "path": {
@@ -388,14 +388,17 @@ async fn try_tool_good_search(state: &McpState, args: &Value, request_id: &str)
    let capped_limit = limit.min(cfg.max_query_results).max(1);
    let capped_per_file = per_file.min(cfg.max_query_per_file).max(1);
 
-   let stream = daemon::connect_matching_daemon(&index_root, &store_id).await?;
+   let query_options = QueryOptions::default();
+   let mut client = daemon::DaemonClient::connect(&index_root, &store_id).await?;
    let outcome = search::send_search_request(
-      stream,
+      &mut client,
       query,
       capped_limit,
       capped_per_file,
       mode,
       rerank,
+      None,
+      &query_options,
       scope_rel.as_deref(),
       &index_root,
    )
@@ -411,6 +414,8 @@ async fn try_tool_good_search(state: &McpState, args: &Value, request_id: &str)
       capped_per_file,
       rerank,
       mode,
+      &query_options,
+      None,
       request_id,
       &outcome,
    )?;
@@ -522,8 +527,11 @@ fn parse_mode(mode: &str) -> std::result::Result<SearchMode, String> {
       "implementation" | "impl" => Ok(SearchMode::Implementation),
       "planning" | "plan" => Ok(SearchMode::Planning),
       "debug" => Ok(SearchMode::Debug),
+      "tests" | "test" => Ok(SearchMode::Tests),
+      "paths" | "path" => Ok(SearchMode::Paths),
       other => Err(format!(
-         "invalid mode '{other}' (expected: balanced|discovery|implementation|planning|debug)"
+         "invalid mode '{other}' (expected: \
+          balanced|discovery|implementation|planning|debug|tests|paths)"
       )),
    }
 }
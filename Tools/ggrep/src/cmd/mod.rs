@@ -14,6 +14,7 @@ pub mod daemon;
 pub mod doctor;
 pub mod eval;
 pub mod gemini_install;
+pub mod grammars;
 pub mod health;
 pub mod index;
 pub mod list;
@@ -21,6 +22,8 @@ pub mod mcp;
 pub mod opencode_install;
 pub mod promote_eval;
 pub mod repair;
+pub mod repl;
+pub mod reset_embeddings;
 pub mod search;
 pub mod serve;
 pub mod setup;
@@ -28,3 +31,4 @@ pub mod status;
 pub mod stop;
 pub mod stop_all;
 pub mod upgrade_store;
+pub mod verify_store;
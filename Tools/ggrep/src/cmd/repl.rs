@@ -0,0 +1,210 @@
+//! Interactive search REPL over a persistent daemon connection.
+//!
+//! Each line is either a query or a `:command` that changes session settings
+//! (mode, result limits, scope). All queries share one daemon connection.
+
+use std::{
+   io::{self, BufRead, Write},
+   path::{Path, PathBuf},
+};
+
+use console::style;
+
+use crate::{
+   Result,
+   cmd::{
+      daemon::DaemonClient,
+      search::{self, SearchOptions, SearchTarget},
+   },
+   config,
+   file::normalize_relative,
+   types::{QueryOptions, SearchMode},
+};
+
+const HELP: &str = "\
+Commands:
+  :mode <balanced|discovery|implementation|planning|debug|tests|paths>
+  :max <n>          maximum total results
+  :per-file <n>     maximum results per file
+  :scope [dir]      restrict to a directory under the index root (no arg clears)
+  :rerank <on|off>  toggle ColBERT reranking
+  :show             print current settings
+  :help             show this help
+  :quit             exit (also Ctrl-D)
+Anything else is run as a search query.";
+
+/// Settings that persist across queries in one session.
+struct Session {
+   mode:     SearchMode,
+   max:      usize,
+   per_file: usize,
+   scope:    Option<PathBuf>,
+   rerank:   bool,
+}
+
+impl Session {
+   fn describe(&self) -> String {
+      format!(
+         "mode={}, max={}, per_file={}, scope={}, rerank={}",
+         format!("{:?}", self.mode).to_lowercase(),
+         self.max,
+         self.per_file,
+         self
+            .scope
+            .as_deref()
+            .map_or_else(|| "<root>".to_string(), |s| s.display().to_string()),
+         if self.rerank { "on" } else { "off" }
+      )
+   }
+
+   /// Applies a `:command` line, returning a message to print.
+   fn apply(&mut self, command: &str, arg: Option<&str>) -> std::result::Result<String, String> {
+      let cfg = config::get();
+      match (command, arg) {
+         ("mode", Some(value)) => {
+            self.mode = parse_mode(value)?;
+         },
+         ("max", Some(value)) => {
+            self.max = parse_count(value)?.min(cfg.max_query_results);
+         },
+         ("per-file", Some(value)) => {
+            self.per_file = parse_count(value)?.min(cfg.max_query_per_file);
+         },
+         ("scope", None) => self.scope = None,
+         ("scope", Some(value)) => {
+            let path = Path::new(value.trim_end_matches('/'));
+            if path.is_absolute() {
+               return Err("scope must be relative to the index root".to_string());
+            }
+            self.scope = Some(
+               normalize_relative(path)
+                  .ok_or_else(|| format!("invalid scope '{value}' (no '..' components)"))?,
+            );
+         },
+         ("rerank", Some("on")) => self.rerank = true,
+         ("rerank", Some("off")) => self.rerank = false,
+         ("show", None) => {},
+         ("mode" | "max" | "per-file" | "rerank", _) => {
+            return Err(format!("usage: see :help for :{command}"));
+         },
+         _ => return Err(format!("unknown command ':{command}' (try :help)")),
+      }
+      Ok(self.describe())
+   }
+}
+
+/// Executes the interactive REPL.
+pub async fn execute(
+   path: Option<PathBuf>,
+   plain: bool,
+   eval_store: bool,
+   store_id: Option<String>,
+) -> Result<()> {
+   let SearchTarget { index_root, store_id, scope, .. } =
+      search::resolve_target(path, eval_store, store_id)?;
+
+   let mut client = Some(DaemonClient::connect(&index_root, &store_id).await?);
+   let mut session =
+      Session { mode: SearchMode::Balanced, max: 10, per_file: 1, scope, rerank: true };
+
+   println!("ggrep repl ({}), store {}", index_root.display(), store_id);
+   println!("{}", session.describe());
+   println!("Type :help for commands, :quit to exit.");
+
+   let stdin = io::stdin();
+   let mut line = String::new();
+   loop {
+      if plain {
+         print!("ggrep> ");
+      } else {
+         print!("{} ", style("ggrep>").cyan().bold());
+      }
+      io::stdout().flush()?;
+
+      line.clear();
+      if stdin.lock().read_line(&mut line)? == 0 {
+         println!();
+         break;
+      }
+      let input = line.trim();
+      if input.is_empty() {
+         continue;
+      }
+
+      if let Some(command) = input.strip_prefix(':') {
+         let (command, arg) = match command.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, Some(arg.trim()).filter(|a| !a.is_empty())),
+            None => (command, None),
+         };
+         match command {
+            "quit" | "q" | "exit" => break,
+            "help" | "h" => println!("{HELP}"),
+            _ => match session.apply(command, arg) {
+               Ok(message) => println!("{message}"),
+               Err(message) => eprintln!("{message}"),
+            },
+         }
+         continue;
+      }
+
+      // A failed query drops its connection; reconnect on the next one and
+      // keep the prompt while the daemon is unreachable.
+      let mut daemon = match client.take() {
+         Some(daemon) => daemon,
+         None => match DaemonClient::connect(&index_root, &store_id).await {
+            Ok(daemon) => daemon,
+            Err(err) => {
+               eprintln!("daemon unavailable: {err}");
+               continue;
+            },
+         },
+      };
+      let outcome = search::send_search_request(
+         &mut daemon,
+         input,
+         session.max.max(1),
+         session.per_file.max(1),
+         session.mode,
+         session.rerank,
+         None,
+         &QueryOptions::default(),
+         session.scope.as_deref(),
+         &index_root,
+      )
+      .await;
+      match outcome {
+         Ok(outcome) => {
+            client = Some(daemon);
+            let options = SearchOptions { plain, mode: session.mode, ..SearchOptions::default() };
+            search::print_outcome(&outcome, input, &index_root, session.scope.as_deref(), &options);
+         },
+         // The connection state is unknown after a failure; start fresh.
+         Err(err) => eprintln!("search failed: {err}"),
+      }
+   }
+
+   Ok(())
+}
+
+fn parse_mode(mode: &str) -> std::result::Result<SearchMode, String> {
+   match mode.trim().to_ascii_lowercase().as_str() {
+      "balanced" => Ok(SearchMode::Balanced),
+      "discovery" => Ok(SearchMode::Discovery),
+      "implementation" | "impl" => Ok(SearchMode::Implementation),
+      "planning" | "plan" => Ok(SearchMode::Planning),
+      "debug" => Ok(SearchMode::Debug),
+      "tests" | "test" => Ok(SearchMode::Tests),
+      "paths" | "path" => Ok(SearchMode::Paths),
+      other => Err(format!(
+         "invalid mode '{other}' (expected: \
+          balanced|discovery|implementation|planning|debug|tests|paths)"
+      )),
+   }
+}
+
+fn parse_count(value: &str) -> std::result::Result<usize, String> {
+   match value.parse::<usize>() {
+      Ok(n) if n > 0 => Ok(n),
+      _ => Err(format!("expected a positive integer, got '{value}'")),
+   }
+}
@@ -0,0 +1,138 @@
+//! Re-embedding command for embedding model swaps.
+//!
+//! Changing the embedding model changes the store id, so the new store starts
+//! empty. This rebuilds it from the chunks already stored for the repository
+//! under the previous model instead of re-reading and re-chunking every file.
+
+use std::{
+   path::{Path, PathBuf},
+   sync::Arc,
+};
+
+use console::style;
+use serde::Serialize;
+
+use crate::{
+   Result, config,
+   embed::{Embedder, candle::CandleEmbedder},
+   error::Error,
+   identity,
+   meta::MetaStore,
+   snapshot::reembed_store,
+   store::LanceStore,
+};
+
+#[derive(Serialize)]
+struct ReembedJson {
+   schema_version:   u32,
+   source_store_id:  String,
+   store_id:         String,
+   base_snapshot_id: String,
+   new_snapshot_id:  String,
+   files:            u64,
+   rows:             u64,
+   duration_ms:      u64,
+}
+
+pub async fn execute(
+   path: Option<PathBuf>,
+   from: Option<String>,
+   json: bool,
+   store_id: Option<String>,
+) -> Result<()> {
+   let cwd = std::env::current_dir()?.canonicalize()?;
+   let requested = path.unwrap_or(cwd).canonicalize()?;
+   let identity = identity::resolve_index_identity(&requested)?;
+   let target_store_id = store_id.unwrap_or(identity.store_id.clone());
+   let source_store_id = match from {
+      Some(from) => from,
+      None => find_source_store(&identity.canonical_root, &target_store_id)?,
+   };
+
+   let store = Arc::new(LanceStore::new()?);
+   let embedder: Arc<dyn Embedder> = Arc::new(CandleEmbedder::new()?);
+   let result = reembed_store(
+      store,
+      embedder,
+      &source_store_id,
+      &target_store_id,
+      &identity.config_fingerprint,
+      &identity.ignore_fingerprint,
+   )
+   .await?;
+
+   if json {
+      let payload = ReembedJson {
+         schema_version:   1,
+         source_store_id:  result.source_store_id,
+         store_id:         result.store_id,
+         base_snapshot_id: result.base_snapshot_id,
+         new_snapshot_id:  result.new_snapshot_id,
+         files:            result.files,
+         rows:             result.rows,
+         duration_ms:      result.duration_ms,
+      };
+      println!("{}", serde_json::to_string_pretty(&payload)?);
+      return Ok(());
+   }
+
+   println!("{}", style("✓ Re-embedding complete").green());
+   println!("  files: {}", style(result.files).dim());
+   println!("  rows: {}", style(result.rows).dim());
+   println!("  snapshot: {}", style(&result.new_snapshot_id).dim());
+   if result.source_store_id != result.store_id {
+      println!(
+         "  {}",
+         style(format!(
+            "built from {}; remove it with `ggrep clean {}` once no longer needed",
+            result.source_store_id, result.source_store_id
+         ))
+         .dim()
+      );
+   }
+
+   Ok(())
+}
+
+/// Picks the store to re-embed: the target itself when it was indexed with
+/// another model (e.g. pinned with `--store`), otherwise the most recently
+/// synced store of the same repository built with another model.
+fn find_source_store(root: &Path, target_store_id: &str) -> Result<String> {
+   let target_meta = MetaStore::load(target_store_id)?;
+   if target_meta.model_mismatch() && target_meta.snapshot_id().is_some() {
+      return Ok(target_store_id.to_string());
+   }
+
+   let prefix = identity::store_id_prefix(root)?;
+   let mut candidates: Vec<(String, String)> = Vec::new();
+   let entries = std::fs::read_dir(config::meta_dir()).into_iter().flatten();
+   for entry in entries.flatten() {
+      let path = entry.path();
+      let Some(candidate) = path
+         .file_name()
+         .and_then(|name| name.to_str())
+         .and_then(|name| name.strip_suffix(".json"))
+      else {
+         continue;
+      };
+      if candidate == target_store_id || !candidate.starts_with(&prefix) {
+         continue;
+      }
+      let meta = MetaStore::load(candidate)?;
+      if meta.model_mismatch() && meta.snapshot_id().is_some() {
+         let synced_at = meta.last_sync_at().unwrap_or_default().to_string();
+         candidates.push((synced_at, candidate.to_string()));
+      }
+   }
+   candidates.sort();
+   candidates
+      .pop()
+      .map(|(_, store_id)| store_id)
+      .ok_or_else(|| Error::Server {
+         op:     "reset-embeddings",
+         reason: format!(
+            "no store of {} was indexed with another embedding model; pass --from <store-id>",
+            root.display()
+         ),
+      })
+}
@@ -5,9 +5,12 @@
 //! options.
 
 use std::{
+   borrow::Cow,
+   collections::BTreeMap,
+   io::{self, Write},
    path::{Path, PathBuf},
    sync::Arc,
-   time::Duration,
+   time::{Duration, Instant, SystemTime},
 };
 
 use console::style;
@@ -18,59 +21,293 @@ use tokio::time;
 use crate::{
    Result,
    chunker::Chunker,
-   cmd::daemon,
+   cmd::{daemon, list},
    config,
    embed::worker::EmbedWorker,
    error::Error,
-   file::{LocalFileSystem, normalize_relative},
-   git, identity,
-   ipc::{self, Request, Response},
-   meta::MetaStore,
-   snapshot::SnapshotManager,
-   search::SearchEngine,
-   store::LanceStore,
-   sync::{SyncEngine, SyncOptions},
-   types::{SearchLimitHit, SearchMode, SearchStatus, SearchTimings, SearchWarning},
-   usock,
-   util::sanitize_output,
+   file::{LocalFileSystem, normalize_relative, sample_candidate_files},
+   git,
+   grammar::{self, UNKNOWN_LANGUAGE},
+   identity,
+   ipc::{Request, Response},
+   meta::{FileHash, INDEX_VERSION, MetaStore},
+   search::{self, SearchEngine, ranking::RankingWeights},
+   snapshot::{
+      SnapshotManager, SnapshotManifest, SnapshotView,
+      manifest::{CHUNK_ROW_SCHEMA_VERSION, MANIFEST_SCHEMA_VERSION},
+   },
+   store::{LanceStore, SearchFilters, VECTOR_INDEX_MIN_ROWS},
+   sync::{CHUNKER_VERSION, SyncEngine, SyncOptions},
+   types::{
+      self, CandidateCounts, EmbedderStatus, FilterStage, QueryExpansion, QueryOptions,
+      RerankStats, ScoreRange, SearchLimitHit, SearchMode, SearchStatus, SearchTimings,
+      SearchWarning, SegmentCandidates, SpanMerge,
+   },
+   util::{format_size, get_dir_size, sanitize_output},
+   version,
 };
 
+/// Directory entries the explain coverage walk visits at most.
+const COVERAGE_WALK_MAX_ENTRIES: usize = 20_000;
+
+/// Smallest indexed/on-disk gap worth flagging, so tiny repos stay quiet.
+const COVERAGE_MIN_GAP: u64 = 50;
+
 /// A single search result with metadata and content.
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct SearchResult {
    path:       PathBuf,
    score:      f32,
+   /// `score` mapped onto 0.0–1.0 within this result set; `match_pct` is
+   /// this value as a percentage.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   norm_score: Option<f32>,
    #[serde(skip_serializing_if = "Option::is_none")]
    match_pct:  Option<u8>,
    content:    String,
    #[serde(skip_serializing_if = "Option::is_none")]
    chunk_type: Option<String>,
+   /// Name of the enclosing definition, when the chunker found one.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   symbol:     Option<String>,
    #[serde(skip_serializing_if = "Option::is_none")]
    start_line: Option<usize>,
    #[serde(skip_serializing_if = "Option::is_none")]
    end_line:   Option<usize>,
    #[serde(skip_serializing_if = "Option::is_none")]
    is_anchor:  Option<bool>,
+   language:   String,
+   /// Last-commit author of `start_line`, set by `--blame`.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   author:     Option<String>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   commit:     Option<String>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   committed_at: Option<String>,
+   #[serde(skip)]
+   segment_table: Option<String>,
+   /// Leading/trailing lines of `content` that come from neighbouring chunks.
+   #[serde(skip)]
+   context_before: usize,
+   #[serde(skip)]
+   context_after: usize,
 }
 
 /// JSON output format for search results.
 #[derive(Debug, Serialize)]
 pub(crate) struct SearchJsonOutput {
    #[serde(flatten)]
-   meta:    SearchMeta,
-   results: Vec<SearchResult>,
+   meta:        SearchMeta,
+   results:     Vec<SearchResult>,
+   /// Per-file result counts with `--count`; `results` is empty then.
    #[serde(skip_serializing_if = "Option::is_none")]
-   explain: Option<SearchExplain>,
+   counts:      Option<Vec<FileCount>>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   explain:     Option<SearchExplain>,
+   /// Set only with `--page-size`/`--cursor`; the inner value is `null` once
+   /// the results are exhausted.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   next_cursor: Option<Option<String>>,
+}
+
+/// Number of results that landed in one file (`--count`).
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct FileCount {
+   path:  PathBuf,
+   count: usize,
 }
 
 #[derive(Debug)]
 pub(crate) struct SearchOutcome {
-   results:    Vec<SearchResult>,
-   status:     SearchStatus,
-   progress:   Option<u8>,
-   timings_ms: Option<SearchTimings>,
-   limits_hit: Vec<SearchLimitHit>,
-   warnings:   Vec<SearchWarning>,
+   results:     Vec<SearchResult>,
+   status:      SearchStatus,
+   progress:    Option<u8>,
+   timings_ms:  Option<SearchTimings>,
+   limits_hit:  Vec<SearchLimitHit>,
+   warnings:    Vec<SearchWarning>,
+   embedder:    Option<EmbedderStatus>,
+   candidates:  Option<CandidateCounts>,
+   segments:    Vec<SegmentCandidates>,
+   rerank:      Option<RerankStats>,
+   /// Set only for results served by the daemon.
+   ipc:         Option<IpcSizes>,
+   /// Snapshot the results were read from, when the search path reports it.
+   snapshot_id: Option<String>,
+   execution:   SearchExecution,
+   /// Synonyms the engine appended to the full-text query.
+   expansions:  Vec<QueryExpansion>,
+   /// Engine filter stages, extended with paging and the CLI-side filters.
+   funnel:      Vec<FilterStage>,
+}
+
+/// Which path served a search, to tell warm daemon latency from cold
+/// in-process runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SearchExecution {
+   Daemon,
+   InProcess,
+}
+
+impl SearchExecution {
+   const fn label(self) -> &'static str {
+      match self {
+         Self::Daemon => "daemon",
+         Self::InProcess => "in_process",
+      }
+   }
+}
+
+/// Serialized daemon request/response payload sizes against their limits.
+#[derive(Debug, Clone, Copy, Serialize)]
+struct IpcSizes {
+   request_bytes:      u64,
+   response_bytes:     u64,
+   max_request_bytes:  u64,
+   max_response_bytes: u64,
+}
+
+const CURSOR_VERSION: u32 = 1;
+
+/// Contents of the opaque `next_cursor` token (hex-encoded JSON).
+#[derive(Debug, Serialize, Deserialize)]
+struct SearchCursor {
+   v:                 u32,
+   snapshot_id:       String,
+   offset:            usize,
+   page_size:         usize,
+   query_fingerprint: String,
+}
+
+impl SearchCursor {
+   fn encode(&self) -> String {
+      hex::encode(serde_json::to_vec(self).unwrap_or_default())
+   }
+
+   fn decode(token: &str) -> Result<Self> {
+      let invalid = || Error::Server {
+         op:     "search",
+         reason: "invalid_request: malformed cursor".to_string(),
+      };
+      let bytes = hex::decode(token.trim()).map_err(|_| invalid())?;
+      let cursor: Self = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+      if cursor.v != CURSOR_VERSION || cursor.page_size == 0 {
+         return Err(invalid());
+      }
+      Ok(cursor)
+   }
+}
+
+/// One page of a `--page-size`/`--cursor` search.
+///
+/// The page size and snapshot come from the cursor once paging has started,
+/// so later pages line up with the first even if config limits change. A
+/// cursor is rejected once a newer snapshot is published, since its offsets
+/// index a ranking that no longer exists.
+#[derive(Debug)]
+struct SearchPage {
+   size:        usize,
+   offset:      usize,
+   snapshot_id: Option<String>,
+   fingerprint: Option<String>,
+}
+
+impl SearchPage {
+   fn first(size: usize) -> Self {
+      Self { size: size.max(1), offset: 0, snapshot_id: None, fingerprint: None }
+   }
+
+   fn resume(token: &str) -> Result<Self> {
+      let cursor = SearchCursor::decode(token)?;
+      Ok(Self {
+         size:        cursor.page_size,
+         offset:      cursor.offset,
+         snapshot_id: Some(cursor.snapshot_id),
+         fingerprint: Some(cursor.query_fingerprint),
+      })
+   }
+
+   fn check_fingerprint(&self, query_fingerprint: &str) -> Result<()> {
+      match &self.fingerprint {
+         Some(expected) if expected != query_fingerprint => Err(Error::Server {
+            op:     "search",
+            reason: "invalid_request: cursor was issued for a different query or options"
+               .to_string(),
+         }),
+         _ => Ok(()),
+      }
+   }
+
+   fn check_snapshot(&self, active_snapshot_id: Option<&str>) -> Result<()> {
+      match (self.snapshot_id.as_deref(), active_snapshot_id) {
+         (Some(cursor), Some(active)) if cursor != active => Err(Error::Server {
+            op:     "search",
+            reason: format!(
+               "invalid_request: cursor was issued for snapshot {cursor}, but the index is now at \
+                {active}; start again without --cursor"
+            ),
+         }),
+         _ => Ok(()),
+      }
+   }
+
+   /// Results to request: everything up to the end of this page plus one, to
+   /// tell whether another page follows.
+   fn fetch_limit(&self) -> usize {
+      self.offset + self.size + 1
+   }
+
+   /// Narrows `outcome` to this page and returns the cursor for the next one,
+   /// or `None` once the results are exhausted.
+   ///
+   /// Searches return at most `max_results`, so a fetch capped below
+   /// [`Self::fetch_limit`] loses the probe row; a capped fetch that came back
+   /// full still counts as having more. The page after it starts past the cap
+   /// and comes back empty with an `offset_exceeds_max_results` limit hit.
+   fn take(
+      &self,
+      outcome: &mut SearchOutcome,
+      query_fingerprint: &str,
+      max_results: usize,
+   ) -> Option<String> {
+      let end = self.offset + self.size;
+      let fetched = outcome.results.len();
+      let capped = self.fetch_limit() > max_results;
+      let more = fetched > end || (capped && fetched >= max_results && self.offset < max_results);
+      if self.offset > 0 && end > max_results {
+         outcome.limits_hit.push(SearchLimitHit {
+            code:     "offset_exceeds_max_results".to_string(),
+            limit:    max_results as u64,
+            observed: Some(end as u64),
+            path_key: None,
+         });
+      }
+      outcome.results.truncate(end);
+      let start = self.offset.min(outcome.results.len());
+      outcome.results.drain(..start);
+      FilterStage::push(
+         &mut outcome.funnel,
+         "page",
+         Some(format!("offset {}, size {}", self.offset, self.size)),
+         outcome.results.len(),
+      );
+      if !more {
+         return None;
+      }
+
+      let snapshot_id = outcome
+         .snapshot_id
+         .clone()
+         .or_else(|| self.snapshot_id.clone())?;
+      let cursor = SearchCursor {
+         v: CURSOR_VERSION,
+         snapshot_id,
+         offset: end,
+         page_size: self.size,
+         query_fingerprint: query_fingerprint.to_string(),
+      };
+      Some(cursor.encode())
+   }
 }
 
 #[derive(Debug, Serialize)]
@@ -91,22 +328,106 @@ pub(crate) struct SearchErrorPayload {
 }
 
 /// Command-line options for search behavior.
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 pub struct SearchOptions {
    pub content:       bool,
    pub no_snippet:    bool,
    pub short_snippet: bool,
    pub long_snippet:  bool,
    pub compact:       bool,
+   /// Prints per-file result counts instead of snippets (`--count`).
+   pub count:         bool,
    pub scores:        bool,
    pub sync:          bool,
    pub dry_run:       bool,
    pub json:          bool,
+   pub json_incremental: bool,
+   /// Emits the meta line, then one line per result (`--ndjson`).
+   pub ndjson:        bool,
    pub explain:       bool,
+   /// Adds the SQL filter predicates to explain output (`--explain-sql`).
+   pub explain_sql:   bool,
+   /// Prints a one-line timing summary after the results (`--timing`).
+   pub timing:        bool,
    pub no_rerank:     bool,
    pub allow_degraded: bool,
    pub plain:         bool,
    pub mode:          SearchMode,
+   /// Flag that selected `mode` (e.g. `-d`); `None` when it is the default.
+   pub mode_flag:     Option<&'static str>,
+   pub format:        SearchFormat,
+   pub max_bytes:     Option<usize>,
+   pub blame:         bool,
+   pub recent_boost_days: Option<u32>,
+   /// Results per page; enables cursor paging.
+   pub page_size:     Option<usize>,
+   /// Ranked results to skip before the first one returned (`--offset`).
+   pub offset:        usize,
+   /// Keeps overlapping chunks of the same file as separate results.
+   pub no_context_dedup: bool,
+   /// Also merges abutting chunks of the same file (implied by `content`).
+   pub merge_adjacent: bool,
+   /// Keeps only files of these languages (`--lang`); empty means any.
+   pub languages:     Vec<String>,
+   /// Drops results whose path matches one of these globs (`--exclude`).
+   pub excludes:      Vec<String>,
+   /// Drops results repeating a higher-ranked result's content.
+   pub dedup_content: bool,
+   /// Skips full-text retrieval for pure vector search (`--no-fts`).
+   pub no_fts:        bool,
+   /// Lines of leading context kept from the previous chunk (`-B`); `None`
+   /// keeps all stored context.
+   pub before_context: Option<usize>,
+   /// Lines of trailing context kept from the next chunk (`-A`).
+   pub after_context: Option<usize>,
+   /// Keeps only results whose own lines match this regex (`--grep`).
+   pub grep:          Option<String>,
+   /// Drops results scoring below this after rerank (`--min-score`).
+   pub min_score:     Option<f32>,
+   /// Underlines query terms in colored snippets (`--highlight`).
+   pub highlight:     bool,
+}
+
+impl SearchOptions {
+   /// How the engine should combine overlapping chunks of the same file.
+   const fn span_merge(&self) -> SpanMerge {
+      if self.no_context_dedup {
+         SpanMerge::Off
+      } else if self.merge_adjacent || self.content {
+         SpanMerge::Adjacent
+      } else {
+         SpanMerge::Overlapping
+      }
+   }
+
+   /// The per-query settings sent to the engine or daemon.
+   fn query_options(&self) -> QueryOptions {
+      QueryOptions {
+         recent_boost_days: self.recent_boost_days,
+         span_merge:        self.span_merge(),
+         languages:         self.languages.clone(),
+         offset:            self.offset,
+         excludes:          self.excludes.clone(),
+         dedup_content:     self.dedup_content,
+         no_fts:            self.no_fts,
+         index_stats:       self.explain,
+         grep:              self.grep.clone(),
+      }
+   }
+
+   /// Whether results are emitted as machine-readable JSON or NDJSON.
+   const fn json_output(&self) -> bool {
+      self.json || self.ndjson
+   }
+}
+
+/// Human-facing output format for search results (ignored with `--json`).
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SearchFormat {
+   #[default]
+   Text,
+   /// GitHub Actions workflow commands (`::notice file=...,line=...::...`).
+   Github,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -128,8 +449,11 @@ impl Default for SnippetMode {
 #[derive(Default, Debug, Clone, Copy)]
 struct FormatOptions {
    compact:      bool,
+   count:        bool,
+   github:       bool,
    scores:       bool,
    plain:        bool,
+   highlight:    bool,
    snippet_mode: SnippetMode,
    mode:         SearchMode,
 }
@@ -144,9 +468,22 @@ pub(crate) struct SearchMeta {
    query_fingerprint: String,
    embed_config_fingerprint: String,
    snapshot_id: Option<String>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   snapshot_created_at: Option<String>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   snapshot_age_secs: Option<u64>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   files_indexed: Option<u64>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   chunks_indexed: Option<u64>,
    degraded: bool,
    git: Option<GitExplain>,
    mode: SearchMode,
+   /// Whether the daemon or an in-process engine produced the results.
+   execution: SearchExecution,
+   /// Order of `results`; always `rank` (final score descending, ties broken
+   /// deterministically). Paging and output budgets only slice this order.
+   sorted_by: &'static str,
    limits: ExplainLimits,
    #[serde(skip_serializing_if = "Vec::is_empty")]
    limits_hit: Vec<SearchLimitHit>,
@@ -154,22 +491,146 @@ pub(crate) struct SearchMeta {
    warnings: Vec<SearchWarning>,
    #[serde(skip_serializing_if = "Option::is_none")]
    timings_ms: Option<JsonTimings>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   embedder: Option<EmbedderStatus>,
 }
 
 #[derive(Debug, Serialize, Clone)]
 pub(crate) struct JsonTimings {
    admission:     u64,
    snapshot_read: u64,
+   embed:         u64,
    retrieve:      u64,
+   rerank:        u64,
    rank:          u64,
    format:        u64,
+   /// Wall-clock time of the whole search, set with `--timing`.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   total:         Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
 pub(crate) struct SearchExplain {
    #[serde(flatten)]
-   meta:          SearchMeta,
-   candidate_mix: CandidateMix,
+   meta:              SearchMeta,
+   candidate_mix:     CandidateMix,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   candidates:        Option<CandidateCounts>,
+   #[serde(skip_serializing_if = "Vec::is_empty")]
+   segments:          Vec<SegmentContribution>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   tombstones:        Option<TombstoneVisibility>,
+   reranked:          bool,
+   rerank_candidates: u64,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   rerank_skipped:    Option<&'static str>,
+   /// ColBERT scores of the reranked candidates; dense score ranges are under
+   /// `candidates`.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   rerank_scores:     Option<ScoreRange>,
+   ranking_weights:   RankingWeights,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   ipc:               Option<IpcSizes>,
+   /// Anchor-only chunking in effect: the snapshot's recorded mode, or config
+   /// `fast_mode` for snapshots that predate it.
+   fast_mode:         bool,
+   /// The live config/ignore fingerprints differ from the ones the searched
+   /// snapshot was built with, so results may reflect stale settings.
+   fingerprint_drift: bool,
+   /// On-disk size of the store directory, all snapshots included.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   store_size_bytes:  Option<u64>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   coverage:          Option<IndexCoverage>,
+   /// Predicates applied to each retrieval source (`--explain-sql`).
+   #[serde(skip_serializing_if = "Option::is_none")]
+   sql_filters:       Option<SearchFilters>,
+   /// Candidates left after each filter stage, from retrieval to the final
+   /// results.
+   #[serde(skip_serializing_if = "Vec::is_empty")]
+   funnel:            Vec<FilterStage>,
+   parallelism:       Parallelism,
+   provenance:        Provenance,
+}
+
+/// Files indexed vs. indexable files found by a bounded walk of the root.
+#[derive(Debug, Serialize)]
+struct IndexCoverage {
+   files_indexed:   u64,
+   candidate_files: u64,
+   /// The walk hit its entry budget, so `candidate_files` is a lower bound.
+   truncated:       bool,
+   /// Fewer than half of the candidates are indexed: the last sync did not
+   /// finish, or ignore rules changed since it ran.
+   under_indexed:   bool,
+}
+
+impl IndexCoverage {
+   fn sample(root: &Path, files_indexed: u64) -> Self {
+      let sample = sample_candidate_files(root, COVERAGE_WALK_MAX_ENTRIES);
+      let gap = sample.candidates.saturating_sub(files_indexed);
+      Self {
+         files_indexed,
+         candidate_files: sample.candidates,
+         truncated: sample.truncated,
+         under_indexed: gap >= COVERAGE_MIN_GAP && files_indexed * 2 < sample.candidates,
+      }
+   }
+}
+
+/// Resolved thread and concurrency settings, to put `retrieve_ms` in context.
+#[derive(Debug, Serialize)]
+struct Parallelism {
+   embed_threads:       usize,
+   segments:            usize,
+   segment_concurrency: usize,
+}
+
+/// Tombstones in the searched snapshot and the candidates they hid, to tell
+/// "not indexed" from "indexed but hidden".
+#[derive(Debug, Serialize)]
+struct TombstoneVisibility {
+   /// Distinct tombstoned paths across the snapshot chain.
+   total:      usize,
+   /// Ranked candidates dropped by `SnapshotView::is_visible`.
+   suppressed: u64,
+}
+
+/// Concrete model and format versions behind the results, so a bug report
+/// carries what `embed_config_fingerprint` only hashes.
+#[derive(Debug, Serialize)]
+struct Provenance {
+   ggrep_version:            String,
+   dense_model:              String,
+   colbert_model:            String,
+   chunker_version:          &'static str,
+   index_version:            &'static str,
+   /// Schema versions recorded in the searched snapshot's manifest; this
+   /// build's when no manifest was read.
+   manifest_schema_version:  u32,
+   chunk_row_schema_version: u32,
+}
+
+/// Candidates retrieved from a segment vs. results it supplied after ranking.
+#[derive(Debug, Serialize)]
+struct SegmentContribution {
+   table:          String,
+   candidates:     u64,
+   results:        usize,
+   /// Row count recorded in the snapshot manifest.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   rows:           Option<u64>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   vector_indexed: Option<bool>,
+}
+
+impl SegmentContribution {
+   /// Large enough for an IVF-PQ index but searched without one, so dense
+   /// retrieval brute-force scanned it.
+   fn missing_vector_index(&self) -> bool {
+      self.vector_indexed == Some(false)
+         && self.rows.is_some_and(|rows| rows >= VECTOR_INDEX_MIN_ROWS)
+   }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -185,6 +646,8 @@ struct ExplainLimits {
    per_file: usize,
    snippet: String,
    max_candidates: usize,
+   candidate_multiplier: usize,
+   store_limit: usize,
    max_total_snippet_bytes: usize,
    max_snippet_bytes_per_result: usize,
    max_open_segments_per_query: usize,
@@ -192,11 +655,14 @@ struct ExplainLimits {
 
 #[derive(Debug, Serialize)]
 pub(crate) struct CandidateMix {
-   total:   usize,
-   code:    usize,
-   docs:    usize,
-   graph:   usize,
-   anchors: usize,
+   total:      usize,
+   code:       usize,
+   docs:       usize,
+   graph:      usize,
+   anchors:    usize,
+   /// Synonyms that fired from `synonyms.toml`.
+   #[serde(skip_serializing_if = "Vec::is_empty")]
+   expansions: Vec<QueryExpansion>,
 }
 
 /// Executes a semantic code search.
@@ -206,15 +672,28 @@ pub async fn execute(
    max: usize,
    per_file: usize,
    options: SearchOptions,
+   cursor: Option<String>,
    eval_store: bool,
    store_id: Option<String>,
 ) -> Result<()> {
    let request_id = uuid::Uuid::new_v4().to_string();
-   match execute_inner(query, path, max, per_file, options, eval_store, store_id, &request_id).await
+   let json = options.json_output();
+   match execute_inner(
+      query,
+      path,
+      max,
+      per_file,
+      options,
+      cursor,
+      eval_store,
+      store_id,
+      &request_id,
+   )
+   .await
    {
       Ok(()) => Ok(()),
       Err(err) => {
-         if options.json {
+         if json {
             emit_json_error(&err, &request_id)?;
             return Err(Error::Reported {
                message:   "json error emitted".to_string(),
@@ -226,16 +705,96 @@ pub async fn execute(
    }
 }
 
-async fn execute_inner(
-   query: String,
-   path: Option<PathBuf>,
+/// The last search that produced results, replayed by `search --repeat`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LastSearch {
+   pub query:    String,
+   /// Absolute directory (or file) the search was scoped to.
+   pub path:     PathBuf,
+   pub mode:     SearchMode,
+   pub max:      usize,
+   pub per_file: usize,
+}
+
+impl LastSearch {
+   fn file_path() -> PathBuf {
+      config::base_dir().join("last_search.json")
+   }
+
+   /// Loads the recorded search, failing when nothing has been searched yet.
+   pub fn load() -> Result<Self> {
+      let path = Self::file_path();
+      let raw = std::fs::read_to_string(&path).map_err(|_| Error::Server {
+         op:     "search",
+         reason: format!("invalid_request: no previous search recorded at {}", path.display()),
+      })?;
+      Ok(serde_json::from_str(&raw)?)
+   }
+
+   /// Replaces recorded values with the ones given explicitly on the
+   /// `--repeat` command line.
+   pub fn with_overrides(
+      mut self,
+      path: Option<PathBuf>,
+      max: Option<usize>,
+      per_file: Option<usize>,
+      mode: Option<SearchMode>,
+   ) -> Self {
+      self.path = path.unwrap_or(self.path);
+      self.max = max.unwrap_or(self.max);
+      self.per_file = per_file.unwrap_or(self.per_file);
+      self.mode = mode.unwrap_or(self.mode);
+      self
+   }
+
+   fn save(&self) -> Result<()> {
+      let path = Self::file_path();
+      if let Some(parent) = path.parent() {
+         std::fs::create_dir_all(parent)?;
+      }
+      // Concurrent searches each write their own temp file; the rename makes
+      // the last one win without readers ever seeing a partial file.
+      let tmp_path = path.with_extension(format!("json.{}.tmp", std::process::id()));
+      std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+      std::fs::rename(&tmp_path, &path)?;
+      Ok(())
+   }
+}
+
+/// Records the search for `--repeat`; a failed write never fails the search.
+fn record_last_search(
+   query: &str,
+   index_root: &Path,
+   scope: Option<&Path>,
+   mode: SearchMode,
    max: usize,
    per_file: usize,
-   options: SearchOptions,
+) {
+   let last = LastSearch {
+      query: query.to_string(),
+      path: scope.map_or_else(|| index_root.to_path_buf(), |scope| index_root.join(scope)),
+      mode,
+      max,
+      per_file,
+   };
+   if let Err(e) = last.save() {
+      tracing::debug!("failed to record last search: {}", e);
+   }
+}
+
+/// Store and scope a search invocation resolves to.
+pub(crate) struct SearchTarget {
+   pub(crate) index_identity: identity::IndexIdentity,
+   pub(crate) index_root:     PathBuf,
+   pub(crate) store_id:       String,
+   pub(crate) scope:          Option<PathBuf>,
+}
+
+pub(crate) fn resolve_target(
+   path: Option<PathBuf>,
    eval_store: bool,
    store_id: Option<String>,
-   request_id: &str,
-) -> Result<()> {
+) -> Result<SearchTarget> {
    let cwd = std::env::current_dir()?.canonicalize()?;
    // Default to searching "here" (current directory) while still using the
    // repo-root store when in a git repo.
@@ -261,10 +820,6 @@ async fn execute_inner(
       },
    };
 
-   let cfg = config::get();
-   let capped_max = max.min(cfg.max_query_results).max(1);
-   let capped_per_file = per_file.min(cfg.max_query_per_file).max(1);
-
    let scope_rel = if filter_path != index_root {
       let rel = filter_path
          .strip_prefix(&index_root)
@@ -276,16 +831,252 @@ async fn execute_inner(
       None
    };
 
+   Ok(SearchTarget { index_identity, index_root, store_id: resolved_store_id, scope: scope_rel })
+}
+
+/// One NDJSON line of `--input-file` output.
+#[derive(Serialize)]
+struct BatchLine<'a> {
+   query:   &'a str,
+   #[serde(flatten)]
+   payload: BatchPayload,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BatchPayload {
+   Output(SearchJsonOutput),
+   Error(SearchErrorJson),
+}
+
+/// Runs every query in `input_file` and prints one NDJSON line per query.
+///
+/// Queries are read one per line; blank lines and `#` comments are skipped.
+/// The daemon connection is opened once and reused for the whole batch. If
+/// no daemon is reachable (or the connection drops), the remaining queries
+/// run in-process. A failing query emits an error line and the batch
+/// continues; the command fails at the end if any query failed.
+pub async fn execute_batch(
+   input_file: PathBuf,
+   path: Option<PathBuf>,
+   max: usize,
+   per_file: usize,
+   options: SearchOptions,
+   eval_store: bool,
+   store_id: Option<String>,
+) -> Result<()> {
+   let raw = std::fs::read_to_string(&input_file)?;
+   let queries: Vec<&str> = raw
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty() && !line.starts_with('#'))
+      .collect();
+
+   search::resolve_languages(&options.languages, &grammar::declared_custom_grammars())?;
+   search::exclude_matcher(&options.excludes)?;
+   search::grep_matcher(options.grep.as_deref())?;
+   let SearchTarget { index_identity, index_root, store_id: resolved_store_id, scope: scope_rel } =
+      resolve_target(path, eval_store, store_id)?;
+   let request_path = scope_rel.as_deref();
+
+   let cfg = config::get();
+   let capped_max = max.min(cfg.max_query_results).max(1);
+   let capped_per_file = per_file.min(cfg.max_query_per_file).max(1);
+   let snippet_mode = resolve_snippet_mode(&options);
+   let rerank = !options.no_rerank;
+   let query_options = options.query_options();
+
+   let mut client = daemon::DaemonClient::connect(&index_root, &resolved_store_id)
+      .await
+      .ok();
+   let mut failed = 0usize;
+
+   for query in queries {
+      let request_id = uuid::Uuid::new_v4().to_string();
+
+      let daemon_outcome = match client.as_mut() {
+         Some(conn) => Some(
+            send_search_request(
+               conn,
+               query,
+               capped_max,
+               capped_per_file,
+               options.mode,
+               rerank,
+               None,
+               &query_options,
+               request_path,
+               &index_root,
+            )
+            .await,
+         ),
+         None => None,
+      };
+      let outcome = match daemon_outcome {
+         Some(Ok(outcome)) => Ok(outcome),
+         Some(Err(e)) if !daemon_unavailable(&e) => {
+            // The daemon answered (busy, timeout, invalid request, ...); report
+            // it for this query and start the next one on a fresh connection.
+            client = daemon::DaemonClient::connect(&index_root, &resolved_store_id)
+               .await
+               .ok();
+            Err(e)
+         },
+         daemon_outcome => {
+            if let Some(Err(e)) = daemon_outcome {
+               tracing::debug!("daemon unavailable; continuing batch in-process: {}", e);
+               client = None;
+            }
+            perform_search(
+               query,
+               &index_root,
+               request_path,
+               &resolved_store_id,
+               capped_max,
+               capped_per_file,
+               rerank,
+               None,
+               &query_options,
+               options.mode,
+               options.allow_degraded,
+            )
+            .await
+         },
+      };
+
+      let output = outcome.and_then(|mut outcome| {
+         apply_context_lines(&mut outcome.results, options.before_context, options.after_context);
+         apply_min_score(&mut outcome, options.min_score);
+         if options.blame {
+            apply_blame(&mut outcome.results, &index_root, &resolved_store_id);
+         }
+         let meta = build_meta(
+            query,
+            &index_identity,
+            &resolved_store_id,
+            request_path,
+            snippet_mode,
+            capped_max,
+            capped_per_file,
+            rerank,
+            options.mode,
+            &query_options,
+            options.min_score,
+            &request_id,
+            &outcome,
+         )?;
+         let explain = options.explain.then(|| build_explain(&meta, &outcome));
+         Ok(build_json_output(meta, outcome, explain))
+      });
+
+      let payload = match output {
+         Ok(mut output) => {
+            if let Some(max_bytes) = options.max_bytes {
+               // The line is the output object with a leading `"query":…,` key
+               // and a trailing newline.
+               let query_key = r#""query":,"#.len() + serde_json::to_string(query)?.len();
+               apply_output_budget(&mut output, max_bytes, |output| {
+                  Ok(serde_json::to_vec(output)?.len() + query_key + 1)
+               })?;
+            }
+            BatchPayload::Output(output)
+         },
+         Err(err) => {
+            failed += 1;
+            BatchPayload::Error(build_json_error(&err, &request_id))
+         },
+      };
+      println!("{}", serde_json::to_string(&BatchLine { query, payload })?);
+   }
+
+   if failed > 0 {
+      return Err(Error::Reported {
+         message:   format!("{failed} batch queries failed"),
+         exit_code: 1,
+      });
+   }
+   Ok(())
+}
+
+async fn execute_inner(
+   query: String,
+   path: Option<PathBuf>,
+   max: usize,
+   per_file: usize,
+   options: SearchOptions,
+   cursor: Option<String>,
+   eval_store: bool,
+   store_id: Option<String>,
+   request_id: &str,
+) -> Result<()> {
+   let started = Instant::now();
+   // Annotations are parsed by the runner; never mix in ANSI escapes.
+   let options =
+      SearchOptions { plain: options.plain || options.format == SearchFormat::Github, ..options };
+   search::resolve_languages(&options.languages, &grammar::declared_custom_grammars())?;
+   search::exclude_matcher(&options.excludes)?;
+   search::grep_matcher(options.grep.as_deref())?;
+   let SearchTarget { index_identity, index_root, store_id: resolved_store_id, scope: scope_rel } =
+      resolve_target(path, eval_store, store_id)?;
+
+   let cfg = config::get();
+   let capped_max = max.min(cfg.max_query_results).max(1);
+   let capped_per_file = per_file.min(cfg.max_query_per_file).max(1);
+
+   let page = match cursor.as_deref() {
+      Some(token) => Some(SearchPage::resume(token)?),
+      None => options
+         .page_size
+         .map(|size| SearchPage::first(size.min(cfg.max_query_results))),
+   };
+   // Paged queries fingerprint on the page size so a cursor only continues
+   // the query that produced it.
+   let capped_max = page.as_ref().map_or(capped_max, |page| page.size);
+   let fetch_max = page.as_ref().map_or(capped_max, SearchPage::fetch_limit);
+   let query_options = options.query_options();
+   let page_fingerprint = match &page {
+      Some(page) => {
+         let fingerprint = compute_query_fingerprint(
+            &query,
+            options.mode,
+            capped_per_file,
+            capped_max,
+            !options.no_rerank,
+            scope_rel.as_deref(),
+            resolve_snippet_mode(&options),
+            &query_options,
+            options.min_score,
+         )?;
+         page.check_fingerprint(&fingerprint)?;
+         Some(fingerprint)
+      },
+      None => None,
+   };
+   if let Some(page) = &page {
+      let meta_store = MetaStore::load(&resolved_store_id).ok();
+      page.check_snapshot(meta_store.as_ref().and_then(MetaStore::snapshot_id))?;
+   }
+   let snapshot_id = page.as_ref().and_then(|page| page.snapshot_id.as_deref());
+
    if options.dry_run {
-      if options.json {
-         let snippet_mode = resolve_snippet_mode(options);
+      if options.json_output() {
+         let snippet_mode = resolve_snippet_mode(&options);
          let outcome = SearchOutcome {
-            results:    vec![],
-            status:     SearchStatus::Ready,
-            progress:   None,
-            timings_ms: None,
-            limits_hit: vec![],
-            warnings:   vec![],
+            results:     vec![],
+            status:      SearchStatus::Ready,
+            progress:    None,
+            timings_ms:  None,
+            limits_hit:  vec![],
+            warnings:    vec![],
+            embedder:    None,
+            candidates:  None,
+            segments:    vec![],
+            rerank:      None,
+            ipc:         None,
+            snapshot_id: None,
+            execution:   SearchExecution::InProcess,
+            expansions:  vec![],
+            funnel:      vec![],
          };
          let meta = build_meta(
             &query,
@@ -297,6 +1088,8 @@ async fn execute_inner(
             capped_per_file,
             !options.no_rerank,
             options.mode,
+            &query_options,
+            options.min_score,
             &request_id,
             &outcome,
          )?;
@@ -305,10 +1098,12 @@ async fn execute_inner(
          } else {
             None
          };
-         println!(
-            "{}",
-            serde_json::to_string(&SearchJsonOutput { meta, results: vec![], explain })?
-         );
+         let next_cursor = page.as_ref().map(|_| None);
+         print_json_output(
+            SearchJsonOutput { meta, results: vec![], counts: None, explain, next_cursor },
+            &options,
+            started,
+         )?;
       } else {
          println!("Dry run: would search for '{query}' in {}", index_root.display());
          if let Some(scope) = &scope_rel {
@@ -322,20 +1117,32 @@ async fn execute_inner(
 
    let request_path = scope_rel.as_deref();
 
-   if let Some(outcome) = try_daemon_search(
+   if let Some(mut outcome) = try_daemon_search(
       &query,
-      capped_max,
+      fetch_max,
       capped_per_file,
       options.mode,
       !options.no_rerank,
+      snapshot_id,
+      &query_options,
       &index_root,
       request_path,
       &resolved_store_id,
    )
    .await?
    {
-      let snippet_mode = resolve_snippet_mode(options);
-      let meta = if options.json || options.explain {
+      record_last_search(&query, &index_root, request_path, options.mode, max, per_file);
+      let next_cursor = page
+         .as_ref()
+         .zip(page_fingerprint.as_deref())
+         .map(|(page, fingerprint)| page.take(&mut outcome, fingerprint, cfg.max_query_results));
+      apply_context_lines(&mut outcome.results, options.before_context, options.after_context);
+      apply_min_score(&mut outcome, options.min_score);
+      if options.blame {
+         apply_blame(&mut outcome.results, &index_root, &resolved_store_id);
+      }
+      let snippet_mode = resolve_snippet_mode(&options);
+      let meta = if options.json_output() || options.explain {
          Some(build_meta(
             &query,
             &index_identity,
@@ -346,6 +1153,8 @@ async fn execute_inner(
             capped_per_file,
             !options.no_rerank,
             options.mode,
+            &query_options,
+            options.min_score,
             &request_id,
             &outcome,
          )?)
@@ -353,53 +1162,40 @@ async fn execute_inner(
          None
       };
       let explain = if options.explain {
-         meta.as_ref().map(|meta| build_explain(meta, &outcome))
+         meta.as_ref().map(|meta| {
+            let mut explain = build_explain(meta, &outcome);
+            if options.explain_sql {
+               explain.sql_filters = Some(sql_filters(
+                  request_path,
+                  &options.languages,
+                  explain.fast_mode,
+                  options.mode,
+                  &index_root,
+               ));
+            }
+            explain
+         })
       } else {
          None
       };
 
-      if options.json {
+      if options.json_output() {
          let meta = meta.expect("meta required for json output");
-         println!(
-            "{}",
-            serde_json::to_string(&SearchJsonOutput { meta, results: outcome.results, explain })?
-         );
+         print_json_output(
+            SearchJsonOutput { meta, results: outcome.results, counts: None, explain, next_cursor },
+            &options,
+            started,
+         )?;
       } else {
-         let format_opts = FormatOptions {
-            compact: options.compact,
-            scores: options.scores,
-            plain: options.plain,
-            snippet_mode,
-            mode: options.mode,
-         };
-         if outcome.results.is_empty() {
-            format_empty_results(
-               &query,
-               &index_root,
-               request_path,
-               outcome.status,
-               outcome.progress,
-               format_opts,
-            );
-         } else {
-            format_results(
-               &outcome.results,
-               &query,
-               &index_root,
-               request_path,
-               format_opts,
-               outcome.status,
-               outcome.progress,
-            );
-         }
-         if let Some(explain) = explain {
-            print_explain(&explain, options.plain);
-         }
+         let format_start = Instant::now();
+         print_outcome(&outcome, &query, &index_root, request_path, &options);
+         print_next_cursor(next_cursor.flatten().as_deref(), &options);
+         print_text_trailer(&mut outcome, explain, format_start, started, &options);
       }
       return Ok(());
    }
 
-   if options.sync && !options.json {
+   if options.sync && !options.json_output() {
       let spinner = ProgressBar::new_spinner();
       spinner.set_style(
          ProgressStyle::default_spinner()
@@ -414,21 +1210,33 @@ async fn execute_inner(
       spinner.finish_with_message("Sync complete");
    }
 
-   let outcome = perform_search(
+   let mut outcome = perform_search(
       &query,
       &index_root,
       request_path,
       &resolved_store_id,
-      capped_max,
+      fetch_max,
       capped_per_file,
       !options.no_rerank,
+      snapshot_id,
+      &query_options,
       options.mode,
       options.allow_degraded,
    )
    .await?;
+   record_last_search(&query, &index_root, request_path, options.mode, max, per_file);
+   let next_cursor = page
+      .as_ref()
+      .zip(page_fingerprint.as_deref())
+      .map(|(page, fingerprint)| page.take(&mut outcome, fingerprint, cfg.max_query_results));
+   apply_context_lines(&mut outcome.results, options.before_context, options.after_context);
+   apply_min_score(&mut outcome, options.min_score);
+   if options.blame {
+      apply_blame(&mut outcome.results, &index_root, &resolved_store_id);
+   }
 
-   let snippet_mode = resolve_snippet_mode(options);
-   let meta = if options.json || options.explain {
+   let snippet_mode = resolve_snippet_mode(&options);
+   let meta = if options.json_output() || options.explain {
       Some(build_meta(
          &query,
          &index_identity,
@@ -439,6 +1247,8 @@ async fn execute_inner(
          capped_per_file,
          !options.no_rerank,
          options.mode,
+         &query_options,
+         options.min_score,
          &request_id,
          &outcome,
       )?)
@@ -446,41 +1256,59 @@ async fn execute_inner(
       None
    };
    let explain = if options.explain {
-      meta.as_ref().map(|meta| build_explain(meta, &outcome))
+      meta.as_ref().map(|meta| {
+         let mut explain = build_explain(meta, &outcome);
+         if options.explain_sql {
+            explain.sql_filters = Some(sql_filters(
+               request_path,
+               &options.languages,
+               explain.fast_mode,
+               options.mode,
+               &index_root,
+            ));
+         }
+         explain
+      })
    } else {
       None
    };
+   let format_start = Instant::now();
 
    if outcome.results.is_empty() {
-      if options.json {
+      if options.json_output() {
          let meta = meta.expect("meta required for json output");
-         println!(
-            "{}",
-            serde_json::to_string(&SearchJsonOutput { meta, results: vec![], explain })?
-         );
+         print_json_output(
+            SearchJsonOutput { meta, results: vec![], counts: None, explain, next_cursor },
+            &options,
+            started,
+         )?;
       } else {
-         println!("No results found for '{query}'");
-         if !options.sync {
-            println!("\nTip: Use --sync to re-index before searching");
-         }
-         if let Some(explain) = explain {
-            print_explain(&explain, options.plain);
+         if options.format == SearchFormat::Text {
+            println!("No results found for '{query}'");
+            if !options.sync {
+               println!("\nTip: Use --sync to re-index before searching");
+            }
          }
+         print_text_trailer(&mut outcome, explain, format_start, started, &options);
       }
       return Ok(());
    }
 
-   if options.json {
+   if options.json_output() {
       let meta = meta.expect("meta required for json output");
-      println!(
-         "{}",
-         serde_json::to_string(&SearchJsonOutput { meta, results: outcome.results, explain })?
-      );
+      print_json_output(
+         SearchJsonOutput { meta, results: outcome.results, counts: None, explain, next_cursor },
+         &options,
+         started,
+      )?;
    } else {
       let format_opts = FormatOptions {
          compact: options.compact,
+         count: options.count,
+         github: options.format == SearchFormat::Github,
          scores: options.scores,
          plain: options.plain,
+         highlight: options.highlight,
          snippet_mode,
          mode: options.mode,
       };
@@ -493,14 +1321,119 @@ async fn execute_inner(
          outcome.status,
          outcome.progress,
       );
-      if let Some(explain) = explain {
-         print_explain(&explain, options.plain);
-      }
+      print_next_cursor(next_cursor.flatten().as_deref(), &options);
+      print_text_trailer(&mut outcome, explain, format_start, started, &options);
    }
 
    Ok(())
 }
 
+/// Folds the time spent printing results into the timings, then prints the
+/// `--timing` summary and the explain block. `started` marks the start of the
+/// whole search, for the wall-clock total.
+fn print_text_trailer(
+   outcome: &mut SearchOutcome,
+   mut explain: Option<SearchExplain>,
+   format_start: Instant,
+   started: Instant,
+   options: &SearchOptions,
+) {
+   let format_ms = format_start.elapsed().as_millis() as u64;
+   if let Some(timings) = outcome.timings_ms.as_mut() {
+      timings.format_ms = format_ms;
+   }
+   if let Some(timings) = explain
+      .as_mut()
+      .and_then(|explain| explain.meta.timings_ms.as_mut())
+   {
+      timings.format = format_ms;
+   }
+
+   if options.timing {
+      let total_ms = started.elapsed().as_millis() as u64;
+      print_timing(outcome.timings_ms.as_ref(), total_ms, options.plain);
+   }
+   if let Some(explain) = explain {
+      print_explain(&explain, options.plain, options.mode_flag);
+   }
+}
+
+/// Prints the `--timing` summary, one `stage=Nms` pair per stage plus the
+/// wall-clock total. Stages overlap or leave gaps (argument parsing, output),
+/// so the total is not their sum.
+fn print_timing(timings: Option<&SearchTimings>, total_ms: u64, plain: bool) {
+   let line = match timings {
+      Some(t) => format!(
+         "timings: admission={}ms snapshot_read={}ms embed={}ms retrieve={}ms rerank={}ms \
+          rank={}ms format={}ms total={}ms",
+         t.admission_ms,
+         t.snapshot_read_ms,
+         t.embed_ms,
+         t.retrieve_ms,
+         t.rerank_ms,
+         t.rank_ms,
+         t.format_ms,
+         total_ms
+      ),
+      None => format!("timings: unavailable total={total_ms}ms"),
+   };
+   if plain {
+      println!("\n{line}");
+   } else {
+      println!("\n{}", style(line).dim());
+   }
+}
+
+/// Prints the `--cursor` hint for the next page, if there is one.
+fn print_next_cursor(next_cursor: Option<&str>, options: &SearchOptions) {
+   let Some(cursor) = next_cursor else {
+      return;
+   };
+   if options.format != SearchFormat::Text {
+      return;
+   }
+   let hint = format!("Next page: --cursor {cursor}");
+   if options.plain {
+      println!("\n{hint}");
+   } else {
+      println!("\n{}", style(hint).dim());
+   }
+}
+
+/// Prints a daemon search outcome in the human-readable format selected by
+/// `options`.
+pub(crate) fn print_outcome(
+   outcome: &SearchOutcome,
+   query: &str,
+   root: &Path,
+   scope: Option<&Path>,
+   options: &SearchOptions,
+) {
+   let format_opts = FormatOptions {
+      compact:      options.compact,
+      count:        options.count,
+      github:       options.format == SearchFormat::Github,
+      scores:       options.scores,
+      plain:        options.plain,
+      highlight:    options.highlight,
+      snippet_mode: resolve_snippet_mode(options),
+      mode:         options.mode,
+   };
+   if outcome.results.is_empty() {
+      format_empty_results(query, root, scope, outcome.status, outcome.progress, format_opts);
+   } else {
+      format_results(
+         &outcome.results,
+         query,
+         root,
+         scope,
+         format_opts,
+         outcome.status,
+         outcome.progress,
+      );
+   }
+}
+
 /// Attempts to execute the search via a running daemon, returning None if
 /// unavailable.
 async fn try_daemon_search(
@@ -509,15 +1442,30 @@ async fn try_daemon_search(
    per_file: usize,
    mode: SearchMode,
    rerank: bool,
+   snapshot_id: Option<&str>,
+   options: &QueryOptions,
    index_root: &Path,
    path: Option<&Path>,
    store_id: &str,
 ) -> Result<Option<SearchOutcome>> {
-   let Ok(stream) = daemon::connect_matching_daemon(index_root, store_id).await else {
+   let Ok(mut client) = daemon::DaemonClient::connect(index_root, store_id).await else {
       return Ok(None);
    };
 
-   match send_search_request(stream, query, max, per_file, mode, rerank, path, index_root).await {
+   match send_search_request(
+      &mut client,
+      query,
+      max,
+      per_file,
+      mode,
+      rerank,
+      snapshot_id,
+      options,
+      path,
+      index_root,
+   )
+   .await
+   {
       Ok(outcome) => Ok(Some(outcome)),
       Err(e) => {
          tracing::debug!("daemon search failed; falling back to in-process search: {}", e);
@@ -526,15 +1474,18 @@ async fn try_daemon_search(
    }
 }
 
-/// Sends a search request to a daemon over the given stream and returns
-/// results.
+/// Sends a search request over an open daemon connection and returns results.
+///
+/// The connection stays open, so callers may issue further requests on it.
 pub(crate) async fn send_search_request(
-   mut stream: usock::Stream,
+   client: &mut daemon::DaemonClient,
    query: &str,
    max: usize,
    per_file: usize,
    mode: SearchMode,
    rerank: bool,
+   snapshot_id: Option<&str>,
+   options: &QueryOptions,
    path: Option<&Path>,
    index_root: &Path,
 ) -> Result<SearchOutcome> {
@@ -548,55 +1499,51 @@ pub(crate) async fn send_search_request(
       mode,
       path: path.map(Path::to_path_buf),
       rerank,
+      snapshot_id: snapshot_id.map(str::to_string),
+      options: options.clone(),
    };
 
-   let mut buffer = ipc::SocketBuffer::new();
-   let response: Response = match time::timeout(timeout, async {
-      buffer.send(&mut stream, &request).await?;
-      buffer
-         .recv_with_limit(&mut stream, config::get().max_response_bytes)
-         .await
-   })
-   .await
-   {
-      Ok(Ok(r)) => r,
-      Ok(Err(e)) => return Err(e),
-      Err(_) => {
-         return Err(
-            Error::Server {
-               op:     "search",
-               reason: format!("timeout waiting for daemon response ({}s)", timeout.as_secs()),
-            }
-            .into(),
-         );
-      },
-   };
-
-   match response {
+   match client.request("search", &request, timeout).await? {
       Response::Search(search_response) => {
+         let (request_bytes, response_bytes) = client.last_exchange_bytes();
+         let cfg = config::get();
+         let ipc = IpcSizes {
+            request_bytes:      request_bytes as u64,
+            response_bytes:     response_bytes as u64,
+            max_request_bytes:  cfg.max_request_bytes as u64,
+            max_response_bytes: cfg.max_response_bytes as u64,
+         };
          let status = search_response.status;
          let progress = search_response.progress;
          let timings_ms = search_response.timings_ms;
-
-         let mut results: Vec<SearchResult> = search_response
-            .results
-            .into_iter()
-            .map(|r| SearchResult {
-               path:       PathBuf::from(sanitize_output(&r.path.to_string_lossy())),
-               score:      r.score,
-               match_pct:  None,
-               content:    sanitize_output(&r.content.into_string()),
-               chunk_type: r.chunk_type.map(|ct| ct.as_lowercase_str().to_string()),
-               start_line: Some(r.start_line as usize),
-               end_line:   Some((r.start_line + r.num_lines) as usize),
-               is_anchor:  r.is_anchor,
-            })
-            .collect();
-
-         apply_match_pcts(&mut results);
+         let embedder = search_response.embedder;
+         let candidates = search_response.candidates;
+         let segments = search_response.segments;
+         let rerank = search_response.rerank;
+         let snapshot_id = search_response.snapshot_id;
+         let expansions = search_response.expansions;
+         let funnel = search_response.funnel;
+
+         let results = cli_results(search_response.results, index_root);
          let limits_hit = sanitize_limits(search_response.limits_hit, index_root);
          let warnings = sanitize_warnings(search_response.warnings, index_root);
-         Ok(SearchOutcome { results, status, progress, timings_ms, limits_hit, warnings })
+         Ok(SearchOutcome {
+            results,
+            status,
+            progress,
+            timings_ms,
+            limits_hit,
+            warnings,
+            embedder,
+            candidates,
+            segments,
+            rerank,
+            ipc: Some(ipc),
+            snapshot_id,
+            execution: SearchExecution::Daemon,
+            expansions,
+            funnel,
+         })
       },
       Response::Error { code, message } => {
          Err(Error::Server { op: "search", reason: format!("{code}: {message}") })
@@ -615,6 +1562,8 @@ async fn perform_search(
    max: usize,
    per_file: usize,
    rerank: bool,
+   snapshot_id: Option<&str>,
+   options: &QueryOptions,
    mode: SearchMode,
    allow_degraded: bool,
 ) -> Result<SearchOutcome> {
@@ -644,13 +1593,20 @@ async fn perform_search(
       fingerprints.ignore_fingerprint,
    );
    let snapshot_start = std::time::Instant::now();
-   let snapshot_view = snapshot_manager.open_snapshot_view().await?;
+   let snapshot_view = match snapshot_id {
+      Some(snapshot_id) => {
+         snapshot_manager
+            .open_snapshot_view_by_id(snapshot_id)
+            .await?
+      },
+      None => snapshot_manager.open_snapshot_view().await?,
+   };
    let snapshot_read_ms = snapshot_start.elapsed().as_millis() as u64;
 
    let engine = SearchEngine::new(store, embedder);
-   let include_anchors = config::get().fast_mode;
+   let include_anchors = snapshot_view.include_anchors();
    let response = engine
-      .search_with_mode(
+      .search_with_options(
          &snapshot_view,
          store_id,
          query,
@@ -660,6 +1616,7 @@ async fn perform_search(
          rerank,
          include_anchors,
          mode,
+         options,
       )
       .await?;
 
@@ -673,44 +1630,144 @@ async fn perform_search(
       });
    }
 
-   let root_str = index_root.to_string_lossy().into_owned();
+   let results = cli_results(response.results, index_root);
+   let limits_hit = sanitize_limits(response.limits_hit, index_root);
+   let warnings = sanitize_warnings(response.warnings, index_root);
+   Ok(SearchOutcome {
+      results,
+      status: response.status,
+      progress: response.progress,
+      timings_ms: response.timings_ms,
+      limits_hit,
+      warnings,
+      embedder: response.embedder,
+      candidates: response.candidates,
+      segments: response.segments,
+      rerank: response.rerank,
+      ipc: None,
+      snapshot_id: Some(snapshot_view.snapshot_id.clone()),
+      execution: SearchExecution::InProcess,
+      expansions: response.expansions,
+      funnel: response.funnel,
+   })
+}
 
-   let mut results: Vec<SearchResult> = response
-      .results
+/// Converts engine results into CLI results. Daemon and in-process searches
+/// both go through here, so the same query renders identically either way:
+/// paths are made relative to `root` (a no-op for daemon results, which the
+/// server already relativized), snippets are sanitized, and the engine's
+/// deterministic order is kept as is.
+fn cli_results(results: Vec<types::SearchResult>, root: &Path) -> Vec<SearchResult> {
+   let mut results: Vec<SearchResult> = results
       .into_iter()
       .map(|r| {
-         let rel_path_str = r
-            .path
-            .strip_prefix(&root_str)
-            .unwrap_or(&r.path)
-            .to_string_lossy()
-            .trim_start_matches('/')
-            .to_string();
-
+         let rel_path = r.path.strip_prefix(root).unwrap_or(&r.path);
          SearchResult {
-            path:       PathBuf::from(sanitize_output(&rel_path_str)),
+            path:       PathBuf::from(sanitize_output(&rel_path.to_string_lossy())),
             score:      r.score,
+            norm_score: None,
             match_pct:  None,
-            content:    sanitize_output(&r.content.into_string()),
+            content:    sanitize_output(r.content.as_str()),
             chunk_type: r.chunk_type.map(|ct| ct.as_lowercase_str().to_string()),
+            symbol:     r.symbol.map(|symbol| symbol.to_string()),
             start_line: Some(r.start_line as usize),
-            end_line:   Some((r.start_line + r.num_lines) as usize),
+            end_line:   Some(r.end_line.unwrap_or(r.start_line + r.num_lines) as usize),
             is_anchor:  r.is_anchor,
+            language:   r.language.unwrap_or_else(|| UNKNOWN_LANGUAGE.to_string()),
+            author:     None,
+            commit:     None,
+            committed_at: None,
+            segment_table: r.segment_table,
+            context_before: r.context_before as usize,
+            context_after: r.context_after as usize,
          }
       })
       .collect();
-
    apply_match_pcts(&mut results);
-   let limits_hit = sanitize_limits(response.limits_hit, index_root);
-   let warnings = sanitize_warnings(response.warnings, index_root);
-   Ok(SearchOutcome {
-      results,
-      status: response.status,
-      progress: response.progress,
-      timings_ms: response.timings_ms,
-      limits_hit,
-      warnings,
-   })
+   results
+}
+
+/// Renders engine results as the `results` array of `search --json`.
+pub fn results_json(
+   results: Vec<types::SearchResult>,
+   root: &Path,
+) -> serde_json::Result<serde_json::Value> {
+   serde_json::to_value(cli_results(results, root))
+}
+
+/// Runs `query` over the whole of `root` and returns the document `search
+/// --json` would print, through the daemon serving `store_id` when
+/// `via_daemon` and in-process otherwise, so the two paths can be compared.
+pub async fn search_json(
+   query: &str,
+   root: &Path,
+   store_id: &str,
+   max: usize,
+   per_file: usize,
+   options: &SearchOptions,
+   via_daemon: bool,
+) -> Result<serde_json::Value> {
+   let index_identity = identity::resolve_index_identity(root)?;
+   let query_options = options.query_options();
+   let rerank = !options.no_rerank;
+   let mut outcome = if via_daemon {
+      let mut client = daemon::DaemonClient::connect(root, store_id).await?;
+      send_search_request(
+         &mut client,
+         query,
+         max,
+         per_file,
+         options.mode,
+         rerank,
+         None,
+         &query_options,
+         None,
+         root,
+      )
+      .await?
+   } else {
+      perform_search(
+         query,
+         root,
+         None,
+         store_id,
+         max,
+         per_file,
+         rerank,
+         None,
+         &query_options,
+         options.mode,
+         options.allow_degraded,
+      )
+      .await?
+   };
+   apply_context_lines(&mut outcome.results, options.before_context, options.after_context);
+   apply_min_score(&mut outcome, options.min_score);
+
+   let request_id = uuid::Uuid::new_v4().to_string();
+   let meta = build_meta(
+      query,
+      &index_identity,
+      store_id,
+      None,
+      resolve_snippet_mode(options),
+      max,
+      per_file,
+      rerank,
+      options.mode,
+      &query_options,
+      options.min_score,
+      &request_id,
+      &outcome,
+   )?;
+   let output = SearchJsonOutput {
+      meta,
+      results: outcome.results,
+      counts: None,
+      explain: None,
+      next_cursor: None,
+   };
+   Ok(serde_json::to_value(output)?)
 }
 
 fn sanitize_limits(limits: Vec<SearchLimitHit>, root: &Path) -> Vec<SearchLimitHit> {
@@ -744,6 +1801,48 @@ fn sanitize_warnings(warnings: Vec<SearchWarning>, root: &Path) -> Vec<SearchWar
       .collect()
 }
 
+/// Prints one GitHub Actions `::notice` annotation per result, using the
+/// first non-blank snippet line as the message.
+fn format_github(results: &[SearchResult]) {
+   const MAX_MESSAGE_CHARS: usize = 120;
+
+   // Anchors are already filtered at query time per the snapshot's
+   // recorded chunking mode.
+   for result in results {
+      let line = result.start_line.unwrap_or(1).max(1);
+      let snippet = result
+         .content
+         .lines()
+         .map(str::trim)
+         .find(|l| !l.is_empty())
+         .unwrap_or_default();
+      let mut message: String = snippet.chars().take(MAX_MESSAGE_CHARS).collect();
+      if snippet.chars().count() > MAX_MESSAGE_CHARS {
+         message.push_str("...");
+      }
+      println!(
+         "::notice file={},line={},title={}::{}",
+         escape_github_property(&result.path.to_string_lossy()),
+         line,
+         escape_github_property(&format!("ggrep match ({:.3})", result.score)),
+         escape_github_data(&message)
+      );
+   }
+}
+
+fn escape_github_data(value: &str) -> String {
+   value
+      .replace('%', "%25")
+      .replace('\r', "%0D")
+      .replace('\n', "%0A")
+}
+
+fn escape_github_property(value: &str) -> String {
+   escape_github_data(value)
+      .replace(':', "%3A")
+      .replace(',', "%2C")
+}
+
 /// Formats and prints search results in human-readable form.
 fn format_results(
    results: &[SearchResult],
@@ -758,6 +1857,22 @@ fn format_results(
    const SHORT_PREVIEW_LINES: usize = 8;
    const LONG_PREVIEW_LINES: usize = 24;
 
+   if options.count {
+      for file in file_counts(results) {
+         if options.plain {
+            println!("{}:{}", file.path.display(), file.count);
+         } else {
+            println!("{}:{}", style(file.path.display()).cyan(), file.count);
+         }
+      }
+      return;
+   }
+
+   if options.github {
+      format_github(results);
+      return;
+   }
+
    if options.compact {
       let mut seen = std::collections::HashSet::<PathBuf>::new();
       for result in results {
@@ -804,11 +1919,12 @@ fn format_results(
       println!();
    }
 
-   let include_anchors = config::get().fast_mode;
-   let display_results: Vec<_> = results
-      .iter()
-      .filter(|r| include_anchors || !r.is_anchor.unwrap_or(false))
-      .collect();
+   let display_results: Vec<_> = results.iter().collect();
+   let terms = if options.highlight && !options.plain {
+      highlight_terms(query)
+   } else {
+      Vec::new()
+   };
 
    let print_one = |idx: usize, result: &&SearchResult| {
       let start_line = result.start_line.unwrap_or(1);
@@ -832,6 +1948,10 @@ fn format_results(
       if options.plain {
          print!("{idx}) {}:{}", result.path.display(), start_line);
 
+         if let Some(label) = symbol_label(result) {
+            print!(" {label}");
+         }
+
          if options.scores {
             if let Some(match_pct) = result.match_pct {
                print!(" (match: {match_pct}%, score: {:.3})", result.score);
@@ -840,6 +1960,10 @@ fn format_results(
             }
          }
 
+         if let Some(label) = blame_label(result) {
+            print!(" {label}");
+         }
+
          println!();
 
          if display_lines > 0 {
@@ -857,6 +1981,10 @@ fn format_results(
          print!("{}", style(format!("{idx}) ")).bold().cyan());
          print!("{}:{}", style(result.path.display()).green(), start_line);
 
+         if let Some(label) = symbol_label(result) {
+            print!(" {}", style(label).magenta());
+         }
+
          if options.scores {
             if let Some(match_pct) = result.match_pct {
                print!(
@@ -868,6 +1996,10 @@ fn format_results(
             }
          }
 
+         if let Some(label) = blame_label(result) {
+            print!(" {}", style(label).yellow());
+         }
+
          println!();
 
          if display_lines > 0 {
@@ -877,7 +2009,7 @@ fn format_results(
                   "{:>width$} {} {}",
                   style(line_num).dim(),
                   style("|").dim(),
-                  line,
+                  highlight_line(line, &terms),
                   width = line_num_width
                );
             }
@@ -905,12 +2037,12 @@ fn format_results(
       return;
    }
 
-   use crate::search::profile::{SearchBucket, bucket_for_path};
+   use crate::search::profile::{SearchBucket, bucket_for};
    let mut code = Vec::new();
    let mut docs = Vec::new();
    let mut graphs = Vec::new();
    for r in &display_results {
-      match bucket_for_path(&r.path) {
+      match bucket_for(&r.path, Some(&r.language)) {
          SearchBucket::Code => code.push(*r),
          SearchBucket::Docs => docs.push(*r),
          SearchBucket::Graph => graphs.push(*r),
@@ -938,6 +2070,81 @@ fn format_results(
    }
 }
 
+/// Query words worth highlighting: edge punctuation trimmed, at least two
+/// characters, longest first so `parse_args` wins over `parse`.
+fn highlight_terms(query: &str) -> Vec<&str> {
+   let mut terms: Vec<&str> = query
+      .split_whitespace()
+      .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_'))
+      .filter(|term| term.chars().count() >= 2)
+      .collect();
+   terms.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+   terms.dedup_by(|a, b| a.to_lowercase() == b.to_lowercase());
+   terms
+}
+
+/// Styles every case-insensitive occurrence of `terms` in `line`. Case is
+/// folded per char (so `É` matches `é`) and matches start and end on char
+/// boundaries, so multibyte text is never split.
+fn highlight_line<'a>(line: &'a str, terms: &[&str]) -> Cow<'a, str> {
+   if terms.is_empty() {
+      return Cow::Borrowed(line);
+   }
+   let mut out = String::with_capacity(line.len());
+   let mut plain_start = 0;
+   let mut chars = line.char_indices();
+   while let Some((i, _)) = chars.next() {
+      let Some(len) = terms
+         .iter()
+         .find_map(|term| folded_match_len(&line[i..], term))
+      else {
+         continue;
+      };
+      out.push_str(&line[plain_start..i]);
+      out.push_str(&style(&line[i..i + len]).yellow().underlined().to_string());
+      plain_start = i + len;
+      while chars.offset() < plain_start {
+         chars.next();
+      }
+   }
+   if plain_start == 0 {
+      return Cow::Borrowed(line);
+   }
+   out.push_str(&line[plain_start..]);
+   Cow::Owned(out)
+}
+
+/// Byte length of the prefix of `text` that equals `term` ignoring case,
+/// compared char by char since folding can change a char's UTF-8 width.
+fn folded_match_len(text: &str, term: &str) -> Option<usize> {
+   if term.is_empty() {
+      return None;
+   }
+   let mut text_chars = text.char_indices();
+   for term_char in term.chars() {
+      let (_, text_char) = text_chars.next()?;
+      if !text_char.to_lowercase().eq(term_char.to_lowercase()) {
+         return None;
+      }
+   }
+   Some(text_chars.next().map_or(text.len(), |(end, _)| end))
+}
+
+/// Aggregates results by file, most results first, ties by path.
+fn file_counts(results: &[SearchResult]) -> Vec<FileCount> {
+   let mut by_path: BTreeMap<&Path, usize> = BTreeMap::new();
+   for result in results {
+      *by_path.entry(&result.path).or_default() += 1;
+   }
+   // Paths come out sorted, so the stable sort keeps ties in path order.
+   let mut counts: Vec<FileCount> = by_path
+      .into_iter()
+      .map(|(path, count)| FileCount { path: path.to_path_buf(), count })
+      .collect();
+   counts.sort_by(|a, b| b.count.cmp(&a.count));
+   counts
+}
+
 fn format_empty_results(
    query: &str,
    root: &Path,
@@ -946,6 +2153,10 @@ fn format_empty_results(
    progress: Option<u8>,
    options: FormatOptions,
 ) {
+   if options.github {
+      return;
+   }
+
    // Keep the same header styling as normal results, but include a clear empty
    // state so indexing-from-scratch doesn't look like a crash.
    if options.plain {
@@ -997,19 +2208,118 @@ fn format_empty_results(
    }
 }
 
+/// Trims the neighbouring-chunk context around each snippet to at most
+/// `before`/`after` lines, like grep's `-B`/`-A`. `start_line` and `end_line`
+/// move with the trimmed region so the line-number gutter stays correct.
+fn apply_context_lines(results: &mut [SearchResult], before: Option<usize>, after: Option<usize>) {
+   if before.is_none() && after.is_none() {
+      return;
+   }
+   for result in results {
+      let lines: Vec<&str> = result.content.split_inclusive('\n').collect();
+      let context_before = result.context_before.min(lines.len());
+      let context_after = result.context_after.min(lines.len() - context_before);
+      let drop_front = before.map_or(0, |keep| context_before.saturating_sub(keep));
+      let drop_back = after.map_or(0, |keep| context_after.saturating_sub(keep));
+      if drop_front == 0 && drop_back == 0 {
+         continue;
+      }
+
+      let kept = &lines[drop_front..lines.len() - drop_back];
+      result.content = kept.concat();
+      result.context_before = context_before - drop_front;
+      result.context_after = context_after - drop_back;
+      result.start_line = result.start_line.map(|start| start + drop_front);
+      result.end_line = result.start_line.map(|start| start + kept.len());
+   }
+}
+
+/// Drops results whose final (post-rerank) score is below `min_score`, then
+/// renormalizes the rest so `norm_score`/`match_pct` describe what is shown.
+fn apply_min_score(outcome: &mut SearchOutcome, min_score: Option<f32>) {
+   if let Some(min_score) = min_score {
+      let before = outcome.results.len();
+      outcome.results.retain(|result| result.score >= min_score);
+      FilterStage::push(
+         &mut outcome.funnel,
+         "min_score",
+         Some(min_score.to_string()),
+         outcome.results.len(),
+      );
+      if outcome.results.len() != before {
+         apply_match_pcts(&mut outcome.results);
+      }
+   }
+}
+
+/// Fills `author`/`commit`/`committed_at` from `git blame` of each result's
+/// start line. Results in untracked files, in files edited since `store_id`
+/// indexed them, or on uncommitted lines are left unannotated.
+fn apply_blame(results: &mut [SearchResult], root: &Path, store_id: &str) {
+   let targets: Vec<(PathBuf, usize)> = results
+      .iter()
+      .map(|r| (r.path.clone(), r.start_line.unwrap_or(1).max(1)))
+      .collect();
+   let meta_store = MetaStore::load(store_id).ok();
+   let is_indexed = |path: &Path, content: &[u8]| {
+      meta_store
+         .as_ref()
+         .and_then(|meta| meta.get_hash(path))
+         .is_some_and(|hash| hash == FileHash::sum(content))
+   };
+   for (result, blame) in results
+      .iter_mut()
+      .zip(git::blame_lines(root, &targets, is_indexed))
+   {
+      let Some(blame) = blame else {
+         continue;
+      };
+      result.author = Some(sanitize_output(&blame.author));
+      result.committed_at = chrono::DateTime::from_timestamp(blame.time, 0)
+         .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+      result.commit = Some(blame.commit);
+   }
+}
+
+/// `[fn name]`-style header naming the definition a result was cut from.
+fn symbol_label(result: &SearchResult) -> Option<String> {
+   let symbol = result.symbol.as_deref()?;
+   let kind = match result.chunk_type.as_deref() {
+      Some("function" | "method") => "fn ",
+      Some("class") => "class ",
+      Some("interface") => "interface ",
+      Some("typealias") => "type ",
+      _ => "",
+   };
+   Some(format!("[{kind}{symbol}]"))
+}
+
+/// Formats the `--blame` annotation shown after a result's location.
+fn blame_label(result: &SearchResult) -> Option<String> {
+   let author = result.author.as_deref()?;
+   let commit = result.commit.as_deref()?;
+   let date = result
+      .committed_at
+      .as_deref()
+      .map_or("", |d| d.get(..10).unwrap_or(d));
+   Some(format!("[{author}, {} {date}]", commit.get(..8).unwrap_or(commit)))
+}
+
 fn apply_match_pcts(results: &mut [SearchResult]) {
    if results.is_empty() {
       return;
    }
 
    let scores: Vec<f32> = results.iter().map(|r| r.score).collect();
+   let norms = crate::util::normalize_scores(&scores);
    let pcts = crate::util::compute_match_pcts(&scores);
-   for (r, pct) in results.iter_mut().zip(pcts) {
+   for ((r, norm), pct) in results.iter_mut().zip(norms).zip(pcts) {
+      r.norm_score = norm;
       r.match_pct = pct;
    }
 }
 
-fn resolve_snippet_mode(options: SearchOptions) -> SnippetMode {
+fn resolve_snippet_mode(options: &SearchOptions) -> SnippetMode {
    if options.content {
       return SnippetMode::Full;
    }
@@ -1037,6 +2347,29 @@ fn snippet_mode_label(mode: SnippetMode) -> &'static str {
 
 const SEARCH_SCHEMA_VERSION: u32 = 1;
 
+fn compute_query_fingerprint(
+   query: &str,
+   mode: SearchMode,
+   per_file: usize,
+   max_results: usize,
+   rerank: bool,
+   scope: Option<&Path>,
+   snippet_mode: SnippetMode,
+   query_options: &QueryOptions,
+   min_score: Option<f32>,
+) -> Result<String> {
+   identity::compute_query_fingerprint(query, identity::QueryFingerprintOptions {
+      mode,
+      per_file,
+      max_results,
+      rerank,
+      scope,
+      snippet: snippet_mode_label(snippet_mode),
+      options: query_options,
+      min_score,
+   })
+}
+
 pub(crate) fn build_meta(
    query: &str,
    index_identity: &identity::IndexIdentity,
@@ -1047,28 +2380,54 @@ pub(crate) fn build_meta(
    per_file: usize,
    rerank: bool,
    mode: SearchMode,
+   query_options: &QueryOptions,
+   min_score: Option<f32>,
    request_id: &str,
    outcome: &SearchOutcome,
 ) -> Result<SearchMeta> {
    let cfg = config::get();
-   let query_fingerprint =
-      identity::compute_query_fingerprint(query, identity::QueryFingerprintOptions {
-         mode,
-         per_file,
-         max_results,
-         rerank,
-         scope,
-         snippet: snippet_mode_label(snippet_mode),
-      })?;
+   let query_fingerprint = compute_query_fingerprint(
+      query,
+      mode,
+      per_file,
+      max_results,
+      rerank,
+      scope,
+      snippet_mode,
+      query_options,
+      min_score,
+   )?;
    let embed_config_fingerprint = identity::compute_embed_config_fingerprint(cfg)?;
+   let (candidate_multiplier, store_limit) = search::store_limit(max_results, mode);
    let meta_store = MetaStore::load(store_id).ok();
-   let snapshot_id = meta_store
+   let snapshot_id = outcome.snapshot_id.clone().or_else(|| {
+      meta_store
+         .as_ref()
+         .and_then(|meta| meta.snapshot_id().map(|s| s.to_string()))
+   });
+   let snapshot_created_at = meta_store
       .as_ref()
-      .and_then(|meta| meta.snapshot_id().map(|s| s.to_string()));
+      .and_then(|meta| meta.snapshot_created_at().map(|s| s.to_string()));
+   let snapshot_age_secs = snapshot_created_at
+      .as_deref()
+      .and_then(|created_at| chrono::DateTime::parse_from_rfc3339(created_at).ok())
+      .map(|created_at| {
+         u64::try_from(
+            chrono::Utc::now()
+               .signed_duration_since(created_at)
+               .num_seconds(),
+         )
+         .unwrap_or(0)
+      });
    let degraded = meta_store
       .as_ref()
       .map(|meta| meta.snapshot_degraded())
       .unwrap_or(false);
+   let counts = snapshot_id.as_deref().and_then(|id| {
+      SnapshotManifest::load(&SnapshotManager::manifest_path_for(store_id, id))
+         .ok()
+         .map(|manifest| manifest.counts)
+   });
 
    let head_sha = git::get_head_sha(&index_identity.canonical_root);
    let dirty = git::is_dirty(&index_identity.canonical_root);
@@ -1087,14 +2446,22 @@ pub(crate) fn build_meta(
       query_fingerprint,
       embed_config_fingerprint,
       snapshot_id,
+      snapshot_created_at,
+      snapshot_age_secs,
+      files_indexed: counts.as_ref().map(|c| c.files_indexed),
+      chunks_indexed: counts.as_ref().map(|c| c.chunks_indexed),
       degraded,
       git: git_info,
       mode,
+      execution: outcome.execution,
+      sorted_by: "rank",
       limits: ExplainLimits {
          max_results,
          per_file,
          snippet: snippet_mode_label(snippet_mode).to_string(),
          max_candidates: cfg.effective_max_candidates(),
+         candidate_multiplier,
+         store_limit,
          max_total_snippet_bytes: cfg.effective_max_total_snippet_bytes(),
          max_snippet_bytes_per_result: cfg.effective_max_snippet_bytes_per_result(),
          max_open_segments_per_query: cfg.effective_max_open_segments_per_query(),
@@ -1104,15 +2471,124 @@ pub(crate) fn build_meta(
       timings_ms: outcome.timings_ms.map(|timings| JsonTimings {
          admission:     timings.admission_ms,
          snapshot_read: timings.snapshot_read_ms,
+         embed:         timings.embed_ms,
          retrieve:      timings.retrieve_ms,
+         rerank:        timings.rerank_ms,
          rank:          timings.rank_ms,
          format:        timings.format_ms,
+         total:         None,
       }),
+      embedder: outcome.embedder.clone(),
    })
 }
 
 pub(crate) fn build_explain(meta: &SearchMeta, outcome: &SearchOutcome) -> SearchExplain {
-   SearchExplain { meta: meta.clone(), candidate_mix: candidate_mix(&outcome.results) }
+   let manifest = meta.snapshot_id.as_deref().and_then(|id| {
+      SnapshotManifest::load(&SnapshotManager::manifest_path_for(&meta.store_id, id)).ok()
+   });
+   SearchExplain {
+      meta:              meta.clone(),
+      candidate_mix:     candidate_mix(&outcome.results, &outcome.expansions),
+      candidates:        outcome.candidates,
+      segments:          segment_contributions(outcome, manifest.as_ref()),
+      tombstones:        manifest.as_ref().and_then(|manifest| {
+         let store_root = config::data_dir().join(&meta.store_id);
+         let view = SnapshotView::from_manifest(manifest.clone(), &store_root).ok()?;
+         Some(TombstoneVisibility {
+            total:      view.tombstone_count(),
+            suppressed: outcome
+               .candidates
+               .map_or(0, |counts| counts.filtered_not_visible),
+         })
+      }),
+      reranked:          outcome.rerank.is_some_and(|stats| stats.reranked()),
+      rerank_candidates: outcome.rerank.map_or(0, |stats| stats.scored),
+      rerank_skipped:    outcome.rerank.and_then(|stats| stats.skipped_reason()),
+      rerank_scores:     outcome.rerank.and_then(|stats| stats.scores),
+      ranking_weights:   RankingWeights::for_mode(meta.mode),
+      ipc:               outcome.ipc,
+      fast_mode:         manifest
+         .as_ref()
+         .and_then(SnapshotManifest::has_fast_segments)
+         .unwrap_or(config::get().fast_mode),
+      fingerprint_drift: manifest.as_ref().is_some_and(|manifest| {
+         manifest.config_fingerprint != meta.config_fingerprint
+            || manifest.ignore_fingerprint != meta.ignore_fingerprint
+      }),
+      store_size_bytes:  get_dir_size(&config::data_dir().join(&meta.store_id)).ok(),
+      coverage:          manifest
+         .as_ref()
+         .zip(meta.files_indexed)
+         .map(|(manifest, files)| {
+            IndexCoverage::sample(Path::new(&manifest.canonical_root), files)
+         }),
+      sql_filters:       None,
+      funnel:            outcome.funnel.clone(),
+      parallelism:       Parallelism {
+         embed_threads:       config::get().default_threads(),
+         segments:            outcome.segments.len(),
+         segment_concurrency: LanceStore::segment_search_concurrency(),
+      },
+      provenance:        Provenance {
+         ggrep_version:            version::version_string(),
+         dense_model:              config::get().dense_model.clone(),
+         colbert_model:            config::get().colbert_model.clone(),
+         chunker_version:          CHUNKER_VERSION,
+         index_version:            INDEX_VERSION,
+         manifest_schema_version:  manifest
+            .as_ref()
+            .map_or(MANIFEST_SCHEMA_VERSION, |manifest| manifest.schema_version),
+         chunk_row_schema_version: manifest
+            .as_ref()
+            .map_or(CHUNK_ROW_SCHEMA_VERSION, |manifest| manifest.chunk_row_schema_version),
+      },
+   }
+}
+
+/// SQL predicates the segment search applies, with the scope made relative to
+/// `root` so explain output carries no absolute paths.
+fn sql_filters(
+   scope: Option<&Path>,
+   languages: &[String],
+   include_anchors: bool,
+   mode: SearchMode,
+   root: &Path,
+) -> SearchFilters {
+   let scope = scope.map(|path| path.strip_prefix(root).unwrap_or(path));
+   let language = search::resolve_languages(languages, &grammar::declared_custom_grammars())
+      .unwrap_or_default()
+      .clause(true);
+   if mode == SearchMode::Paths {
+      return SearchFilters::anchors_only(scope, language.as_deref());
+   }
+   SearchFilters::new(scope, language.as_deref(), include_anchors)
+}
+
+fn segment_contributions(
+   outcome: &SearchOutcome,
+   manifest: Option<&SnapshotManifest>,
+) -> Vec<SegmentContribution> {
+   outcome
+      .segments
+      .iter()
+      .map(|segment| SegmentContribution {
+         table:          segment.table.clone(),
+         candidates:     segment.candidates,
+         results:        outcome
+            .results
+            .iter()
+            .filter(|r| r.segment_table.as_deref() == Some(segment.table.as_str()))
+            .count(),
+         rows:           manifest.and_then(|manifest| {
+            manifest
+               .segments
+               .iter()
+               .find(|s| s.table == segment.table)
+               .map(|s| s.rows)
+         }),
+         vector_indexed: segment.vector_indexed,
+      })
+      .collect()
 }
 
 pub(crate) fn build_json_output(
@@ -1120,20 +2596,26 @@ pub(crate) fn build_json_output(
    outcome: SearchOutcome,
    explain: Option<SearchExplain>,
 ) -> SearchJsonOutput {
-   SearchJsonOutput { meta, results: outcome.results, explain }
+   SearchJsonOutput { meta, results: outcome.results, counts: None, explain, next_cursor: None }
 }
 
-fn candidate_mix(results: &[SearchResult]) -> CandidateMix {
-   use crate::search::profile::{SearchBucket, bucket_for_path};
+fn candidate_mix(results: &[SearchResult], expansions: &[QueryExpansion]) -> CandidateMix {
+   use crate::search::profile::{SearchBucket, bucket_for};
 
-   let mut mix =
-      CandidateMix { total: results.len(), code: 0, docs: 0, graph: 0, anchors: 0 };
+   let mut mix = CandidateMix {
+      total:      results.len(),
+      code:       0,
+      docs:       0,
+      graph:      0,
+      anchors:    0,
+      expansions: expansions.to_vec(),
+   };
 
    for result in results {
       if result.is_anchor.unwrap_or(false) {
          mix.anchors += 1;
       }
-      match bucket_for_path(&result.path) {
+      match bucket_for(&result.path, Some(&result.language)) {
          SearchBucket::Code => mix.code += 1,
          SearchBucket::Docs => mix.docs += 1,
          SearchBucket::Graph => mix.graph += 1,
@@ -1143,6 +2625,17 @@ fn candidate_mix(results: &[SearchResult]) -> CandidateMix {
    mix
 }
 
+/// Whether a daemon search error means the daemon cannot serve requests at
+/// all (connection lost or protocol mismatch), as opposed to an answer the
+/// caller should see.
+fn daemon_unavailable(err: &Error) -> bool {
+   match err {
+      Error::Io(_) | Error::Ipc(_) => true,
+      Error::Server { .. } => classify_error(err).0 == "incompatible",
+      _ => false,
+   }
+}
+
 fn classify_error(err: &Error) -> (String, String) {
    if let Error::Server { reason, .. } = err {
       if let Some((code, message)) = reason.split_once(':') {
@@ -1189,13 +2682,181 @@ pub(crate) fn build_json_error(err: &Error, request_id: &str) -> SearchErrorJson
    }
 }
 
+/// Prints the JSON output, either buffered as a single document,
+/// incrementally, or as NDJSON.
+fn print_json_output(
+   mut output: SearchJsonOutput,
+   options: &SearchOptions,
+   started: Instant,
+) -> Result<()> {
+   if options.timing
+      && let Some(timings) = output.meta.timings_ms.as_mut()
+   {
+      timings.total = Some(started.elapsed().as_millis() as u64);
+   }
+   if options.count {
+      output.counts = Some(file_counts(&output.results));
+      output.results.clear();
+   }
+   if let Some(max_bytes) = options.max_bytes {
+      apply_output_budget(&mut output, max_bytes, |output| {
+         let mut rendered = Vec::new();
+         write_json_output(&mut rendered, output, options)?;
+         Ok(rendered.len())
+      })?;
+   }
+
+   let stdout = io::stdout();
+   let mut out = io::BufWriter::new(stdout.lock());
+   write_json_output(&mut out, &output, options)?;
+   out.flush()?;
+   Ok(())
+}
+
+/// Writes `output` in the layout selected by `options`, newline-terminated.
+fn write_json_output(
+   out: &mut impl Write,
+   output: &SearchJsonOutput,
+   options: &SearchOptions,
+) -> Result<()> {
+   if options.ndjson {
+      write_ndjson(out, output)
+   } else if options.json_incremental {
+      write_json_incremental(out, output)
+   } else {
+      serde_json::to_writer(&mut *out, output)?;
+      out.write_all(b"\n")?;
+      Ok(())
+   }
+}
+
+/// Shrinks `output` so the bytes actually written fit in `max_bytes`.
+///
+/// `measure` returns the length of `output` as it will be printed (escaping,
+/// layout and trailing newline included). Snippet content is trimmed starting
+/// from the lowest-ranked result; if the output still does not fit once every
+/// snippet is empty, results are dropped from the bottom. A
+/// `max_output_bytes` limit hit records the original size. Only the meta can
+/// exceed the budget, when it alone is larger than `max_bytes`.
+fn apply_output_budget(
+   output: &mut SearchJsonOutput,
+   max_bytes: usize,
+   measure: impl Fn(&SearchJsonOutput) -> Result<usize>,
+) -> Result<()> {
+   let observed = measure(output)?;
+   if observed <= max_bytes {
+      return Ok(());
+   }
+
+   let hit = SearchLimitHit {
+      code:     "max_output_bytes".to_string(),
+      limit:    max_bytes as u64,
+      observed: Some(observed as u64),
+      path_key: None,
+   };
+   if let Some(explain) = output.explain.as_mut() {
+      explain.meta.limits_hit.push(hit.clone());
+   }
+   output.meta.limits_hit.push(hit);
+
+   for idx in (0..output.results.len()).rev() {
+      let size = measure(output)?;
+      if size <= max_bytes {
+         return Ok(());
+      }
+      let content = &mut output.results[idx].content;
+      let mut keep = content.len().saturating_sub(size - max_bytes);
+      while !content.is_char_boundary(keep) {
+         keep -= 1;
+      }
+      content.truncate(keep);
+   }
+
+   while !output.results.is_empty() && measure(output)? > max_bytes {
+      output.results.pop();
+   }
+   Ok(())
+}
+
+/// Trailing part of the incremental JSON output, emitted after `results`.
+#[derive(Serialize)]
+struct SearchJsonSummary<'a> {
+   #[serde(flatten)]
+   meta:        &'a SearchMeta,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   counts:      Option<&'a Vec<FileCount>>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   explain:     Option<&'a SearchExplain>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   next_cursor: Option<&'a Option<String>>,
+}
+
+/// Streams the `results` array element-by-element, followed by the meta and
+/// explain fields as trailing keys of the same object.
+///
+/// The document is equivalent to the buffered output apart from key order, so
+/// consumers can start parsing results before the summary is written.
+fn write_json_incremental(out: &mut impl Write, output: &SearchJsonOutput) -> Result<()> {
+   out.write_all(b"{\"results\":[")?;
+   for (idx, result) in output.results.iter().enumerate() {
+      if idx > 0 {
+         out.write_all(b",")?;
+      }
+      serde_json::to_writer(&mut *out, result)?;
+   }
+   out.write_all(b"]")?;
+
+   let summary = serde_json::to_string(&SearchJsonSummary {
+      meta:        &output.meta,
+      counts:      output.counts.as_ref(),
+      explain:     output.explain.as_ref(),
+      next_cursor: output.next_cursor.as_ref(),
+   })?;
+   let fields = summary
+      .strip_prefix('{')
+      .and_then(|s| s.strip_suffix('}'))
+      .unwrap_or_default();
+   if !fields.is_empty() {
+      out.write_all(b",")?;
+      out.write_all(fields.as_bytes())?;
+   }
+   out.write_all(b"}\n")?;
+   Ok(())
+}
+
+/// Writes the meta (with explain and cursor) as the first line, then one
+/// line per result (or per file with `--count`), flushing each so consumers
+/// can act on results early.
+fn write_ndjson(out: &mut impl Write, output: &SearchJsonOutput) -> Result<()> {
+   serde_json::to_writer(&mut *out, &SearchJsonSummary {
+      meta:        &output.meta,
+      counts:      None,
+      explain:     output.explain.as_ref(),
+      next_cursor: output.next_cursor.as_ref(),
+   })?;
+   out.write_all(b"\n")?;
+   if let Some(counts) = &output.counts {
+      for file in counts {
+         serde_json::to_writer(&mut *out, file)?;
+         out.write_all(b"\n")?;
+      }
+      return Ok(());
+   }
+   for result in &output.results {
+      serde_json::to_writer(&mut *out, result)?;
+      out.write_all(b"\n")?;
+      out.flush()?;
+   }
+   Ok(())
+}
+
 fn emit_json_error(err: &Error, request_id: &str) -> Result<()> {
    let payload = build_json_error(err, request_id);
    println!("{}", serde_json::to_string(&payload)?);
    Ok(())
 }
 
-fn print_explain(explain: &SearchExplain, plain: bool) {
+fn print_explain(explain: &SearchExplain, plain: bool, mode_flag: Option<&str>) {
    if plain {
       println!("\nExplain:");
    } else {
@@ -1205,6 +2866,12 @@ fn print_explain(explain: &SearchExplain, plain: bool) {
    let meta = &explain.meta;
    println!("  request_id: {}", meta.request_id);
    println!("  store_id: {}", meta.store_id);
+   let mode = format!("{:?}", meta.mode).to_lowercase();
+   match mode_flag {
+      Some(flag) => println!("  mode: {mode} (from {flag})"),
+      None => println!("  mode: {mode} (default)"),
+   }
+   println!("  execution: {}", meta.execution.label());
    println!("  config_fingerprint: {}", meta.config_fingerprint);
    println!("  ignore_fingerprint: {}", meta.ignore_fingerprint);
    println!("  query_fingerprint: {}", meta.query_fingerprint);
@@ -1212,6 +2879,46 @@ fn print_explain(explain: &SearchExplain, plain: bool) {
    if let Some(snapshot_id) = &meta.snapshot_id {
       println!("  snapshot_id: {}", snapshot_id);
    }
+   if let Some(created_at) = &meta.snapshot_created_at {
+      match meta.snapshot_age_secs {
+         Some(age_secs) => println!(
+            "  snapshot_created_at: {} (indexed {})",
+            created_at,
+            list::format_time_ago(SystemTime::now() - Duration::from_secs(age_secs))
+         ),
+         None => println!("  snapshot_created_at: {}", created_at),
+      }
+   }
+   if let (Some(files), Some(chunks)) = (meta.files_indexed, meta.chunks_indexed) {
+      println!(
+         "  corpus: files={}, chunks={} (returned {} of {} chunks)",
+         files, chunks, explain.candidate_mix.total, chunks
+      );
+   }
+   if let Some(size) = explain.store_size_bytes {
+      println!("  store_size: {} ({} bytes)", format_size(size), size);
+   }
+   if let Some(coverage) = &explain.coverage {
+      println!(
+         "  coverage: files_indexed={}, candidate_files={}{}",
+         coverage.files_indexed,
+         coverage.candidate_files,
+         if coverage.truncated {
+            "+ (sampled)"
+         } else {
+            ""
+         }
+      );
+      if coverage.under_indexed {
+         let warning =
+            "    warning: most indexable files on disk are not indexed; re-run `ggrep index`";
+         if plain {
+            println!("{warning}");
+         } else {
+            println!("{}", style(warning).yellow());
+         }
+      }
+   }
    if let Some(git) = &meta.git {
       if let Some(head_sha) = &git.head_sha {
          println!("  git_head: {}", head_sha);
@@ -1223,12 +2930,15 @@ fn print_explain(explain: &SearchExplain, plain: bool) {
    }
 
    println!(
-      "  limits: max_results={}, per_file={}, snippet={}, max_candidates={}, \
-       max_total_snippet_bytes={}, max_snippet_bytes_per_result={}, max_open_segments_per_query={}",
+      "  limits: max_results={}, per_file={}, snippet={}, max_candidates={}, store_limit={} ({}x, \
+       per source), max_total_snippet_bytes={}, max_snippet_bytes_per_result={}, \
+       max_open_segments_per_query={}",
       meta.limits.max_results,
       meta.limits.per_file,
       meta.limits.snippet,
       meta.limits.max_candidates,
+      meta.limits.store_limit,
+      meta.limits.candidate_multiplier,
       meta.limits.max_total_snippet_bytes,
       meta.limits.max_snippet_bytes_per_result,
       meta.limits.max_open_segments_per_query
@@ -1242,11 +2952,209 @@ fn print_explain(explain: &SearchExplain, plain: bool) {
       explain.candidate_mix.graph,
       explain.candidate_mix.anchors
    );
+   if !explain.candidate_mix.expansions.is_empty() {
+      let expansions: Vec<String> = explain
+         .candidate_mix
+         .expansions
+         .iter()
+         .map(|e| format!("{} -> {}", e.term, e.synonyms.join(", ")))
+         .collect();
+      println!("  expansions: {}", expansions.join("; "));
+   }
+   if !explain.funnel.is_empty() {
+      println!("  filters:");
+      for stage in &explain.funnel {
+         let removed = stage
+            .removed
+            .map(|removed| format!(" (-{removed})"))
+            .unwrap_or_default();
+         let filter = stage
+            .filter
+            .as_deref()
+            .map(|filter| format!(" [{filter}]"))
+            .unwrap_or_default();
+         println!("    {}: {}{}{}", stage.stage, stage.remaining, removed, filter);
+      }
+   }
+
+   let weights = &explain.ranking_weights;
+   println!(
+      "  ranking_weights ({}): function_boost={}, test_penalty={}, test_boost={}, \
+       doc_multiplier={}, graph_multiplier={}",
+      mode,
+      weights.function_boost,
+      weights.test_penalty,
+      weights.test_boost,
+      weights.doc_multiplier,
+      weights.graph_multiplier
+   );
+
+   println!("  fast_mode: {}", explain.fast_mode);
+   if explain.fast_mode {
+      println!(
+         "    note: results are file-level anchors; structural chunking is disabled (reindex with \
+          `ggrep index --reset --full`)"
+      );
+   }
+
+   println!("  fingerprint_drift: {}", explain.fingerprint_drift);
+   if explain.fingerprint_drift {
+      let warning = "    warning: config or ignore rules changed since this snapshot was built; \
+                     re-index (`ggrep index`) to apply them";
+      if plain {
+         println!("{warning}");
+      } else {
+         println!("{}", style(warning).yellow());
+      }
+   }
+
+   let provenance = &explain.provenance;
+   println!(
+      "  provenance: ggrep {}, dense_model={}, colbert_model={}, chunker={}, index={}, \
+       manifest_schema=v{}, chunk_row_schema=v{}",
+      provenance.ggrep_version,
+      provenance.dense_model,
+      provenance.colbert_model,
+      provenance.chunker_version,
+      provenance.index_version,
+      provenance.manifest_schema_version,
+      provenance.chunk_row_schema_version
+   );
+
+   let parallelism = &explain.parallelism;
+   println!(
+      "  parallelism: embed_threads={}, segments={}, segment_concurrency={}{}",
+      parallelism.embed_threads,
+      parallelism.segments,
+      parallelism.segment_concurrency,
+      if parallelism.segment_concurrency > 1 && parallelism.segments > 1 {
+         " (parallel)"
+      } else {
+         " (sequential)"
+      }
+   );
+
+   if let Some(ipc) = &explain.ipc {
+      println!(
+         "  ipc: request_bytes={} (max {}), response_bytes={} (max {})",
+         ipc.request_bytes, ipc.max_request_bytes, ipc.response_bytes, ipc.max_response_bytes
+      );
+   }
+
+   if explain.reranked {
+      println!("  rerank: colbert-scored {} candidates", explain.rerank_candidates);
+   } else if let Some(reason) = explain.rerank_skipped {
+      println!("  rerank: skipped ({reason})");
+   }
+
+   if !explain.segments.is_empty() {
+      println!("  segments: {}", explain.segments.len());
+      for segment in &explain.segments {
+         let rows = segment
+            .rows
+            .map(|rows| format!(", rows={rows}"))
+            .unwrap_or_default();
+         let vector_index = match segment.vector_indexed {
+            Some(true) => ", vector_index=yes",
+            Some(false) => ", vector_index=no",
+            None => "",
+         };
+         println!(
+            "    {}: candidates={}, results={}{}{}",
+            segment.table, segment.candidates, segment.results, rows, vector_index
+         );
+      }
+      let unindexed = explain
+         .segments
+         .iter()
+         .filter(|segment| segment.missing_vector_index())
+         .count();
+      if unindexed > 0 {
+         let warning = format!(
+            "    warning: {unindexed} segment(s) with {VECTOR_INDEX_MIN_ROWS}+ rows have no \
+             vector index, so dense search scans every row; run `ggrep compact --force` or \
+             re-index"
+         );
+         if plain {
+            println!("{warning}");
+         } else {
+            println!("{}", style(warning).yellow());
+         }
+      }
+   }
+
+   if let Some(counts) = &explain.candidates {
+      println!(
+         "  candidates (raw/deduped): vector={}/{}, doc={}/{}, graph={}/{}, fts={}/{}, total={}/{}",
+         counts.vector.raw,
+         counts.vector.deduped,
+         counts.doc.raw,
+         counts.doc.deduped,
+         counts.graph.raw,
+         counts.graph.deduped,
+         counts.fts.raw,
+         counts.fts.deduped,
+         counts.raw_total(),
+         counts.deduped_total()
+      );
+      let ranges: Vec<String> = [
+         ("vector", counts.vector.scores),
+         ("doc", counts.doc.scores),
+         ("graph", counts.graph.scores),
+         ("fts", counts.fts.scores),
+         ("rerank", explain.rerank_scores),
+      ]
+      .into_iter()
+      .filter_map(|(source, range)| {
+         range.map(|r| format!("{source}={:.3}/{:.3}/{:.3}", r.min, r.mean, r.max))
+      })
+      .collect();
+      if !ranges.is_empty() {
+         println!("  score ranges (min/mean/max): {}", ranges.join(", "));
+      }
+      if counts.filtered_not_visible > 0 {
+         println!(
+            "  filtered_not_visible: {} (hidden by tombstones or newer segments)",
+            counts.filtered_not_visible
+         );
+      }
+   }
+
+   if let Some(tombstones) = &explain.tombstones {
+      println!(
+         "  tombstones: total={}, suppressed_candidates={}",
+         tombstones.total, tombstones.suppressed
+      );
+   }
+
+   if let Some(filters) = &explain.sql_filters {
+      println!("  sql_filters:");
+      println!("    code: {}", filters.code);
+      println!("    doc: {}", filters.doc);
+      println!("    graph: {}", filters.graph);
+      println!("    fts: {}", filters.fts);
+   }
+
+   if let Some(embedder) = &meta.embedder {
+      println!("  embedder: backend={}, ready={}", embedder.backend, embedder.ready);
+      if embedder.backend == "dummy" {
+         println!("  note: dummy embedder active; scores are not semantic");
+      } else if !embedder.ready {
+         println!("  note: embedder was still warming up; results may be degraded");
+      }
+   }
 
    if let Some(timings) = &meta.timings_ms {
       println!(
-         "  timings_ms: admission={}, snapshot_read={}, retrieve={}, rank={}, format={}",
-         timings.admission, timings.snapshot_read, timings.retrieve, timings.rank, timings.format
+         "  timings_ms: admission={}, snapshot_read={}, embed={}, retrieve={}, rerank={}, \
+          rank={}, format={}",
+         timings.admission,
+         timings.snapshot_read,
+         timings.embed,
+         timings.retrieve,
+         timings.rerank,
+         timings.rank,
+         timings.format
       );
    }
 
@@ -1275,3 +3183,470 @@ fn print_explain(explain: &SearchExplain, plain: bool) {
       }
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn result(path: &str, content: &str) -> SearchResult {
+      SearchResult {
+         path:           PathBuf::from(path),
+         score:          1.0,
+         norm_score:     None,
+         match_pct:      None,
+         content:        content.to_string(),
+         chunk_type:     None,
+         symbol:         None,
+         start_line:     Some(10),
+         end_line:       Some(10 + content.lines().count()),
+         is_anchor:      None,
+         language:       "rust".to_string(),
+         author:         None,
+         commit:         None,
+         committed_at:   None,
+         segment_table:  None,
+         context_before: 0,
+         context_after:  0,
+      }
+   }
+
+   #[test]
+   fn file_counts_rank_files_by_hits() {
+      let results: Vec<_> =
+         ["src/b.rs", "src/a.rs", "src/c.rs", "src/b.rs", "src/a.rs", "src/b.rs"]
+            .into_iter()
+            .map(|path| result(path, "fn f() {}\n"))
+            .collect();
+      let counts = file_counts(&results);
+      let counts: Vec<_> = counts
+         .iter()
+         .map(|file| (file.path.to_str().unwrap(), file.count))
+         .collect();
+      assert_eq!(counts, [("src/b.rs", 3), ("src/a.rs", 2), ("src/c.rs", 1)]);
+      assert!(file_counts(&[]).is_empty());
+
+      let tied = [result("src/z.rs", ""), result("src/y.rs", "")];
+      let tied: Vec<_> = file_counts(&tied)
+         .into_iter()
+         .map(|file| file.path)
+         .collect();
+      assert_eq!(tied, [PathBuf::from("src/y.rs"), PathBuf::from("src/z.rs")]);
+   }
+
+   #[test]
+   fn highlight_line_folds_case_across_scripts() {
+      console::set_colors_enabled(true);
+      let mark = |text: &str| style(text).yellow().underlined().to_string();
+
+      assert_eq!(highlight_line("no match here", &["zzz"]), "no match here");
+      assert!(matches!(highlight_line("plain", &[]), Cow::Borrowed(_)));
+      assert_eq!(
+         highlight_line("let Config = config::load();", &["CONFIG"]),
+         format!("let {} = {}::load();", mark("Config"), mark("config"))
+      );
+      // Multibyte text around and inside matches stays intact.
+      assert_eq!(
+         highlight_line("// Überprüfe die Größe: GRÖSSE", &["größe"]),
+         format!("// Überprüfe die {}: GRÖSSE", mark("Größe"))
+      );
+      assert_eq!(
+         highlight_line("ÉCOLE école Ecole", &["école"]),
+         format!("{} {} Ecole", mark("ÉCOLE"), mark("école"))
+      );
+      // The Kelvin sign folds to an ASCII `k` but is three bytes wide.
+      assert_eq!(
+         highlight_line("5\u{212A} ok", &["k"]),
+         format!("5{} o{}", mark("\u{212A}"), mark("k"))
+      );
+      assert_eq!(
+         highlight_line("日本語のテキスト", &["テキスト"]),
+         format!("日本語の{}", mark("テキスト"))
+      );
+   }
+
+   fn outcome(results: Vec<SearchResult>) -> SearchOutcome {
+      SearchOutcome {
+         results,
+         status: SearchStatus::Ready,
+         progress: None,
+         timings_ms: None,
+         limits_hit: vec![],
+         warnings: vec![],
+         embedder: None,
+         candidates: None,
+         segments: vec![],
+         rerank: None,
+         ipc: None,
+         snapshot_id: None,
+         execution: SearchExecution::InProcess,
+         expansions: vec![],
+         funnel: vec![],
+      }
+   }
+
+   #[test]
+   fn min_score_filters_and_renormalizes() {
+      let scored = |score: f32| SearchResult { score, ..result("src/a.rs", "") };
+      let mut results: Vec<_> = [0.9, 0.5, 0.2, 0.1].into_iter().map(scored).collect();
+      apply_match_pcts(&mut results);
+      let mut filtered = outcome(results);
+
+      apply_min_score(&mut filtered, None);
+      assert_eq!(filtered.results.len(), 4);
+      assert!(filtered.funnel.is_empty());
+
+      let before: Vec<_> = filtered.results.iter().map(|r| r.norm_score).collect();
+      apply_min_score(&mut filtered, Some(0.3));
+      let scores: Vec<_> = filtered.results.iter().map(|r| r.score).collect();
+      assert_eq!(scores, [0.9, 0.5]);
+      let stage = filtered.funnel.last().expect("min_score stage");
+      assert_eq!((stage.stage.as_str(), stage.remaining), ("min_score", 2));
+
+      // Percentages describe the results shown, not the ones dropped.
+      let norms: Vec<_> = filtered.results.iter().map(|r| r.norm_score).collect();
+      assert_eq!(norms, crate::util::normalize_scores(&scores));
+      assert_ne!(norms, before[..2]);
+      let pcts: Vec<_> = filtered.results.iter().map(|r| r.match_pct).collect();
+      assert_eq!(pcts, crate::util::compute_match_pcts(&scores));
+   }
+
+   #[test]
+   fn context_lines_trim_both_ends() {
+      // Two lines of context on each side of a two-line match.
+      let mut with_context = result("src/a.rs", "b1\nb2\nm1\nm2\na1\na2\n");
+      with_context.context_before = 2;
+      with_context.context_after = 2;
+      let mut results = vec![with_context];
+
+      apply_context_lines(&mut results, None, None);
+      assert_eq!(results[0].content, "b1\nb2\nm1\nm2\na1\na2\n");
+
+      apply_context_lines(&mut results, Some(1), Some(1));
+      assert_eq!(results[0].content, "b2\nm1\nm2\na1\n");
+      assert_eq!((results[0].context_before, results[0].context_after), (1, 1));
+      assert_eq!(results[0].start_line, Some(11));
+      assert_eq!(results[0].end_line, Some(15));
+
+      // Asking for more context than there is keeps what is there.
+      apply_context_lines(&mut results, Some(5), None);
+      assert_eq!(results[0].content, "b2\nm1\nm2\na1\n");
+      assert_eq!(results[0].start_line, Some(11));
+   }
+
+   #[test]
+   fn zero_context_keeps_only_the_match() {
+      let mut with_context = result("src/a.rs", "b1\nm1\na1\na2");
+      with_context.context_before = 1;
+      with_context.context_after = 2;
+      let mut results = vec![with_context, result("src/b.rs", "m1\nm2\n")];
+
+      apply_context_lines(&mut results, Some(0), Some(0));
+      assert_eq!(results[0].content, "m1\n");
+      assert_eq!((results[0].context_before, results[0].context_after), (0, 0));
+      assert_eq!((results[0].start_line, results[0].end_line), (Some(11), Some(12)));
+      // Results without neighbouring context are left alone.
+      assert_eq!(results[1].content, "m1\nm2\n");
+      assert_eq!(results[1].start_line, Some(10));
+   }
+
+   fn ranked(count: usize, snapshot_id: &str) -> SearchOutcome {
+      let results = (0..count)
+         .map(|i| result(&format!("src/f{i}.rs"), "fn f() {}\n"))
+         .collect();
+      SearchOutcome { snapshot_id: Some(snapshot_id.to_string()), ..outcome(results) }
+   }
+
+   fn paths(outcome: &SearchOutcome) -> Vec<String> {
+      outcome
+         .results
+         .iter()
+         .map(|r| r.path.to_string_lossy().into_owned())
+         .collect()
+   }
+
+   #[test]
+   fn cursor_resumes_where_the_last_page_ended() {
+      let first = SearchPage::first(2);
+      let mut out = ranked(first.fetch_limit(), "snap-1");
+      let token = first.take(&mut out, "fp", 100).expect("second page");
+      assert_eq!(paths(&out), ["src/f0.rs", "src/f1.rs"]);
+
+      let second = SearchPage::resume(&token).expect("valid cursor");
+      second.check_fingerprint("fp").expect("same query");
+      second
+         .check_snapshot(Some("snap-1"))
+         .expect("same snapshot");
+      assert_eq!(second.snapshot_id.as_deref(), Some("snap-1"));
+      // Five results in all: the second page is full, the third is the last.
+      let mut out = ranked(5, "snap-1");
+      let token = second.take(&mut out, "fp", 100).expect("third page");
+      assert_eq!(paths(&out), ["src/f2.rs", "src/f3.rs"]);
+
+      let third = SearchPage::resume(&token).expect("valid cursor");
+      let mut out = ranked(5, "snap-1");
+      assert_eq!(third.take(&mut out, "fp", 100), None);
+      assert_eq!(paths(&out), ["src/f4.rs"]);
+   }
+
+   #[test]
+   fn cursor_is_rejected_for_another_query_or_snapshot() {
+      let mut out = ranked(3, "snap-1");
+      let token = SearchPage::first(1)
+         .take(&mut out, "fp", 100)
+         .expect("second page");
+      let page = SearchPage::resume(&token).expect("valid cursor");
+
+      let err = page.check_fingerprint("other").unwrap_err();
+      assert_eq!(classify_error(&err).0, "invalid_request");
+      let err = page.check_snapshot(Some("snap-2")).unwrap_err();
+      assert_eq!(classify_error(&err).0, "invalid_request");
+      page
+         .check_snapshot(None)
+         .expect("no active snapshot to compare");
+      assert!(SearchPage::resume("not-a-cursor").is_err());
+   }
+
+   #[test]
+   fn query_fingerprint_covers_ranking_and_filter_options() {
+      let fingerprint = |options: &QueryOptions, min_score: Option<f32>| {
+         compute_query_fingerprint(
+            "retry",
+            SearchMode::Balanced,
+            1,
+            10,
+            true,
+            None,
+            SnippetMode::Default,
+            options,
+            min_score,
+         )
+         .expect("fingerprint")
+      };
+      let base = QueryOptions::default();
+      let plain = fingerprint(&base, None);
+
+      let variants = [
+         QueryOptions { languages: vec!["rust".to_string()], ..base.clone() },
+         QueryOptions { excludes: vec!["vendor/**".to_string()], ..base.clone() },
+         QueryOptions { grep: Some("retry".to_string()), ..base.clone() },
+         QueryOptions { recent_boost_days: Some(0), ..base.clone() },
+         QueryOptions { dedup_content: true, ..base.clone() },
+         QueryOptions { no_fts: true, ..base.clone() },
+         QueryOptions { span_merge: SpanMerge::Off, ..base.clone() },
+      ];
+      for options in &variants {
+         assert_ne!(fingerprint(options, None), plain, "{options:?}");
+      }
+      assert_ne!(fingerprint(&base, Some(0.5)), plain);
+
+      // The page offset and explain diagnostics don't change the query.
+      let paged = QueryOptions { offset: 20, index_stats: true, ..base.clone() };
+      assert_eq!(fingerprint(&paged, None), plain);
+   }
+
+   #[test]
+   fn capped_fetch_that_comes_back_full_has_more() {
+      // The last page inside a 100-result cap: the probe row was cut, but
+      // the fetch came back full, so results may continue past it.
+      let page =
+         SearchPage { size: 50, offset: 50, snapshot_id: None, fingerprint: None };
+      assert!(page.fetch_limit() > 100);
+      let mut out = ranked(100, "snap-1");
+      let token = page.take(&mut out, "fp", 100).expect("next page");
+      assert_eq!(out.results.len(), 50);
+
+      // That next page starts past the cap: empty, final, and flagged.
+      let past = SearchPage::resume(&token).expect("valid cursor");
+      let mut out = ranked(100, "snap-1");
+      assert_eq!(past.take(&mut out, "fp", 100), None);
+      assert!(out.results.is_empty());
+      let codes: Vec<_> = out.limits_hit.iter().map(|hit| hit.code.as_str()).collect();
+      assert_eq!(codes, ["offset_exceeds_max_results"]);
+
+      let mut out = ranked(80, "snap-1");
+      assert_eq!(page.take(&mut out, "fp", 100), None);
+      assert_eq!(out.results.len(), 30);
+   }
+
+   #[test]
+   fn batch_falls_back_only_when_daemon_is_unavailable() {
+      let lost = Error::Io(io::Error::from(io::ErrorKind::BrokenPipe));
+      assert!(daemon_unavailable(&lost));
+      let ipc = Error::Ipc(crate::error::IpcError::MessageTooLarge { len: 2, limit: 1 });
+      assert!(daemon_unavailable(&ipc));
+      let incompatible =
+         Error::Server { op: "search", reason: "incompatible: protocol 2".to_string() };
+      assert!(daemon_unavailable(&incompatible));
+
+      for reason in [
+         "busy: too many in-flight requests",
+         "timeout: search exceeded budget",
+         "invalid_request: offset too large",
+         "timeout waiting for daemon response (45s)",
+      ] {
+         let err = Error::Server { op: "search", reason: reason.to_string() };
+         assert!(!daemon_unavailable(&err), "{reason}");
+      }
+   }
+
+   #[test]
+   fn repeat_keeps_explicit_flags() {
+      let last = LastSearch {
+         query:    "parse config".to_string(),
+         path:     PathBuf::from("/repo/src"),
+         mode:     SearchMode::Implementation,
+         max:      25,
+         per_file: 3,
+      };
+
+      let replayed = last.clone().with_overrides(None, None, None, None);
+      assert_eq!(replayed, last);
+
+      let overridden = last.with_overrides(
+         Some(PathBuf::from("/repo/docs")),
+         Some(5),
+         Some(1),
+         Some(SearchMode::Discovery),
+      );
+      assert_eq!(overridden.query, "parse config");
+      assert_eq!(overridden.path, PathBuf::from("/repo/docs"));
+      assert_eq!((overridden.max, overridden.per_file), (5, 1));
+      assert_eq!(overridden.mode, SearchMode::Discovery);
+   }
+
+   #[test]
+   fn json_timings_report_total_only_when_asked() {
+      let mut timings = JsonTimings {
+         admission:     1,
+         snapshot_read: 2,
+         embed:         3,
+         retrieve:      4,
+         rerank:        5,
+         rank:          6,
+         format:        7,
+         total:         None,
+      };
+      let json = serde_json::to_value(&timings).expect("serialize");
+      assert!(json.get("total").is_none());
+
+      timings.total = Some(40);
+      let json = serde_json::to_value(&timings).expect("serialize");
+      assert_eq!(json["total"], 40);
+   }
+
+   fn json_output(results: Vec<SearchResult>) -> SearchJsonOutput {
+      let meta = SearchMeta {
+         schema_version: SEARCH_SCHEMA_VERSION,
+         request_id: "req".to_string(),
+         store_id: "store".to_string(),
+         config_fingerprint: "cfg".to_string(),
+         ignore_fingerprint: "ign".to_string(),
+         query_fingerprint: "qfp".to_string(),
+         embed_config_fingerprint: "efp".to_string(),
+         snapshot_id: Some("snap-1".to_string()),
+         snapshot_created_at: None,
+         snapshot_age_secs: None,
+         files_indexed: None,
+         chunks_indexed: None,
+         degraded: false,
+         git: None,
+         mode: SearchMode::Balanced,
+         execution: SearchExecution::InProcess,
+         sorted_by: "rank",
+         limits: ExplainLimits {
+            max_results: 10,
+            per_file: 5,
+            snippet: "default".to_string(),
+            max_candidates: 100,
+            candidate_multiplier: 4,
+            store_limit: 40,
+            max_total_snippet_bytes: 1 << 20,
+            max_snippet_bytes_per_result: 1 << 16,
+            max_open_segments_per_query: 16,
+         },
+         limits_hit: vec![],
+         warnings: vec![],
+         timings_ms: None,
+         embedder: None,
+      };
+      build_json_output(meta, outcome(results), None)
+   }
+
+   #[test]
+   fn output_budget_bounds_the_printed_bytes() {
+      // Quotes, backslashes, newlines and control characters grow when escaped.
+      let content = "let s = \"a\\\\b\";\n\t\u{1}é\n".repeat(40);
+      let results: Vec<_> = (0..6)
+         .map(|i| result(&format!("src/\"q{i}\".rs"), &content))
+         .collect();
+
+      for options in [
+         SearchOptions::default(),
+         SearchOptions { ndjson: true, ..SearchOptions::default() },
+         SearchOptions { json_incremental: true, ..SearchOptions::default() },
+      ] {
+         for max_bytes in [3000, 6000] {
+            let mut output = json_output(results.clone());
+            let render = |output: &SearchJsonOutput| -> Result<Vec<u8>> {
+               let mut rendered = Vec::new();
+               write_json_output(&mut rendered, output, &options)?;
+               Ok(rendered)
+            };
+            apply_output_budget(&mut output, max_bytes, |output| Ok(render(output)?.len()))
+               .expect("budget");
+
+            let rendered = render(&output).expect("render");
+            assert!(
+               rendered.len() <= max_bytes,
+               "{} > {max_bytes} (ndjson: {}, incremental: {})",
+               rendered.len(),
+               options.ndjson,
+               options.json_incremental,
+            );
+            assert!(!output.results.is_empty());
+            assert_eq!(output.meta.limits_hit[0].code, "max_output_bytes");
+         }
+      }
+   }
+
+   #[test]
+   fn ndjson_writes_meta_then_one_result_per_line() {
+      let mut output = json_output(vec![result("src/a.rs", "a\n"), result("src/b.rs", "b\n")]);
+      output.next_cursor = Some(Some("cursor".to_string()));
+      let mut out = Vec::new();
+      write_ndjson(&mut out, &output).expect("write");
+
+      let text = String::from_utf8(out).expect("utf-8");
+      assert!(text.ends_with('\n'), "every line is newline-terminated");
+      let lines: Vec<serde_json::Value> = text
+         .lines()
+         .map(|line| serde_json::from_str(line).expect("one JSON object per line"))
+         .collect();
+      assert_eq!(lines.len(), 3);
+      assert!(lines.iter().all(serde_json::Value::is_object));
+
+      // The meta record comes first and carries the cursor, never results.
+      assert_eq!(lines[0]["request_id"], "req");
+      assert_eq!(lines[0]["next_cursor"], "cursor");
+      assert!(lines[0].get("results").is_none());
+      // Then the results, in rank order.
+      for (line, path) in lines[1..].iter().zip(["src/a.rs", "src/b.rs"]) {
+         assert_eq!(line["path"], path);
+         assert!(line.get("request_id").is_none());
+      }
+
+      // With `--count`, the per-file counts replace the results.
+      output.counts = Some(file_counts(&output.results));
+      output.results.clear();
+      let mut out = Vec::new();
+      write_ndjson(&mut out, &output).expect("write");
+      let text = String::from_utf8(out).expect("utf-8");
+      let lines: Vec<serde_json::Value> = text
+         .lines()
+         .map(|line| serde_json::from_str(line).expect("one JSON object per line"))
+         .collect();
+      assert_eq!(lines.len(), 3);
+      assert_eq!(lines[1]["path"], "src/a.rs");
+      assert_eq!(lines[1]["count"], 1);
+   }
+}
@@ -6,7 +6,7 @@
 
 use std::{
    collections::{HashMap, HashSet, VecDeque},
-   path::{Path, PathBuf},
+   path::{Component, Path, PathBuf},
    sync::{
       Arc,
       atomic::{AtomicBool, AtomicU8, AtomicU64, AtomicUsize, Ordering},
@@ -25,6 +25,7 @@ use tokio::{
 use crate::{
    Result, config,
    embed::{Embedder, DummyEmbedder, candle::CandleEmbedder},
+   error::{Error, IpcError},
    file::{
       FileWatcher, IgnorePatterns, LocalFileSystem, WatchAction, normalize_relative,
       resolve_candidate,
@@ -33,13 +34,16 @@ use crate::{
    ipc::{self, Request, Response, ServerStatus},
    meta::MetaStore,
    snapshot::{
-      CompactionOptions, SnapshotManager, SnapshotManifest, compaction_overdue, compact_store,
-      gc_snapshots, pins::SnapshotPins, GcOptions,
+      ChunkingMode, CompactionOptions, SnapshotManager, SnapshotManifest, compaction_overdue,
+      compact_store, gc_snapshots, pins::SnapshotPins, GcOptions,
    },
    search::SearchEngine,
    store::LanceStore,
    sync::{ChangeSet, SyncEngine, SyncOptions},
-   types::{SearchMode, SearchResponse, SearchResult, SearchStatus, SearchTimings, SyncProgress},
+   types::{
+      QueryOptions, SearchMode, SearchResponse, SearchResult, SearchStatus, SearchTimings,
+      SyncProgress,
+   },
    usock,
    util::sanitize_output,
    version,
@@ -51,6 +55,8 @@ const PERF_WINDOW: usize = 200;
 struct Server {
    store: Arc<LanceStore>,
    embedder: Arc<dyn Embedder>,
+   /// Shared across queries so repeated queries hit its embedding cache.
+   engine: SearchEngine,
    store_id: String,
    config_fingerprint: String,
    ignore_fingerprint: String,
@@ -73,6 +79,8 @@ struct Server {
    snapshot_meta: RwLock<SnapshotMeta>,
    snapshot_pins: SnapshotPins,
    allow_degraded: bool,
+   chunking: Option<ChunkingMode>,
+   max_chunks_per_file: Option<usize>,
    compaction_in_progress: AtomicBool,
    perf_metrics: ParkingMutex<PerfMetrics>,
    query_total: AtomicU64,
@@ -193,6 +201,41 @@ impl Server {
       metrics.record(latency_ms, segments, PERF_WINDOW);
    }
 
+   /// Waits for the initial sync, then runs one throwaway query so segment
+   /// handles and embedder models are open before the first real request.
+   /// Returns the number of segments touched and the elapsed time.
+   async fn prewarm(&self) -> Result<(usize, u64)> {
+      while self.indexing.load(Ordering::Relaxed) {
+         time::sleep(Duration::from_millis(100)).await;
+      }
+
+      let start = Instant::now();
+      let snapshot_manager = SnapshotManager::new(
+         Arc::clone(&self.store),
+         self.store_id.clone(),
+         self.config_fingerprint.clone(),
+         self.ignore_fingerprint.clone(),
+      );
+      let snapshot_view = snapshot_manager.open_snapshot_view().await?;
+      let _pin = self.pin_snapshot(&snapshot_view.snapshot_id);
+      self
+         .engine
+         .search_with_mode(
+            &snapshot_view,
+            &self.store_id,
+            "prewarm",
+            1,
+            1,
+            None,
+            true,
+            snapshot_view.include_anchors(),
+            SearchMode::Balanced,
+         )
+         .await?;
+
+      Ok((snapshot_view.segment_tables().len(), start.elapsed().as_millis() as u64))
+   }
+
    fn perf_snapshot(&self) -> (u64, u64, u64) {
       let metrics = self.perf_metrics.lock();
       metrics.snapshot()
@@ -320,6 +363,9 @@ pub async fn execute(
    path: Option<PathBuf>,
    store_id: Option<String>,
    allow_degraded: bool,
+   prewarm: bool,
+   chunking: Option<ChunkingMode>,
+   max_chunks_per_file: Option<usize>,
 ) -> Result<()> {
    let cwd = std::env::current_dir()?.canonicalize()?;
    let requested = path.unwrap_or(cwd).canonicalize()?;
@@ -360,6 +406,16 @@ pub async fn execute(
       Arc::new(CandleEmbedder::new()?)
    };
 
+   let embedder_desc = embedder.describe();
+   println!("Embedder: {}", style(&embedder_desc).dim());
+   if embedder_desc.is_dummy() {
+      println!(
+         "{}",
+         style("Warning: dummy embedder active (GGREP_DUMMY_EMBEDDER); results are not semantic")
+            .yellow()
+      );
+   }
+
    if !embedder.is_ready() {
       println!("{}", style("Waiting for embedder to initialize...").yellow());
       time::sleep(Duration::from_millis(500)).await;
@@ -376,9 +432,11 @@ pub async fn execute(
          created_at:  meta.snapshot_created_at().map(|s| s.to_string()),
       })
       .unwrap_or_default();
+   let engine = SearchEngine::new(Arc::clone(&store), Arc::clone(&embedder));
    let server = Arc::new(Server {
       store,
       embedder,
+      engine,
       store_id: resolved_store_id,
       config_fingerprint,
       ignore_fingerprint,
@@ -403,6 +461,8 @@ pub async fn execute(
       snapshot_meta: RwLock::new(snapshot_meta),
       snapshot_pins: SnapshotPins::default(),
       allow_degraded,
+      chunking,
+      max_chunks_per_file,
       compaction_in_progress: AtomicBool::new(false),
       perf_metrics: ParkingMutex::new(PerfMetrics::new()),
       query_total: AtomicU64::new(0),
@@ -444,6 +504,21 @@ pub async fn execute(
       }
    });
 
+   if prewarm {
+      // Runs alongside the accept loop so clients are served while it warms up.
+      println!("{}", style("Prewarming segments and embedder...").dim());
+      let prewarm_server = Arc::clone(&server);
+      tokio::spawn(async move {
+         match prewarm_server.prewarm().await {
+            Ok((segments, elapsed_ms)) => println!(
+               "{}",
+               style(format!("Prewarm complete ({segments} segments, {elapsed_ms}ms)")).green()
+            ),
+            Err(e) => println!("{}", style(format!("Prewarm skipped: {e}")).yellow()),
+         }
+      });
+   }
+
    println!("\n{}", style("Server listening").green());
    println!("{}", style("Press Ctrl+C to stop").dim());
 
@@ -526,6 +601,15 @@ impl Server {
             .await
          {
             Ok(req) => req,
+            Err(Error::Ipc(IpcError::MessageTooLarge { len, limit })) => {
+               // The body is left unread, so answer once and drop the connection.
+               let response = Response::Error {
+                  code:    "request_too_large".to_string(),
+                  message: format!("request of {len} bytes exceeds max_request_bytes ({limit})"),
+               };
+               let _ = buffer.send(&mut stream, &response).await;
+               break;
+            },
             Err(e) => {
                if e.to_string().contains("failed to read length") {
                   break;
@@ -571,7 +655,16 @@ impl Server {
                   client_id = hello_client_id;
                   self.handle_handshake(protocol_versions, store_id, config_fingerprint)
                },
-               Request::Search { query, limit, per_file, mode, path, rerank } => {
+               Request::Search {
+                  query,
+                  limit,
+                  per_file,
+                  mode,
+                  path,
+                  rerank,
+                  snapshot_id,
+                  options,
+               } => {
                   self
                      .handle_search(
                         query,
@@ -580,6 +673,8 @@ impl Server {
                         mode,
                         path,
                         rerank,
+                        snapshot_id.as_deref(),
+                        &options,
                         client_id.as_deref(),
                      )
                      .await
@@ -606,6 +701,7 @@ impl Server {
                         .saturating_sub(self.open_handles_sem.available_permits())
                         as u64,
                      segments_budget:   self.max_open_segments_global as u64,
+                     query_cache_hits: self.engine.query_cache_hits(),
                   },
                }
                },
@@ -617,7 +713,24 @@ impl Server {
             }
          };
 
-         if let Err(e) = buffer.send(&mut stream, &response).await {
+         let max_response_bytes = config::get().max_response_bytes;
+         let sent = match buffer
+            .send_with_limit(&mut stream, &response, max_response_bytes)
+            .await
+         {
+            Err(Error::Ipc(IpcError::MessageTooLarge { len, limit })) => {
+               let response = Response::Error {
+                  code:    "response_too_large".to_string(),
+                  message: format!(
+                     "response of {len} bytes exceeds max_response_bytes ({limit}); request fewer \
+                      results or raise the limit"
+                  ),
+               };
+               buffer.send(&mut stream, &response).await
+            },
+            sent => sent,
+         };
+         if let Err(e) = sent {
             tracing::debug!("Client write error: {}", e);
             break;
          }
@@ -656,6 +769,8 @@ impl Server {
       mode: SearchMode,
       path: Option<PathBuf>,
       rerank: bool,
+      snapshot_id: Option<&str>,
+      options: &QueryOptions,
       client_id: Option<&str>,
    ) -> Response {
       if query.is_empty() {
@@ -665,6 +780,17 @@ impl Server {
          };
       }
 
+      let search_path = match path.as_deref().map(|p| resolve_search_scope(&self.root, p)) {
+         Some(None) => {
+            return Response::Error {
+               code:    "invalid_request".to_string(),
+               message: "path must stay within the repository root".to_string(),
+            };
+         },
+         Some(Some(scope)) => Some(scope),
+         None => None,
+      };
+
       self.query_total.fetch_add(1, Ordering::Relaxed);
 
       let cfg = config::get();
@@ -702,15 +828,6 @@ impl Server {
          time::sleep(Duration::from_millis(delay_ms)).await;
       }
 
-      let search_path = path.as_ref().map(|p| {
-         if p.is_absolute() {
-            p.clone()
-         } else {
-            self.root.join(p)
-         }
-      });
-
-      let engine = SearchEngine::new(Arc::clone(&self.store), Arc::clone(&self.embedder));
       let snapshot_start = Instant::now();
       let snapshot_manager = SnapshotManager::new(
          Arc::clone(&self.store),
@@ -718,7 +835,11 @@ impl Server {
          self.config_fingerprint.clone(),
          self.ignore_fingerprint.clone(),
       );
-      let snapshot_view = match snapshot_manager.open_snapshot_view().await {
+      let snapshot_view = match snapshot_id {
+         Some(snapshot_id) => snapshot_manager.open_snapshot_view_by_id(snapshot_id).await,
+         None => snapshot_manager.open_snapshot_view().await,
+      };
+      let snapshot_view = match snapshot_view {
          Ok(view) => view,
          Err(e) => {
             drop(open_handle_permit);
@@ -733,7 +854,7 @@ impl Server {
       let _pin = self.pin_snapshot(&snapshot_view.snapshot_id);
       let snapshot_read_ms = snapshot_start.elapsed().as_millis() as u64;
       let store_id = self.store_id.as_str();
-      let include_anchors = config::get().fast_mode;
+      let include_anchors = snapshot_view.include_anchors();
       let segments_touched = snapshot_view.segment_tables().len();
       let remaining = deadline.saturating_duration_since(Instant::now());
       if remaining.is_zero() {
@@ -745,7 +866,7 @@ impl Server {
       }
 
       let mut shutdown_rx = self.shutdown.subscribe();
-      let search_fut = engine.search_with_mode(
+      let search_fut = self.engine.search_with_options(
          &snapshot_view,
          store_id,
          &query,
@@ -755,6 +876,7 @@ impl Server {
          rerank,
          include_anchors,
          mode,
+         options,
       );
 
       let query_start = Instant::now();
@@ -851,8 +973,13 @@ impl Server {
                      segment_table:   r.segment_table.clone(),
                      start_line:      r.start_line,
                      num_lines:       r.num_lines,
+                     end_line:        Some(r.start_line + r.num_lines),
                      chunk_type:      r.chunk_type,
+                     symbol:          r.symbol,
                      is_anchor:       r.is_anchor,
+                     language:        r.language,
+                     context_before:  r.context_before,
+                     context_after:   r.context_after,
                   }
                })
                .collect();
@@ -888,6 +1015,13 @@ impl Server {
                timings_ms,
                limits_hit,
                warnings,
+               embedder: response.embedder,
+               candidates: response.candidates,
+               segments: response.segments,
+               rerank: response.rerank,
+               snapshot_id: Some(snapshot_view.snapshot_id.clone()),
+               expansions: response.expansions,
+               funnel: response.funnel,
             })
          },
          Err(e) => Response::Error {
@@ -1198,7 +1332,12 @@ impl Server {
             &root,
             changeset,
             false,
-            SyncOptions { allow_degraded: self.allow_degraded, ..SyncOptions::default() },
+            SyncOptions {
+               allow_degraded: self.allow_degraded,
+               chunking: self.chunking,
+               max_chunks_per_file: self.max_chunks_per_file,
+               ..SyncOptions::default()
+            },
             &mut callback,
          )
          .await;
@@ -1323,10 +1462,60 @@ fn handshake_response(
    }
 }
 
+/// Resolves a client-supplied search scope against `root`, lexically
+/// normalizing `.`/`..`. Returns `None` when the scope (or, if it exists, the
+/// path it resolves to through symlinks) escapes `root`.
+fn resolve_search_scope(root: &Path, path: &Path) -> Option<PathBuf> {
+   let joined = if path.is_absolute() {
+      path.to_path_buf()
+   } else {
+      root.join(path)
+   };
+   let mut scope = PathBuf::new();
+   for component in joined.components() {
+      match component {
+         Component::CurDir => {},
+         Component::ParentDir => {
+            if !scope.pop() {
+               return None;
+            }
+         },
+         other => scope.push(other),
+      }
+   }
+   if !scope.starts_with(root) {
+      return None;
+   }
+   if let Ok(real) = scope.canonicalize()
+      && !real.starts_with(root)
+   {
+      return None;
+   }
+   Some(scope)
+}
+
 #[cfg(test)]
 mod tests {
    use super::*;
 
+   #[test]
+   fn search_scope_rejects_paths_escaping_root() {
+      let root = Path::new("/repo");
+      assert_eq!(resolve_search_scope(root, Path::new("src")), Some(PathBuf::from("/repo/src")));
+      assert_eq!(
+         resolve_search_scope(root, Path::new("./src/../docs")),
+         Some(PathBuf::from("/repo/docs"))
+      );
+      assert_eq!(resolve_search_scope(root, Path::new("..")), None);
+      assert_eq!(resolve_search_scope(root, Path::new("../other/src")), None);
+      assert_eq!(resolve_search_scope(root, Path::new("src/../../etc")), None);
+      assert_eq!(resolve_search_scope(root, Path::new("/etc")), None);
+      assert_eq!(
+         resolve_search_scope(root, Path::new("/repo/src")),
+         Some(PathBuf::from("/repo/src"))
+      );
+   }
+
    #[test]
    fn handshake_mismatch_store_id_returns_invalid_request() {
       let response = handshake_response("store-a", "cfg", &[2], "store-b", "cfg");
@@ -20,15 +20,77 @@ use crate::{
    usock, util,
 };
 
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const RPC_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// Result of asking a running daemon for its health.
+enum DaemonProbe {
+   Healthy(ipc::ServerStatus),
+   /// Socket present but nothing accepts connections.
+   Stale,
+   Incompatible,
+   Unresponsive,
+   /// Answered the health request with something other than a status.
+   Unknown,
+}
+
+impl DaemonProbe {
+   fn state(&self) -> &'static str {
+      match self {
+         Self::Healthy(status) if status.indexing => "indexing",
+         Self::Healthy(_) => "ready",
+         Self::Stale => "stale",
+         Self::Incompatible => "incompatible",
+         Self::Unresponsive => "unresponsive",
+         Self::Unknown => "unknown",
+      }
+   }
+}
+
+async fn probe_daemon(store_id: &str, buffer: &mut ipc::SocketBuffer) -> DaemonProbe {
+   let mut stream = match time::timeout(CONNECT_TIMEOUT, usock::Stream::connect(store_id)).await {
+      Ok(Ok(s)) => s,
+      Ok(Err(_)) | Err(_) => return DaemonProbe::Stale,
+   };
+
+   let config_fingerprint = MetaStore::load(store_id)
+      .ok()
+      .and_then(|meta| meta.config_fingerprint().map(|s| s.to_string()))
+      .unwrap_or_default();
+
+   let handshake = time::timeout(
+      RPC_TIMEOUT,
+      client_handshake(&mut stream, store_id, &config_fingerprint, "ggrep-status"),
+   )
+   .await;
+
+   if !matches!(handshake, Ok(Ok(HandshakeOutcome::Compatible))) {
+      return DaemonProbe::Incompatible;
+   }
+
+   let sent = time::timeout(RPC_TIMEOUT, buffer.send(&mut stream, &Request::Health)).await;
+   if !matches!(sent, Ok(Ok(()))) {
+      return DaemonProbe::Unresponsive;
+   }
+
+   let recv = time::timeout(
+      RPC_TIMEOUT,
+      buffer.recv_with_limit(&mut stream, config::get().max_response_bytes),
+   )
+   .await;
+   match recv {
+      Ok(Ok(Response::Health { status })) => DaemonProbe::Healthy(status),
+      Ok(Ok(_)) => DaemonProbe::Unknown,
+      Ok(Err(_)) | Err(_) => DaemonProbe::Unresponsive,
+   }
+}
+
 /// Executes the status command to show running servers.
 pub async fn execute(json: bool) -> Result<()> {
    if json {
       return execute_json().await;
    }
 
-   const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
-   const RPC_TIMEOUT: Duration = Duration::from_millis(2000);
-
    let servers = usock::list_running_servers();
 
    if servers.is_empty() {
@@ -41,45 +103,9 @@ pub async fn execute(json: bool) -> Result<()> {
 
    let mut buffer = ipc::SocketBuffer::new();
    for store_id in servers {
-      let stream = match time::timeout(CONNECT_TIMEOUT, usock::Stream::connect(&store_id)).await {
-         Ok(Ok(s)) => s,
-         Ok(Err(_)) | Err(_) => {
-            println!("  {} {} {}", style("●").red(), store_id, style("(stale)").dim());
-            continue;
-         },
-      };
-
-      let mut stream = stream;
-
-      let config_fingerprint = MetaStore::load(&store_id)
-         .ok()
-         .and_then(|meta| meta.config_fingerprint().map(|s| s.to_string()))
-         .unwrap_or_default();
-
-      let handshake = time::timeout(
-         RPC_TIMEOUT,
-         client_handshake(&mut stream, &store_id, &config_fingerprint, "ggrep-status"),
-      )
-      .await;
-
-      if !matches!(handshake, Ok(Ok(HandshakeOutcome::Compatible))) {
-         println!("  {} {} {}", style("●").yellow(), store_id, style("(incompatible)").dim());
-         continue;
-      }
-
-      let sent = time::timeout(RPC_TIMEOUT, buffer.send(&mut stream, &Request::Health)).await;
-      if !matches!(sent, Ok(Ok(()))) {
-         println!("  {} {} {}", style("●").yellow(), store_id, style("(unresponsive)").dim());
-         continue;
-      }
-
-      let recv = time::timeout(
-         RPC_TIMEOUT,
-         buffer.recv_with_limit(&mut stream, config::get().max_response_bytes),
-      )
-      .await;
-      match recv {
-         Ok(Ok(Response::Health { status })) => {
+      let probe = probe_daemon(&store_id, &mut buffer).await;
+      match &probe {
+         DaemonProbe::Healthy(status) => {
             let state = if status.indexing {
                format!("indexing {}%", status.progress)
             } else {
@@ -92,11 +118,12 @@ pub async fn execute(json: bool) -> Result<()> {
                style(format!("({state}, files: {})", status.files)).dim()
             );
          },
-         Ok(Ok(_)) => {
-            println!("  {} {} {}", style("●").yellow(), store_id, style("(unknown)").dim());
+         DaemonProbe::Stale => {
+            println!("  {} {} {}", style("●").red(), store_id, style("(stale)").dim());
          },
-         Ok(Err(_)) | Err(_) => {
-            println!("  {} {} {}", style("●").yellow(), store_id, style("(unresponsive)").dim());
+         _ => {
+            let state = format!("({})", probe.state());
+            println!("  {} {} {}", style("●").yellow(), store_id, style(state).dim());
          },
       }
    }
@@ -117,6 +144,19 @@ struct StatusJson {
    queries:            QueriesJson,
    resources:          ResourcesJson,
    performance:        PerformanceJson,
+   /// Every daemon running on this machine, not just the one for this repo.
+   daemons:            Vec<RunningDaemonJson>,
+}
+
+#[derive(Serialize)]
+struct RunningDaemonJson {
+   store_id:    String,
+   socket_path: String,
+   pid:         Option<u32>,
+   state:       &'static str,
+   /// Full health report; `None` unless the daemon answered the health
+   /// request.
+   status:      Option<ipc::ServerStatus>,
 }
 
 #[derive(Serialize)]
@@ -159,6 +199,7 @@ struct QueriesJson {
    busy_total:      u64,
    timeouts_total:  u64,
    slow_total:      u64,
+   cache_hits:      u64,
 }
 
 #[derive(Serialize)]
@@ -214,9 +255,6 @@ async fn execute_json() -> Result<()> {
 }
 
 pub(crate) async fn collect_status_json(path: &std::path::Path, pretty: bool) -> Result<String> {
-   const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
-   const RPC_TIMEOUT: Duration = Duration::from_millis(2000);
-
    let identity = identity::resolve_index_identity(path)?;
 
    let pid = usock::read_pid(&identity.store_id);
@@ -292,6 +330,7 @@ pub(crate) async fn collect_status_json(path: &std::path::Path, pretty: bool) ->
    let busy_total = status.as_ref().map(|s| s.busy_total).unwrap_or(0);
    let timeouts_total = status.as_ref().map(|s| s.timeouts_total).unwrap_or(0);
    let slow_total = status.as_ref().map(|s| s.slow_total).unwrap_or(0);
+   let cache_hits = status.as_ref().map(|s| s.query_cache_hits).unwrap_or(0);
    let indexing = status.as_ref().map(|s| s.indexing).unwrap_or(false);
 
    let store_path = config::data_dir().join(&identity.store_id);
@@ -345,6 +384,8 @@ pub(crate) async fn collect_status_json(path: &std::path::Path, pretty: bool) ->
    let compaction_time_last_ms =
       meta_store.as_ref().and_then(|m| m.last_compaction_duration_ms());
 
+   let daemons = collect_running_daemons().await;
+
    let json = StatusJson {
       schema_version: 1,
       store_id: identity.store_id,
@@ -380,6 +421,7 @@ pub(crate) async fn collect_status_json(path: &std::path::Path, pretty: bool) ->
          busy_total,
          timeouts_total,
          slow_total,
+         cache_hits,
       },
       resources: ResourcesJson {
          embed_global: EmbedGlobalJson {
@@ -411,6 +453,7 @@ pub(crate) async fn collect_status_json(path: &std::path::Path, pretty: bool) ->
          compaction_time_last_ms,
          compaction_time_budget_ms: cfg.budget_compaction_ms,
       },
+      daemons,
    };
 
    if pretty {
@@ -420,6 +463,26 @@ pub(crate) async fn collect_status_json(path: &std::path::Path, pretty: bool) ->
    }
 }
 
+async fn collect_running_daemons() -> Vec<RunningDaemonJson> {
+   let mut buffer = ipc::SocketBuffer::new();
+   let mut daemons = Vec::new();
+   for store_id in usock::list_running_servers() {
+      let probe = probe_daemon(&store_id, &mut buffer).await;
+      let state = probe.state();
+      daemons.push(RunningDaemonJson {
+         socket_path: usock::socket_path(&store_id).to_string_lossy().into_owned(),
+         pid: usock::read_pid(&store_id),
+         state,
+         status: match probe {
+            DaemonProbe::Healthy(status) => Some(status),
+            _ => None,
+         },
+         store_id,
+      });
+   }
+   daemons
+}
+
 fn pid_started_at(store_id: &str) -> Option<String> {
    let path = usock::pid_path(store_id);
    let meta = fs::metadata(path).ok()?;
@@ -0,0 +1,429 @@
+//! Deep store consistency check.
+//!
+//! Combines the manifest, segment, segment index and tombstone checks that
+//! `audit` and snapshot verification perform piecemeal, and reports every
+//! discrepancy instead of stopping at the first one.
+
+use std::{
+   collections::HashSet,
+   path::{Path, PathBuf},
+   sync::Arc,
+};
+
+use console::style;
+use serde::Serialize;
+
+use crate::{
+   Result, config,
+   error::Error,
+   identity,
+   snapshot::{
+      SnapshotManager, SnapshotManifest, compute_tombstone_artifact,
+      manifest::{CHUNK_ROW_SCHEMA_VERSION, MANIFEST_SCHEMA_VERSION},
+      read_segment_file_index, tombstone_ratio,
+   },
+   store::LanceStore,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+   Error,
+   Warning,
+}
+
+/// Command that usually resolves a finding, in the order they should be tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Remedy {
+   Repair,
+   Compact,
+   Reset,
+}
+
+impl Remedy {
+   const fn command(self) -> &'static str {
+      match self {
+         Self::Repair => "ggrep repair",
+         Self::Compact => "ggrep compact",
+         Self::Reset => "ggrep index --reset",
+      }
+   }
+}
+
+#[derive(Debug, Serialize)]
+struct Finding {
+   severity: Severity,
+   code:     &'static str,
+   message:  String,
+   remedy:   Remedy,
+}
+
+/// Outcome of [`verify`]; serializes to the `verify-store --json` report.
+#[derive(Debug, Serialize)]
+pub struct VerifyStoreJson {
+   schema_version: u32,
+   store_id:       String,
+   snapshot_id:    Option<String>,
+   ok:             bool,
+   errors:         usize,
+   warnings:       usize,
+   findings:       Vec<Finding>,
+}
+
+#[derive(Default)]
+struct Report {
+   findings: Vec<Finding>,
+}
+
+impl Report {
+   fn error(&mut self, code: &'static str, message: String, remedy: Remedy) {
+      self
+         .findings
+         .push(Finding { severity: Severity::Error, code, message, remedy });
+   }
+
+   fn warning(&mut self, code: &'static str, message: String, remedy: Remedy) {
+      self
+         .findings
+         .push(Finding { severity: Severity::Warning, code, message, remedy });
+   }
+
+   fn count(&self, severity: Severity) -> usize {
+      self
+         .findings
+         .iter()
+         .filter(|f| f.severity == severity)
+         .count()
+   }
+}
+
+/// Executes the verify-store command.
+pub async fn execute(path: Option<PathBuf>, json: bool, store_id: Option<String>) -> Result<()> {
+   let payload = verify(path, store_id).await?;
+   if json {
+      println!("{}", serde_json::to_string_pretty(&payload)?);
+   } else {
+      print_report(&payload);
+   }
+
+   if payload.ok {
+      return Ok(());
+   }
+   Err(
+      Error::Server {
+         op:     "verify-store",
+         reason: format!("{} consistency error(s) found", payload.errors),
+      }
+      .into(),
+   )
+}
+
+/// Runs every consistency check against the active snapshot of the store for
+/// `path` (or `store_id`) and collects the findings.
+pub async fn verify(path: Option<PathBuf>, store_id: Option<String>) -> Result<VerifyStoreJson> {
+   let cwd = std::env::current_dir()?.canonicalize()?;
+   let requested = path.unwrap_or(cwd).canonicalize()?;
+   let identity = identity::resolve_index_identity(&requested)?;
+   let root_store_id = store_id.unwrap_or(identity.store_id.clone());
+
+   let store = Arc::new(LanceStore::new()?);
+   let snapshot_manager = SnapshotManager::new(
+      store.clone(),
+      root_store_id.clone(),
+      identity.config_fingerprint.clone(),
+      identity.ignore_fingerprint.clone(),
+   );
+
+   let mut report = Report::default();
+   let snapshot_id = snapshot_manager.read_active_snapshot_id()?;
+   match &snapshot_id {
+      Some(snapshot_id) => {
+         match SnapshotManifest::load(&snapshot_manager.manifest_path(snapshot_id)) {
+            Ok(manifest) => {
+               check_manifest(&identity, &manifest, &mut report);
+               check_segments(&store, &root_store_id, &manifest, &mut report).await;
+               check_segment_index(&snapshot_manager, &manifest, &mut report);
+               check_tombstones(&snapshot_manager, &manifest, &mut report);
+            },
+            Err(e) => report.error(
+               "manifest_unreadable",
+               format!("active snapshot {snapshot_id} manifest unreadable: {e}"),
+               Remedy::Reset,
+            ),
+         }
+      },
+      None => {
+         report.error("no_active_snapshot", "no active snapshot".to_string(), Remedy::Reset);
+      },
+   }
+
+   let errors = report.count(Severity::Error);
+   Ok(VerifyStoreJson {
+      schema_version: 1,
+      store_id: root_store_id,
+      snapshot_id,
+      ok: errors == 0,
+      errors,
+      warnings: report.count(Severity::Warning),
+      findings: report.findings,
+   })
+}
+
+fn check_manifest(
+   identity: &identity::IndexIdentity,
+   manifest: &SnapshotManifest,
+   report: &mut Report,
+) {
+   if manifest.schema_version != MANIFEST_SCHEMA_VERSION {
+      report.error(
+         "manifest_schema",
+         format!("unsupported manifest schema version {}", manifest.schema_version),
+         Remedy::Reset,
+      );
+   }
+   if manifest.chunk_row_schema_version != CHUNK_ROW_SCHEMA_VERSION {
+      report.error(
+         "chunk_row_schema",
+         format!("unsupported chunk row schema version {}", manifest.chunk_row_schema_version),
+         Remedy::Reset,
+      );
+   }
+   if manifest.config_fingerprint != identity.config_fingerprint {
+      report.error(
+         "config_fingerprint_mismatch",
+         "manifest config fingerprint does not match current config".to_string(),
+         Remedy::Reset,
+      );
+   }
+   if manifest.ignore_fingerprint != identity.ignore_fingerprint {
+      report.warning(
+         "ignore_fingerprint_mismatch",
+         "manifest ignore fingerprint does not match current ignore rules".to_string(),
+         Remedy::Reset,
+      );
+   }
+
+   let segment_rows: u64 = manifest.segments.iter().map(|s| s.rows).sum();
+   if segment_rows != manifest.counts.chunks_indexed {
+      report.error(
+         "counts_mismatch",
+         format!(
+            "manifest chunk count {} does not match segment rows {}",
+            manifest.counts.chunks_indexed, segment_rows
+         ),
+         Remedy::Reset,
+      );
+   }
+   let tombstone_count: u64 = manifest.tombstones.iter().map(|t| t.count).sum();
+   if tombstone_count != manifest.counts.tombstones_added {
+      report.error(
+         "tombstone_counts_mismatch",
+         format!(
+            "manifest tombstone count {} does not match tombstone artifacts {}",
+            manifest.counts.tombstones_added, tombstone_count
+         ),
+         Remedy::Compact,
+      );
+   }
+
+   let ratio = tombstone_ratio(manifest);
+   let max_ratio = config::get().max_tombstone_ratio;
+   if max_ratio > 0.0 && ratio > max_ratio {
+      report.warning(
+         "tombstone_ratio",
+         format!("tombstone ratio {ratio:.3} exceeds {max_ratio}"),
+         Remedy::Compact,
+      );
+   }
+
+   if manifest.degraded {
+      report.warning(
+         "degraded_snapshot",
+         format!("snapshot is degraded ({} file error(s))", manifest.file_errors().count()),
+         Remedy::Repair,
+      );
+   }
+
+   let cap = config::get().max_segments_per_snapshot;
+   if cap > 0 && manifest.segments.len() > cap {
+      report.warning(
+         "segment_cap",
+         format!("{} segments exceed max_segments_per_snapshot ({cap})", manifest.segments.len()),
+         Remedy::Compact,
+      );
+   }
+}
+
+async fn check_segments(
+   store: &LanceStore,
+   store_id: &str,
+   manifest: &SnapshotManifest,
+   report: &mut Report,
+) {
+   let mut lance_rows = 0u64;
+   let mut all_present = true;
+   for segment in &manifest.segments {
+      let metadata = match store.segment_metadata(store_id, &segment.table).await {
+         Ok(metadata) => metadata,
+         Err(e) => {
+            all_present = false;
+            report.error(
+               "segment_missing",
+               format!("segment {} unreadable: {e}", segment.table),
+               Remedy::Repair,
+            );
+            continue;
+         },
+      };
+      lance_rows += metadata.rows;
+      if metadata.rows != segment.rows {
+         report.error(
+            "segment_rows_mismatch",
+            format!(
+               "segment {} has {} rows, manifest records {}",
+               segment.table, metadata.rows, segment.rows
+            ),
+            Remedy::Repair,
+         );
+      }
+      if metadata.size_bytes != segment.size_bytes || metadata.sha256 != segment.sha256 {
+         report.error(
+            "segment_checksum_mismatch",
+            format!("segment {} sha256/size does not match manifest", segment.table),
+            Remedy::Repair,
+         );
+      }
+   }
+
+   if all_present && lance_rows != manifest.counts.chunks_indexed {
+      report.error(
+         "row_count_mismatch",
+         format!(
+            "LanceDB holds {} rows, manifest counts {}",
+            lance_rows, manifest.counts.chunks_indexed
+         ),
+         Remedy::Reset,
+      );
+   }
+}
+
+fn check_segment_index(
+   snapshot_manager: &SnapshotManager,
+   manifest: &SnapshotManifest,
+   report: &mut Report,
+) {
+   let path = snapshot_manager
+      .snapshot_dir(&manifest.snapshot_id)
+      .join("segment_file_index.jsonl");
+   if !path.exists() {
+      report.warning(
+         "segment_index_missing",
+         "segment_file_index.jsonl missing; repair cannot map files to segments".to_string(),
+         Remedy::Reset,
+      );
+      return;
+   }
+   let mapping = match read_segment_file_index(&path) {
+      Ok(mapping) => mapping,
+      Err(e) => {
+         report.error(
+            "segment_index_unreadable",
+            format!("segment_file_index.jsonl unreadable: {e}"),
+            Remedy::Reset,
+         );
+         return;
+      },
+   };
+
+   let live: HashSet<&str> = manifest.segments.iter().map(|s| s.table.as_str()).collect();
+   let mut dangling: Vec<&String> = mapping
+      .iter()
+      .filter(|(_, segment)| !live.contains(segment.as_str()))
+      .map(|(path_key, _)| path_key)
+      .collect();
+   if !dangling.is_empty() {
+      dangling.sort();
+      report.error(
+         "segment_index_dangling",
+         format!(
+            "{} file(s) map to segments not in the manifest (e.g. {})",
+            dangling.len(),
+            dangling[0]
+         ),
+         Remedy::Repair,
+      );
+   }
+}
+
+fn check_tombstones(
+   snapshot_manager: &SnapshotManager,
+   manifest: &SnapshotManifest,
+   report: &mut Report,
+) {
+   let store_root = snapshot_manager.store_root();
+   for tombstone in &manifest.tombstones {
+      if let Some(owner) = tombstone_snapshot_id(&tombstone.path)
+         && !snapshot_manager.snapshot_dir(owner).exists()
+      {
+         report.error(
+            "tombstone_snapshot_missing",
+            format!("tombstone {} references missing snapshot {owner}", tombstone.path),
+            Remedy::Compact,
+         );
+         continue;
+      }
+
+      match compute_tombstone_artifact(&store_root.join(&tombstone.path)) {
+         Ok((size_bytes, sha256, count)) => {
+            if size_bytes != tombstone.size_bytes
+               || sha256 != tombstone.sha256
+               || count != tombstone.count
+            {
+               report.error(
+                  "tombstone_checksum_mismatch",
+                  format!("tombstone {} does not match manifest checksum", tombstone.path),
+                  Remedy::Compact,
+               );
+            }
+         },
+         Err(e) => report.error(
+            "tombstone_missing",
+            format!("tombstone {} unreadable: {e}", tombstone.path),
+            Remedy::Compact,
+         ),
+      }
+   }
+}
+
+/// Extracts `<id>` from a `snapshots/<id>/...` artifact path.
+fn tombstone_snapshot_id(path: &str) -> Option<&str> {
+   let mut components = Path::new(path).components();
+   match components.next()?.as_os_str().to_str()? {
+      "snapshots" => components.next()?.as_os_str().to_str(),
+      _ => None,
+   }
+}
+
+fn print_report(report: &VerifyStoreJson) {
+   let (errors, warnings) = (report.errors, report.warnings);
+   if report.findings.is_empty() {
+      println!("{}", style("✓ Store consistent").green());
+      return;
+   }
+
+   for finding in &report.findings {
+      let label = match finding.severity {
+         Severity::Error => style("error").red().bold(),
+         Severity::Warning => style("warning").yellow(),
+      };
+      println!("  {label} [{}] {}", finding.code, finding.message);
+   }
+
+   let mut remedies: Vec<Remedy> = report.findings.iter().map(|f| f.remedy).collect();
+   remedies.sort();
+   remedies.dedup();
+   println!("{errors} error(s), {warnings} warning(s)");
+   let commands: Vec<&str> = remedies.iter().map(|r| r.command()).collect();
+   println!("{}", style(format!("Suggested: {}", commands.join(", then "))).yellow());
+}
@@ -1,6 +1,7 @@
 //! Configuration management for model settings, performance tuning, and paths.
 
 use std::{
+   collections::BTreeMap,
    fs,
    path::{Path, PathBuf},
    sync::OnceLock,
@@ -13,7 +14,10 @@ use figment::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::error::{ConfigError, Result};
+use crate::{
+   error::{ConfigError, Result},
+   types::SearchMode,
+};
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
 
@@ -34,8 +38,14 @@ pub struct Config {
    pub colbert_model: String,
    pub dense_dim:     usize,
    pub colbert_dim:   usize,
+   /// Dense similarity: `dot` assumes the model emits normalized vectors,
+   /// `cosine` normalizes query and document vectors when scoring
+   pub dense_metric:  DenseMetric,
 
    pub query_prefix: String,
+   /// Query prefix for a search mode, replacing `query_prefix` for queries in
+   /// that mode, e.g. `discovery = "Represent this code search query: "`
+   pub mode_query_prefixes: BTreeMap<SearchMode, String>,
    pub doc_prefix: String,
    pub dense_max_length: usize,
    pub colbert_max_length: usize,
@@ -45,6 +55,9 @@ pub struct Config {
    pub max_file_size_bytes: u64,
    pub max_chunks_per_file: usize,
    pub max_bytes_per_sync: u64,
+   /// Syncs a file may fail to read or chunk before it is quarantined until
+   /// its content changes (0, the default, never quarantines automatically)
+   pub quarantine_after_failures: u32,
    pub max_threads: usize,
    pub max_concurrent_queries: usize,
    pub max_query_queue: usize,
@@ -53,6 +66,16 @@ pub struct Config {
    pub max_query_results: usize,
    pub max_query_per_file: usize,
    pub max_candidates: usize,
+   /// Overrides the per-mode retrieval multiplier (2x balanced, 10x others)
+   pub candidate_multiplier: Option<usize>,
+   /// Boost results from files modified within this many days (0 disables)
+   pub recent_boost_days: u32,
+   /// Multiplier for a file modified just now; decays linearly to 1.0 at the
+   /// edge of the `recent_boost_days` window
+   pub recent_boost_factor: f32,
+   /// Query embeddings kept per search engine for repeated queries (0
+   /// disables the cache)
+   pub query_cache_capacity: u64,
    pub max_total_snippet_bytes: usize,
    pub max_snippet_bytes_per_result: usize,
    pub max_open_segments_per_query: usize,
@@ -81,6 +104,7 @@ pub struct Config {
    pub max_tombstones_per_snapshot: usize,
    pub compaction_overdue_segments: usize,
    pub compaction_overdue_tombstones: usize,
+   pub max_tombstone_ratio: f64,
 
    pub port:                     u16,
    pub idle_timeout_secs:        u64,
@@ -90,11 +114,42 @@ pub struct Config {
    pub low_impact:      bool,
    pub disable_gpu:     bool,
    pub fast_mode:       bool,
+   /// Build and query full-text indexes next to the dense vectors. Not part
+   /// of the config fingerprint: toggle it together with `index --reset`.
+   pub enable_fts:      bool,
    pub offline:         bool,
    pub profile_enabled: bool,
    pub skip_meta_save:  bool,
    pub debug_models:    bool,
    pub debug_embed:     bool,
+
+   /// Language for a file extension (lowercase, no dot), bypassing content
+   /// detection for ambiguous extensions, e.g. `m = "matlab"`
+   pub language_overrides: BTreeMap<String, String>,
+
+   /// Extra file extensions to index with simple (grammar-less) chunking,
+   /// e.g. `["proto", "graphql"]`; `*.proto` and `.proto` are accepted too
+   pub extra_index_extensions: Vec<String>,
+   /// Search bucket for files indexed through `extra_index_extensions`
+   pub extra_index_bucket:     ExtraIndexBucket,
+}
+
+/// Similarity function for dense vector scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DenseMetric {
+   #[default]
+   Dot,
+   Cosine,
+}
+
+/// Search bucket that `extra_index_extensions` files are ranked in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtraIndexBucket {
+   #[default]
+   Code,
+   Docs,
 }
 
 impl Default for Config {
@@ -108,7 +163,9 @@ impl Default for Config {
             .to_string(),
          dense_dim: 384,
          colbert_dim: 96,
+         dense_metric: DenseMetric::default(),
          query_prefix: String::new(),
+         mode_query_prefixes: BTreeMap::new(),
          doc_prefix: String::new(),
          dense_max_length: 256,
          colbert_max_length: 256,
@@ -118,6 +175,7 @@ impl Default for Config {
          max_file_size_bytes: MAX_FILE_SIZE_BYTES_CAP,
          max_chunks_per_file: MAX_CHUNKS_PER_FILE_CAP,
          max_bytes_per_sync: MAX_BYTES_PER_SYNC_CAP,
+         quarantine_after_failures: 0,
          max_threads: 32,
          max_concurrent_queries: 8,
          max_query_queue: 32,
@@ -126,6 +184,10 @@ impl Default for Config {
          max_query_results: 200,
          max_query_per_file: 50,
          max_candidates: 2000,
+         candidate_multiplier: None,
+         recent_boost_days: 0,
+         recent_boost_factor: 1.25,
+         query_cache_capacity: 256,
          max_total_snippet_bytes: 1_048_576,
          max_snippet_bytes_per_result: 32_768,
          max_open_segments_per_query: 64,
@@ -154,6 +216,7 @@ impl Default for Config {
          max_tombstones_per_snapshot: 250_000,
          compaction_overdue_segments: 48,
          compaction_overdue_tombstones: 200_000,
+         max_tombstone_ratio: 0.5,
          port: 4444,
          idle_timeout_secs: 30 * 60,
          idle_check_interval_secs: 60,
@@ -161,11 +224,15 @@ impl Default for Config {
          low_impact: false,
          disable_gpu: false,
          fast_mode: false,
+         enable_fts: true,
          offline: false,
          profile_enabled: false,
          skip_meta_save: false,
          debug_models: false,
          debug_embed: false,
+         language_overrides: BTreeMap::new(),
+         extra_index_extensions: Vec::new(),
+         extra_index_bucket: ExtraIndexBucket::Code,
       }
    }
 }
@@ -263,6 +330,20 @@ impl Config {
          .max(1)
    }
 
+   /// Prefix prepended to queries in `mode` before encoding; documents are
+   /// never encoded with it.
+   pub fn query_prefix_for(&self, mode: SearchMode) -> &str {
+      self
+         .mode_query_prefixes
+         .get(&mode)
+         .map_or(self.query_prefix.as_str(), String::as_str)
+   }
+
+   /// Whether `ext` (no dot) is listed in `extra_index_extensions`.
+   pub fn is_extra_index_extension(&self, ext: &str) -> bool {
+      extension_listed(&self.extra_index_extensions, ext)
+   }
+
    pub fn effective_max_concurrent_queries_per_client(&self) -> usize {
       if self.max_concurrent_queries_per_client == 0 {
          return self.max_concurrent_queries.max(1);
@@ -274,6 +355,18 @@ impl Config {
    }
 }
 
+/// Whether `ext` (no dot) is in `extensions`, whose entries may be written as
+/// `proto`, `.proto` or `*.proto`; case is ignored.
+pub fn extension_listed(extensions: &[String], ext: &str) -> bool {
+   extensions.iter().any(|listed| {
+      let listed = listed.strip_prefix('*').unwrap_or(listed);
+      listed
+         .strip_prefix('.')
+         .unwrap_or(listed)
+         .eq_ignore_ascii_case(ext)
+   })
+}
+
 /// Returns the global configuration instance
 pub fn get() -> &'static Config {
    CONFIG.get_or_init(Config::load)
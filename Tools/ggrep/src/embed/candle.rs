@@ -28,9 +28,10 @@ use tokio::sync::Mutex;
 
 use crate::{
    Str, config,
-   embed::{Embedder, HybridEmbedding, QueryEmbedding},
+   embed::{Embedder, HybridEmbedding, QueryEmbedding, QueryTruncation},
    error::Result,
    models,
+   types::SearchMode,
    util::ArtifactLock,
 };
 
@@ -140,6 +141,28 @@ impl fmt::Debug for ColbertModelState {
    }
 }
 
+/// Token ids and attention mask for one text, cut to the encoder's max length
+struct Tokenized {
+   ids:      Vec<u32>,
+   mask:     Vec<u32>,
+   /// Token count before truncation
+   full_len: usize,
+}
+
+impl Tokenized {
+   const fn truncated(&self) -> bool {
+      self.full_len > self.ids.len()
+   }
+
+   const fn truncation(&self) -> Option<QueryTruncation> {
+      if self.truncated() {
+         Some(QueryTruncation { tokens: self.full_len, max_length: self.ids.len() })
+      } else {
+         None
+      }
+   }
+}
+
 /// Errors that can occur during embedding operations
 #[derive(Debug, thiserror::Error)]
 pub enum EmbeddingError {
@@ -485,28 +508,25 @@ impl CandleEmbedder {
       hex::encode(hasher.finalize())
    }
 
-   fn tokenize_impl(
-      tokenizer: &Tokenizer,
-      text: &str,
-      max_len: usize,
-   ) -> Result<(Vec<u32>, Vec<u32>)> {
+   fn tokenize_impl(tokenizer: &Tokenizer, text: &str, max_len: usize) -> Result<Tokenized> {
       let encoding = tokenizer.encode(text, true).map_err(EmbeddingError::from)?;
-      let mut token_ids = encoding.get_ids().to_vec();
-      let mut attention_mask = vec![1u32; token_ids.len()];
-      if token_ids.len() > max_len {
-         token_ids.truncate(max_len);
-         attention_mask.truncate(max_len);
+      let mut ids = encoding.get_ids().to_vec();
+      let full_len = ids.len();
+      let mut mask = vec![1u32; ids.len()];
+      if ids.len() > max_len {
+         ids.truncate(max_len);
+         mask.truncate(max_len);
       }
-      Ok((token_ids, attention_mask))
+      Ok(Tokenized { ids, mask, full_len })
    }
 
-   async fn tokenize_dense(&self, text: &str) -> Result<(Vec<u32>, Vec<u32>)> {
+   async fn tokenize_dense(&self, text: &str) -> Result<Tokenized> {
       let Models(dense, _) = self.models().await?;
       let max_len = config::get().dense_max_length;
       Self::tokenize_impl(&dense.tokenizer, text, max_len)
    }
 
-   async fn tokenize_dense_batch(&self, texts: &[Str]) -> Result<Vec<(Vec<u32>, Vec<u32>)>> {
+   async fn tokenize_dense_batch(&self, texts: &[Str]) -> Result<Vec<Tokenized>> {
       let Models(dense, _) = self.models().await?;
       let max_len = config::get().dense_max_length;
       texts
@@ -515,13 +535,13 @@ impl CandleEmbedder {
          .collect()
    }
 
-   async fn tokenize_colbert(&self, text: &str) -> Result<(Vec<u32>, Vec<u32>)> {
+   async fn tokenize_colbert(&self, text: &str) -> Result<Tokenized> {
       let Models(_, colbert) = self.models().await?;
       let max_len = config::get().colbert_max_length;
       Self::tokenize_impl(&colbert.tokenizer, text, max_len)
    }
 
-   async fn tokenize_colbert_batch(&self, texts: &[Str]) -> Result<Vec<(Vec<u32>, Vec<u32>)>> {
+   async fn tokenize_colbert_batch(&self, texts: &[Str]) -> Result<Vec<Tokenized>> {
       let Models(_, colbert) = self.models().await?;
       let max_len = config::get().colbert_max_length;
       texts
@@ -530,6 +550,23 @@ impl CandleEmbedder {
          .collect()
    }
 
+   /// Reports the encoder that cuts the query shortest, if any does.
+   async fn query_truncation(
+      &self,
+      text: &str,
+      dense_only: bool,
+   ) -> Result<Option<QueryTruncation>> {
+      let dense = self.tokenize_dense(text).await?.truncation();
+      if dense_only {
+         return Ok(dense);
+      }
+      let colbert = self.tokenize_colbert(text).await?.truncation();
+      Ok(match (dense, colbert) {
+         (Some(d), Some(c)) => Some(if c.max_length < d.max_length { c } else { d }),
+         (d, c) => d.or(c),
+      })
+   }
+
    fn normalize_l2(embeddings: &mut [f32]) {
       let norm: f32 = embeddings.iter().map(|x| x * x).sum::<f32>().sqrt();
       if norm > 0.0 {
@@ -601,7 +638,8 @@ impl CandleEmbedder {
    }
 
    async fn compute_dense_embedding(&self, text: &str) -> Result<Vec<f32>> {
-      let (token_ids, attention_mask) = self.tokenize_dense(text).await?;
+      let Tokenized { ids: token_ids, mask: attention_mask, .. } =
+         self.tokenize_dense(text).await?;
 
       let token_ids_tensor = Tensor::new(&token_ids[..], &self.device)
          .map_err(EmbeddingError::CreateTensor)?
@@ -661,7 +699,7 @@ impl CandleEmbedder {
    async fn compute_dense_embeddings_batch_inner(
       &self,
       indices: &[usize],
-      tokenized: &[(Vec<u32>, Vec<u32>)],
+      tokenized: &[Tokenized],
    ) -> Result<Array2<f32>> {
       if indices.is_empty() {
          return Ok(Array2::default((0, 0)));
@@ -669,7 +707,7 @@ impl CandleEmbedder {
 
       let max_len = indices
          .iter()
-         .map(|&i| tokenized[i].0.len())
+         .map(|&i| tokenized[i].ids.len())
          .max()
          .unwrap_or(0);
       let batch_size = indices.len();
@@ -678,7 +716,7 @@ impl CandleEmbedder {
       let mut all_attention_masks = Vec::with_capacity(batch_size * max_len);
 
       for &idx in indices {
-         let (token_ids, attention_mask) = &tokenized[idx];
+         let Tokenized { ids: token_ids, mask: attention_mask, .. } = &tokenized[idx];
          all_token_ids.extend(token_ids);
          all_token_ids.extend(std::iter::repeat_n(0u32, max_len - token_ids.len()));
          all_attention_masks.extend(attention_mask);
@@ -752,7 +790,8 @@ impl CandleEmbedder {
    }
 
    async fn compute_colbert_embedding(&self, text: &str) -> Result<Array2<f32>> {
-      let (token_ids, attention_mask) = self.tokenize_colbert(text).await?;
+      let Tokenized { ids: token_ids, mask: attention_mask, .. } =
+         self.tokenize_colbert(text).await?;
       let seq_len = token_ids.len();
 
       let token_ids_tensor = Tensor::new(&token_ids[..], &self.device)
@@ -805,7 +844,7 @@ impl CandleEmbedder {
    async fn compute_colbert_embeddings_batch_inner(
       &self,
       indices: &[usize],
-      tokenized: &[(Vec<u32>, Vec<u32>)],
+      tokenized: &[Tokenized],
    ) -> Result<Vec<Array2<f32>>> {
       if indices.is_empty() {
          return Ok(Vec::new());
@@ -813,7 +852,7 @@ impl CandleEmbedder {
 
       let max_len = indices
          .iter()
-         .map(|&i| tokenized[i].0.len())
+         .map(|&i| tokenized[i].ids.len())
          .max()
          .unwrap_or(0);
       let batch_size = indices.len();
@@ -822,7 +861,7 @@ impl CandleEmbedder {
       let mut all_attention_masks = Vec::with_capacity(batch_size * max_len);
 
       for &idx in indices {
-         let (token_ids, attention_mask) = &tokenized[idx];
+         let Tokenized { ids: token_ids, mask: attention_mask, .. } = &tokenized[idx];
          all_token_ids.extend(token_ids);
          all_token_ids.extend(std::iter::repeat_n(0u32, max_len - token_ids.len()));
          all_attention_masks.extend(attention_mask);
@@ -871,7 +910,7 @@ impl CandleEmbedder {
 
       let mut results = Vec::with_capacity(batch_size);
       for (i, &idx) in indices.iter().enumerate() {
-         let seq_len = tokenized[idx].0.len();
+         let seq_len = tokenized[idx].ids.len();
          let base = i * max_len * dim;
          let end = base + seq_len * dim;
          let mut data = flat[base..end].to_vec();
@@ -896,7 +935,7 @@ impl CandleEmbedder {
       if cfg.fast_mode {
          let dense_lengths: Vec<usize> = dense_tokenized
             .iter()
-            .map(|(dense_ids, _)| dense_ids.len())
+            .map(|tokenized| tokenized.ids.len())
             .collect();
 
          let buckets = Self::bucket_by_length(&dense_lengths, 32);
@@ -918,8 +957,12 @@ impl CandleEmbedder {
                   Ok(dense_matrix) => {
                      for (i, &orig_idx) in batch_indices.iter().enumerate() {
                         let dense = dense_matrix.row(i).to_vec();
-                        results[orig_idx] =
-                           Some(HybridEmbedding { dense, colbert: Vec::new(), colbert_scale: 1.0 });
+                        results[orig_idx] = Some(HybridEmbedding {
+                           dense,
+                           colbert: Vec::new(),
+                           colbert_scale: 1.0,
+                           truncated: dense_tokenized[orig_idx].truncated(),
+                        });
                      }
                      offset = end;
                   },
@@ -946,7 +989,7 @@ impl CandleEmbedder {
       let combined_lengths: Vec<usize> = dense_tokenized
          .iter()
          .zip(colbert_tokenized.iter())
-         .map(|((dense_ids, _), (colbert_ids, _))| dense_ids.len().max(colbert_ids.len()))
+         .map(|(dense, colbert)| dense.ids.len().max(colbert.ids.len()))
          .collect();
 
       let buckets = Self::bucket_by_length(&combined_lengths, 32);
@@ -970,7 +1013,10 @@ impl CandleEmbedder {
                      let dense = dense_matrix.row(i).to_vec();
                      let colbert_tokens = &colbert_embeddings[i];
                      let (colbert, colbert_scale) = Self::quantize_embeddings(colbert_tokens);
-                     results[orig_idx] = Some(HybridEmbedding { dense, colbert, colbert_scale });
+                     let truncated = dense_tokenized[orig_idx].truncated()
+                        || colbert_tokenized[orig_idx].truncated();
+                     results[orig_idx] =
+                        Some(HybridEmbedding { dense, colbert, colbert_scale, truncated });
                   }
                   offset = end;
                },
@@ -995,8 +1041,8 @@ impl CandleEmbedder {
    async fn try_compute_batch_indexed(
       &self,
       indices: &[usize],
-      dense_tokenized: &[(Vec<u32>, Vec<u32>)],
-      colbert_tokenized: &[(Vec<u32>, Vec<u32>)],
+      dense_tokenized: &[Tokenized],
+      colbert_tokenized: &[Tokenized],
    ) -> Result<(Array2<f32>, Vec<Array2<f32>>)> {
       let dense_embeddings = self
          .compute_dense_embeddings_batch_inner(indices, dense_tokenized)
@@ -1010,7 +1056,7 @@ impl CandleEmbedder {
    async fn try_compute_dense_batch_indexed(
       &self,
       indices: &[usize],
-      dense_tokenized: &[(Vec<u32>, Vec<u32>)],
+      dense_tokenized: &[Tokenized],
    ) -> Result<Array2<f32>> {
       self
          .compute_dense_embeddings_batch_inner(indices, dense_tokenized)
@@ -1105,12 +1151,13 @@ impl Embedder for CandleEmbedder {
       Self::compute_hybrid(self, texts).await
    }
 
-   async fn encode_query(&self, text: &str) -> Result<QueryEmbedding> {
+   async fn encode_query(&self, text: &str, mode: SearchMode) -> Result<QueryEmbedding> {
       let cfg = config::get();
-      let query_text = if cfg.query_prefix.is_empty() {
+      let prefix = cfg.query_prefix_for(mode);
+      let query_text = if prefix.is_empty() {
          text.to_string()
       } else {
-         format!("{}{}", cfg.query_prefix, text)
+         format!("{prefix}{text}")
       };
 
       if cfg.debug_embed {
@@ -1123,6 +1170,14 @@ impl Embedder for CandleEmbedder {
       } else {
          self.compute_colbert_embedding(&query_text).await?
       };
+      let truncated = self.query_truncation(&query_text, cfg.fast_mode).await?;
+      if let Some(truncation) = truncated {
+         tracing::warn!(
+            "query truncated: {} tokens exceed the encoder max length of {}",
+            truncation.tokens,
+            truncation.max_length
+         );
+      }
 
       if cfg.debug_embed {
          tracing::info!(
@@ -1132,12 +1187,16 @@ impl Embedder for CandleEmbedder {
          );
       }
 
-      Ok(QueryEmbedding { dense, colbert })
+      Ok(QueryEmbedding { dense, colbert, truncated })
    }
 
    fn is_ready(&self) -> bool {
       self.models.get().is_some()
    }
+
+   fn backend_name(&self) -> &'static str {
+      "candle"
+   }
 }
 
 impl Default for CandleEmbedder {
@@ -4,8 +4,9 @@ use ndarray::Array2;
 
 use crate::{
    Str,
-   embed::{Embedder, HybridEmbedding, QueryEmbedding},
+   embed::{Embedder, EmbedderDescription, HybridEmbedding, QueryEmbedding},
    error::Result,
+   types::SearchMode,
 };
 
 #[derive(Debug, Clone)]
@@ -28,20 +29,39 @@ impl Embedder for DummyEmbedder {
          if !dense.is_empty() {
             dense[0] = text.as_str().len() as f32;
          }
-         out.push(HybridEmbedding { dense, colbert: Vec::new(), colbert_scale: 1.0 });
+         out.push(HybridEmbedding {
+            dense,
+            colbert: Vec::new(),
+            colbert_scale: 1.0,
+            truncated: false,
+         });
       }
       Ok(out)
    }
 
-   async fn encode_query(&self, text: &str) -> Result<QueryEmbedding> {
+   async fn encode_query(&self, text: &str, _mode: SearchMode) -> Result<QueryEmbedding> {
       let mut dense = vec![0.0; self.dense_dim];
       if !dense.is_empty() {
          dense[0] = text.len() as f32;
       }
-      Ok(QueryEmbedding { dense, colbert: Array2::zeros((0, 0)) })
+      Ok(QueryEmbedding { dense, colbert: Array2::zeros((0, 0)), truncated: None })
    }
 
    fn is_ready(&self) -> bool {
       true
    }
+
+   fn backend_name(&self) -> &'static str {
+      "dummy"
+   }
+
+   fn describe(&self) -> EmbedderDescription {
+      EmbedderDescription {
+         backend:       self.backend_name(),
+         dense_model:   None,
+         colbert_model: None,
+         dense_dim:     self.dense_dim,
+         ready:         true,
+      }
+   }
 }
@@ -8,14 +8,14 @@ pub mod dummy;
 pub(crate) mod limiter;
 pub mod worker;
 
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
 
 pub use candle::CandleEmbedder;
 pub use dummy::DummyEmbedder;
 use ndarray::Array2;
 pub use worker::EmbedWorker;
 
-use crate::{Str, error::Result};
+use crate::{Str, config, error::Result, types::SearchMode};
 
 /// Hybrid embedding representation combining dense and sparse vectors
 ///
@@ -29,15 +29,65 @@ pub struct HybridEmbedding {
    pub colbert:       Vec<u8>,
    /// Scale factor for dequantizing `ColBERT` embeddings
    pub colbert_scale: f64,
+   /// Whether the text exceeded an encoder's max length and lost its tail
+   pub truncated:     bool,
 }
 
 /// Query embedding with both dense and `ColBERT` representations
 #[derive(Debug, Clone)]
 pub struct QueryEmbedding {
    /// Dense semantic embedding vector
-   pub dense:   Vec<f32>,
+   pub dense:     Vec<f32>,
    /// `ColBERT` token embeddings matrix (rows = tokens, cols = dim)
-   pub colbert: Array2<f32>,
+   pub colbert:   Array2<f32>,
+   /// Set when the query exceeded an encoder's max length
+   pub truncated: Option<QueryTruncation>,
+}
+
+/// A query cut short by an encoder's `dense_max_length`/`colbert_max_length`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryTruncation {
+   /// Tokens the full query encodes to
+   pub tokens:     usize,
+   /// Tokens the encoder kept
+   pub max_length: usize,
+}
+
+/// Diagnostic summary of an embedder: which backend is active and what it
+/// loads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedderDescription {
+   /// Backend name as reported by [`Embedder::backend_name`]
+   pub backend:       &'static str,
+   /// Dense model id, if the backend loads one
+   pub dense_model:   Option<String>,
+   /// `ColBERT` model id, if the backend loads one
+   pub colbert_model: Option<String>,
+   /// Dimension of produced dense vectors
+   pub dense_dim:     usize,
+   /// Whether the models are loaded and ready
+   pub ready:         bool,
+}
+
+impl EmbedderDescription {
+   /// Returns true for the deterministic test embedder, whose scores carry no
+   /// semantic meaning
+   pub fn is_dummy(&self) -> bool {
+      self.backend == "dummy"
+   }
+}
+
+impl fmt::Display for EmbedderDescription {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(f, "{} (dim={}", self.backend, self.dense_dim)?;
+      if let Some(model) = &self.dense_model {
+         write!(f, ", dense={model}")?;
+      }
+      if let Some(model) = &self.colbert_model {
+         write!(f, ", colbert={model}")?;
+      }
+      write!(f, ", ready={})", self.ready)
+   }
 }
 
 /// Text embedding trait for generating hybrid embeddings
@@ -45,10 +95,24 @@ pub struct QueryEmbedding {
 pub trait Embedder: Send + Sync {
    /// Computes hybrid embeddings for multiple texts
    async fn compute_hybrid(&self, texts: &[Str]) -> Result<Vec<HybridEmbedding>>;
-   /// Encodes a query with optional prefix
-   async fn encode_query(&self, text: &str) -> Result<QueryEmbedding>;
+   /// Encodes a query with the prefix configured for `mode`
+   async fn encode_query(&self, text: &str, mode: SearchMode) -> Result<QueryEmbedding>;
    /// Returns whether the embedder models are loaded and ready
    fn is_ready(&self) -> bool;
+   /// Short name of the backend producing embeddings (e.g. `candle`, `dummy`)
+   fn backend_name(&self) -> &'static str;
+   /// Describes the backend and the models it serves; defaults to the
+   /// configured models
+   fn describe(&self) -> EmbedderDescription {
+      let cfg = config::get();
+      EmbedderDescription {
+         backend:       self.backend_name(),
+         dense_model:   Some(cfg.dense_model.clone()),
+         colbert_model: Some(cfg.colbert_model.clone()),
+         dense_dim:     cfg.dense_dim,
+         ready:         self.is_ready(),
+      }
+   }
 }
 
 #[async_trait::async_trait]
@@ -57,11 +121,19 @@ impl<T: Embedder + ?Sized> Embedder for Arc<T> {
       (**self).compute_hybrid(texts).await
    }
 
-   async fn encode_query(&self, text: &str) -> Result<QueryEmbedding> {
-      (**self).encode_query(text).await
+   async fn encode_query(&self, text: &str, mode: SearchMode) -> Result<QueryEmbedding> {
+      (**self).encode_query(text, mode).await
    }
 
    fn is_ready(&self) -> bool {
       (**self).is_ready()
    }
+
+   fn backend_name(&self) -> &'static str {
+      (**self).backend_name()
+   }
+
+   fn describe(&self) -> EmbedderDescription {
+      (**self).describe()
+   }
 }
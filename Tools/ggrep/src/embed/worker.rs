@@ -14,9 +14,11 @@ use tokio_util::sync::CancellationToken;
 use crate::{
    Str, config,
    embed::{
-      DummyEmbedder, Embedder, HybridEmbedding, QueryEmbedding, candle::EmbeddingError, limiter,
+      DummyEmbedder, Embedder, EmbedderDescription, HybridEmbedding, QueryEmbedding,
+      candle::EmbeddingError, limiter,
    },
    error::Result,
+   types::SearchMode,
 };
 
 struct WorkerMessage {
@@ -161,13 +163,21 @@ impl Embedder for EmbedWorker {
       Self::compute_hybrid(self, texts).await
    }
 
-   async fn encode_query(&self, text: &str) -> Result<QueryEmbedding> {
-      self.embedder.encode_query(text).await
+   async fn encode_query(&self, text: &str, mode: SearchMode) -> Result<QueryEmbedding> {
+      self.embedder.encode_query(text, mode).await
    }
 
    fn is_ready(&self) -> bool {
       self.workers.is_some()
    }
+
+   fn backend_name(&self) -> &'static str {
+      self.embedder.backend_name()
+   }
+
+   fn describe(&self) -> EmbedderDescription {
+      EmbedderDescription { ready: self.is_ready(), ..self.embedder.describe() }
+   }
 }
 
 #[cfg(test)]
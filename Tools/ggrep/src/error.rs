@@ -183,8 +183,8 @@ impl Error {
 #[derive(Debug, Error)]
 pub enum IpcError {
    /// The message size exceeds the maximum allowed size.
-   #[error("message too large: {0} bytes")]
-   MessageTooLarge(usize),
+   #[error("message too large: {len} bytes exceeds limit of {limit} bytes")]
+   MessageTooLarge { len: usize, limit: usize },
 
    /// Failed to serialize a message for IPC transmission.
    #[error("failed to serialize: {0}")]
@@ -7,12 +7,13 @@ use std::{
 };
 
 use git2::Repository;
+use walkdir::WalkDir;
 
 use crate::{
    config,
    error::{Error, Result},
-   file::{IgnorePatterns, ResolvedPath, resolve_candidate},
-   grammar::EXTENSION_MAP,
+   file::{DEFAULT_IGNORE_DIRS, IgnorePatterns, ResolvedPath, resolve_candidate},
+   grammar::{EXTENSION_MAP, declared_custom_grammars},
 };
 
 /// Additional extensions for text-based files without tree-sitter grammar
@@ -58,14 +59,30 @@ pub trait FileSystem {
 
 /// Local file system implementation that discovers files via git or directory
 /// traversal.
-pub struct LocalFileSystem;
+pub struct LocalFileSystem {
+   /// Extensions indexed on top of the built-in and configured ones, for one
+   /// run (`ggrep index --include`).
+   extra_extensions:  Vec<String>,
+   /// Extensions claimed by custom grammars when discovery was created.
+   custom_extensions: Vec<String>,
+}
 
 impl LocalFileSystem {
-   pub const fn new() -> Self {
-      Self
+   pub fn new() -> Self {
+      Self::with_extra_extensions(Vec::new())
+   }
+
+   /// Discovery that also indexes files with one of `extensions`, written as
+   /// in `extra_index_extensions` (`proto`, `.proto` or `*.proto`).
+   pub fn with_extra_extensions(extensions: Vec<String>) -> Self {
+      let custom_extensions = declared_custom_grammars()
+         .into_iter()
+         .flat_map(|grammar| grammar.extensions)
+         .collect();
+      Self { extra_extensions: extensions, custom_extensions }
    }
 
-   fn is_supported_extension(path: &Path) -> bool {
+   fn is_supported_extension(&self, path: &Path) -> bool {
       let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
       let filename = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
 
@@ -76,11 +93,16 @@ impl LocalFileSystem {
          // Special filename patterns
          || filename.eq_ignore_ascii_case("dockerfile")
          || filename.eq_ignore_ascii_case("makefile")
+         // Then extensions the config opts into
+         || (!ext.is_empty() && config::get().is_extra_index_extension(ext))
+         || (!ext.is_empty() && config::extension_listed(&self.extra_extensions, ext))
+         // Then extensions claimed by custom grammars
+         || (!ext.is_empty() && config::extension_listed(&self.custom_extensions, ext))
    }
 
-   fn should_include_file(path: &Path, metadata: Option<&fs::Metadata>) -> bool {
+   fn should_include_file(&self, path: &Path, metadata: Option<&fs::Metadata>) -> bool {
       let max_file_size = config::get().effective_max_file_size_bytes();
-      if !Self::is_supported_extension(path) {
+      if !self.is_supported_extension(path) {
          return false;
       }
 
@@ -99,7 +121,7 @@ impl LocalFileSystem {
       }
    }
 
-   fn get_git_files(root: &Path) -> Result<Vec<PathBuf>> {
+   fn get_git_files(&self, root: &Path) -> Result<Vec<PathBuf>> {
       let repo = Repository::discover(root).map_err(Error::OpenRepository)?;
 
       let mut files = Vec::new();
@@ -134,7 +156,7 @@ impl LocalFileSystem {
             let file_path = repo_root_abs.join(path_str);
             if file_path.exists()
                && file_path.starts_with(&root_abs)
-               && Self::should_include_file(&file_path, None)
+               && self.should_include_file(&file_path, None)
             {
                files.push(file_path);
             }
@@ -151,7 +173,7 @@ impl LocalFileSystem {
             let file_path = repo_root_abs.join(line);
             if file_path.exists()
                && file_path.starts_with(&root_abs)
-               && Self::should_include_file(&file_path, None)
+               && self.should_include_file(&file_path, None)
             {
                files.push(file_path);
             }
@@ -165,11 +187,11 @@ impl LocalFileSystem {
       path.join(".git").exists()
    }
 
-   fn get_walkdir_files(root: &Path) -> Vec<PathBuf> {
-      Self::get_walkdir_files_recursive(root, root)
+   fn get_walkdir_files(&self, root: &Path) -> Vec<PathBuf> {
+      self.get_walkdir_files_recursive(root, root)
    }
 
-   fn get_walkdir_files_recursive(dir: &Path, root: &Path) -> Vec<PathBuf> {
+   fn get_walkdir_files_recursive(&self, dir: &Path, root: &Path) -> Vec<PathBuf> {
       let mut files = Vec::new();
 
       let Ok(entries) = fs::read_dir(dir) else {
@@ -191,17 +213,17 @@ impl LocalFileSystem {
 
          if file_type.is_dir() {
             if path != root && Self::is_git_repository(&path) {
-               if let Ok(git_files) = Self::get_git_files(&path) {
+               if let Ok(git_files) = self.get_git_files(&path) {
                   files.extend(git_files);
                } else {
-                  files.extend(Self::get_walkdir_files_recursive(&path, &path));
+                  files.extend(self.get_walkdir_files_recursive(&path, &path));
                }
             } else {
-               files.extend(Self::get_walkdir_files_recursive(&path, root));
+               files.extend(self.get_walkdir_files_recursive(&path, root));
             }
          } else if (file_type.is_file() || file_type.is_symlink())
             && let Ok(metadata) = entry.metadata()
-            && Self::should_include_file(&path, Some(&metadata))
+            && self.should_include_file(&path, Some(&metadata))
          {
             files.push(path);
          }
@@ -214,9 +236,9 @@ impl LocalFileSystem {
 impl FileSystem for LocalFileSystem {
    fn get_files(&self, root: &Path) -> Result<Box<dyn Iterator<Item = ResolvedPath>>> {
       let files = if Repository::discover(root).is_ok() {
-         Self::get_git_files(root)?
+         self.get_git_files(root)?
       } else {
-         Self::get_walkdir_files(root)
+         self.get_walkdir_files(root)
       };
 
       let ignore_patterns = IgnorePatterns::new(root);
@@ -240,21 +262,123 @@ impl Default for LocalFileSystem {
    }
 }
 
+/// Result of a bounded walk counting files that discovery could index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CandidateSample {
+   /// Files with an indexable extension and size seen by the walk.
+   pub candidates: u64,
+   /// The walk hit its entry budget, so `candidates` is a lower bound.
+   pub truncated:  bool,
+}
+
+/// Counts indexable files under `root`, visiting at most `max_entries`
+/// directory entries.
+///
+/// Applies the same hidden-entry, default-directory and
+/// `.gitignore`/`.ggignore` rules as discovery, so ignored build output is
+/// not counted as missing from the index.
+pub fn sample_candidate_files(root: &Path, max_entries: usize) -> CandidateSample {
+   let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+   let ignore_patterns = IgnorePatterns::new(&root);
+   let walker = WalkDir::new(&root)
+      .follow_links(false)
+      .into_iter()
+      .filter_entry(|entry| {
+         if entry.depth() == 0 {
+            return true;
+         }
+         let name = entry.file_name().to_string_lossy();
+         !name.starts_with('.')
+            && !(entry.file_type().is_dir() && DEFAULT_IGNORE_DIRS.iter().any(|d| *d == name))
+            && !ignore_patterns.is_ignored(entry.path())
+      });
+
+   let discovery = LocalFileSystem::new();
+   let mut sample = CandidateSample { candidates: 0, truncated: false };
+   for (visited, entry) in walker.filter_map(|e| e.ok()).enumerate() {
+      if visited >= max_entries {
+         sample.truncated = true;
+         break;
+      }
+      if entry.file_type().is_file()
+         && let Ok(metadata) = entry.metadata()
+         && discovery.should_include_file(entry.path(), Some(&metadata))
+      {
+         sample.candidates += 1;
+      }
+   }
+   sample
+}
+
 #[cfg(test)]
 mod tests {
    use super::*;
 
    #[test]
    fn supported_extension_recognized() {
-      assert!(LocalFileSystem::is_supported_extension(Path::new("test.rs")));
-      assert!(LocalFileSystem::is_supported_extension(Path::new("test.ts")));
-      assert!(LocalFileSystem::is_supported_extension(Path::new("test.py")));
-      assert!(!LocalFileSystem::is_supported_extension(Path::new("test.bin")));
+      assert!(LocalFileSystem::new().is_supported_extension(Path::new("test.rs")));
+      assert!(LocalFileSystem::new().is_supported_extension(Path::new("test.ts")));
+      assert!(LocalFileSystem::new().is_supported_extension(Path::new("test.py")));
+      assert!(!LocalFileSystem::new().is_supported_extension(Path::new("test.bin")));
+   }
+
+   #[test]
+   fn extra_extensions_extend_discovery() {
+      let tmp = tempfile::TempDir::new().unwrap();
+      let root = tmp.path().canonicalize().unwrap();
+      for name in ["main.rs", "schema.xyz", "LEGACY.XYZ", "notes.abc"] {
+         fs::write(root.join(name), "message Ping {}\n").unwrap();
+      }
+      let discovered = |fs: LocalFileSystem| {
+         let mut keys: Vec<_> = fs
+            .get_files(&root)
+            .unwrap()
+            .map(|file| file.path_key.to_string_lossy().into_owned())
+            .collect();
+         keys.sort();
+         keys
+      };
+
+      assert_eq!(discovered(LocalFileSystem::new()), ["main.rs"]);
+      let extra = LocalFileSystem::with_extra_extensions(vec!["*.xyz".to_string()]);
+      assert_eq!(discovered(extra), ["LEGACY.XYZ", "main.rs", "schema.xyz"]);
    }
 
    #[test]
    fn hidden_files_filtered() {
-      assert!(!LocalFileSystem::should_include_file(Path::new(".hidden.rs"), None));
-      assert!(LocalFileSystem::should_include_file(Path::new("visible.rs"), None));
+      assert!(!LocalFileSystem::new().should_include_file(Path::new(".hidden.rs"), None));
+      assert!(LocalFileSystem::new().should_include_file(Path::new("visible.rs"), None));
+   }
+
+   #[test]
+   fn candidate_sample_honors_ignore_files() {
+      let tmp = tempfile::TempDir::new().unwrap();
+      fs::create_dir_all(tmp.path().join("src")).unwrap();
+      fs::create_dir_all(tmp.path().join("generated")).unwrap();
+      fs::write(tmp.path().join(".gitignore"), "generated/\n").unwrap();
+      fs::write(tmp.path().join(".ggignore"), "*.gen.rs\n").unwrap();
+      for name in ["src/a.rs", "src/b.gen.rs", "generated/c.rs", "generated/d.rs"] {
+         fs::write(tmp.path().join(name), "fn x() {}\n").unwrap();
+      }
+
+      let sample = sample_candidate_files(tmp.path(), 100);
+      assert_eq!(sample, CandidateSample { candidates: 1, truncated: false });
+   }
+
+   #[test]
+   fn candidate_sample_skips_default_dirs_and_stops_at_budget() {
+      let tmp = tempfile::TempDir::new().unwrap();
+      fs::create_dir_all(tmp.path().join("src")).unwrap();
+      fs::create_dir_all(tmp.path().join("target")).unwrap();
+      for name in ["src/a.rs", "src/b.rs", "target/c.rs", "notes.bin"] {
+         fs::write(tmp.path().join(name), "fn x() {}\n").unwrap();
+      }
+
+      let full = sample_candidate_files(tmp.path(), 100);
+      assert_eq!(full, CandidateSample { candidates: 2, truncated: false });
+
+      let bounded = sample_candidate_files(tmp.path(), 2);
+      assert!(bounded.truncated);
+      assert!(bounded.candidates < 2);
    }
 }
@@ -42,7 +42,7 @@ const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
 
 const IGNORE_FILENAMES: &[&str] = &[".gitignore", ".ggignore", ".smignore"];
 
-const DEFAULT_IGNORE_DIRS: &[&str] = &[
+pub(crate) const DEFAULT_IGNORE_DIRS: &[&str] = &[
    ".git",
    "Datasets",
    "node_modules",
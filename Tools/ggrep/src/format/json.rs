@@ -70,8 +70,13 @@ mod tests {
             segment_table:   None,
             start_line:      10,
             num_lines:       1,
+            end_line:        None,
             chunk_type:      Some(ChunkType::Function),
+            symbol:          None,
             is_anchor:       Some(false),
+            language:        None,
+            context_before:  0,
+            context_after:   0,
          },
          SearchResult {
             path:            "src/lib.rs".into(),
@@ -82,8 +87,13 @@ mod tests {
             segment_table:   None,
             start_line:      5,
             num_lines:       1,
+            end_line:        None,
             chunk_type:      Some(ChunkType::Function),
+            symbol:          None,
             is_anchor:       Some(true),
+            language:        None,
+            context_before:  0,
+            context_after:   0,
          },
       ];
 
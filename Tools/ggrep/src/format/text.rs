@@ -316,8 +316,13 @@ mod tests {
          segment_table: None,
          start_line,
          num_lines: content.lines().count() as u32,
+         end_line: None,
          chunk_type: Some(ChunkType::Function),
+         symbol: None,
          is_anchor: Some(false),
+         language: None,
+         context_before: 0,
+         context_after: 0,
          content,
       }
    }
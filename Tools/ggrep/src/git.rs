@@ -1,11 +1,12 @@
 //! Git repository utilities for store identification and file tracking
 
 use std::{
+   collections::HashMap,
    ffi::OsStr,
    path::{Path, PathBuf},
 };
 
-use git2::{Repository, Status, StatusOptions};
+use git2::{Blame, BlameOptions, Repository, Status, StatusOptions};
 use sha2::{Digest, Sha256};
 
 use crate::error::{Error, Result};
@@ -62,6 +63,77 @@ pub fn is_dirty(path: &Path) -> Option<bool> {
    Some(dirty)
 }
 
+/// Last commit to touch a line, as reported by `git blame`.
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+   pub commit: String,
+   pub author: String,
+   /// Commit time in seconds since the Unix epoch.
+   pub time:   i64,
+}
+
+/// Blames one line (1-based) of each `(path, line)` target, opening the
+/// repository once. Lines are numbered as in the working-tree file, which is
+/// only blamed when `is_indexed` accepts its bytes as the content that was
+/// indexed; otherwise the result's line numbers may no longer match it.
+/// Targets outside the repository, untracked or since-modified files, and
+/// lines not yet committed yield `None`.
+pub fn blame_lines(
+   root: &Path,
+   targets: &[(PathBuf, usize)],
+   is_indexed: impl Fn(&Path, &[u8]) -> bool,
+) -> Vec<Option<BlameLine>> {
+   let Some(repo) = Repository::discover(root).ok() else {
+      return vec![None; targets.len()];
+   };
+   let Some(workdir) = repo.workdir().and_then(|w| w.canonicalize().ok()) else {
+      return vec![None; targets.len()];
+   };
+
+   // Several results often share a file; blame each one once.
+   let mut by_path: HashMap<&Path, Vec<usize>> = HashMap::new();
+   for (idx, (path, _)) in targets.iter().enumerate() {
+      by_path.entry(path.as_path()).or_default().push(idx);
+   }
+   let mut out = vec![None; targets.len()];
+   for (path, indices) in by_path {
+      let Ok(abs) = root.join(path).canonicalize() else {
+         continue;
+      };
+      let (Some(rel), Ok(content)) = (abs.strip_prefix(&workdir).ok(), std::fs::read(&abs)) else {
+         continue;
+      };
+      if !is_indexed(path, &content) {
+         continue;
+      }
+      // Blame the working-tree bytes on top of HEAD, so uncommitted edits
+      // shift nothing and are left unattributed.
+      let Ok(committed) = repo.blame_file(rel, Some(&mut BlameOptions::new())) else {
+         continue;
+      };
+      let Ok(blame) = committed.blame_buffer(&content) else {
+         continue;
+      };
+      for idx in indices {
+         out[idx] = blame_line(&blame, targets[idx].1);
+      }
+   }
+   out
+}
+
+fn blame_line(blame: &Blame<'_>, line: usize) -> Option<BlameLine> {
+   let hunk = blame.get_line(line)?;
+   if hunk.final_commit_id().is_zero() {
+      return None;
+   }
+   let signature = hunk.final_signature();
+   Some(BlameLine {
+      commit: hunk.final_commit_id().to_string(),
+      author: signature.name()?.to_string(),
+      time:   signature.when().seconds(),
+   })
+}
+
 /// Returns paths for untracked files (excluding ignored files).
 pub fn untracked_paths(path: &Path) -> Option<Vec<PathBuf>> {
    let repo = Repository::discover(path).ok()?;
@@ -210,6 +282,49 @@ mod tests {
 	      assert_eq!(extract_owner_repo(url), Some("GoodFarming-goodgrep".to_string()));
 	   }
 
+   fn commit_all(repo: &Repository, message: &str) -> git2::Oid {
+      let mut index = repo.index().unwrap();
+      index
+         .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+         .unwrap();
+      index.write().unwrap();
+      let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+      let time = git2::Time::new(1_700_000_000, 0);
+      let sig = git2::Signature::new("Ada", "ada@example.com", &time).unwrap();
+      let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+      let parents: Vec<_> = parent.iter().collect();
+      repo
+         .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+         .unwrap()
+   }
+
+   #[test]
+   fn blame_follows_the_indexed_working_tree_content() {
+      let dir = tempfile::TempDir::new().unwrap();
+      let repo = Repository::init(dir.path()).unwrap();
+      std::fs::write(dir.path().join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+      let commit = commit_all(&repo, "init");
+
+      // Dirty the tree: a new first line shifts the committed ones down.
+      std::fs::write(dir.path().join("lib.rs"), "// new\nfn a() {}\nfn b() {}\n").unwrap();
+      std::fs::write(dir.path().join("untracked.rs"), "fn c() {}\n").unwrap();
+      let lib = PathBuf::from("lib.rs");
+      let targets =
+         [(lib.clone(), 1), (lib.clone(), 3), (lib.clone(), 9), (PathBuf::from("untracked.rs"), 1)];
+
+      let blamed = blame_lines(dir.path(), &targets, |_, _| true);
+      assert!(blamed[0].is_none(), "uncommitted line: {:?}", blamed[0]);
+      let line = blamed[1].as_ref().expect("committed line");
+      assert_eq!(line.commit, commit.to_string());
+      assert_eq!((line.author.as_str(), line.time), ("Ada", 1_700_000_000));
+      assert!(blamed[2].is_none());
+      assert!(blamed[3].is_none());
+
+      // A file changed since it was indexed is not blamed at all.
+      let stale = blame_lines(dir.path(), &targets[..2], |_, bytes| bytes.starts_with(b"fn a"));
+      assert!(stale.iter().all(Option::is_none), "{stale:?}");
+   }
+
    #[test]
    fn path_hash_computed() {
       let path = Path::new("/tmp/test");
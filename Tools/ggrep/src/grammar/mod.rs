@@ -1,10 +1,12 @@
 //! Tree-sitter grammar management and loading
 
 use std::{
+   collections::BTreeMap,
    path::{Path, PathBuf},
    time::Duration,
 };
 
+use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use tokio::fs;
 use tree_sitter::{Language, Parser, WasmStore, wasmtime};
@@ -62,6 +64,10 @@ pub const GRAMMAR_URLS: &[GrammarPair] = &[
     ("elixir",     "https://github.com/elixir-lang/tree-sitter-elixir/releases/download/v0.3.4/tree-sitter-elixir.wasm"),
 ];
 
+/// Language reported for chunks without a detected language, including rows
+/// from segments written before languages were recorded.
+pub const UNKNOWN_LANGUAGE: &str = "unknown";
+
 /// Maps file extensions to language names
 pub static EXTENSION_MAP: &[(&str, &str)] = &[
    ("js", "javascript"),
@@ -117,6 +123,7 @@ pub static EXTENSION_MAP: &[(&str, &str)] = &[
    ("kt", "kotlin"),
    ("kts", "kotlin"),
    ("makefile", "make"),
+   ("dockerfile", "dockerfile"),
    ("mk", "make"),
    ("m", "objc"),
    ("mm", "objc"),
@@ -134,13 +141,154 @@ pub static EXTENSION_MAP: &[(&str, &str)] = &[
    ("odin", "odin"),
 ];
 
+/// Extensions shared by several languages. `EXTENSION_MAP` holds the default;
+/// [`GrammarManager::disambiguate_extension`] picks from file content.
+pub const AMBIGUOUS_EXTENSIONS: &[&str] = &["m", "v", "sc"];
+
+/// How many leading lines content disambiguation looks at.
+const DISAMBIGUATION_LINES: usize = 200;
+
+/// Manifest declaring custom grammars, e.g. `[languages.foo]` with
+/// `extensions = ["foo"]` loads `tree-sitter-foo.wasm` from the same
+/// directory.
+pub const CUSTOM_GRAMMARS_FILE: &str = "grammars.toml";
+
+/// Directory to read custom grammars from instead of the grammar cache.
+pub const GRAMMAR_DIR_OVERRIDE_ENV: &str = "GGREP_GRAMMAR_DIR_OVERRIDE";
+
+/// A grammar declared in [`CUSTOM_GRAMMARS_FILE`]. It is only ever loaded
+/// from its local WASM file, never downloaded, and its extensions take
+/// precedence over `EXTENSION_MAP`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomGrammar {
+   pub language:   String,
+   pub extensions: Vec<String>,
+   pub wasm_path:  PathBuf,
+}
+
+impl CustomGrammar {
+   fn claims(&self, ext: &str) -> bool {
+      self.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext))
+   }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CustomGrammarsFile {
+   #[serde(default)]
+   languages: BTreeMap<String, CustomGrammarEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CustomGrammarEntry {
+   #[serde(default)]
+   extensions: Vec<String>,
+}
+
+/// Directory holding [`CUSTOM_GRAMMARS_FILE`]: `GGREP_GRAMMAR_DIR_OVERRIDE`
+/// when set, otherwise the grammar cache directory.
+pub fn custom_grammar_dir() -> PathBuf {
+   std::env::var_os(GRAMMAR_DIR_OVERRIDE_ENV)
+      .filter(|dir| !dir.is_empty())
+      .map_or_else(|| config::grammar_dir().clone(), PathBuf::from)
+}
+
+/// Custom grammars currently declared in [`custom_grammar_dir`]. A
+/// [`GrammarManager`] reads them once when it is created; callers without one
+/// (discovery, fingerprints) read the manifest themselves.
+pub fn declared_custom_grammars() -> Vec<CustomGrammar> {
+   load_custom_grammars(&custom_grammar_dir())
+}
+
+fn load_custom_grammars(dir: &Path) -> Vec<CustomGrammar> {
+   let path = dir.join(CUSTOM_GRAMMARS_FILE);
+   let Ok(raw) = std::fs::read_to_string(&path) else {
+      return Vec::new();
+   };
+   let file: CustomGrammarsFile = match toml::from_str(&raw) {
+      Ok(file) => file,
+      Err(err) => {
+         tracing::warn!("ignoring custom grammars in {}: {}", path.display(), err);
+         return Vec::new();
+      },
+   };
+
+   file
+      .languages
+      .into_iter()
+      .filter_map(|(language, entry)| {
+         let language = language.to_ascii_lowercase();
+         if !is_plain_name(&language) {
+            tracing::warn!("ignoring custom grammar {language:?} in {}", path.display());
+            return None;
+         }
+         let extensions = entry
+            .extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+            .filter(|ext| {
+               let plain = is_plain_name(ext);
+               if !plain {
+                  tracing::warn!(
+                     "ignoring extension {ext:?} of custom grammar {language} in {}",
+                     path.display()
+                  );
+               }
+               plain
+            })
+            .collect();
+         Some(CustomGrammar {
+            wasm_path: dir.join(format!("tree-sitter-{language}.wasm")),
+            language,
+            extensions,
+         })
+      })
+      .collect()
+}
+
+/// The grammar in `custom` claiming `ext`, if any.
+pub fn custom_grammar_for_extension<'a>(
+   custom: &'a [CustomGrammar],
+   ext: &str,
+) -> Option<&'a CustomGrammar> {
+   custom.iter().find(|grammar| grammar.claims(ext))
+}
+
+/// Lists the file extensions `language` resolves from, with the `custom`
+/// grammars taking their extensions over `EXTENSION_MAP`; empty if the
+/// language is unknown.
+pub fn language_to_extensions<'a>(custom: &'a [CustomGrammar], language: &str) -> Vec<&'a str> {
+   let declared = custom
+      .iter()
+      .filter(|grammar| grammar.language.eq_ignore_ascii_case(language))
+      .flat_map(|grammar| grammar.extensions.iter().map(String::as_str));
+   let builtin = EXTENSION_MAP
+      .iter()
+      .filter(|(ext, lang)| {
+         lang.eq_ignore_ascii_case(language) && custom_grammar_for_extension(custom, ext).is_none()
+      })
+      .map(|(ext, _)| *ext);
+   declared.chain(builtin).collect()
+}
+
+/// Custom grammar names and extensions end up in file names and SQL
+/// filters, so only `[A-Za-z0-9_+-]` is accepted.
+fn is_plain_name(name: &str) -> bool {
+   !name.is_empty()
+      && name
+         .bytes()
+         .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'+' | b'-'))
+}
+
 /// Manages downloading, caching, and loading tree-sitter grammars
 pub struct GrammarManager {
    grammar_dir:   PathBuf,
    engine:        wasmtime::Engine,
-   languages:     moka::future::Cache<&'static str, Language>,
-   failed:        moka::future::Cache<&'static str, ()>,
+   languages:     moka::future::Cache<String, Language>,
+   failed:        moka::future::Cache<String, ()>,
    auto_download: bool,
+   /// Grammars declared in [`CUSTOM_GRAMMARS_FILE`] when the manager was
+   /// created.
+   custom:        Vec<CustomGrammar>,
 }
 
 impl std::fmt::Debug for GrammarManager {
@@ -149,6 +297,7 @@ impl std::fmt::Debug for GrammarManager {
          .field("languages", &self.languages)
          .field("grammars_dir", &self.grammar_dir)
          .field("auto_download", &self.auto_download)
+         .field("custom", &self.custom)
          .finish()
    }
 }
@@ -160,17 +309,39 @@ impl GrammarManager {
    }
 
    pub fn with_auto_download(auto_download: bool) -> Result<Self> {
-      let grammar_dir = config::grammar_dir();
-      std::fs::create_dir_all(grammar_dir).map_err(ConfigError::CreateGrammarsDir)?;
+      Self::with_custom_grammars(
+         config::grammar_dir().clone(),
+         auto_download,
+         declared_custom_grammars(),
+      )
+   }
+
+   /// Creates a manager caching grammars in `grammar_dir` that resolves the
+   /// `custom` grammars ahead of the built-in ones.
+   pub fn with_custom_grammars(
+      grammar_dir: PathBuf,
+      auto_download: bool,
+      custom: Vec<CustomGrammar>,
+   ) -> Result<Self> {
+      std::fs::create_dir_all(&grammar_dir).map_err(ConfigError::CreateGrammarsDir)?;
+      for grammar in &custom {
+         tracing::debug!(
+            "custom grammar {} for .{} from {}",
+            grammar.language,
+            grammar.extensions.join(", ."),
+            grammar.wasm_path.display()
+         );
+      }
 
       let engine = wasmtime::Engine::default();
 
       Ok(Self {
-         grammar_dir: grammar_dir.clone(),
+         grammar_dir,
          engine,
          languages: moka::future::Cache::builder().max_capacity(32).build(),
          failed: moka::future::Cache::builder().max_capacity(128).build(),
          auto_download,
+         custom,
       })
    }
 
@@ -179,14 +350,149 @@ impl GrammarManager {
       &self.grammar_dir
    }
 
-   /// Converts a file extension to a language name
-   pub fn extension_to_language(ext: &str) -> Option<&'static str> {
+   /// Custom grammars this manager resolves ahead of the built-in ones
+   pub fn custom_grammars(&self) -> &[CustomGrammar] {
+      &self.custom
+   }
+
+   /// The custom grammar claiming `ext`, if any
+   pub fn custom_grammar_for_extension(&self, ext: &str) -> Option<&CustomGrammar> {
+      custom_grammar_for_extension(&self.custom, ext)
+   }
+
+   /// Converts a file extension to a language name, preferring custom
+   /// grammars over `EXTENSION_MAP`
+   pub fn extension_to_language(&self, ext: &str) -> Option<&str> {
+      if let Some(grammar) = self.custom_grammar_for_extension(ext) {
+         return Some(&grammar.language);
+      }
       EXTENSION_MAP
          .iter()
          .find(|(e, _)| e.eq_ignore_ascii_case(ext))
          .map(|(_, lang)| *lang)
    }
 
+   /// Lists the file extensions a language name resolves from; empty if the
+   /// language is unknown
+   pub fn language_to_extensions(&self, language: &str) -> Vec<&str> {
+      language_to_extensions(&self.custom, language)
+   }
+
+   /// Resolves the language for a path from its extension, falling back to
+   /// well-known extensionless file names (e.g. `Makefile`).
+   pub fn path_to_language(&self, path: &Path) -> Option<&str> {
+      if let Some(lang) = path
+         .extension()
+         .and_then(|e| e.to_str())
+         .and_then(|ext| self.extension_to_language(ext))
+      {
+         return Some(lang);
+      }
+      let name = path.file_name()?.to_str()?;
+      match name.to_ascii_lowercase().as_str() {
+         "makefile" | "gnumakefile" => Some("make"),
+         "dockerfile" | "containerfile" => Some("dockerfile"),
+         "build" | "workspace" | "build.bazel" | "workspace.bazel" => Some("starlark"),
+         _ => None,
+      }
+   }
+
+   /// Resolves the language for a path, using `content` to choose between the
+   /// languages that share an ambiguous extension. A `language_overrides`
+   /// entry for the extension takes precedence over both, then a custom
+   /// grammar claiming it.
+   pub fn path_content_to_language(&self, path: &Path, content: &str) -> Option<&str> {
+      if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+         let ext = ext.to_ascii_lowercase();
+         if let Some(lang) = config::get().language_overrides.get(&ext) {
+            return Some(lang.as_str());
+         }
+         if let Some(grammar) = self.custom_grammar_for_extension(&ext) {
+            return Some(&grammar.language);
+         }
+         if let Some(lang) = Self::disambiguate_extension(&ext, content) {
+            return Some(lang);
+         }
+      }
+      self.path_to_language(path)
+   }
+
+   /// Picks the language of a file with an ambiguous extension from keywords
+   /// in its leading lines. Returns `None` for unambiguous extensions or when
+   /// nothing in the content is conclusive, leaving the `EXTENSION_MAP`
+   /// default in place.
+   ///
+   /// MATLAB, Coq, V and Scheme have no bundled grammar, so files detected as
+   /// one of them fall back to simple chunking instead of being parsed with
+   /// the wrong grammar.
+   pub fn disambiguate_extension(ext: &str, content: &str) -> Option<&'static str> {
+      // Skip scanning content for the extensions that cannot be ambiguous.
+      if !AMBIGUOUS_EXTENSIONS.contains(&ext) {
+         return None;
+      }
+      let lines: Vec<&str> = content
+         .lines()
+         .take(DISAMBIGUATION_LINES)
+         .map(str::trim_start)
+         .collect();
+      let any = |prefixes: &[&str]| {
+         lines
+            .iter()
+            .any(|line| prefixes.iter().any(|prefix| line.starts_with(prefix)))
+      };
+
+      match ext {
+         "m" if any(&["#import", "#include", "@interface", "@implementation", "@protocol"]) => {
+            Some("objc")
+         },
+         "m" if any(&["%", "function ", "classdef "]) => Some("matlab"),
+         "v" if any(&["endmodule", "always @", "`timescale"]) => Some("verilog"),
+         "v" if any(&["Theorem ", "Lemma ", "Proof.", "Qed.", "Require Import ", "Inductive "]) => {
+            Some("coq")
+         },
+         "v" if any(&["fn ", "pub fn ", "module main"]) => Some("vlang"),
+         "sc" if any(&["object ", "class ", "trait ", "def ", "val ", "import ", "package "]) => {
+            Some("scala")
+         },
+         "sc" if any(&["(define", "(import", "(library"]) => Some("scheme"),
+         _ => None,
+      }
+   }
+
+   /// Resolves a language from a `#!` interpreter line at the start of
+   /// `content`.
+   pub fn shebang_to_language(content: &str) -> Option<&'static str> {
+      let line = content.lines().next()?.strip_prefix("#!")?;
+      let mut parts = line.split_whitespace();
+      let mut interpreter = parts.next()?.rsplit('/').next()?;
+      if interpreter == "env" {
+         interpreter = parts.find(|arg| !arg.starts_with('-') && !arg.contains('='))?;
+      }
+      let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+      match interpreter {
+         "sh" | "bash" | "zsh" | "dash" | "ksh" => Some("bash"),
+         "python" | "pypy" => Some("python"),
+         "node" | "nodejs" | "deno" | "bun" => Some("javascript"),
+         "ts-node" | "tsx" => Some("typescript"),
+         "ruby" => Some("ruby"),
+         "php" => Some("php"),
+         "lua" | "luajit" => Some("lua"),
+         "elixir" => Some("elixir"),
+         "julia" => Some("julia"),
+         "scala" => Some("scala"),
+         "make" => Some("make"),
+         _ => None,
+      }
+   }
+
+   /// Detects the language of a file from its path, falling back to the
+   /// shebang line for extensionless scripts.
+   pub fn detect_language(&self, path: &Path, content: &str) -> Option<&str> {
+      self
+         .path_content_to_language(path, content)
+         .or_else(|| Self::shebang_to_language(content))
+   }
+
    /// Returns the download URL for a grammar by language name
    pub fn grammar_url(lang: &str) -> Option<&'static str> {
       GRAMMAR_URLS
@@ -221,6 +527,16 @@ impl GrammarManager {
          .map(|(lang, _)| *lang)
    }
 
+   /// Returns an iterator of cached languages whose recorded download URL no
+   /// longer matches the pinned one in `GRAMMAR_URLS`. Grammars cached before
+   /// sources were recorded are not reported.
+   pub fn stale_languages(&self) -> impl Iterator<Item = &'static str> + Clone {
+      GRAMMAR_URLS
+         .iter()
+         .filter(|(lang, url)| is_stale_source(&self.grammar_path(lang), url))
+         .map(|(lang, _)| *lang)
+   }
+
    fn load_language(&self, lang: &str, bytes: &[u8]) -> Result<Language> {
       let mut store = WasmStore::new(&self.engine).map_err(ChunkerError::CreateWasmStore)?;
       store
@@ -230,9 +546,20 @@ impl GrammarManager {
 
    /// Downloads and loads a grammar, using cached version if available
    pub async fn download_grammar(&self, pair: GrammarPair) -> Result<Language> {
+      self.fetch_grammar(pair, false).await
+   }
+
+   /// Downloads and loads a grammar, replacing any cached copy even when it
+   /// is current and intact.
+   pub async fn redownload_grammar(&self, pair: GrammarPair) -> Result<Language> {
+      self.fetch_grammar(pair, true).await
+   }
+
+   async fn fetch_grammar(&self, pair: GrammarPair, force: bool) -> Result<Language> {
       let (lang, url) = pair;
       let dest = self.grammar_path(lang);
-      if dest.exists() {
+      // A stale cache is still better than nothing when downloads are off.
+      if !force && dest.exists() && !(self.auto_download && is_stale_source(&dest, url)) {
          let bytes = match fs::read(&dest).await {
             Ok(b) => b,
             Err(err) => {
@@ -252,11 +579,12 @@ impl GrammarManager {
                   );
                   let _ = fs::remove_file(&dest).await;
                   let _ = fs::remove_file(dest.with_extension("sha256")).await;
+                  let _ = fs::remove_file(dest.with_extension("source")).await;
                } else if let Ok(language) = self.load_language(lang, &bytes) {
                   return Ok(language);
                }
             } else if let Ok(language) = self.load_language(lang, &bytes) {
-               let _ = write_checksum(&dest, &bytes).await;
+               let _ = write_checksum(&dest, &bytes, None).await;
                return Ok(language);
             }
          }
@@ -276,7 +604,7 @@ impl GrammarManager {
       let lock_path = dest.with_extension("lock");
       let _lock = ArtifactLock::acquire(&lock_path, Duration::from_secs(60)).await?;
 
-      if dest.exists() {
+      if !force && dest.exists() && !is_stale_source(&dest, url) {
          let language = fs::read(&dest)
             .await
             .map_err(Error::from)
@@ -338,13 +666,21 @@ impl GrammarManager {
          }
          let _ = fs::remove_file(&tmp_path).await;
       }
-      let _ = write_checksum(&dest, &bytes).await;
+      let _ = write_checksum(&dest, &bytes, Some(url)).await;
 
       Ok(language)
    }
 
    /// Gets a language by name, downloading if necessary
    pub async fn get_language(&self, lang: &str) -> Result<Option<Language>> {
+      if let Some(grammar) = self
+         .custom
+         .iter()
+         .find(|grammar| grammar.language.eq_ignore_ascii_case(lang))
+      {
+         return Ok(self.get_custom_language(grammar).await);
+      }
+
       let pair = GRAMMAR_URLS
          .iter()
          .find(|(l, _)| l.eq_ignore_ascii_case(lang));
@@ -352,11 +688,11 @@ impl GrammarManager {
          return Ok(None);
       };
 
-      if let Some(cached) = self.languages.get(&pair.0).await {
+      if let Some(cached) = self.languages.get(pair.0).await {
          return Ok(Some(cached));
       }
 
-      if self.failed.get(&pair.0).await.is_some() {
+      if self.failed.get(pair.0).await.is_some() {
          return Ok(None);
       }
 
@@ -366,22 +702,67 @@ impl GrammarManager {
             // Treat on-demand grammar downloads as best-effort: chunking will
             // fall back to simple chunking if a grammar cannot be fetched.
             tracing::warn!("failed to download grammar for {}: {}", pair.0, e);
-            self.failed.insert(pair.0, ()).await;
+            self.failed.insert(pair.0.to_string(), ()).await;
             return Ok(None);
          },
       };
 
-      self.languages.insert(pair.0, language.clone()).await;
+      self
+         .languages
+         .insert(pair.0.to_string(), language.clone())
+         .await;
       Ok(Some(language))
    }
 
-   /// Gets a language for a file path based on its extension
-   pub async fn get_language_for_path(&self, path: &Path) -> Result<Option<Language>> {
-      let lang = path
-         .extension()
-         .and_then(|e| e.to_str())
-         .and_then(Self::extension_to_language);
-      let Some(lang) = lang else {
+   /// Whether `lang` has a grammar that could not be downloaded or loaded, so
+   /// its files fall back to simple chunking.
+   pub async fn has_failed(&self, lang: &str) -> bool {
+      self.failed.get(lang).await.is_some()
+   }
+
+   /// Loads a custom grammar from its local WASM file. Failures are logged
+   /// once and leave the language to simple chunking.
+   async fn get_custom_language(&self, grammar: &CustomGrammar) -> Option<Language> {
+      if let Some(cached) = self.languages.get(&grammar.language).await {
+         return Some(cached);
+      }
+      if self.failed.get(&grammar.language).await.is_some() {
+         return None;
+      }
+
+      let language = fs::read(&grammar.wasm_path)
+         .await
+         .map_err(Error::from)
+         .and_then(|bytes| self.load_language(&grammar.language, &bytes));
+      match language {
+         Ok(language) => {
+            self
+               .languages
+               .insert(grammar.language.clone(), language.clone())
+               .await;
+            Some(language)
+         },
+         Err(e) => {
+            tracing::warn!(
+               "failed to load custom grammar {} from {}: {}",
+               grammar.language,
+               grammar.wasm_path.display(),
+               e
+            );
+            self.failed.insert(grammar.language.clone(), ()).await;
+            None
+         },
+      }
+   }
+
+   /// Gets a language for a file path based on its extension or file name,
+   /// using `content` to settle ambiguous extensions
+   pub async fn get_language_for_path(
+      &self,
+      path: &Path,
+      content: &str,
+   ) -> Result<Option<Language>> {
+      let Some(lang) = self.path_content_to_language(path, content) else {
          return Ok(None);
       };
       self.get_language(lang).await
@@ -414,11 +795,202 @@ async fn read_checksum(path: &Path) -> Option<String> {
    }
 }
 
-async fn write_checksum(path: &Path, bytes: &[u8]) -> Result<()> {
+/// Writes the `.sha256` sidecar and, when the grammar was downloaded, a
+/// `.source` sidecar with the URL it came from.
+async fn write_checksum(path: &Path, bytes: &[u8], source: Option<&str>) -> Result<()> {
    let checksum_path = path.with_extension("sha256");
    let digest = hex::encode(Sha256::digest(bytes));
    fs::write(checksum_path, digest)
       .await
       .map_err(ConfigError::WriteWasmFile)?;
+   if let Some(source) = source {
+      fs::write(path.with_extension("source"), source)
+         .await
+         .map_err(ConfigError::WriteWasmFile)?;
+   }
    Ok(())
 }
+
+/// Whether the grammar at `path` was downloaded from a URL other than `url`.
+fn is_stale_source(path: &Path, url: &str) -> bool {
+   path.exists()
+      && std::fs::read_to_string(path.with_extension("source"))
+         .is_ok_and(|source| !source.trim().is_empty() && source.trim() != url)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn shebang_detection() {
+      assert_eq!(GrammarManager::shebang_to_language("#!/bin/sh\necho hi"), Some("bash"));
+      assert_eq!(GrammarManager::shebang_to_language("#!/usr/bin/env python3\n"), Some("python"));
+      assert_eq!(
+         GrammarManager::shebang_to_language("#!/usr/bin/env -S node --flag\n"),
+         Some("javascript")
+      );
+      assert_eq!(GrammarManager::shebang_to_language("#!/usr/bin/perl\n"), None);
+      assert_eq!(GrammarManager::shebang_to_language("echo hi\n"), None);
+   }
+
+   #[test]
+   fn language_to_extensions_reverses_map() {
+      let (_dir, gm) = builtin();
+      assert_eq!(gm.language_to_extensions("rust"), vec!["rs"]);
+      assert_eq!(gm.language_to_extensions("Python"), vec!["py", "pyi"]);
+      assert!(gm.language_to_extensions("cobol").is_empty());
+   }
+
+   fn manager(custom: Vec<CustomGrammar>) -> (tempfile::TempDir, GrammarManager) {
+      let dir = tempfile::TempDir::new().unwrap();
+      let gm =
+         GrammarManager::with_custom_grammars(dir.path().to_path_buf(), false, custom).unwrap();
+      (dir, gm)
+   }
+
+   /// A manager without custom grammars; nothing is downloaded.
+   fn builtin() -> (tempfile::TempDir, GrammarManager) {
+      manager(Vec::new())
+   }
+
+   #[test]
+   fn loads_custom_grammars_manifest() {
+      let dir = tempfile::TempDir::new().unwrap();
+      assert!(load_custom_grammars(dir.path()).is_empty());
+
+      std::fs::write(
+         dir.path().join(CUSTOM_GRAMMARS_FILE),
+         "[languages.acme]\nextensions = [\"a'b\", \"c%\", \"c++\"]\n[languages.\"../x\"]\n",
+      )
+      .unwrap();
+      let grammars = load_custom_grammars(dir.path());
+      assert_eq!(grammars.len(), 1);
+      assert_eq!(grammars[0].extensions, vec!["c++"]);
+
+      std::fs::write(dir.path().join(CUSTOM_GRAMMARS_FILE), "languages = 3\n").unwrap();
+      assert!(load_custom_grammars(dir.path()).is_empty());
+   }
+
+   #[test]
+   fn custom_grammars_resolve_languages() {
+      let dir = tempfile::TempDir::new().unwrap();
+      std::fs::write(
+         dir.path().join(CUSTOM_GRAMMARS_FILE),
+         "[languages.Acme]\nextensions = [\".acme\", \"ACM\", \"v\"]\n",
+      )
+      .unwrap();
+      let (_grammars, gm) = manager(load_custom_grammars(dir.path()));
+      assert_eq!(gm.custom_grammars()[0].wasm_path, dir.path().join("tree-sitter-acme.wasm"));
+
+      assert_eq!(gm.detect_language(Path::new("src/widget.acme"), ""), Some("acme"));
+      assert_eq!(gm.detect_language(Path::new("src/WIDGET.ACM"), ""), Some("acme"));
+      // A custom grammar's extensions win over `EXTENSION_MAP` and content.
+      let verilog = "module counter(input clk);\nendmodule\n";
+      assert_eq!(gm.detect_language(Path::new("rtl/top.v"), verilog), Some("acme"));
+      assert_eq!(gm.language_to_extensions("ACME"), vec!["acme", "acm", "v"]);
+      assert!(!gm.language_to_extensions("verilog").contains(&"v"));
+      assert_eq!(gm.detect_language(Path::new("src/main.rs"), ""), Some("rust"));
+
+      // Only the manager that read the manifest knows the grammar.
+      let (_dir, plain) = builtin();
+      assert_eq!(plain.detect_language(Path::new("src/widget.acme"), ""), None);
+      assert_eq!(plain.detect_language(Path::new("rtl/top.v"), verilog), Some("verilog"));
+   }
+
+   #[tokio::test]
+   async fn unloadable_custom_grammar_falls_back_to_simple_chunking() {
+      let dir = tempfile::TempDir::new().unwrap();
+      let grammar = CustomGrammar {
+         language:   "acme".to_string(),
+         extensions: vec!["acme".to_string()],
+         wasm_path:  dir.path().join("tree-sitter-acme.wasm"),
+      };
+      std::fs::write(&grammar.wasm_path, b"not wasm").unwrap();
+      let (_dir, gm) = manager(vec![grammar]);
+
+      let language = gm
+         .get_language_for_path(Path::new("src/widget.acme"), "")
+         .await
+         .expect("no download is attempted");
+      assert!(language.is_none());
+      assert!(gm.has_failed("acme").await);
+      assert!(!gm.has_failed("rust").await);
+   }
+
+   #[test]
+   fn detect_language_prefers_extension() {
+      let (_dir, gm) = builtin();
+      let rs = Path::new("src/main.rs");
+      assert_eq!(gm.detect_language(rs, "#!/bin/sh\n"), Some("rust"));
+      let script = Path::new("scripts/deploy");
+      assert_eq!(gm.detect_language(script, "#!/bin/bash\n"), Some("bash"));
+      assert_eq!(gm.detect_language(Path::new("Makefile"), ""), Some("make"));
+      assert_eq!(gm.detect_language(Path::new("Dockerfile"), ""), Some("dockerfile"));
+      assert_eq!(gm.detect_language(script, "plain text"), None);
+   }
+
+   #[test]
+   fn disambiguates_m_files() {
+      let (_dir, gm) = builtin();
+      let objc = "#import <Foundation/Foundation.h>\n\n@interface Foo : NSObject\n@end\n";
+      let matlab = "% Compute the mean\nfunction m = average(x)\n  m = sum(x) / numel(x);\nend\n";
+      let path = Path::new("src/foo.m");
+      assert_eq!(gm.detect_language(path, objc), Some("objc"));
+      assert_eq!(gm.detect_language(path, matlab), Some("matlab"));
+      assert_eq!(gm.detect_language(path, "x = 1;\n"), Some("objc"));
+   }
+
+   #[test]
+   fn disambiguates_v_files() {
+      let (_dir, gm) = builtin();
+      let verilog = "module counter(input clk);\n  always @(posedge clk) count <= 1;\nendmodule\n";
+      let coq =
+         "Require Import Arith.\n\nTheorem plus_O : forall n, n + 0 = n.\nProof.\n  auto.\nQed.\n";
+      let vlang = "module main\n\nfn main() {\n\tprintln('hello')\n}\n";
+      let path = Path::new("rtl/top.v");
+      assert_eq!(gm.detect_language(path, verilog), Some("verilog"));
+      assert_eq!(gm.detect_language(path, coq), Some("coq"));
+      assert_eq!(gm.detect_language(path, vlang), Some("vlang"));
+   }
+
+   #[test]
+   fn disambiguates_sc_files() {
+      let (_dir, gm) = builtin();
+      let scala =
+         "import scala.io.Source\n\nobject Main {\n  def main(args: Array[String]): Unit = ()\n}\n";
+      let scheme = ";; factorial\n(define (fact n)\n  (if (= n 0) 1 (* n (fact (- n 1)))))\n";
+      let path = Path::new("scripts/build.sc");
+      assert_eq!(gm.detect_language(path, scala), Some("scala"));
+      assert_eq!(gm.detect_language(path, scheme), Some("scheme"));
+   }
+
+   #[test]
+   fn unambiguous_extensions_ignore_content() {
+      let (_dir, gm) = builtin();
+      assert_eq!(GrammarManager::disambiguate_extension("rs", "% not matlab\n"), None);
+      assert_eq!(gm.detect_language(Path::new("lib.rs"), "(define x 1)\n"), Some("rust"));
+      // Ambiguous extensions still fall back to their `EXTENSION_MAP` default.
+      for ext in AMBIGUOUS_EXTENSIONS {
+         assert!(EXTENSION_MAP.iter().any(|(e, _)| e == ext), "{ext} has no default");
+      }
+   }
+
+   #[test]
+   fn stale_source_compares_recorded_url() {
+      let dir = tempfile::TempDir::new().unwrap();
+      let wasm = dir.path().join("tree-sitter-rust.wasm");
+      let pinned = "https://example.com/v2/tree-sitter-rust.wasm";
+      assert!(!is_stale_source(&wasm, pinned));
+
+      std::fs::write(&wasm, b"wasm").unwrap();
+      assert!(!is_stale_source(&wasm, pinned), "unrecorded source is not stale");
+
+      std::fs::write(wasm.with_extension("source"), pinned).unwrap();
+      assert!(!is_stale_source(&wasm, pinned));
+
+      std::fs::write(wasm.with_extension("source"), "https://example.com/v1/tree-sitter-rust.wasm")
+         .unwrap();
+      assert!(is_stale_source(&wasm, pinned));
+   }
+}
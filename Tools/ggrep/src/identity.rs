@@ -1,6 +1,7 @@
 //! Repo identity and fingerprinting utilities.
 
 use std::{
+   collections::BTreeMap,
    fs,
    path::{Path, PathBuf},
 };
@@ -13,11 +14,11 @@ use crate::{
    config::{self, Config},
    file::{canonical_root, ignore::collect_ignore_files, path_key_from_real},
    git, grammar, meta,
-   types::SearchMode,
+   types::{QueryOptions, SearchMode},
 };
 
 const CONFIG_FINGERPRINT_VERSION: &str = "config-fingerprint-v1";
-const QUERY_FINGERPRINT_VERSION: &str = "query-fingerprint-v1";
+const QUERY_FINGERPRINT_VERSION: &str = "query-fingerprint-v2";
 const EMBED_CONFIG_FINGERPRINT_VERSION: &str = "embed-config-fingerprint-v1";
 const STORE_ID_HASH_LEN: usize = 12;
 
@@ -90,6 +91,7 @@ pub fn compute_config_fingerprint_with_config(
    };
 
    let grammar_urls_hash = hash_grammar_urls();
+   let custom_grammars_hash = hash_custom_grammars();
 
    let input = ConfigFingerprintInput {
       version: CONFIG_FINGERPRINT_VERSION,
@@ -111,6 +113,10 @@ pub fn compute_config_fingerprint_with_config(
       },
       repo_config_hash,
       grammar_urls_hash,
+      language_overrides: (!cfg.language_overrides.is_empty()).then_some(&cfg.language_overrides),
+      extra_index_extensions: (!cfg.extra_index_extensions.is_empty())
+         .then_some(&cfg.extra_index_extensions),
+      custom_grammars_hash,
    };
 
    let payload = serde_json::to_vec(&input)?;
@@ -150,6 +156,10 @@ pub struct QueryFingerprintOptions<'a> {
    pub rerank:      bool,
    pub scope:       Option<&'a Path>,
    pub snippet:     &'a str,
+   /// Ranking and filtering options; `offset` and `index_stats` are ignored
+   /// since they pick a page or add diagnostics rather than change the query.
+   pub options:     &'a QueryOptions,
+   pub min_score:   Option<f32>,
 }
 
 pub fn compute_query_fingerprint(query: &str, opts: QueryFingerprintOptions<'_>) -> Result<String> {
@@ -163,6 +173,8 @@ pub fn compute_query_fingerprint(query: &str, opts: QueryFingerprintOptions<'_>)
       rerank: opts.rerank,
       scope,
       snippet: opts.snippet,
+      options: QueryOptions { offset: 0, index_stats: false, ..opts.options.clone() },
+      min_score: opts.min_score,
    };
    let payload = serde_json::to_vec(&input)?;
    Ok(hex::encode(Sha256::digest(payload)))
@@ -185,6 +197,16 @@ pub fn compute_embed_config_fingerprint(cfg: &Config) -> Result<String> {
 }
 
 fn build_store_id(root: &Path, config_fingerprint: &str) -> Result<String> {
+   let prefix = store_id_prefix(root)?;
+   let cfg_hash = truncate_hash(config_fingerprint, STORE_ID_HASH_LEN);
+
+   Ok(format!("{prefix}{cfg_hash}"))
+}
+
+/// The part of a store id shared by every config of `root`: stores built for
+/// the same repo with other settings (e.g. another embedding model) start
+/// with it.
+pub fn store_id_prefix(root: &Path) -> Result<String> {
    let base = git::resolve_repo_slug(root)?.unwrap_or_else(|| {
       root
          .file_name()
@@ -194,9 +216,8 @@ fn build_store_id(root: &Path, config_fingerprint: &str) -> Result<String> {
    });
    let root_hash = hash_path(root);
    let root_hash = truncate_hash(&root_hash, STORE_ID_HASH_LEN);
-   let cfg_hash = truncate_hash(config_fingerprint, STORE_ID_HASH_LEN);
 
-   Ok(format!("{base}-{root_hash}-{cfg_hash}"))
+   Ok(format!("{base}-{root_hash}-"))
 }
 
 fn hash_path(path: &Path) -> String {
@@ -224,15 +245,47 @@ fn hash_grammar_urls() -> String {
    hex::encode(hasher.finalize())
 }
 
+/// Hashes custom grammar declarations and their WASM bytes, so swapping a
+/// grammar re-chunks the files it parses.
+fn hash_custom_grammars() -> Option<String> {
+   let grammars = grammar::declared_custom_grammars();
+   if grammars.is_empty() {
+      return None;
+   }
+   let mut hasher = Sha256::new();
+   for grammar in &grammars {
+      hasher.update(grammar.language.as_bytes());
+      hasher.update([0u8]);
+      for ext in &grammar.extensions {
+         hasher.update(ext.as_bytes());
+         hasher.update([0u8]);
+      }
+      if let Ok(bytes) = fs::read(&grammar.wasm_path) {
+         hasher.update(Sha256::digest(bytes));
+      }
+      hasher.update([0u8]);
+   }
+   Some(hex::encode(hasher.finalize()))
+}
+
 #[derive(Serialize)]
 struct ConfigFingerprintInput<'a> {
-   version:           &'static str,
-   index_version:     &'static str,
-   chunker:           ChunkerFingerprint,
-   embeddings:        EmbeddingFingerprint<'a>,
-   limits:            LimitsFingerprint,
-   repo_config_hash:  Option<&'a str>,
-   grammar_urls_hash: String,
+   version:                &'static str,
+   index_version:          &'static str,
+   chunker:                ChunkerFingerprint,
+   embeddings:             EmbeddingFingerprint<'a>,
+   limits:                 LimitsFingerprint,
+   repo_config_hash:       Option<&'a str>,
+   grammar_urls_hash:      String,
+   /// Omitted when empty so existing stores keep their fingerprint.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   language_overrides:     Option<&'a BTreeMap<String, String>>,
+   /// Omitted when empty, like `language_overrides`.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   extra_index_extensions: Option<&'a Vec<String>>,
+   /// Omitted when no custom grammars are declared.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   custom_grammars_hash:   Option<String>,
 }
 
 #[derive(Serialize)]
@@ -271,6 +324,8 @@ struct QueryFingerprintInput<'a> {
    rerank:      bool,
    scope:       Option<String>,
    snippet:     &'a str,
+   options:     QueryOptions,
+   min_score:   Option<f32>,
 }
 
 #[derive(Serialize)]
@@ -300,6 +355,25 @@ mod tests {
       assert_ne!(fp1, fp2);
    }
 
+   #[test]
+   fn config_fingerprint_changes_with_language_overrides() {
+      let mut cfg = Config::default();
+      let fp1 = compute_config_fingerprint_with_config(&cfg, None).unwrap();
+      cfg.language_overrides
+         .insert("m".to_string(), "matlab".to_string());
+      let fp2 = compute_config_fingerprint_with_config(&cfg, None).unwrap();
+      assert_ne!(fp1, fp2);
+   }
+
+   #[test]
+   fn config_fingerprint_changes_with_extra_index_extensions() {
+      let mut cfg = Config::default();
+      let fp1 = compute_config_fingerprint_with_config(&cfg, None).unwrap();
+      cfg.extra_index_extensions.push("proto".to_string());
+      let fp2 = compute_config_fingerprint_with_config(&cfg, None).unwrap();
+      assert_ne!(fp1, fp2);
+   }
+
    #[test]
    fn ignore_fingerprint_changes_with_ignore_content() {
       let tmp = TempDir::new().unwrap();
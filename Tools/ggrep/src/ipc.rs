@@ -9,10 +9,13 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use crate::{
    Result,
    error::IpcError,
-   types::{SearchMode, SearchResponse},
+   types::{QueryOptions, SearchMode, SearchResponse},
 };
 
-pub const PROTOCOL_VERSIONS: &[u32] = &[2];
+/// Messages are postcard-encoded, which is not self-describing: adding or
+/// reordering a field in any wire type needs a new version here, or peers on
+/// either side of an upgrade misdecode each other.
+pub const PROTOCOL_VERSIONS: &[u32] = &[3];
 const SCHEMA_VERSION_QUERY_SUCCESS: u32 = 1;
 const SCHEMA_VERSION_QUERY_ERROR: u32 = 1;
 const SCHEMA_VERSION_STATUS: u32 = 1;
@@ -81,12 +84,15 @@ pub enum Request {
       client_capabilities: Vec<String>,
    },
    Search {
-      query:    String,
-      limit:    usize,
-      per_file: usize,
-      mode:     SearchMode,
-      path:     Option<PathBuf>,
-      rerank:   bool,
+      query:       String,
+      limit:       usize,
+      per_file:    usize,
+      mode:        SearchMode,
+      path:        Option<PathBuf>,
+      rerank:      bool,
+      /// Snapshot to search instead of the active one (cursor paging).
+      snapshot_id: Option<String>,
+      options:     QueryOptions,
    },
    Health,
    Gc {
@@ -138,11 +144,15 @@ pub struct ServerStatus {
    pub segments_touched_max: u64,
    pub segments_open:     u64,
    pub segments_budget:   u64,
+   /// Queries whose embedding came from the daemon's query cache.
+   pub query_cache_hits:  u64,
 }
 
 /// Stack-allocated buffer for socket I/O operations
 pub struct SocketBuffer {
-   buf: SmallVec<[u8; 2048]>,
+   buf:            SmallVec<[u8; 2048]>,
+   sent_bytes:     usize,
+   received_bytes: usize,
 }
 
 const DEFAULT_MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
@@ -161,7 +171,17 @@ impl Default for SocketBuffer {
 
 impl SocketBuffer {
    pub fn new() -> Self {
-      Self { buf: SmallVec::new() }
+      Self { buf: SmallVec::new(), sent_bytes: 0, received_bytes: 0 }
+   }
+
+   /// Payload size of the last message sent, excluding the length prefix.
+   pub const fn sent_bytes(&self) -> usize {
+      self.sent_bytes
+   }
+
+   /// Payload size of the last message received, excluding the length prefix.
+   pub const fn received_bytes(&self) -> usize {
+      self.received_bytes
    }
 
    #[allow(
@@ -171,6 +191,26 @@ impl SocketBuffer {
    )]
    /// Serializes and sends a message with length prefix
    pub async fn send<W, T>(&mut self, writer: &mut W, msg: &T) -> Result<()>
+   where
+      W: AsyncWrite + Unpin,
+      T: Serialize,
+   {
+      self.send_with_limit(writer, msg, usize::MAX).await
+   }
+
+   #[allow(
+      clippy::future_not_send,
+      reason = "Generic async function with references - Send bound would be too restrictive for \
+                trait"
+   )]
+   /// Like [`Self::send`], but refuses to write a payload over `max_len` so
+   /// the peer never sees a message it would reject.
+   pub async fn send_with_limit<W, T>(
+      &mut self,
+      writer: &mut W,
+      msg: &T,
+      max_len: usize,
+   ) -> Result<()>
    where
       W: AsyncWrite + Unpin,
       T: Serialize,
@@ -178,10 +218,14 @@ impl SocketBuffer {
       self.buf.clear();
       self.buf.resize(4, 0u8);
       _ = postcard::to_extend(msg, &mut *self).map_err(IpcError::Serialize)?;
-      let payload_len = (self.buf.len() - 4) as u32;
-      *self.buf.first_chunk_mut().unwrap() = payload_len.to_le_bytes();
+      let payload_len = self.buf.len() - 4;
+      if payload_len > max_len {
+         return Err(IpcError::MessageTooLarge { len: payload_len, limit: max_len }.into());
+      }
+      *self.buf.first_chunk_mut().unwrap() = (payload_len as u32).to_le_bytes();
       writer.write_all(&self.buf).await.map_err(IpcError::Write)?;
       writer.flush().await.map_err(IpcError::Write)?;
+      self.sent_bytes = payload_len;
       Ok(())
    }
 
@@ -213,7 +257,7 @@ impl SocketBuffer {
       let len = u32::from_le_bytes(len_buf) as usize;
 
       if len > max_len {
-         return Err(IpcError::MessageTooLarge(len).into());
+         return Err(IpcError::MessageTooLarge { len, limit: max_len }.into());
       }
 
       self.buf.resize(len, 0u8);
@@ -221,6 +265,40 @@ impl SocketBuffer {
          .read_exact(self.buf.as_mut_slice())
          .await
          .map_err(IpcError::Read)?;
+      self.received_bytes = len;
       postcard::from_bytes(&self.buf).map_err(|e| IpcError::Deserialize(e).into())
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::types::FilterStage;
+
+   #[test]
+   fn negotiate_protocol_rejects_previous_wire_format() {
+      assert_eq!(negotiate_protocol(&[2]), None);
+      assert_eq!(negotiate_protocol(&[2, 3]), Some(3));
+   }
+
+   #[test]
+   fn filter_stage_round_trips_through_postcard() {
+      let stages = vec![
+         FilterStage {
+            stage:     "retrieve".into(),
+            filter:    None,
+            removed:   None,
+            remaining: 9,
+         },
+         FilterStage {
+            stage:     "exclude".into(),
+            filter:    Some("target/**".into()),
+            removed:   Some(2),
+            remaining: 7,
+         },
+      ];
+      let bytes = postcard::to_allocvec(&stages).unwrap();
+      let decoded: Vec<FilterStage> = postcard::from_bytes(&bytes).unwrap();
+      assert_eq!(decoded, stages);
+   }
+}
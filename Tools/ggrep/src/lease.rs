@@ -64,6 +64,15 @@ pub struct WriterLease {
    heartbeat:   Option<JoinHandle<()>>,
 }
 
+/// Staging transaction of the store's current writer, if its lease is live.
+pub fn live_staging_txn_id(store_id: &str) -> Option<String> {
+   let lease = read_lease(&lease_path(store_id)).ok()?;
+   if lease.is_stale(Utc::now()) {
+      return None;
+   }
+   lease.staging_txn_id
+}
+
 impl WriterLease {
    pub async fn acquire(store_id: &str) -> Result<Self> {
       let cfg = config::get();
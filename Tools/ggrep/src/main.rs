@@ -3,7 +3,14 @@ use std::{path::PathBuf, sync::LazyLock};
 use clap::{Parser, Subcommand};
 use ggrep::{
    Error, Result,
-   cmd::{self, search::SearchOptions},
+   cmd::{
+      self,
+      clean::CleanScope,
+      grammars::GrammarsAction,
+      index::IndexProgress,
+      search::{SearchFormat, SearchOptions},
+   },
+   snapshot::ChunkingMode,
    types::SearchMode,
    version,
 };
@@ -37,29 +44,23 @@ struct Cli {
 enum Cmd {
    #[command(about = "Search indexed code semantically")]
    Search {
-      #[arg(help = "Search query")]
-      query: String,
+      #[arg(help = "Search query", required_unless_present_any = ["input_file", "repeat"])]
+      query: Option<String>,
 
       #[arg(help = "Directory to search (default: cwd)")]
       path: Option<PathBuf>,
 
-      #[arg(
-         short = 'm',
-         long,
-         alias = "max-count",
-         default_value = "10",
-         help = "Maximum total results"
-      )]
-      max: usize,
+      #[arg(short = 'm', long, alias = "max-count", help = "Maximum total results [default: 10]")]
+      max: Option<usize>,
 
-      #[arg(long, default_value = "1", help = "Maximum results per file")]
-      per_file: usize,
+      #[arg(long, help = "Maximum results per file [default: 1]")]
+      per_file: Option<usize>,
 
       #[arg(
          short = 'd',
          long,
          help = "Discovery mode (favor breadth across code + docs + graphs)",
-         conflicts_with_all = ["implementation", "planning", "debug_mode"]
+         conflicts_with_all = ["implementation", "planning", "debug_mode", "tests", "path_query"]
       )]
       discovery: bool,
 
@@ -67,7 +68,7 @@ enum Cmd {
          short = 'i',
          long,
          help = "Implementation mode (favor code)",
-         conflicts_with_all = ["discovery", "planning", "debug_mode"]
+         conflicts_with_all = ["discovery", "planning", "debug_mode", "tests", "path_query"]
       )]
       implementation: bool,
 
@@ -75,7 +76,7 @@ enum Cmd {
          short = 'p',
          long,
          help = "Planning mode (favor docs + graphs)",
-         conflicts_with_all = ["discovery", "implementation", "debug_mode"]
+         conflicts_with_all = ["discovery", "implementation", "debug_mode", "tests", "path_query"]
       )]
       planning: bool,
 
@@ -83,10 +84,31 @@ enum Cmd {
          short = 'b',
          long = "debug",
          help = "Debug mode (favor debugging code paths)",
-         conflicts_with_all = ["discovery", "implementation", "planning"]
+         conflicts_with_all = ["discovery", "implementation", "planning", "tests", "path_query"]
       )]
       debug_mode: bool,
 
+      #[arg(
+         short = 't',
+         long,
+         help = "Tests mode (favor test files)",
+         conflicts_with_all = [
+            "discovery",
+            "implementation",
+            "planning",
+            "debug_mode",
+            "path_query"
+         ]
+      )]
+      tests: bool,
+
+      #[arg(
+         long,
+         help = "Path query mode (rank file-level anchors to find the file playing a role)",
+         conflicts_with_all = ["discovery", "implementation", "planning", "debug_mode", "tests"]
+      )]
+      path_query: bool,
+
       #[arg(short = 'c', long, help = "Show full content")]
       content: bool,
 
@@ -102,6 +124,13 @@ enum Cmd {
       #[arg(long, help = "Show file paths only (like grep -l)")]
       compact: bool,
 
+      #[arg(
+         long,
+         conflicts_with = "compact",
+         help = "Show per-file result counts only (like grep -c)"
+      )]
+      count: bool,
+
       #[arg(long, help = "Show relevance scores")]
       scores: bool,
 
@@ -117,12 +146,179 @@ enum Cmd {
       #[arg(long, help = "JSON output")]
       json: bool,
 
+      #[arg(
+         long,
+         requires = "json",
+         help = "Stream JSON results incrementally, with meta as trailing keys"
+      )]
+      json_incremental: bool,
+
+      #[arg(
+         long,
+         conflicts_with_all = ["json", "input_file"],
+         help = "NDJSON output: meta on the first line, then one result per line"
+      )]
+      ndjson: bool,
+
+      #[arg(
+         long,
+         value_name = "N",
+         requires = "json",
+         help = "Cap serialized JSON output at N bytes (trims snippets, then drops results)"
+      )]
+      max_bytes: Option<usize>,
+
+      #[arg(
+         long,
+         value_name = "FILE",
+         requires = "json",
+         conflicts_with_all = ["json_incremental", "dry_run"],
+         help = "Run one query per line from FILE, emitting one NDJSON line per query"
+      )]
+      input_file: Option<PathBuf>,
+
+      #[arg(
+         long,
+         value_name = "N",
+         conflicts_with = "input_file",
+         help = "Return N results per page with a cursor for the next page"
+      )]
+      page_size: Option<usize>,
+
+      #[arg(
+         long,
+         value_name = "TOKEN",
+         conflicts_with = "input_file",
+         help = "Continue a paged search from a previous next_cursor"
+      )]
+      cursor: Option<String>,
+
+      #[arg(
+         long,
+         value_name = "N",
+         default_value_t = 0,
+         conflicts_with_all = ["page_size", "cursor"],
+         help = "Skip the first N ranked results"
+      )]
+      offset: usize,
+
+      #[arg(
+         long,
+         conflicts_with_all = ["query", "input_file"],
+         help = "Re-run the last search with its query, mode, limits, and scope"
+      )]
+      repeat: bool,
+
       #[arg(long, help = "Show explainability metadata")]
       explain: bool,
 
+      #[arg(long, hide = true, requires = "explain")]
+      explain_sql: bool,
+
+      #[arg(long, help = "Print a timing breakdown after the results (JSON: timings_ms.total)")]
+      timing: bool,
+
       #[arg(long, help = "Skip ColBERT reranking")]
       no_rerank: bool,
 
+      #[arg(long, help = "Keep overlapping chunks of the same file as separate results")]
+      no_context_dedup: bool,
+
+      #[arg(
+         long,
+         conflicts_with = "no_context_dedup",
+         help = "Merge consecutive chunks of the same file into one span (default with --content)"
+      )]
+      merge_adjacent: bool,
+
+      #[arg(
+         long = "lang",
+         value_name = "LANGUAGE",
+         help = "Only search files of this language, e.g. rust (repeatable)"
+      )]
+      languages: Vec<String>,
+
+      #[arg(
+         long = "exclude",
+         value_name = "GLOB",
+         help = "Drop results whose path matches GLOB, e.g. node_modules (repeatable)"
+      )]
+      excludes: Vec<String>,
+
+      #[arg(long, help = "Drop results whose content repeats a higher-ranked result")]
+      dedup_content: bool,
+
+      #[arg(
+         long = "no-fts",
+         alias = "vector-only",
+         help = "Skip full-text retrieval and rank by vector similarity only"
+      )]
+      no_fts: bool,
+
+      #[arg(
+         short = 'B',
+         long,
+         value_name = "NUM",
+         help = "Keep at most NUM lines of context before each match (default: previous chunk)"
+      )]
+      before_context: Option<usize>,
+
+      #[arg(
+         short = 'A',
+         long,
+         value_name = "NUM",
+         help = "Keep at most NUM lines of context after each match (default: next chunk)"
+      )]
+      after_context: Option<usize>,
+
+      #[arg(
+         long,
+         value_name = "PATTERN",
+         help = "Keep only results whose snippet matches this regex"
+      )]
+      grep: Option<String>,
+
+      #[arg(
+         long,
+         value_name = "SCORE",
+         help = "Drop results whose final score (after rerank) is below SCORE"
+      )]
+      min_score: Option<f32>,
+
+      #[arg(long, help = "Highlight query terms in snippets (ignored with --plain)")]
+      highlight: bool,
+
+      #[arg(long, help = "Annotate each result with the last commit to touch its start line")]
+      blame: bool,
+
+      #[arg(
+         long,
+         value_name = "DAYS",
+         help = "Boost files modified in the last DAYS days (0 disables; default: config)"
+      )]
+      recent_boost: Option<u32>,
+
+      #[arg(long, help = "Use the default store id with an '-eval' suffix")]
+      eval_store: bool,
+
+      #[arg(long, help = "Disable ANSI colors and use simpler formatting")]
+      plain: bool,
+
+      #[arg(
+         long,
+         value_enum,
+         default_value_t,
+         conflicts_with_all = ["json", "compact", "count"],
+         help = "Output format (github emits Actions ::notice annotations)"
+      )]
+      format: SearchFormat,
+   },
+
+   #[command(about = "Interactive search session over a persistent daemon connection")]
+   Repl {
+      #[arg(help = "Directory to search (default: cwd)")]
+      path: Option<PathBuf>,
+
       #[arg(long, help = "Use the default store id with an '-eval' suffix")]
       eval_store: bool,
 
@@ -138,6 +334,9 @@ enum Cmd {
       #[arg(long, help = "Output JSON report path (default: temp dir)")]
       out: Option<PathBuf>,
 
+      #[arg(long, help = "Also render the report as a self-contained HTML page")]
+      html: Option<PathBuf>,
+
       #[arg(long, help = "Directory to search/index (default: cwd)")]
       path: Option<PathBuf>,
 
@@ -156,13 +355,16 @@ enum Cmd {
       #[arg(
          long,
          help = "Override search mode for all cases \
-                 (balanced|discovery|implementation|planning|debug)"
+                 (balanced|discovery|implementation|planning|debug|tests|paths)"
       )]
       mode: Option<String>,
 
       #[arg(long, help = "Skip ColBERT reranking for all cases")]
       no_rerank: bool,
 
+      #[arg(long, help = "Skip full-text retrieval for all cases (pure vector search)")]
+      vector_only: bool,
+
       #[arg(long, help = "Include anchor chunks in evaluation")]
       include_anchors: bool,
 
@@ -175,6 +377,9 @@ enum Cmd {
       #[arg(long, help = "Fail if mean MRR is below this threshold (0..1)")]
       fail_under_mrr: Option<f32>,
 
+      #[arg(long, help = "Fail if mean nDCG@k is below this threshold (0..1)")]
+      fail_under_ndcg: Option<f32>,
+
       #[arg(long, help = "Baseline eval JSON for regression gating")]
       baseline: Option<PathBuf>,
 
@@ -183,6 +388,30 @@ enum Cmd {
 
       #[arg(long, help = "Allowed mean MRR drop vs baseline (0..1)")]
       baseline_max_drop_mrr: Option<f32>,
+
+      #[arg(long, default_value = "1", help = "Run each case N times and report variance")]
+      repeat: usize,
+
+      #[arg(long, help = "Shuffle case order on every run")]
+      shuffle: bool,
+
+      #[arg(long, default_value = "1", help = "Throwaway queries to run before timing the suite")]
+      warmup: usize,
+
+      #[arg(short = 'j', long, default_value = "1", help = "Evaluate up to N cases concurrently")]
+      jobs: usize,
+   },
+
+   #[command(name = "eval-diff", about = "Compare two eval reports case by case")]
+   EvalDiff {
+      #[arg(help = "Earlier eval JSON report")]
+      before: PathBuf,
+
+      #[arg(help = "Later eval JSON report")]
+      after: PathBuf,
+
+      #[arg(long, help = "Output JSON")]
+      json: bool,
    },
 
    #[command(about = "Index a directory for semantic search")]
@@ -201,6 +430,48 @@ enum Cmd {
 
       #[arg(long, help = "Allow degraded snapshots when syncing")]
       allow_degraded: bool,
+
+      #[arg(
+         long,
+         help = "Quarantine files that fail to index instead of failing or degrading the snapshot"
+      )]
+      quiet_errors: bool,
+
+      #[arg(long, conflicts_with = "dry_run", help = "Print a JSON summary of the built index")]
+      stats_json: bool,
+
+      #[arg(
+         long,
+         value_name = "FORMAT",
+         value_enum,
+         default_value_t,
+         help = "Progress output (json writes one progress object per line to stderr)"
+      )]
+      progress: IndexProgress,
+
+      #[arg(long, conflicts_with = "full", help = "Anchor-only chunking (quick, lower recall)")]
+      fast: bool,
+
+      #[arg(long, help = "Structural chunking (slower, better recall)")]
+      full: bool,
+
+      #[arg(
+         long,
+         value_name = "N",
+         value_parser = clap::value_parser!(usize).range(1..),
+         help = "Override the per-file chunk cap for this run"
+      )]
+      max_chunks_per_file: Option<usize>,
+
+      #[arg(long, value_name = "GLOB", help = "Only index files matching this glob (repeatable)")]
+      only: Vec<String>,
+
+      #[arg(
+         long,
+         value_name = "EXT",
+         help = "Also index files with this extension this run, e.g. '*.proto' (repeatable)"
+      )]
+      include: Vec<String>,
    },
 
    #[command(about = "Start a background daemon for faster searches")]
@@ -210,6 +481,23 @@ enum Cmd {
 
       #[arg(long, help = "Allow degraded snapshots when syncing")]
       allow_degraded: bool,
+
+      #[arg(long, help = "Open segments and load the embedder in the background on startup")]
+      prewarm: bool,
+
+      #[arg(long, conflicts_with = "full", help = "Anchor-only chunking (quick, lower recall)")]
+      fast: bool,
+
+      #[arg(long, help = "Structural chunking (slower, better recall)")]
+      full: bool,
+
+      #[arg(
+         long,
+         value_name = "N",
+         value_parser = clap::value_parser!(usize).range(1..),
+         help = "Override the per-file chunk cap for this run"
+      )]
+      max_chunks_per_file: Option<usize>,
    },
 
    #[command(about = "Stop the daemon for a directory")]
@@ -242,6 +530,18 @@ enum Cmd {
       json: bool,
    },
 
+   #[command(
+      name = "verify-store",
+      about = "Deep consistency check of manifest, segments, segment index, and tombstones"
+   )]
+   VerifyStore {
+      #[arg(short = 'p', long, help = "Directory to verify (default: cwd)")]
+      path: Option<PathBuf>,
+
+      #[arg(long, help = "JSON output")]
+      json: bool,
+   },
+
    #[command(about = "Compact index segments and prune tombstones")]
    Compact {
       #[arg(short = 'p', long, help = "Directory to compact (default: cwd)")]
@@ -254,6 +554,25 @@ enum Cmd {
       json: bool,
    },
 
+   #[command(
+      name = "reset-embeddings",
+      about = "Re-embed stored chunks with the configured model, without re-chunking"
+   )]
+   ResetEmbeddings {
+      #[arg(short = 'p', long, help = "Directory to re-embed (default: cwd)")]
+      path: Option<PathBuf>,
+
+      #[arg(
+         long,
+         value_name = "STORE_ID",
+         help = "Store to take chunks from (default: this repo's store built with another model)"
+      )]
+      from: Option<String>,
+
+      #[arg(long, help = "JSON output")]
+      json: bool,
+   },
+
    #[command(name = "upgrade-store", about = "Upgrade store format (placeholder)")]
    UpgradeStore {
       #[arg(short = 'p', long, help = "Directory to upgrade (default: cwd)")]
@@ -273,6 +592,20 @@ enum Cmd {
 
       #[arg(long, help = "Clean all stores")]
       all: bool,
+
+      #[arg(
+         long,
+         conflicts_with = "keep_data",
+         help = "Also remove downloaded models and grammars"
+      )]
+      everything: bool,
+
+      #[arg(
+         long,
+         conflicts_with_all = ["store_id", "all"],
+         help = "Remove only downloaded models and grammars; keep index data"
+      )]
+      keep_data: bool,
    },
 
    #[command(name = "clone-store", about = "Clone a store to a new store id")]
@@ -304,6 +637,13 @@ enum Cmd {
       #[arg(long, help = "GC orphaned stores under ~/.ggrep/data")]
       stores: bool,
 
+      #[arg(
+         long,
+         conflicts_with = "stores",
+         help = "GC stale temp files, unheld lock files and orphaned staging dirs"
+      )]
+      artifacts: bool,
+
       #[arg(long, help = "Delete instead of dry-run")]
       force: bool,
 
@@ -311,6 +651,15 @@ enum Cmd {
       json: bool,
    },
 
+   #[command(about = "List, fetch or prune cached tree-sitter grammars")]
+   Grammars {
+      #[command(subcommand)]
+      action: GrammarsCmd,
+
+      #[arg(long, global = true, help = "JSON output")]
+      json: bool,
+   },
+
    #[command(about = "Download and configure embedding models")]
    Setup,
 
@@ -345,6 +694,36 @@ enum Cmd {
    Mcp,
 }
 
+/// Actions for `ggrep grammars`
+#[derive(Subcommand)]
+enum GrammarsCmd {
+   #[command(about = "Show installed and missing grammars with size and checksum status")]
+   List,
+
+   #[command(about = "Download grammars for the given languages")]
+   Fetch {
+      #[arg(required = true, help = "Languages to download")]
+      languages: Vec<String>,
+
+      #[arg(long, help = "Download again even if a valid copy is cached")]
+      force: bool,
+   },
+
+   #[command(about = "Remove cached grammars that are neither built in nor custom")]
+   Prune,
+}
+
+/// Maps the mutually exclusive `--fast`/`--full` flags to an explicit mode.
+const fn chunking_flag(fast: bool, full: bool) -> Option<ChunkingMode> {
+   if fast {
+      Some(ChunkingMode::Fast)
+   } else if full {
+      Some(ChunkingMode::Full)
+   } else {
+      None
+   }
+}
+
 #[tokio::main]
 async fn main() {
    tracing_subscriber::fmt()
@@ -363,8 +742,17 @@ async fn main() {
 async fn run(cli: Cli) -> Result<()> {
    if cli.command.is_none() && !cli.query.is_empty() {
       let query = cli.query.join(" ");
-      return cmd::search::execute(query, None, 10, 1, SearchOptions::default(), false, cli.store)
-         .await;
+      return cmd::search::execute(
+         query,
+         None,
+         10,
+         1,
+         SearchOptions::default(),
+         None,
+         false,
+         cli.store,
+      )
+      .await;
    }
 
    match cli.command {
@@ -377,60 +765,138 @@ async fn run(cli: Cli) -> Result<()> {
          implementation,
          planning,
          debug_mode,
+         tests,
+         path_query,
          content,
          no_snippet,
          short_snippet,
          long_snippet,
          compact,
+         count,
          scores,
          sync,
          dry_run,
          allow_degraded,
          json,
+         json_incremental,
+         ndjson,
          explain,
+         explain_sql,
+         timing,
          no_rerank,
+         no_context_dedup,
+         merge_adjacent,
+         languages,
+         excludes,
+         dedup_content,
+         no_fts,
+         before_context,
+         after_context,
+         grep,
+         min_score,
+         highlight,
+         blame,
+         recent_boost,
          eval_store,
          plain,
+         format,
+         max_bytes,
+         input_file,
+         page_size,
+         cursor,
+         offset,
+         repeat,
       }) => {
-         cmd::search::execute(
-            query,
-            path,
-            max,
-            per_file,
-            SearchOptions {
-               content,
-               no_snippet,
-               short_snippet,
-               long_snippet,
-               compact,
-               scores,
-               sync,
-               dry_run,
-               allow_degraded,
-               json,
-               explain,
-               no_rerank,
-               plain,
-               mode: if discovery {
-                  SearchMode::Discovery
-               } else if implementation {
-                  SearchMode::Implementation
-               } else if planning {
-                  SearchMode::Planning
-               } else if debug_mode {
-                  SearchMode::Debug
-               } else {
-                  SearchMode::Balanced
-               },
-            },
-            eval_store,
-            cli.store,
-         )
-         .await
+         let explicit_mode = if discovery {
+            Some(SearchMode::Discovery)
+         } else if implementation {
+            Some(SearchMode::Implementation)
+         } else if planning {
+            Some(SearchMode::Planning)
+         } else if debug_mode {
+            Some(SearchMode::Debug)
+         } else if tests {
+            Some(SearchMode::Tests)
+         } else if path_query {
+            Some(SearchMode::Paths)
+         } else {
+            None
+         };
+         // Flags given alongside --repeat win over the recorded search.
+         let (query, path, max, per_file, mode) = if repeat {
+            let last =
+               cmd::search::LastSearch::load()?.with_overrides(path, max, per_file, explicit_mode);
+            (Some(last.query), Some(last.path), last.max, last.per_file, last.mode)
+         } else {
+            (
+               query,
+               path,
+               max.unwrap_or(10),
+               per_file.unwrap_or(1),
+               explicit_mode.unwrap_or_default(),
+            )
+         };
+         let mode_flag = mode.flag();
+         let options = SearchOptions {
+            content,
+            no_snippet,
+            short_snippet,
+            long_snippet,
+            compact,
+            count,
+            scores,
+            sync,
+            dry_run,
+            allow_degraded,
+            json,
+            json_incremental,
+            ndjson,
+            explain,
+            explain_sql,
+            timing,
+            no_rerank,
+            plain,
+            mode,
+            mode_flag,
+            format,
+            max_bytes,
+            blame,
+            recent_boost_days: recent_boost,
+            page_size,
+            offset,
+            no_context_dedup,
+            merge_adjacent,
+            languages,
+            excludes,
+            dedup_content,
+            no_fts,
+            before_context,
+            after_context,
+            grep,
+            min_score,
+            highlight,
+         };
+         if let Some(input_file) = input_file {
+            // With --input-file there is no query positional, so a lone
+            // positional argument is the directory to search.
+            let path = path.or_else(|| query.map(PathBuf::from));
+            cmd::search::execute_batch(
+               input_file, path, max, per_file, options, eval_store, cli.store,
+            )
+            .await
+         } else {
+            let query = query.unwrap_or_default();
+            cmd::search::execute(query, path, max, per_file, options, cursor, eval_store, cli.store)
+               .await
+         }
+      },
+      Some(Cmd::Repl { path, eval_store, plain }) => {
+         cmd::repl::execute(path, plain, eval_store, cli.store).await
       },
       Some(Cmd::Eval {
          cases,
          out,
+         html,
          path,
          only,
          no_sync,
@@ -438,17 +904,24 @@ async fn run(cli: Cli) -> Result<()> {
          per_file,
          mode,
          no_rerank,
+         vector_only,
          include_anchors,
          eval_store,
          fail_under_pass_rate,
          fail_under_mrr,
+         fail_under_ndcg,
          baseline,
          baseline_max_drop_pass_rate,
          baseline_max_drop_mrr,
+         repeat,
+         shuffle,
+         warmup,
+         jobs,
       }) => {
          cmd::eval::execute(
             cases,
             out,
+            html,
             path,
             only,
             no_sync,
@@ -456,43 +929,110 @@ async fn run(cli: Cli) -> Result<()> {
             per_file,
             mode,
             no_rerank,
+            vector_only,
             include_anchors,
             eval_store,
             fail_under_pass_rate,
             fail_under_mrr,
+            fail_under_ndcg,
             baseline,
             baseline_max_drop_pass_rate,
             baseline_max_drop_mrr,
+            repeat,
+            shuffle,
+            warmup,
+            jobs,
             cli.store,
          )
          .await
       },
-      Some(Cmd::Index { path, dry_run, reset, eval_store, allow_degraded }) => {
-         cmd::index::execute(path, dry_run, reset, eval_store, allow_degraded, cli.store).await
+      Some(Cmd::EvalDiff { before, after, json }) => cmd::eval::execute_diff(before, after, json),
+      Some(Cmd::Index {
+         path,
+         dry_run,
+         reset,
+         eval_store,
+         allow_degraded,
+         quiet_errors,
+         stats_json,
+         progress,
+         fast,
+         full,
+         max_chunks_per_file,
+         only,
+         include,
+      }) => {
+         cmd::index::execute(
+            path,
+            dry_run,
+            reset,
+            eval_store,
+            allow_degraded,
+            quiet_errors,
+            stats_json,
+            progress,
+            chunking_flag(fast, full),
+            max_chunks_per_file,
+            only,
+            include,
+            cli.store,
+         )
+         .await
       },
-      Some(Cmd::Serve { path, allow_degraded }) => {
-         cmd::serve::execute(path, cli.store, allow_degraded).await
+      Some(Cmd::Serve { path, allow_degraded, prewarm, fast, full, max_chunks_per_file }) => {
+         cmd::serve::execute(
+            path,
+            cli.store,
+            allow_degraded,
+            prewarm,
+            chunking_flag(fast, full),
+            max_chunks_per_file,
+         )
+         .await
       },
       Some(Cmd::Stop { path }) => cmd::stop::execute(path).await,
       Some(Cmd::StopAll) => cmd::stop_all::execute().await,
       Some(Cmd::Status { json }) => cmd::status::execute(json).await,
       Some(Cmd::Health { json }) => cmd::health::execute(json).await,
       Some(Cmd::Audit { path, json }) => cmd::audit::execute(path, json, cli.store).await,
+      Some(Cmd::VerifyStore { path, json }) => {
+         cmd::verify_store::execute(path, json, cli.store).await
+      },
       Some(Cmd::Compact { path, force, json }) => {
          cmd::compact::execute(path, force, json, cli.store).await
       }
+      Some(Cmd::ResetEmbeddings { path, from, json }) => {
+         cmd::reset_embeddings::execute(path, from, json, cli.store).await
+      }
       Some(Cmd::UpgradeStore { path }) => cmd::upgrade_store::execute(path, cli.store),
       Some(Cmd::Repair { path }) => cmd::repair::execute(path, cli.store).await,
-      Some(Cmd::Clean { store_id, all }) => cmd::clean::execute(store_id, all),
+      Some(Cmd::Clean { store_id, all, everything, keep_data }) => {
+         let scope = if everything {
+            CleanScope::Everything
+         } else if keep_data {
+            CleanScope::CachesOnly
+         } else {
+            CleanScope::Stores
+         };
+         cmd::clean::execute(store_id, all, scope)
+      },
       Some(Cmd::CloneStore { from, to, overwrite }) => {
          cmd::clone_store::execute(from, to, overwrite)
       },
       Some(Cmd::PromoteEval { path, overwrite }) => {
          cmd::promote_eval::execute(path, overwrite, cli.store)
       },
-      Some(Cmd::Gc { path, stores, force, json }) => {
-         cmd::gc::execute(stores, force, json, path, cli.store).await
+      Some(Cmd::Gc { path, stores, artifacts, force, json }) => {
+         cmd::gc::execute(stores, artifacts, force, json, path, cli.store).await
       }
+      Some(Cmd::Grammars { action, json }) => {
+         let action = match action {
+            GrammarsCmd::List => GrammarsAction::List,
+            GrammarsCmd::Fetch { languages, force } => GrammarsAction::Fetch { languages, force },
+            GrammarsCmd::Prune => GrammarsAction::Prune,
+         };
+         cmd::grammars::execute(action, json).await
+      },
       Some(Cmd::Setup) => cmd::setup::execute().await,
       Some(Cmd::Doctor) => cmd::doctor::execute(),
       Some(Cmd::List { json }) => cmd::list::execute(json),
@@ -1,7 +1,7 @@
 //! File metadata tracking for incremental indexing
 
 use std::{
-   collections::HashMap,
+   collections::{BTreeMap, HashMap},
    fmt, fs,
    path::{Path, PathBuf},
 };
@@ -24,6 +24,30 @@ pub struct FileMeta {
    pub head_hash: Option<FileHash>,
 }
 
+/// A file that failed to index since it was last indexed successfully. Once
+/// quarantined (by `ggrep index --quiet-errors`, or after
+/// `quarantine_after_failures` failures) syncs skip it while its content is
+/// the version that failed: same mtime and size, or the same content hash.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileFailure {
+   /// Times the file failed to index, across edits.
+   pub failures:     u32,
+   pub mtime:        u64,
+   pub size:         u64,
+   /// Hash of the content that failed; `None` when it could not be read.
+   #[serde(default)]
+   pub content_hash: Option<FileHash>,
+   pub code:         String,
+   pub message:      String,
+   #[serde(default)]
+   pub last_failure: Option<String>,
+   #[serde(default)]
+   pub quarantined:  bool,
+   /// Chunk cap a `chunk_cap_exceeded` failure ran into.
+   #[serde(default)]
+   pub chunk_cap:    Option<usize>,
+}
+
 /// SHA-256 hash of file contents
 #[derive(Serialize, Deserialize, Copy, Clone, Default, Eq, PartialEq, Hash)]
 #[repr(transparent)]
@@ -132,6 +156,8 @@ pub struct MetaStore {
    #[serde(default, skip_serializing)]
    hashes:                HashMap<PathBuf, FileHash>,
    #[serde(default)]
+   failures:              BTreeMap<PathBuf, FileFailure>,
+   #[serde(default)]
    model:                 Option<ModelSignature>,
    #[serde(default)]
    index_version:         Option<String>,
@@ -186,6 +212,7 @@ impl MetaStore {
          Self {
             files: HashMap::new(),
             hashes: HashMap::new(),
+            failures: BTreeMap::new(),
             model: None,
             index_version: None,
             config_fingerprint: None,
@@ -284,6 +311,7 @@ impl MetaStore {
       size: u64,
       head_hash: FileHash,
    ) {
+      self.failures.remove(&path);
       self
          .files
          .insert(path, FileMeta { hash, mtime, size, head_hash: Some(head_hash) });
@@ -296,6 +324,120 @@ impl MetaStore {
       self.dirty = true;
    }
 
+   /// Counts a failure to index `path` at `mtime`/`size` (with content
+   /// `content_hash`, when it was read) and returns the failures since it was
+   /// last indexed. Does not quarantine it.
+   pub fn note_failure(
+      &mut self,
+      path: PathBuf,
+      mtime: u64,
+      size: u64,
+      content_hash: Option<FileHash>,
+      code: String,
+      message: String,
+   ) -> u32 {
+      let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+      let (failures, quarantined) = self
+         .failures
+         .get(&path)
+         .map_or((0, false), |f| (f.failures, f.quarantined));
+      let failures = failures.saturating_add(1);
+      self.failures.insert(path, FileFailure {
+         failures,
+         mtime,
+         size,
+         content_hash,
+         code,
+         message,
+         last_failure: Some(now),
+         quarantined,
+         chunk_cap: None,
+      });
+      self.dirty = true;
+      failures
+   }
+
+   /// Quarantines a file that failed to index at `mtime`/`size`, bumping its
+   /// failure count.
+   pub fn record_failure(
+      &mut self,
+      path: PathBuf,
+      mtime: u64,
+      size: u64,
+      content_hash: Option<FileHash>,
+      code: String,
+      message: String,
+   ) {
+      self.note_failure(path.clone(), mtime, size, content_hash, code, message);
+      self.quarantine(&path);
+   }
+
+   /// Quarantines a file with a recorded failure.
+   pub fn quarantine(&mut self, path: &Path) {
+      if let Some(failure) = self.failures.get_mut(path) {
+         failure.quarantined = true;
+         self.dirty = true;
+      }
+   }
+
+   /// Whether `path` is quarantined and unchanged since it last failed.
+   pub fn is_quarantined(&self, path: &Path, mtime: u64, size: u64) -> bool {
+      self
+         .failures
+         .get(path)
+         .is_some_and(|f| f.quarantined && f.mtime == mtime && f.size == size)
+   }
+
+   /// Whether `path` is quarantined and `hash` is the content that failed,
+   /// though its mtime or size moved (e.g. a checkout rewrote it). Records the
+   /// new mtime and size so later syncs skip it without reading it.
+   pub fn quarantine_holds(&mut self, path: &Path, hash: FileHash, mtime: u64, size: u64) -> bool {
+      let Some(failure) = self.failures.get_mut(path) else {
+         return false;
+      };
+      if !failure.quarantined || failure.content_hash != Some(hash) {
+         return false;
+      }
+      failure.mtime = mtime;
+      failure.size = size;
+      self.dirty = true;
+      true
+   }
+
+   /// Records the chunk cap that `path`'s last failure exceeded.
+   pub fn note_chunk_cap(&mut self, path: &Path, cap: usize) {
+      if let Some(failure) = self.failures.get_mut(path) {
+         failure.chunk_cap = Some(cap);
+         self.dirty = true;
+      }
+   }
+
+   /// Forgets `chunk_cap_exceeded` failures recorded under a cap below `cap`,
+   /// releasing their quarantine so a raised cap retries them.
+   pub fn release_chunk_cap_failures(&mut self, cap: usize) {
+      let before = self.failures.len();
+      self.failures.retain(|_, f| {
+         f.code != "chunk_cap_exceeded" || f.chunk_cap.is_some_and(|recorded| recorded >= cap)
+      });
+      if self.failures.len() != before {
+         self.dirty = true;
+      }
+   }
+
+   /// Quarantined files, by path.
+   pub fn quarantined(&self) -> impl Iterator<Item = (&PathBuf, &FileFailure)> {
+      self.failures.iter().filter(|(_, f)| f.quarantined)
+   }
+
+   /// Forgets quarantined files that no longer exist under `root`.
+   pub fn prune_failures(&mut self, root: &Path) {
+      let before = self.failures.len();
+      self.failures.retain(|path, _| root.join(path).exists());
+      if self.failures.len() != before {
+         self.dirty = true;
+      }
+   }
+
    /// Saves the metadata store to disk if dirty
    pub fn save(&mut self) -> Result<()> {
       if !self.dirty {
@@ -313,6 +455,27 @@ impl MetaStore {
       Ok(())
    }
 
+   /// Replaces the tracked files with `other`'s, for a store rebuilt from
+   /// `other`'s chunks.
+   pub fn copy_files_from(&mut self, other: &Self) {
+      self.files.clone_from(&other.files);
+      self.dirty = true;
+   }
+
+   /// Forgets every recorded failure, releasing quarantined files.
+   pub fn clear_failures(&mut self) {
+      if !self.failures.is_empty() {
+         self.failures.clear();
+         self.dirty = true;
+      }
+   }
+
+   /// Replaces the recorded failures with those of `other`.
+   pub fn copy_failures_from(&mut self, other: &Self) {
+      self.failures.clone_from(&other.failures);
+      self.dirty = true;
+   }
+
    /// Returns an iterator over all tracked file paths
    pub fn all_paths(&self) -> impl Iterator<Item = &PathBuf> {
       self.files.keys()
@@ -355,7 +518,8 @@ impl MetaStore {
       self.dirty = true;
    }
 
-   /// Clears all tracked metadata.
+   /// Clears all tracked metadata. Failure counts describe files rather than
+   /// the store, so they are kept; see [`Self::clear_failures`].
    pub fn clear_all(&mut self) {
       if !self.files.is_empty() {
          self.files.clear();
@@ -386,6 +550,7 @@ impl MetaStore {
    /// Clears all tracked metadata and records the current index signature
    pub fn reset_for_signature_change(&mut self) {
       self.files.clear();
+      self.failures.clear();
       self.model = Some(ModelSignature::current());
       self.index_version = Some(current_index_version());
       self.config_fingerprint = None;
@@ -571,6 +736,71 @@ mod tests {
       });
    }
 
+   #[test]
+   fn quarantine_holds_until_the_file_changes() {
+      with_temp_home(|_| {
+         let mut store = MetaStore::load("quarantine_test").unwrap();
+         let path = PathBuf::from("src/poison.rs");
+         let poison = FileHash::sum(b"poison");
+         for mtime in [10, 11] {
+            let (code, message) = ("chunk_failed".to_string(), "boom".to_string());
+            store.record_failure(path.clone(), mtime, 20, Some(poison), code, message);
+         }
+         assert!(store.is_quarantined(&path, 11, 20));
+         assert!(!store.is_quarantined(&path, 12, 20));
+         // Touched but unchanged: still quarantined, now at the new mtime.
+         assert!(!store.quarantine_holds(&path, FileHash::sum(b"fixed"), 12, 20));
+         assert!(store.quarantine_holds(&path, poison, 12, 20));
+         assert!(store.is_quarantined(&path, 12, 20));
+         assert_eq!(store.quarantined().next().map(|(_, f)| f.failures), Some(2));
+         store.save().unwrap();
+
+         let mut reloaded = MetaStore::load("quarantine_test").unwrap();
+         assert!(reloaded.is_quarantined(&path, 12, 20));
+         reloaded.set_meta(path.clone(), FileHash::sum(b"ok"), 13, 20, FileHash::sum(b"ok"));
+         assert_eq!(reloaded.quarantined().count(), 0);
+      });
+   }
+
+   #[test]
+   fn failures_quarantine_only_when_asked() {
+      with_temp_home(|_| {
+         let mut store = MetaStore::load("failure_count_test").unwrap();
+         let path = PathBuf::from("src/flaky.rs");
+         let mut note =
+            || store.note_failure(path.clone(), 10, 20, None, "read_failed".into(), "x".into());
+         assert_eq!(note(), 1);
+         assert_eq!(note(), 2);
+         assert!(!store.is_quarantined(&path, 10, 20));
+         assert_eq!(store.quarantined().count(), 0);
+
+         store.quarantine(&path);
+         assert!(store.is_quarantined(&path, 10, 20));
+         let (_, failure) = store.quarantined().next().unwrap();
+         assert_eq!(failure.failures, 2);
+         assert!(failure.last_failure.is_some());
+      });
+   }
+
+   #[test]
+   fn raising_the_chunk_cap_releases_its_quarantine() {
+      with_temp_home(|_| {
+         let mut store = MetaStore::load("chunk_cap_release_test").unwrap();
+         let big = PathBuf::from("src/big.rs");
+         let unreadable = PathBuf::from("src/unreadable.rs");
+         let cap = "chunk_cap_exceeded".to_string();
+         store.record_failure(big.clone(), 10, 20, None, cap, "too many chunks".into());
+         store.note_chunk_cap(&big, 3);
+         store.record_failure(unreadable.clone(), 10, 20, None, "read_failed".into(), "x".into());
+
+         store.release_chunk_cap_failures(3);
+         assert_eq!(store.quarantined().count(), 2, "same cap keeps both");
+         store.release_chunk_cap_failures(8);
+         let left: Vec<_> = store.quarantined().map(|(path, _)| path.clone()).collect();
+         assert_eq!(left, [unreadable]);
+      });
+   }
+
    #[test]
    fn all_paths_returns_keys() {
       with_temp_home(|_| {
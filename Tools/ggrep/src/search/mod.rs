@@ -4,31 +4,131 @@
 pub mod colbert;
 pub mod profile;
 pub mod ranking;
+pub mod synonyms;
 
-use std::{path::Path, sync::Arc};
+use std::{
+   collections::HashSet,
+   path::Path,
+   sync::{
+      Arc,
+      atomic::{AtomicU64, Ordering},
+   },
+   time::{SystemTime, UNIX_EPOCH},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use moka::future::Cache;
+use regex::Regex;
 
 use crate::{
    config,
-   embed::{Embedder, limiter},
-   error::Result,
+   embed::{Embedder, QueryEmbedding, limiter},
+   error::{Error, Result},
+   grammar::{self, CustomGrammar, EXTENSION_MAP},
+   identity,
+   meta::{FileHash, MetaStore},
    snapshot::SnapshotView,
-   store::{LanceStore, SearchParams},
+   store::{LanceStore, LanguageFilter, SearchParams},
    types::{
-      SearchLimitHit, SearchMode, SearchResponse, SearchTimings, sort_and_dedup_limits,
+      CandidateCounts, EmbedderStatus, FilterStage, QueryOptions, SearchLimitHit, SearchMode,
+      SearchResponse, SearchTimings, SearchWarning, SpanMerge, sort_and_dedup_limits,
       sort_and_dedup_warnings, sort_results_deterministic,
    },
 };
 
+/// Each `--exclude` glob (and `--grep`) deepens retrieval by one more
+/// `limit`, up to this many extra multiples.
+const EXCLUDE_FETCH_MAX: usize = 3;
+
+/// Snapshots whose meta store an engine keeps for the recent-files boost.
+const META_STORE_CACHE_CAPACITY: u64 = 8;
+
+/// Identifies a query embedding: the same text and mode under the same
+/// embedding config always encode to the same vectors.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct QueryCacheKey {
+   query:       String,
+   mode:        SearchMode,
+   fingerprint: String,
+}
+
 /// High-level search engine orchestrating embeddings, vector search, and
 /// reranking.
 pub struct SearchEngine {
-   store:    Arc<LanceStore>,
-   embedder: Arc<dyn Embedder>,
+   store:             Arc<LanceStore>,
+   embedder:          Arc<dyn Embedder>,
+   /// Recently embedded queries; `None` when `query_cache_capacity` is 0.
+   query_cache:       Option<Cache<QueryCacheKey, Arc<QueryEmbedding>>>,
+   query_cache_hits:  AtomicU64,
+   embed_fingerprint: String,
+   /// Custom grammars declared when the engine was built, for `--lang`.
+   custom_grammars:   Vec<CustomGrammar>,
+   /// Meta stores read for the recent-files boost, per store and snapshot.
+   meta_stores:       Cache<(String, String), Arc<MetaStore>>,
 }
 
 impl SearchEngine {
    pub fn new(store: Arc<LanceStore>, embedder: Arc<dyn Embedder>) -> Self {
-      Self { store, embedder }
+      Self::with_query_cache(store, embedder, config::get().query_cache_capacity)
+   }
+
+   /// Like [`Self::new`], caching up to `capacity` query embeddings instead of
+   /// the configured `query_cache_capacity`; 0 disables the cache.
+   pub fn with_query_cache(
+      store: Arc<LanceStore>,
+      embedder: Arc<dyn Embedder>,
+      capacity: u64,
+   ) -> Self {
+      let cfg = config::get();
+      let query_cache = (capacity > 0).then(|| Cache::builder().max_capacity(capacity).build());
+      Self {
+         store,
+         embedder,
+         query_cache,
+         query_cache_hits: AtomicU64::new(0),
+         embed_fingerprint: identity::compute_embed_config_fingerprint(cfg).unwrap_or_default(),
+         custom_grammars: grammar::declared_custom_grammars(),
+         meta_stores: Cache::builder()
+            .max_capacity(META_STORE_CACHE_CAPACITY)
+            .build(),
+      }
+   }
+
+   /// Queries answered from the embedding cache since the engine was built.
+   pub fn query_cache_hits(&self) -> u64 {
+      self.query_cache_hits.load(Ordering::Relaxed)
+   }
+
+   /// Embeds `query`, reusing the cached embedding of an identical earlier
+   /// query.
+   async fn encode_query(&self, query: &str, mode: SearchMode) -> Result<Arc<QueryEmbedding>> {
+      let Some(cache) = &self.query_cache else {
+         return Ok(Arc::new(self.embedder.encode_query(query, mode).await?));
+      };
+      let key = QueryCacheKey {
+         query: query.to_string(),
+         mode,
+         fingerprint: self.embed_fingerprint.clone(),
+      };
+      if let Some(cached) = cache.get(&key).await {
+         self.query_cache_hits.fetch_add(1, Ordering::Relaxed);
+         return Ok(cached);
+      }
+      let encoded = Arc::new(self.embedder.encode_query(query, mode).await?);
+      cache.insert(key, Arc::clone(&encoded)).await;
+      Ok(encoded)
+   }
+
+   /// The meta store of `store_id`, read once per snapshot: file metadata is
+   /// saved when a sync publishes the snapshot.
+   async fn meta_store(&self, store_id: &str, snapshot: &SnapshotView) -> Option<Arc<MetaStore>> {
+      let key = (store_id.to_string(), snapshot.snapshot_id.clone());
+      if let Some(cached) = self.meta_stores.get(&key).await {
+         return Some(cached);
+      }
+      let meta_store = Arc::new(MetaStore::load(store_id).ok()?);
+      self.meta_stores.insert(key, Arc::clone(&meta_store)).await;
+      Some(meta_store)
    }
 
    /// Searches a store for code matching a natural language query.
@@ -73,15 +173,74 @@ impl SearchEngine {
       include_anchors: bool,
       mode: SearchMode,
    ) -> Result<SearchResponse> {
+      self
+         .search_with_options(
+            snapshot,
+            store_id,
+            query,
+            limit,
+            per_file_limit,
+            path_filter,
+            rerank,
+            include_anchors,
+            mode,
+            &QueryOptions::default(),
+         )
+         .await
+   }
+
+   /// Like [`Self::search_with_mode`], with the per-query settings in
+   /// `options`. Files modified in the last `recent_boost_days` days are
+   /// boosted (`None` uses the configured default, `Some(0)` disables the
+   /// boost), and ranked chunks of the same file are combined according to
+   /// `span_merge` before selection. A non-empty `languages` keeps only chunks
+   /// whose indexed language is one of them (see [`resolve_languages`]). The
+   /// first `offset` ranked results are skipped after per-file limits apply,
+   /// so pages are stable; pages end at `max_query_results`, and one
+   /// reaching past it records an `offset_exceeds_max_results` limit hit.
+   /// Results whose path matches one of `excludes` (see [`exclude_matcher`])
+   /// are dropped after retrieval, as are results whose own lines do not
+   /// match `grep` (see [`grep_matcher`]). Terms in the user synonym dictionary
+   /// are expanded for full-text retrieval only (see [`synonyms`]). With
+   /// `dedup_content`, a ranked result whose own (context-free) text repeats a
+   /// higher-ranked one is dropped before selection. With `no_fts`, only
+   /// vector retrieval runs. Each filter stage records how many candidates
+   /// it left in the response's `funnel`.
+   pub async fn search_with_options(
+      &self,
+      snapshot: &SnapshotView,
+      store_id: &str,
+      query: &str,
+      limit: usize,
+      per_file_limit: usize,
+      path_filter: Option<&Path>,
+      rerank: bool,
+      include_anchors: bool,
+      mode: SearchMode,
+      options: &QueryOptions,
+   ) -> Result<SearchResponse> {
+      let QueryOptions { span_merge, offset, dedup_content, .. } = *options;
+      let (languages, excludes) = (&options.languages, &options.excludes);
+      let language_filter = resolve_languages(languages, &self.custom_grammars)?;
+      let excluded = exclude_matcher(excludes)?;
+      let grep = grep_matcher(options.grep.as_deref())?;
+      let embedder_status = EmbedderStatus {
+         backend: self.embedder.backend_name().to_string(),
+         ready:   self.embedder.is_ready(),
+      };
+      let (fts_query, expansions) = synonyms::expand_query(query, synonyms::synonyms());
       let embed_start = std::time::Instant::now();
       let _permit = limiter::acquire().await?;
-      let query_enc = self.embedder.encode_query(query).await?;
+      let query_enc = self.encode_query(query, mode).await?;
       let embed_ms = embed_start.elapsed().as_millis() as u64;
 
-      let store_limit = match mode {
-         SearchMode::Balanced => limit.saturating_mul(2).max(limit),
-         _ => limit.saturating_mul(10).max(limit),
-      };
+      // Excluded paths and `--grep` misses are dropped after retrieval; fetch
+      // deeper so they do not starve the page.
+      let post_filters = excludes.len() + usize::from(grep.is_some());
+      let fetch_limit = limit
+         .saturating_add(offset)
+         .saturating_mul(1 + post_filters.min(EXCLUDE_FETCH_MAX));
+      let (_, store_limit) = store_limit(fetch_limit, mode);
 
       let retrieve_start = std::time::Instant::now();
       let mut response = self
@@ -89,25 +248,80 @@ impl SearchEngine {
          .search_segments(SearchParams {
             store_id,
             tables: snapshot.segment_tables(),
-            query_text: query,
+            query_text: &fts_query,
             query_vector: &query_enc.dense,
             query_colbert: &query_enc.colbert,
             limit: store_limit,
             path_filter,
+            languages: &language_filter,
             rerank,
-            include_anchors,
+            include_anchors: include_anchors || mode == SearchMode::Paths,
+            anchors_only: mode == SearchMode::Paths,
+            lexical: !options.no_fts,
+            index_stats: options.index_stats,
          })
          .await?;
-      let retrieve_ms = retrieve_start.elapsed().as_millis() as u64 + embed_ms;
+      let mut funnel = Vec::new();
+      FilterStage::push(
+         &mut funnel,
+         "retrieved",
+         retrieval_filters(path_filter, languages, mode),
+         response.results.len(),
+      );
+      if let Some(excluded) = &excluded {
+         response.results.retain(|r| !excluded.is_match(&r.path));
+         FilterStage::push(
+            &mut funnel,
+            "exclude",
+            Some(excludes.join(", ")),
+            response.results.len(),
+         );
+      }
+      if let Some(grep) = &grep {
+         response.results.retain(|r| grep.is_match(r.own_content()));
+         FilterStage::push(
+            &mut funnel,
+            "grep",
+            Some(grep.as_str().to_string()),
+            response.results.len(),
+         );
+      }
+      let rerank_ms = response.rerank.map_or(0, |stats| stats.elapsed_us / 1000);
+      let retrieve_ms = (retrieve_start.elapsed().as_millis() as u64).saturating_sub(rerank_ms);
 
       let cfg = config::get();
       let mut limits_hit = std::mem::take(&mut response.limits_hit);
       let mut warnings = std::mem::take(&mut response.warnings);
+      if let Some(truncation) = query_enc.truncated {
+         warnings.push(SearchWarning {
+            code:     "query_truncated".to_string(),
+            message:  format!(
+               "query is {} tokens; only the first {} were embedded",
+               truncation.tokens, truncation.max_length
+            ),
+            path_key: None,
+         });
+      }
+      if snapshot.manifest.mixed_chunking() {
+         warnings.push(SearchWarning {
+            code:     "mixed_chunking".to_string(),
+            message:  "snapshot mixes fast and full chunked segments; run `ggrep index --reset` \
+                       with --fast or --full"
+               .to_string(),
+            path_key: None,
+         });
+      }
 
       let max_candidates = cfg.effective_max_candidates();
       if response.results.len() > max_candidates {
          let observed = response.results.len() as u64;
          response.results.truncate(max_candidates);
+         FilterStage::push(
+            &mut funnel,
+            "max_candidates",
+            Some(max_candidates.to_string()),
+            max_candidates,
+         );
          limits_hit.push(SearchLimitHit {
             code:     "max_candidates".to_string(),
             limit:    max_candidates as u64,
@@ -118,15 +332,100 @@ impl SearchEngine {
 
       let rank_start = std::time::Instant::now();
       ranking::apply_structural_boost_with_mode(&mut response.results, mode);
+      let recent_boost_days = options.recent_boost_days.unwrap_or(cfg.recent_boost_days);
+      if recent_boost_days > 0
+         && let Some(meta_store) = self.meta_store(store_id, snapshot).await
+      {
+         let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+         ranking::apply_recent_boost(
+            &mut response.results,
+            |path| meta_store.get_meta(path).map(|meta| meta.mtime),
+            u64::from(recent_boost_days) * 86_400,
+            cfg.recent_boost_factor,
+            now,
+         );
+      }
 
       sort_results_deterministic(&mut response.results);
 
+      let before_visibility = response.results.len();
       response.results.retain(|r| {
          let key = r.path.to_string_lossy();
          snapshot.is_visible(key.as_ref(), r.segment_table.as_deref())
       });
+      let filtered_not_visible = (before_visibility - response.results.len()) as u64;
+      FilterStage::push(&mut funnel, "visible", None, response.results.len());
+      if filtered_not_visible > 0 {
+         response
+            .candidates
+            .get_or_insert_with(CandidateCounts::default)
+            .filtered_not_visible += filtered_not_visible;
+      }
+
+      if dedup_content {
+         let before_dedup = response.results.len();
+         let mut seen: HashSet<FileHash> = HashSet::with_capacity(before_dedup);
+         response
+            .results
+            .retain(|r| seen.insert(FileHash::sum(r.own_content().trim())));
+         FilterStage::push(&mut funnel, "dedup_content", None, response.results.len());
+         if response.results.len() < before_dedup {
+            // `limit` is how many distinct results were kept, `observed` how
+            // many ranked results there were before dedup.
+            limits_hit.push(SearchLimitHit {
+               code:     "content_dedup".to_string(),
+               limit:    response.results.len() as u64,
+               observed: Some(before_dedup as u64),
+               path_key: None,
+            });
+         }
+      }
 
-      response.results = profile::select_for_mode(response.results, limit, per_file_limit, mode);
+      // Spans merge before selection so `limit` and `offset` count merged
+      // results: pages neither skip nor repeat what a merge absorbed.
+      if span_merge != SpanMerge::Off {
+         response.results = ranking::merge_spans(response.results, span_merge);
+         sort_results_deterministic(&mut response.results);
+         FilterStage::push(&mut funnel, "merge_spans", None, response.results.len());
+      }
+      // Only the top `max_query_results` are ranked; a page reaching past them
+      // comes back short (or empty) and says so rather than shifting back.
+      let window = cfg.max_query_results;
+      let page_end = limit.saturating_add(offset).min(window);
+      if offset > 0 && offset.saturating_add(limit) > window {
+         limits_hit.push(SearchLimitHit {
+            code:     "offset_exceeds_max_results".to_string(),
+            limit:    window as u64,
+            observed: Some(offset.saturating_add(limit) as u64),
+            path_key: None,
+         });
+      }
+      let (selected, per_file_dropped) =
+         profile::select_for_mode_tracked(response.results, page_end, per_file_limit, mode);
+      FilterStage::push(
+         &mut funnel,
+         "limit",
+         Some(format!("{page_end} per query, {per_file_limit} per file")),
+         selected.len(),
+      );
+      response.results = selected;
+      // Mode quotas pick results bucket by bucket; callers get them in rank
+      // order.
+      sort_results_deterministic(&mut response.results);
+      response.results.drain(..offset.min(response.results.len()));
+      if offset > 0 {
+         FilterStage::push(&mut funnel, "offset", Some(offset.to_string()), response.results.len());
+      }
+      for (path_key, dropped) in per_file_dropped {
+         limits_hit.push(SearchLimitHit {
+            code:     "per_file_truncated".to_string(),
+            limit:    per_file_limit as u64,
+            observed: Some((per_file_limit + dropped) as u64),
+            path_key: Some(path_key),
+         });
+      }
       let rank_ms = rank_start.elapsed().as_millis() as u64;
 
       apply_snippet_caps(
@@ -141,17 +440,161 @@ impl SearchEngine {
       response.timings_ms = Some(SearchTimings {
          admission_ms: 0,
          snapshot_read_ms: 0,
+         embed_ms,
          retrieve_ms,
+         rerank_ms,
          rank_ms,
          format_ms: 0,
       });
       response.limits_hit = limits_hit;
       response.warnings = warnings;
+      response.embedder = Some(embedder_status);
+      response.expansions = expansions;
+      response.funnel = funnel;
 
       Ok(response)
    }
 }
 
+/// Returns `(multiplier, store_limit)`: how many candidates each retrieval
+/// source fetches for a query returning `limit` results. The multiplier
+/// defaults to 2x for balanced mode and 10x otherwise, and can be overridden
+/// with `candidate_multiplier`.
+pub fn store_limit(limit: usize, mode: SearchMode) -> (usize, usize) {
+   let multiplier = config::get()
+      .candidate_multiplier
+      .unwrap_or(match mode {
+         SearchMode::Balanced => 2,
+         _ => 10,
+      })
+      .max(1);
+   (multiplier, limit.saturating_mul(multiplier).max(limit))
+}
+
+/// Describes the filters retrieval itself applies, which head the funnel: the
+/// path scope, `--lang` and `--path-query`.
+fn retrieval_filters(
+   path_filter: Option<&Path>,
+   languages: &[String],
+   mode: SearchMode,
+) -> Option<String> {
+   let mut filters = Vec::new();
+   if let Some(scope) = path_filter {
+      filters.push(format!("scope {}", scope.display()));
+   }
+   if !languages.is_empty() {
+      filters.push(format!("lang {}", languages.join(",")));
+   }
+   if mode == SearchMode::Paths {
+      filters.push("anchors only".to_string());
+   }
+   (!filters.is_empty()).then(|| filters.join("; "))
+}
+
+/// Builds the `--exclude` matcher; `None` when there is nothing to exclude.
+/// A glob without `/` matches that name at any depth (`node_modules` drops
+/// `web/node_modules/x.js`), and a glob matching a directory drops everything
+/// under it.
+pub fn exclude_matcher(excludes: &[String]) -> Result<Option<GlobSet>> {
+   if excludes.is_empty() {
+      return Ok(None);
+   }
+   let mut builder = GlobSetBuilder::new();
+   for exclude in excludes {
+      let pattern = exclude.trim_end_matches('/');
+      let pattern = if pattern.contains('/') {
+         pattern.trim_start_matches("./").to_string()
+      } else {
+         format!("**/{pattern}")
+      };
+      for pattern in [pattern.clone(), format!("{pattern}/**")] {
+         let glob = Glob::new(&pattern).map_err(|e| Error::Server {
+            op:     "search",
+            reason: format!("invalid_request: invalid --exclude glob '{exclude}': {e}"),
+         })?;
+         builder.add(glob);
+      }
+   }
+   builder.build().map(Some).map_err(|e| Error::Server {
+      op:     "search",
+      reason: format!("invalid_request: invalid --exclude globs: {e}"),
+   })
+}
+
+/// Compiles the `--grep` pattern; `None` when there is none.
+pub fn grep_matcher(grep: Option<&str>) -> Result<Option<Regex>> {
+   grep
+      .map(|pattern| {
+         Regex::new(pattern).map_err(|e| Error::Server {
+            op:     "search",
+            reason: format!("invalid_request: invalid --grep pattern: {e}"),
+         })
+      })
+      .transpose()
+}
+
+/// Resolves `--lang` names to the languages chunks record at index time,
+/// case-insensitively, rejecting names no extension, custom grammar or
+/// `language_overrides` entry maps to. Extensionless files (`Makefile`,
+/// shebang scripts) match through their recorded language.
+pub fn resolve_languages(languages: &[String], custom: &[CustomGrammar]) -> Result<LanguageFilter> {
+   let overrides = &config::get().language_overrides;
+   let mut filter = LanguageFilter::default();
+   for language in languages {
+      let mut extensions: Vec<String> = grammar::language_to_extensions(custom, language)
+         .into_iter()
+         .filter(|ext| !overrides.contains_key(*ext))
+         .map(str::to_string)
+         .collect();
+      let overridden = overrides
+         .iter()
+         .filter(|(_, lang)| lang.eq_ignore_ascii_case(language));
+      let name = match overridden.clone().next() {
+         Some((_, lang)) => lang.clone(),
+         None => {
+            let known = EXTENSION_MAP
+               .iter()
+               .map(|(_, lang)| *lang)
+               .chain(custom.iter().map(|grammar| grammar.language.as_str()))
+               .find(|lang| lang.eq_ignore_ascii_case(language));
+            let Some(known) = known else {
+               return Err(unknown_language(language, custom));
+            };
+            known.to_string()
+         },
+      };
+      extensions.extend(overridden.map(|(ext, _)| ext.clone()));
+      if !filter.languages.contains(&name) {
+         filter.languages.push(name);
+      }
+      filter.extensions.extend(extensions);
+   }
+   Ok(filter)
+}
+
+fn unknown_language(language: &str, custom: &[CustomGrammar]) -> Error {
+   let mut known: Vec<&str> = EXTENSION_MAP
+      .iter()
+      .map(|(_, lang)| *lang)
+      .chain(custom.iter().map(|grammar| grammar.language.as_str()))
+      .chain(
+         config::get()
+            .language_overrides
+            .values()
+            .map(String::as_str),
+      )
+      .collect();
+   known.sort_unstable();
+   known.dedup();
+   Error::Server {
+      op:     "search",
+      reason: format!(
+         "invalid_request: unknown language '{language}' (known: {})",
+         known.join(", ")
+      ),
+   }
+}
+
 fn apply_snippet_caps(
    results: &mut [crate::types::SearchResult],
    max_total_bytes: usize,
@@ -223,3 +666,67 @@ fn truncate_str_bytes(input: &crate::Str, max_bytes: usize) -> (crate::Str, bool
    let truncated = crate::Str::copy_from_str(&s[..idx]);
    (truncated, true)
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn excludes(globs: &[&str]) -> GlobSet {
+      let globs: Vec<String> = globs.iter().map(|g| (*g).to_string()).collect();
+      exclude_matcher(&globs).unwrap().unwrap()
+   }
+
+   #[test]
+   fn exclude_matcher_expands_names_and_directories() {
+      assert!(exclude_matcher(&[]).unwrap().is_none());
+
+      // A bare name matches at any depth, as a file or a directory.
+      let names = excludes(&["node_modules", "*.lock"]);
+      assert!(names.is_match("node_modules/a.js"));
+      assert!(names.is_match("web/node_modules/pkg/index.js"));
+      assert!(names.is_match("Cargo.lock"));
+      assert!(names.is_match("crates/x/Cargo.lock"));
+      assert!(!names.is_match("src/node_modules_helper.rs"));
+
+      // A glob with `/` is anchored at the root; `./` and trailing `/` are
+      // dropped, and everything below a matched directory goes too.
+      let paths = excludes(&["./src/gen/", "tests/*.snap"]);
+      assert!(paths.is_match("src/gen"));
+      assert!(paths.is_match("src/gen/deep/mod.rs"));
+      assert!(!paths.is_match("lib/src/gen/mod.rs"));
+      assert!(!paths.is_match("src/generated.rs"));
+      assert!(paths.is_match("tests/a.snap"));
+
+      let err = exclude_matcher(&["src/[".to_string()]).unwrap_err();
+      assert!(err.to_string().contains("invalid --exclude glob"), "{err}");
+   }
+
+   #[test]
+   fn own_content_strips_context_lines() {
+      let result = crate::types::SearchResult {
+         path:            "src/a.rs".into(),
+         content:         crate::Str::from_string("prev\nfn a() {}\nnext\n".to_string()),
+         score:           1.0,
+         secondary_score: None,
+         row_id:          None,
+         segment_table:   None,
+         start_line:      0,
+         num_lines:       1,
+         end_line:        None,
+         chunk_type:      None,
+         symbol:          None,
+         is_anchor:       None,
+         language:        None,
+         context_before:  1,
+         context_after:   1,
+      };
+      assert_eq!(result.own_content(), "fn a() {}\n");
+
+      let grep = grep_matcher(Some("prev|next")).unwrap().unwrap();
+      assert!(grep.is_match(&result.content));
+      assert!(!grep.is_match(result.own_content()));
+      assert!(grep_matcher(None).unwrap().is_none());
+      let err = grep_matcher(Some("(")).unwrap_err();
+      assert!(err.to_string().contains("invalid --grep pattern"), "{err}");
+   }
+}
@@ -1,9 +1,13 @@
 use std::{
-   collections::{HashMap, HashSet},
+   collections::{BTreeMap, HashMap, HashSet},
    path::Path,
 };
 
-use crate::types::{SearchMode, SearchResult};
+use crate::{
+   config::{self, ExtraIndexBucket},
+   grammar::UNKNOWN_LANGUAGE,
+   types::{SearchMode, SearchResult},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchBucket {
@@ -18,30 +22,96 @@ pub fn bucket_for_path(path: &Path) -> SearchBucket {
       "mmd" | "mermaid" => SearchBucket::Graph,
       "md" | "mdx" | "markdown" | "txt" | "json" | "html" | "htm" | "css" | "yaml" | "yml"
       | "toml" => SearchBucket::Docs,
+      ext if !ext.is_empty() && config::get().is_extra_index_extension(ext) => {
+         match config::get().extra_index_bucket {
+            ExtraIndexBucket::Code => SearchBucket::Code,
+            ExtraIndexBucket::Docs => SearchBucket::Docs,
+         }
+      },
+      _ => SearchBucket::Code,
+   }
+}
+
+/// Whether a path looks like a test: `*_test.*`, `test_*.*`, `*.test.*` and
+/// `*.spec.*` files, or anything under a `tests/`, `test/`, `spec/` or
+/// `__tests__/` directory.
+pub fn is_test_path(path: &Path) -> bool {
+   let in_test_dir = path.parent().is_some_and(|dir| {
+      dir.components().any(|component| {
+         let name = component.as_os_str().to_string_lossy();
+         ["tests", "test", "spec", "__tests__"]
+            .iter()
+            .any(|test_dir| name.eq_ignore_ascii_case(test_dir))
+      })
+   });
+   if in_test_dir {
+      return true;
+   }
+   let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+      return false;
+   };
+   let name = name.to_ascii_lowercase();
+   let stem = name.split_once('.').map_or(name.as_str(), |(stem, _)| stem);
+   stem.ends_with("_test")
+      || stem.starts_with("test_")
+      || name.contains(".test.")
+      || name.contains(".spec.")
+}
+
+/// Buckets a language name recorded at index time.
+pub fn bucket_for_language(language: &str) -> SearchBucket {
+   match language {
+      "markdown" | "json" | "html" | "css" | "yaml" | "toml" => SearchBucket::Docs,
       _ => SearchBucket::Code,
    }
 }
 
+/// Buckets a result by its stored language, falling back to the path
+/// extension for rows indexed without one (e.g. diagrams, older segments).
+pub fn bucket_for(path: &Path, language: Option<&str>) -> SearchBucket {
+   match language {
+      Some(lang) if lang != UNKNOWN_LANGUAGE => bucket_for_language(lang),
+      _ => bucket_for_path(path),
+   }
+}
+
 pub fn select_for_mode(
    results: Vec<SearchResult>,
    limit: usize,
    per_file_limit: usize,
    mode: SearchMode,
 ) -> Vec<SearchResult> {
+   select_for_mode_tracked(results, limit, per_file_limit, mode).0
+}
+
+/// Like [`select_for_mode`], but also returns how many chunks each path lost
+/// to `per_file_limit` (chunks that were skipped and never selected later).
+pub fn select_for_mode_tracked(
+   results: Vec<SearchResult>,
+   limit: usize,
+   per_file_limit: usize,
+   mode: SearchMode,
+) -> (Vec<SearchResult>, BTreeMap<String, usize>) {
    if limit == 0 || results.is_empty() {
-      return Vec::new();
+      return (Vec::new(), BTreeMap::new());
    }
 
-   if mode == SearchMode::Balanced {
+   // Path queries rank whole files, so bucket quotas would only get in the way.
+   if matches!(mode, SearchMode::Balanced | SearchMode::Paths) {
       return apply_per_file_then_truncate(results, limit, per_file_limit);
    }
 
    let quotas = quotas_for_mode(limit, mode);
 
-   let all_results = results;
+   let mut all_results = results;
+   if mode == SearchMode::Tests {
+      // Tests are picked first, both for the quotas and when filling the
+      // remaining slots; other results only take what is left.
+      all_results.sort_by_key(|result| !is_test_path(&result.path));
+   }
    let mut by_bucket: [Vec<SearchResult>; 3] = [Vec::new(), Vec::new(), Vec::new()];
    for result in &all_results {
-      let bucket = bucket_for_path(&result.path);
+      let bucket = bucket_for(&result.path, result.language.as_deref());
       match bucket {
          SearchBucket::Code => by_bucket[0].push(result.clone()),
          SearchBucket::Docs => by_bucket[1].push(result.clone()),
@@ -51,7 +121,7 @@ pub fn select_for_mode(
 
    let mut selected: Vec<SearchResult> = Vec::with_capacity(limit);
    let mut selected_keys: HashSet<(String, u32)> = HashSet::new();
-   let mut per_file_counts: HashMap<String, usize> = HashMap::new();
+   let mut per_file_counts = PerFileCounts::default();
 
    let mut code_selected = pick_from_bucket(
       &by_bucket[0],
@@ -81,7 +151,7 @@ pub fn select_for_mode(
 
    if selected.len() >= limit {
       selected.truncate(limit);
-      return selected;
+      return (selected, per_file_counts.dropped(&selected_keys));
    }
 
    // Fill remaining slots with the best remaining results in overall score
@@ -109,20 +179,40 @@ pub fn select_for_mode(
    }
 
    selected.truncate(limit);
-   selected
+   let dropped = per_file_counts.dropped(&selected_keys);
+   (selected, dropped)
+}
+
+/// Per-path selection counts plus the chunks rejected by the per-file limit.
+#[derive(Default)]
+struct PerFileCounts {
+   counts:   HashMap<String, usize>,
+   rejected: HashSet<(String, u32)>,
+}
+
+impl PerFileCounts {
+   /// Rejected chunks that were not picked up by a later pass, grouped by path.
+   fn dropped(&self, selected_keys: &HashSet<(String, u32)>) -> BTreeMap<String, usize> {
+      let mut dropped = BTreeMap::new();
+      for key in self.rejected.difference(selected_keys) {
+         *dropped.entry(key.0.clone()).or_insert(0) += 1;
+      }
+      dropped
+   }
 }
 
 fn apply_per_file_then_truncate(
    mut results: Vec<SearchResult>,
    limit: usize,
    per_file_limit: usize,
-) -> Vec<SearchResult> {
+) -> (Vec<SearchResult>, BTreeMap<String, usize>) {
    if per_file_limit == 0 {
       results.truncate(limit);
-      return results;
+      return (results, BTreeMap::new());
    }
 
    let mut counts: HashMap<String, usize> = HashMap::new();
+   let mut dropped: BTreeMap<String, usize> = BTreeMap::new();
    let mut out: Vec<SearchResult> = Vec::with_capacity(limit);
 
    for result in results {
@@ -130,15 +220,16 @@ fn apply_per_file_then_truncate(
          break;
       }
       let key = result.path.display().to_string();
-      let count = counts.entry(key).or_insert(0);
+      let count = counts.entry(key.clone()).or_insert(0);
       if *count >= per_file_limit {
+         *dropped.entry(key).or_insert(0) += 1;
          continue;
       }
       *count += 1;
       out.push(result);
    }
 
-   out
+   (out, dropped)
 }
 
 fn pick_from_bucket(
@@ -146,7 +237,7 @@ fn pick_from_bucket(
    quota: usize,
    per_file_limit: usize,
    selected_keys: &mut HashSet<(String, u32)>,
-   per_file_counts: &mut HashMap<String, usize>,
+   per_file_counts: &mut PerFileCounts,
 ) -> Vec<SearchResult> {
    if quota == 0 {
       return Vec::new();
@@ -168,7 +259,7 @@ fn can_take(
    result: &SearchResult,
    per_file_limit: usize,
    selected_keys: &mut HashSet<(String, u32)>,
-   per_file_counts: &mut HashMap<String, usize>,
+   per_file_counts: &mut PerFileCounts,
 ) -> bool {
    if result.is_anchor.unwrap_or(false) {
       return false;
@@ -181,15 +272,16 @@ fn can_take(
    }
 
    if per_file_limit > 0 {
-      let count = per_file_counts.get(&path_key).copied().unwrap_or(0);
+      let count = per_file_counts.counts.get(&path_key).copied().unwrap_or(0);
       if count >= per_file_limit {
+         per_file_counts.rejected.insert(key);
          return false;
       }
    }
 
    selected_keys.insert(key);
    if per_file_limit > 0 {
-      let count = per_file_counts.entry(path_key).or_insert(0);
+      let count = per_file_counts.counts.entry(path_key).or_insert(0);
       *count += 1;
    }
 
@@ -209,14 +301,19 @@ fn quotas_for_mode(limit: usize, mode: SearchMode) -> Quotas {
       SearchMode::Implementation => (6, 2, 2),
       SearchMode::Planning => (2, 6, 2),
       SearchMode::Debug => (7, 2, 1),
-      SearchMode::Balanced => (4, 3, 3),
+      SearchMode::Tests => (8, 2, 0),
+      SearchMode::Balanced | SearchMode::Paths => (4, 3, 3),
    };
 
    let mut min = Quotas { code: 0, docs: 0, graph: 0 };
    if limit >= 3 {
       min.code = 1;
       min.docs = 1;
-      min.graph = if mode == SearchMode::Debug { 0 } else { 1 };
+      min.graph = if matches!(mode, SearchMode::Debug | SearchMode::Tests) {
+         0
+      } else {
+         1
+      };
    } else if limit == 2 {
       min.code = 1;
       min.docs = 1;
@@ -294,3 +391,82 @@ fn allocate(limit: usize, w_code: usize, w_docs: usize, w_graph: usize) -> (usiz
 
    (q_code, q_docs, q_graph)
 }
+
+#[cfg(test)]
+mod tests {
+   use std::path::PathBuf;
+
+   use super::*;
+   use crate::Str;
+
+   fn make_result(path: &str, start_line: u32, score: f32) -> SearchResult {
+      SearchResult {
+         path: PathBuf::from(path),
+         content: Str::default(),
+         score,
+         secondary_score: None,
+         row_id: None,
+         segment_table: None,
+         start_line,
+         num_lines: 10,
+         end_line: None,
+         chunk_type: None,
+         symbol: None,
+         is_anchor: Some(false),
+         language: None,
+         context_before: 0,
+         context_after: 0,
+      }
+   }
+
+   #[test]
+   fn per_file_drops_are_reported() {
+      for mode in [SearchMode::Balanced, SearchMode::Implementation] {
+         let results = vec![
+            make_result("src/hot.rs", 1, 0.9),
+            make_result("src/hot.rs", 20, 0.8),
+            make_result("src/hot.rs", 40, 0.7),
+            make_result("src/other.rs", 1, 0.6),
+            make_result("src/third.rs", 1, 0.5),
+         ];
+
+         let (selected, dropped) = select_for_mode_tracked(results, 3, 1, mode);
+
+         assert_eq!(selected.len(), 3, "{mode:?}");
+         assert_eq!(dropped.get("src/hot.rs"), Some(&2), "{mode:?}");
+         assert_eq!(dropped.len(), 1, "{mode:?}");
+      }
+   }
+
+   #[test]
+   fn tests_mode_selects_test_paths_first() {
+      let results = vec![
+         make_result("src/lexer.rs", 1, 0.9),
+         make_result("src/parser.rs", 1, 0.8),
+         make_result("tests/lexer.rs", 1, 0.5),
+         make_result("src/lexer_test.go", 1, 0.4),
+      ];
+
+      let selected = select_for_mode(results, 2, 1, SearchMode::Tests);
+
+      let paths: Vec<_> = selected.iter().map(|r| r.path.to_str().unwrap()).collect();
+      assert_eq!(paths, ["tests/lexer.rs", "src/lexer_test.go"]);
+   }
+
+   #[test]
+   fn test_paths_are_recognized() {
+      for path in [
+         "tests/query.rs",
+         "pkg/spec/model_spec.rb",
+         "src/parser_test.go",
+         "test_parser.py",
+         "web/app.test.ts",
+         "web/app.spec.js",
+      ] {
+         assert!(is_test_path(Path::new(path)), "{path}");
+      }
+      for path in ["src/parser.rs", "src/testing.rs", "src/contest.rs", "docs/latest.md"] {
+         assert!(!is_test_path(Path::new(path)), "{path}");
+      }
+   }
+}
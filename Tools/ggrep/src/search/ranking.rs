@@ -1,14 +1,26 @@
 //! Result ranking utilities for boosting code structure and limiting per-file
 //! results.
 
-use std::path::Path;
+use std::{
+   collections::HashMap,
+   path::{Path, PathBuf},
+};
 
-use crate::types::{ChunkType, SearchMode, SearchResult};
+use serde::Serialize;
 
-#[derive(Debug, Clone, Copy)]
+use crate::{
+   Str,
+   search::profile::{SearchBucket, bucket_for, bucket_for_path, is_test_path},
+   types::{ChunkType, SearchMode, SearchResult, SpanMerge},
+};
+
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct RankingWeights {
    pub function_boost:   f32,
    pub test_penalty:     f32,
+   /// Multiplier for any test path (see [`is_test_path`]); `1.0` outside
+   /// tests mode.
+   pub test_boost:       f32,
    pub doc_multiplier:   f32,
    pub graph_multiplier: f32,
 }
@@ -18,6 +30,7 @@ impl RankingWeights {
       Self {
          function_boost:   1.25,
          test_penalty:     0.85,
+         test_boost:       1.0,
          doc_multiplier:   0.5,
          graph_multiplier: 1.0,
       }
@@ -29,27 +42,45 @@ impl RankingWeights {
          SearchMode::Discovery => Self {
             function_boost:   1.15,
             test_penalty:     0.9,
+            test_boost:       1.0,
             doc_multiplier:   1.0,
             graph_multiplier: 1.05,
          },
          SearchMode::Implementation => Self {
             function_boost:   1.25,
             test_penalty:     0.85,
+            test_boost:       1.0,
             doc_multiplier:   0.65,
             graph_multiplier: 0.9,
          },
          SearchMode::Planning => Self {
             function_boost:   1.1,
             test_penalty:     0.9,
+            test_boost:       1.0,
             doc_multiplier:   1.15,
             graph_multiplier: 1.1,
          },
          SearchMode::Debug => Self {
             function_boost:   1.2,
             test_penalty:     0.95,
+            test_boost:       1.0,
             doc_multiplier:   0.85,
             graph_multiplier: 0.95,
          },
+         SearchMode::Tests => Self {
+            function_boost:   1.15,
+            test_penalty:     1.0,
+            test_boost:       1.6,
+            doc_multiplier:   0.5,
+            graph_multiplier: 0.5,
+         },
+         SearchMode::Paths => Self {
+            function_boost:   1.0,
+            test_penalty:     0.85,
+            test_boost:       1.0,
+            doc_multiplier:   0.8,
+            graph_multiplier: 0.8,
+         },
       }
    }
 }
@@ -89,15 +120,47 @@ pub fn apply_structural_boost_with_weights(results: &mut [SearchResult], weights
       if is_test_file(&result.path) {
          result.score *= weights.test_penalty;
       }
+      if is_test_path(&result.path) {
+         result.score *= weights.test_boost;
+      }
 
-      if is_graph_file(&result.path) {
-         result.score *= weights.graph_multiplier;
-      } else if is_doc_or_config(&result.path) {
-         result.score *= weights.doc_multiplier;
+      match bucket_for(&result.path, result.language.as_deref()) {
+         SearchBucket::Graph => result.score *= weights.graph_multiplier,
+         SearchBucket::Docs => result.score *= weights.doc_multiplier,
+         SearchBucket::Code if is_doc_or_config(&result.path) => {
+            result.score *= weights.doc_multiplier;
+         },
+         SearchBucket::Code => {},
       }
    }
 }
 
+/// Boosts results from recently modified files. `mtime_of` returns a file's
+/// modification time in Unix seconds; a file modified at `now` gets `factor`,
+/// decaying linearly to no boost at `window_secs` old.
+pub fn apply_recent_boost(
+   results: &mut [SearchResult],
+   mtime_of: impl Fn(&Path) -> Option<u64>,
+   window_secs: u64,
+   factor: f32,
+   now: u64,
+) {
+   if window_secs == 0 || factor <= 1.0 {
+      return;
+   }
+   for result in results.iter_mut() {
+      let Some(mtime) = mtime_of(&result.path) else {
+         continue;
+      };
+      let age = now.saturating_sub(mtime);
+      if age >= window_secs {
+         continue;
+      }
+      let freshness = 1.0 - age as f32 / window_secs as f32;
+      result.score *= 1.0 + (factor - 1.0) * freshness;
+   }
+}
+
 /// Deduplicates results by (path, `start_line`), keeping the highest-scoring
 /// duplicate.
 pub fn deduplicate(mut results: Vec<SearchResult>) -> Vec<SearchResult> {
@@ -171,6 +234,111 @@ pub fn apply_per_file_limit(mut results: Vec<SearchResult>, limit: usize) -> Vec
    final_results
 }
 
+/// Combines same-file results whose line ranges overlap (or, with
+/// [`SpanMerge::Adjacent`], abut) into one spanning snippet, so neighbouring
+/// chunks do not repeat each other's context lines.
+///
+/// The merged result keeps the position and metadata of the higher-ranked
+/// chunk and the best score of the pair. Anchors are never merged. Runs over
+/// the whole ranked candidate list, so only chunks of the same file are
+/// compared.
+pub fn merge_spans(results: Vec<SearchResult>, merge: SpanMerge) -> Vec<SearchResult> {
+   if merge == SpanMerge::Off {
+      return results;
+   }
+
+   // A slot empties when its chunk is absorbed into a higher-ranked one.
+   let mut merged: Vec<Option<SearchResult>> = Vec::with_capacity(results.len());
+   let mut slots_by_path: HashMap<PathBuf, Vec<usize>> = HashMap::new();
+   for result in results {
+      let slots = slots_by_path.entry(result.path.clone()).or_default();
+      let touching = |merged: &[Option<SearchResult>], slot: usize, other: &SearchResult| {
+         merged[slot]
+            .as_ref()
+            .is_some_and(|kept| spans_touch(kept, other, merge))
+      };
+      let Some(mut idx) = slots
+         .iter()
+         .copied()
+         .find(|&slot| touching(&merged, slot, &result))
+      else {
+         slots.push(merged.len());
+         merged.push(Some(result));
+         continue;
+      };
+      absorb_span(merged[idx].as_mut().expect("live slot"), result);
+
+      // A grown span can now reach another kept chunk of the same file.
+      while let Some(other) = slots.iter().copied().find(|&other| {
+         other != idx && touching(&merged, other, merged[idx].as_ref().expect("live slot"))
+      }) {
+         let (keep, drop) = (idx.min(other), idx.max(other));
+         let dropped = merged[drop].take().expect("live slot");
+         absorb_span(merged[keep].as_mut().expect("live slot"), dropped);
+         slots.retain(|&slot| slot != drop);
+         idx = keep;
+      }
+   }
+
+   merged.into_iter().flatten().collect()
+}
+
+/// Returns the `[start, end)` lines covered by a result's content.
+fn span_of(result: &SearchResult) -> (u32, u32) {
+   let lines = match result.content.lines().count() as u32 {
+      0 => result.num_lines,
+      lines => lines,
+   };
+   (result.start_line, result.start_line + lines.max(1))
+}
+
+fn spans_touch(a: &SearchResult, b: &SearchResult, merge: SpanMerge) -> bool {
+   if a.path != b.path || a.is_anchor == Some(true) || b.is_anchor == Some(true) {
+      return false;
+   }
+   let (a_start, a_end) = span_of(a);
+   let (b_start, b_end) = span_of(b);
+   match merge {
+      SpanMerge::Off => false,
+      SpanMerge::Overlapping => a_start < b_end && b_start < a_end,
+      SpanMerge::Adjacent => a_start <= b_end && b_start <= a_end,
+   }
+}
+
+fn absorb_span(kept: &mut SearchResult, other: SearchResult) {
+   let (first, second) = if other.start_line < kept.start_line {
+      (&other, &*kept)
+   } else {
+      (&*kept, &other)
+   };
+   let (first_start, first_end) = span_of(first);
+   let (second_start, second_end) = span_of(second);
+
+   let mut content = first.content.as_str().to_owned();
+   if second_end > first_end {
+      if !content.is_empty() && !content.ends_with('\n') {
+         content.push('\n');
+      }
+      let skip = first_end.saturating_sub(second_start) as usize;
+      for line in second.content.split_inclusive('\n').skip(skip) {
+         content.push_str(line);
+      }
+   }
+
+   let context_after = if second_end > first_end {
+      second.context_after
+   } else {
+      first.context_after
+   };
+   kept.context_before = first.context_before;
+   kept.context_after = context_after;
+   kept.start_line = first_start;
+   kept.num_lines = first_end.max(second_end) - first_start;
+   kept.end_line = None;
+   kept.content = Str::from_string(content);
+   kept.score = kept.score.max(other.score);
+}
+
 fn is_test_file(path: &Path) -> bool {
    let Some(path_str) = path.to_str() else {
       return false;
@@ -181,19 +349,11 @@ fn is_test_file(path: &Path) -> bool {
 }
 
 fn is_doc_or_config(path: &Path) -> bool {
-   if path.extension().is_some_and(|ext| {
-      ext.eq_ignore_ascii_case("md")
-         || ext.eq_ignore_ascii_case("mdx")
-         || ext.eq_ignore_ascii_case("txt")
-         || ext.eq_ignore_ascii_case("json")
-         || ext.eq_ignore_ascii_case("html")
-         || ext.eq_ignore_ascii_case("htm")
-         || ext.eq_ignore_ascii_case("css")
-         || ext.eq_ignore_ascii_case("yaml")
-         || ext.eq_ignore_ascii_case("yml")
-         || ext.eq_ignore_ascii_case("toml")
-         || ext.eq_ignore_ascii_case("lock")
-   }) {
+   if bucket_for_path(path) == SearchBucket::Docs
+      || path
+         .extension()
+         .is_some_and(|ext| ext.eq_ignore_ascii_case("lock"))
+   {
       return true;
    }
 
@@ -203,18 +363,12 @@ fn is_doc_or_config(path: &Path) -> bool {
    contains_ci(path_str, "/docs/")
 }
 
-fn is_graph_file(path: &Path) -> bool {
-   path
-      .extension()
-      .is_some_and(|ext| ext.eq_ignore_ascii_case("mmd") || ext.eq_ignore_ascii_case("mermaid"))
-}
-
 #[cfg(test)]
 mod tests {
    use std::path::PathBuf;
 
    use super::*;
-   use crate::Str;
+   use crate::{Str, grammar::UNKNOWN_LANGUAGE};
 
    fn make_result(path: &str, start_line: u32, score: f32, chunk_type: ChunkType) -> SearchResult {
       SearchResult {
@@ -226,8 +380,13 @@ mod tests {
          segment_table: None,
          start_line,
          num_lines: 10,
+         end_line: None,
          chunk_type: Some(chunk_type),
+         symbol: None,
          is_anchor: Some(false),
+         language: None,
+         context_before: 0,
+         context_after: 0,
       }
    }
 
@@ -248,6 +407,45 @@ mod tests {
       assert!((results[3].score - 0.5).abs() < 1e-6);
    }
 
+   #[test]
+   fn test_structural_boost_prefers_stored_language() {
+      let mut script = make_result("bin/release", 1, 1.0, ChunkType::Block);
+      script.language = Some("bash".to_string());
+      let mut legacy = make_result("guide.md", 1, 1.0, ChunkType::Other);
+      legacy.language = Some(UNKNOWN_LANGUAGE.to_string());
+      let mut results = vec![script, legacy];
+
+      apply_structural_boost(&mut results);
+
+      assert!((results[0].score - 1.0).abs() < 1e-6);
+      assert!((results[1].score - 0.5).abs() < 1e-6);
+   }
+
+   #[test]
+   fn test_apply_recent_boost_decays_with_age() {
+      const DAY: u64 = 86_400;
+      let now = 100 * DAY;
+      let mut results = vec![
+         make_result("fresh.rs", 1, 1.0, ChunkType::Block),
+         make_result("half.rs", 1, 1.0, ChunkType::Block),
+         make_result("old.rs", 1, 1.0, ChunkType::Block),
+         make_result("unknown.rs", 1, 1.0, ChunkType::Block),
+      ];
+      let mtime_of = |path: &Path| match path.to_str()? {
+         "fresh.rs" => Some(now),
+         "half.rs" => Some(now - 5 * DAY),
+         "old.rs" => Some(now - 30 * DAY),
+         _ => None,
+      };
+
+      apply_recent_boost(&mut results, mtime_of, 10 * DAY, 1.5, now);
+
+      assert!((results[0].score - 1.5).abs() < 1e-6);
+      assert!((results[1].score - 1.25).abs() < 1e-6);
+      assert!((results[2].score - 1.0).abs() < 1e-6);
+      assert!((results[3].score - 1.0).abs() < 1e-6);
+   }
+
    #[test]
    fn test_deduplicate() {
       let results = vec![
@@ -285,6 +483,60 @@ mod tests {
       assert_eq!(file1_count, 2);
    }
 
+   #[test]
+   fn test_merge_spans_joins_overlapping_context() {
+      let mut first = make_result("src/lib.rs", 10, 0.9, ChunkType::Function);
+      first.content = Str::from("fn a() {\n   one();\n}\nfn b() {\n");
+      let mut second = make_result("src/lib.rs", 12, 0.7, ChunkType::Function);
+      second.content = Str::from("}\nfn b() {\n   two();\n}\n");
+      let other = make_result("src/main.rs", 12, 0.8, ChunkType::Function);
+
+      let merged = merge_spans(vec![first.clone(), other, second.clone()], SpanMerge::Overlapping);
+
+      assert_eq!(merged.len(), 2);
+      assert_eq!(merged[0].start_line, 10);
+      assert_eq!(merged[0].num_lines, 6);
+      assert_eq!(merged[0].content, "fn a() {\n   one();\n}\nfn b() {\n   two();\n}\n");
+      assert!((merged[0].score - 0.9).abs() < 1e-6);
+
+      let kept = merge_spans(vec![first, second], SpanMerge::Off);
+      assert_eq!(kept.len(), 2);
+   }
+
+   #[test]
+   fn test_merge_spans_joins_consecutive_chunks_of_one_function() {
+      let mut head = make_result("src/lib.rs", 20, 0.6, ChunkType::Function);
+      head.content = Str::from("fn long() {\n   step_one();\n");
+      let mut tail = make_result("src/lib.rs", 22, 0.8, ChunkType::Function);
+      tail.content = Str::from("   step_two();\n}\n");
+
+      let separate = merge_spans(vec![tail.clone(), head.clone()], SpanMerge::Overlapping);
+      assert_eq!(separate.len(), 2);
+
+      let merged = merge_spans(vec![tail, head], SpanMerge::Adjacent);
+      assert_eq!(merged.len(), 1);
+      assert_eq!(merged[0].start_line, 20);
+      assert_eq!(merged[0].num_lines, 4);
+      assert_eq!(merged[0].content, "fn long() {\n   step_one();\n   step_two();\n}\n");
+      assert!((merged[0].score - 0.8).abs() < 1e-6);
+   }
+
+   #[test]
+   fn test_merge_spans_chains_through_grown_span() {
+      let mut first = make_result("src/lib.rs", 1, 0.9, ChunkType::Block);
+      first.content = Str::from("a\nb\n");
+      let mut third = make_result("src/lib.rs", 5, 0.8, ChunkType::Block);
+      third.content = Str::from("e\nf\n");
+      let mut second = make_result("src/lib.rs", 3, 0.7, ChunkType::Block);
+      second.content = Str::from("c\nd\n");
+
+      let merged = merge_spans(vec![first, third, second], SpanMerge::Adjacent);
+
+      assert_eq!(merged.len(), 1);
+      assert_eq!(merged[0].start_line, 1);
+      assert_eq!(merged[0].content, "a\nb\nc\nd\ne\nf\n");
+   }
+
    #[test]
    fn test_is_test_file() {
       assert!(is_test_file(Path::new("src/main.test.ts")));
@@ -293,6 +545,21 @@ mod tests {
       assert!(!is_test_file(Path::new("src/main.rs")));
    }
 
+   #[test]
+   fn test_tests_mode_boosts_test_paths() {
+      let mut results = vec![
+         make_result("src/parser.rs", 1, 1.0, ChunkType::Block),
+         make_result("tests/parser.rs", 1, 1.0, ChunkType::Block),
+         make_result("src/parser_test.go", 1, 1.0, ChunkType::Block),
+      ];
+
+      apply_structural_boost_with_mode(&mut results, SearchMode::Tests);
+
+      assert!((results[0].score - 1.0).abs() < 1e-6);
+      assert!((results[1].score - 1.6).abs() < 1e-6);
+      assert!((results[2].score - 1.6).abs() < 1e-6);
+   }
+
    #[test]
    fn test_is_doc_or_config() {
       assert!(is_doc_or_config(Path::new("README.md")));
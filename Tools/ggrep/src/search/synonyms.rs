@@ -0,0 +1,138 @@
+//! Query expansion from a user synonym dictionary.
+//!
+//! `synonyms.toml` in the ggrep base directory maps a term to the terms it
+//! should also match, e.g. `auth = ["authentication", "authorization"]`.
+//! Expansions are appended to the full-text query only; the dense embedding
+//! sees the query as typed so extra terms do not dilute the vector.
+
+use std::{
+   collections::{BTreeMap, BTreeSet},
+   path::Path,
+   sync::OnceLock,
+};
+
+use crate::{config, types::QueryExpansion};
+
+/// Synonym dictionary file, read from [`config::base_dir`].
+pub const SYNONYMS_FILE: &str = "synonyms.toml";
+
+/// Lowercased term to expansion list, read once per process. A missing or
+/// invalid file yields an empty dictionary.
+pub fn synonyms() -> &'static BTreeMap<String, Vec<String>> {
+   static SYNONYMS: OnceLock<BTreeMap<String, Vec<String>>> = OnceLock::new();
+   SYNONYMS.get_or_init(|| load_synonyms(&config::base_dir().join(SYNONYMS_FILE)))
+}
+
+fn load_synonyms(path: &Path) -> BTreeMap<String, Vec<String>> {
+   let Ok(raw) = std::fs::read_to_string(path) else {
+      return BTreeMap::new();
+   };
+   let file: BTreeMap<String, Vec<String>> = match toml::from_str(&raw) {
+      Ok(file) => file,
+      Err(err) => {
+         tracing::warn!("ignoring synonyms in {}: {}", path.display(), err);
+         return BTreeMap::new();
+      },
+   };
+   file
+      .into_iter()
+      .map(|(term, expansions)| {
+         let expansions = expansions
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+         (term.trim().to_lowercase(), expansions)
+      })
+      .collect()
+}
+
+/// Returns the full-text query with synonyms of the query's terms appended,
+/// and which terms fired. Synonyms already in the query are not repeated.
+pub fn expand_query(
+   query: &str,
+   dictionary: &BTreeMap<String, Vec<String>>,
+) -> (String, Vec<QueryExpansion>) {
+   if dictionary.is_empty() {
+      return (query.to_string(), Vec::new());
+   }
+   let terms: Vec<String> = query
+      .split(|c: char| !c.is_alphanumeric() && c != '_')
+      .filter(|term| !term.is_empty())
+      .map(str::to_lowercase)
+      .collect();
+   let mut seen: BTreeSet<String> = terms.iter().cloned().collect();
+
+   let mut expanded = query.to_string();
+   let mut expansions: Vec<QueryExpansion> = Vec::new();
+   for term in &terms {
+      let Some(candidates) = dictionary.get(term) else {
+         continue;
+      };
+      if expansions.iter().any(|e| &e.term == term) {
+         continue;
+      }
+      let synonyms: Vec<String> = candidates
+         .iter()
+         .filter(|synonym| seen.insert(synonym.to_lowercase()))
+         .cloned()
+         .collect();
+      if synonyms.is_empty() {
+         continue;
+      }
+      for synonym in &synonyms {
+         expanded.push(' ');
+         expanded.push_str(synonym);
+      }
+      expansions.push(QueryExpansion { term: term.clone(), synonyms });
+   }
+   (expanded, expansions)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn dictionary() -> BTreeMap<String, Vec<String>> {
+      BTreeMap::from([
+         ("auth".to_string(), vec!["authentication".to_string(), "login".to_string()]),
+         ("db".to_string(), vec!["database".to_string()]),
+      ])
+   }
+
+   #[test]
+   fn expands_matching_terms_case_insensitively() {
+      let (fts, expansions) = expand_query("Auth token refresh", &dictionary());
+      assert_eq!(fts, "Auth token refresh authentication login");
+      assert_eq!(expansions, vec![QueryExpansion {
+         term:     "auth".to_string(),
+         synonyms: vec!["authentication".to_string(), "login".to_string()],
+      }]);
+   }
+
+   #[test]
+   fn skips_synonyms_already_in_query() {
+      let (fts, expansions) = expand_query("auth login auth", &dictionary());
+      assert_eq!(fts, "auth login auth authentication");
+      assert_eq!(expansions.len(), 1);
+      assert_eq!(expansions[0].synonyms, vec!["authentication".to_string()]);
+   }
+
+   #[test]
+   fn no_match_leaves_query_unchanged() {
+      let (fts, expansions) = expand_query("parse config", &dictionary());
+      assert_eq!(fts, "parse config");
+      assert!(expansions.is_empty());
+   }
+
+   #[test]
+   fn missing_file_is_empty() {
+      let dir = tempfile::TempDir::new().expect("temp dir");
+      assert!(load_synonyms(&dir.path().join(SYNONYMS_FILE)).is_empty());
+
+      let path = dir.path().join(SYNONYMS_FILE);
+      std::fs::write(&path, "Auth = [\"authentication\", \" \"]\n").expect("write");
+      let loaded = load_synonyms(&path);
+      assert_eq!(loaded.get("auth"), Some(&vec!["authentication".to_string()]));
+   }
+}
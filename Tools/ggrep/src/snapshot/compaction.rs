@@ -71,11 +71,29 @@ struct TombstoneEntry {
    path_key: String,
 }
 
+/// Tombstone entries per live file in the snapshot chain; 0.0 for an empty
+/// snapshot. Both sides count paths: a tombstone hides every row of one file.
+pub fn tombstone_ratio(manifest: &SnapshotManifest) -> f64 {
+   let tombstones: u64 = manifest.tombstones.iter().map(|t| t.count).sum();
+   let live = manifest.counts.files_indexed;
+   if tombstones == 0 {
+      0.0
+   } else if live == 0 {
+      f64::INFINITY
+   } else {
+      tombstones as f64 / live as f64
+   }
+}
+
 pub fn compaction_overdue(manifest: &SnapshotManifest) -> bool {
    let cfg = config::get();
    let segments = manifest.segments.len();
    let tombstones: u64 = manifest.tombstones.iter().map(|t| t.count).sum();
 
+   // Churny repos hit the ratio long before the absolute caps; check it first.
+   if cfg.max_tombstone_ratio > 0.0 && tombstone_ratio(manifest) > cfg.max_tombstone_ratio {
+      return true;
+   }
    if cfg.compaction_overdue_segments > 0 && segments >= cfg.compaction_overdue_segments {
       return true;
    }
@@ -144,6 +162,11 @@ pub async fn compact_store(
          });
       }
 
+      if base_manifest.mixed_chunking() {
+         tracing::warn!(
+            "compacting segments with mixed chunking modes; the compacted segment records none"
+         );
+      }
       let tombstones = load_tombstones(&base_manifest, &snapshot_manager.store_root())?;
       let build = build_compaction_segment(
          Arc::clone(&store),
@@ -187,6 +210,7 @@ pub async fn compact_store(
             rows: metadata.rows,
             size_bytes: metadata.size_bytes,
             sha256: metadata.sha256,
+            chunking: base_manifest.chunking_mode(),
          });
          segments_after = 1;
       }
@@ -275,7 +299,7 @@ pub async fn compact_store(
    )
 }
 
-fn load_tombstones(
+pub(crate) fn load_tombstones(
    manifest: &SnapshotManifest,
    store_root: &Path,
 ) -> Result<HashSet<String>> {
@@ -340,7 +364,7 @@ async fn build_compaction_segment(
    Ok(CompactionBuild { snapshot_id, table_name, rows_after, path_keys })
 }
 
-fn filter_batch(
+pub(crate) fn filter_batch(
    batch: &RecordBatch,
    tombstones: &HashSet<String>,
    path_keys: &mut HashSet<String>,
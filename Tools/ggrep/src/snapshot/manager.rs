@@ -9,6 +9,7 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 
@@ -20,9 +21,41 @@ use crate::{
    util::{fail_point, fsync_dir},
 };
 
-use super::manifest::{CHUNK_ROW_SCHEMA_VERSION, MANIFEST_SCHEMA_VERSION, SnapshotManifest};
+use super::manifest::{
+   CHUNK_ROW_SCHEMA_VERSION, ChunkingMode, MANIFEST_SCHEMA_VERSION, SnapshotManifest,
+};
 use super::view::SnapshotView;
 
+/// File inside a staging directory that records the in-flight sync.
+const STAGING_TXN_FILE: &str = "txn.json";
+
+/// Identity of an in-flight sync, written into its staging directory so a run
+/// interrupted before publish can be resumed instead of starting over.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StagingTxn {
+   pub staging_txn_id:      String,
+   pub snapshot_id:         String,
+   pub segment_table:       String,
+   pub parent_snapshot_id:  Option<String>,
+   pub config_fingerprint:  String,
+   pub created_at:          String,
+   /// Chunking mode and per-file chunk cap the staged rows were built with;
+   /// neither is part of the config fingerprint. `None` in records written
+   /// before they were tracked, which are never resumed.
+   #[serde(default)]
+   pub chunking:            Option<ChunkingMode>,
+   #[serde(default)]
+   pub max_chunks_per_file: Option<usize>,
+}
+
+impl StagingTxn {
+   /// Reads the transaction record from a staging directory, if present.
+   pub fn load(staging_path: &Path) -> Option<Self> {
+      let raw = fs::read(staging_path.join(STAGING_TXN_FILE)).ok()?;
+      serde_json::from_slice(&raw).ok()
+   }
+}
+
 #[derive(Clone)]
 pub struct SnapshotManager {
    store:              Arc<LanceStore>,
@@ -58,25 +91,22 @@ impl SnapshotManager {
    }
 
    pub fn read_active_snapshot_id(&self) -> Result<Option<String>> {
-      let path = self.active_snapshot_path();
-      match fs::read_to_string(&path) {
-         Ok(raw) => {
-            let id = raw.lines().next().unwrap_or("").trim();
-            if id.is_empty() {
-               Ok(None)
-            } else {
-               Ok(Some(id.to_string()))
-            }
-         },
-         Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
-         Err(e) => Err(e.into()),
-      }
+      read_active_snapshot_id_at(&self.store_root())
    }
 
    pub fn manifest_path(&self, snapshot_id: &str) -> PathBuf {
       self.snapshot_dir(snapshot_id).join("manifest.json")
    }
 
+   /// Manifest location for callers that have no open store handle.
+   pub fn manifest_path_for(store_id: &str, snapshot_id: &str) -> PathBuf {
+      config::data_dir()
+         .join(store_id)
+         .join("snapshots")
+         .join(snapshot_id)
+         .join("manifest.json")
+   }
+
    pub fn snapshot_dir(&self, snapshot_id: &str) -> PathBuf {
       self.snapshots_dir().join(snapshot_id)
    }
@@ -91,7 +121,60 @@ impl SnapshotManager {
       Ok(path)
    }
 
+   /// Creates the staging directory for `txn` and records it for resume.
+   pub fn create_staging_txn(&self, txn: &StagingTxn) -> Result<PathBuf> {
+      let path = self.create_staging(&txn.staging_txn_id)?;
+      let raw = serde_json::to_vec_pretty(txn)?;
+      fs::write(path.join(STAGING_TXN_FILE), raw)?;
+      Ok(path)
+   }
+
+   /// Finds the newest interrupted sync that can be resumed: same config
+   /// fingerprint, chunking mode and chunk cap, built on the currently active
+   /// snapshot, and with its segment table still present in the store.
+   ///
+   /// Callers must hold the writer lease, so no staging directory found here
+   /// belongs to a live writer.
+   pub async fn find_resumable_staging(
+      &self,
+      chunking: ChunkingMode,
+      max_chunks_per_file: usize,
+   ) -> Result<Option<StagingTxn>> {
+      let staging_dir = self.staging_dir();
+      if !staging_dir.exists() {
+         return Ok(None);
+      }
+      let active = self.read_active_snapshot_id()?;
+      let tables = self.store.list_tables(&self.store_id).await?;
+
+      let mut best: Option<StagingTxn> = None;
+      for entry in fs::read_dir(&staging_dir)? {
+         let entry = entry?;
+         let Some(txn) = StagingTxn::load(&entry.path()) else {
+            continue;
+         };
+         if txn.config_fingerprint != self.config_fingerprint
+            || txn.chunking != Some(chunking)
+            || txn.max_chunks_per_file != Some(max_chunks_per_file)
+            || txn.parent_snapshot_id != active
+            || !tables.contains(&txn.segment_table)
+         {
+            continue;
+         }
+         if best.as_ref().is_none_or(|b| txn.created_at > b.created_at) {
+            best = Some(txn);
+         }
+      }
+      Ok(best)
+   }
+
    pub fn cleanup_staging(&self) -> Result<()> {
+      self.cleanup_staging_except(None)
+   }
+
+   /// Removes staging directories older than `staging_ttl_ms`, keeping
+   /// `keep` (a transaction being resumed) regardless of age.
+   pub fn cleanup_staging_except(&self, keep: Option<&str>) -> Result<()> {
       let ttl = std::time::Duration::from_millis(config::get().staging_ttl_ms);
       let now = SystemTime::now();
       let staging_dir = self.staging_dir();
@@ -104,6 +187,9 @@ impl SnapshotManager {
             continue;
          }
          let path = entry.path();
+         if keep.is_some_and(|id| entry.file_name() == id) {
+            continue;
+         }
          let mtime = entry.metadata().and_then(|m| m.modified()).unwrap_or(now);
          if now.duration_since(mtime).unwrap_or(ttl) > ttl {
             let _ = fs::remove_dir_all(&path);
@@ -155,6 +241,25 @@ impl SnapshotManager {
       )
    }
 
+   /// Opens a specific snapshot, e.g. one pinned by a search cursor. Unlike
+   /// [`Self::open_snapshot_view`] there is no fallback: a snapshot that was
+   /// collected or fails verification is an error.
+   pub async fn open_snapshot_view_by_id(&self, snapshot_id: &str) -> Result<SnapshotView> {
+      let path = self.manifest_path(snapshot_id);
+      if !path.exists() {
+         return Err(
+            Error::Server {
+               op:     "snapshot",
+               reason: format!("snapshot {snapshot_id} no longer exists"),
+            }
+            .into(),
+         );
+      }
+      let manifest = SnapshotManifest::load(&path)?;
+      self.verify_manifest(&manifest).await?;
+      SnapshotView::from_manifest(manifest, &self.store_root())
+   }
+
    pub fn write_active_snapshot(&self, snapshot_id: &str) -> Result<()> {
       let path = self.active_snapshot_path();
       if let Some(parent) = path.parent() {
@@ -336,6 +441,23 @@ pub fn compute_tombstone_artifact(path: &Path) -> Result<(u64, String, u64)> {
    Ok((size_bytes, digest, count))
 }
 
+/// Reads `ACTIVE_SNAPSHOT` under a store root, for callers without an open
+/// store handle.
+pub fn read_active_snapshot_id_at(store_root: &Path) -> Result<Option<String>> {
+   match fs::read_to_string(store_root.join("ACTIVE_SNAPSHOT")) {
+      Ok(raw) => {
+         let id = raw.lines().next().unwrap_or("").trim();
+         if id.is_empty() {
+            Ok(None)
+         } else {
+            Ok(Some(id.to_string()))
+         }
+      },
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e.into()),
+   }
+}
+
 pub fn segment_table_name(snapshot_id: &str, seq: usize) -> String {
    format!("seg_{snapshot_id}_{seq}")
 }
@@ -20,6 +20,29 @@ pub struct SnapshotGitInfo {
    pub untracked_included: bool,
 }
 
+/// How a segment's chunks were produced.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkingMode {
+   /// Anchor chunks only; search must include anchors to see these files.
+   Fast,
+   /// Structural chunking; anchors are excluded at search time.
+   Full,
+}
+
+impl ChunkingMode {
+   pub const fn from_fast(fast: bool) -> Self {
+      if fast { Self::Fast } else { Self::Full }
+   }
+
+   pub const fn as_str(self) -> &'static str {
+      match self {
+         Self::Fast => "fast",
+         Self::Full => "full",
+      }
+   }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SnapshotSegmentRef {
    pub kind:       String,
@@ -28,6 +51,9 @@ pub struct SnapshotSegmentRef {
    pub rows:       u64,
    pub size_bytes: u64,
    pub sha256:     String,
+   /// Absent for segments written before the mode was recorded.
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub chunking:   Option<ChunkingMode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -39,11 +65,18 @@ pub struct SnapshotTombstoneRef {
    pub sha256:     String,
 }
 
+/// `SnapshotError` code for languages chunked without their tree-sitter
+/// grammar. These entries are recorded even when the snapshot is not degraded.
+pub const GRAMMAR_MISSING_CODE: &str = "grammar_missing";
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SnapshotError {
    pub code:     String,
    pub message:  String,
    pub path_key: String,
+   /// Language the entry concerns (set for `grammar_missing`).
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub language: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -74,6 +107,56 @@ pub struct SnapshotManifest {
 }
 
 impl SnapshotManifest {
+   /// Languages whose files were chunked without a grammar, so they lack
+   /// structural chunk types.
+   pub fn grammar_missing_languages(&self) -> Vec<&str> {
+      let mut languages: Vec<&str> = self
+         .errors
+         .iter()
+         .filter(|error| error.code == GRAMMAR_MISSING_CODE)
+         .filter_map(|error| error.language.as_deref())
+         .collect();
+      languages.sort_unstable();
+      languages.dedup();
+      languages
+   }
+
+   /// Entries for files that failed to index, leaving out the
+   /// `grammar_missing` warnings recorded alongside them.
+   pub fn file_errors(&self) -> impl Iterator<Item = &SnapshotError> {
+      self
+         .errors
+         .iter()
+         .filter(|error| error.code != GRAMMAR_MISSING_CODE)
+   }
+
+   /// Distinct chunking modes recorded across segments.
+   pub fn chunking_modes(&self) -> Vec<ChunkingMode> {
+      [ChunkingMode::Fast, ChunkingMode::Full]
+         .into_iter()
+         .filter(|mode| self.segments.iter().any(|s| s.chunking == Some(*mode)))
+         .collect()
+   }
+
+   /// Single recorded chunking mode, or `None` when unrecorded or mixed.
+   pub fn chunking_mode(&self) -> Option<ChunkingMode> {
+      match self.chunking_modes().as_slice() {
+         [mode] => Some(*mode),
+         _ => None,
+      }
+   }
+
+   pub fn mixed_chunking(&self) -> bool {
+      self.chunking_modes().len() > 1
+   }
+
+   /// Whether any segment is anchor-only; `None` when no segment records a
+   /// mode.
+   pub fn has_fast_segments(&self) -> Option<bool> {
+      let modes = self.chunking_modes();
+      (!modes.is_empty()).then(|| modes.contains(&ChunkingMode::Fast))
+   }
+
    pub fn load(path: &Path) -> Result<Self> {
       let raw = fs::read_to_string(path)?;
       let manifest: SnapshotManifest = serde_json::from_str(&raw)?;
@@ -7,13 +7,20 @@ pub mod segment_index;
 pub mod view;
 pub mod compaction;
 pub mod gc;
+pub mod reembed;
 
 pub use manifest::{
-   SnapshotCounts, SnapshotError, SnapshotGitInfo, SnapshotManifest, SnapshotSegmentRef,
-   SnapshotTombstoneRef,
+   ChunkingMode, GRAMMAR_MISSING_CODE, SnapshotCounts, SnapshotError, SnapshotGitInfo,
+   SnapshotManifest, SnapshotSegmentRef, SnapshotTombstoneRef,
+};
+pub use manager::{
+   SnapshotManager, StagingTxn, compute_dir_hash, compute_tombstone_artifact,
+   read_active_snapshot_id_at, segment_table_name,
 };
-pub use manager::{SnapshotManager, compute_dir_hash, compute_tombstone_artifact, segment_table_name};
 pub use segment_index::{SegmentFileIndexEntry, read_segment_file_index, write_segment_file_index};
 pub use view::SnapshotView;
-pub use compaction::{CompactionOptions, CompactionResult, compact_store, compaction_overdue};
+pub use compaction::{
+   CompactionOptions, CompactionResult, compact_store, compaction_overdue, tombstone_ratio,
+};
 pub use gc::{GcOptions, GcReport, gc_snapshots};
+pub use reembed::{ReembedResult, reembed_store};
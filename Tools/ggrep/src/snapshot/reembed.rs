@@ -0,0 +1,279 @@
+//! Re-embedding stored chunks after an embedding model change.
+//!
+//! Every row keeps the chunk text it was embedded from, so when only the model
+//! changed the active snapshot's rows can be embedded again as they are, with
+//! no file reads and no chunking. The rows land in one new segment published
+//! as a fresh snapshot of the target store.
+
+use std::{
+   collections::{HashMap, HashSet},
+   fs,
+   sync::Arc,
+   time::Instant,
+};
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use lancedb::query::ExecutableQuery;
+use uuid::Uuid;
+
+use crate::{
+   Result, config,
+   embed::Embedder,
+   error::Error,
+   lease::WriterLease,
+   meta::MetaStore,
+   snapshot::{
+      SnapshotCounts, SnapshotGitInfo, SnapshotManager, SnapshotManifest, SnapshotSegmentRef,
+      SnapshotTombstoneRef,
+      compaction::{filter_batch, load_tombstones},
+      segment_table_name, write_segment_file_index,
+   },
+   store::LanceStore,
+   util,
+};
+
+#[derive(Debug, Clone)]
+pub struct ReembedResult {
+   pub source_store_id:  String,
+   pub store_id:         String,
+   pub base_snapshot_id: String,
+   pub new_snapshot_id:  String,
+   pub files:            u64,
+   pub rows:             u64,
+   pub duration_ms:      u64,
+}
+
+/// Re-embeds the active snapshot of `source_store_id` with the configured
+/// models and publishes it to `store_id`, which may be the same store. Only
+/// valid when the embedding model is all that changed since the source was
+/// indexed; an index format change needs re-chunking.
+pub async fn reembed_store(
+   store: Arc<LanceStore>,
+   embedder: Arc<dyn Embedder>,
+   source_store_id: &str,
+   store_id: &str,
+   config_fingerprint: &str,
+   ignore_fingerprint: &str,
+) -> Result<ReembedResult> {
+   let start = Instant::now();
+   let source_meta = MetaStore::load(source_store_id)?;
+   if source_meta.index_mismatch() {
+      return Err(reembed_error(format!(
+         "index format changed since {source_store_id} was built; re-index with `ggrep index \
+          --reset`"
+      )));
+   }
+   if !source_meta.model_mismatch() {
+      return Err(reembed_error(format!(
+         "{source_store_id} already uses the configured embedding model"
+      )));
+   }
+
+   let source_manager = SnapshotManager::new(
+      Arc::clone(&store),
+      source_store_id.to_string(),
+      source_meta
+         .config_fingerprint()
+         .unwrap_or_default()
+         .to_string(),
+      source_meta
+         .ignore_fingerprint()
+         .unwrap_or_default()
+         .to_string(),
+   );
+   let Some(base_snapshot_id) = source_manager.read_active_snapshot_id()? else {
+      return Err(reembed_error(format!("{source_store_id} has no snapshot to re-embed")));
+   };
+   let base_manifest = SnapshotManifest::load(&source_manager.manifest_path(&base_snapshot_id))?;
+   source_manager.verify_manifest(&base_manifest).await?;
+
+   let same_store = source_store_id == store_id;
+   let snapshot_manager = SnapshotManager::new(
+      Arc::clone(&store),
+      store_id.to_string(),
+      config_fingerprint.to_string(),
+      ignore_fingerprint.to_string(),
+   );
+   let target_base = snapshot_manager.read_active_snapshot_id()?;
+   if !same_store && target_base.is_some() {
+      return Err(reembed_error(format!(
+         "{store_id} is already indexed; remove it with `ggrep clean {store_id}` to rebuild it \
+          from {source_store_id}"
+      )));
+   }
+
+   let tombstones = load_tombstones(&base_manifest, &source_manager.store_root())?;
+   let snapshot_id = Uuid::new_v4().to_string();
+   let table_name = segment_table_name(&snapshot_id, 0);
+   let mut path_keys: HashSet<String> = HashSet::new();
+   let built = build_reembedded_segment(
+      &store,
+      embedder.as_ref(),
+      source_store_id,
+      store_id,
+      &table_name,
+      &base_manifest,
+      &tombstones,
+      &mut path_keys,
+   )
+   .await;
+   let rows = match built {
+      Ok(rows) => rows,
+      Err(err) => {
+         let _ = store.drop_table(store_id, &table_name).await;
+         return Err(err);
+      },
+   };
+
+   let lease = WriterLease::acquire(store_id).await?;
+   if snapshot_manager.read_active_snapshot_id()? != target_base {
+      let _ = store.drop_table(store_id, &table_name).await;
+      return Err(reembed_error(format!("{store_id} was re-indexed while re-embedding; retry")));
+   }
+
+   let mut segments: Vec<SnapshotSegmentRef> = Vec::new();
+   let mut rows_after = rows;
+   if rows > 0 {
+      store.create_fts_index(store_id, &table_name).await?;
+      store.create_vector_index(store_id, &table_name).await?;
+      let metadata = store.segment_metadata(store_id, &table_name).await?;
+      rows_after = metadata.rows;
+      segments.push(SnapshotSegmentRef {
+         kind:       "reembedded".to_string(),
+         ref_type:   "lancedb_table".to_string(),
+         table:      table_name.clone(),
+         rows:       metadata.rows,
+         size_bytes: metadata.size_bytes,
+         sha256:     metadata.sha256,
+         chunking:   base_manifest.chunking_mode(),
+      });
+   }
+
+   let created_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+   let snapshot_dir = snapshot_manager.snapshot_dir(&snapshot_id);
+   fs::create_dir_all(&snapshot_dir)?;
+   if !path_keys.is_empty() && rows > 0 {
+      let segment_index: HashMap<String, String> = path_keys
+         .iter()
+         .map(|path_key| (path_key.clone(), table_name.clone()))
+         .collect();
+      write_segment_file_index(&snapshot_dir.join("segment_file_index.jsonl"), &segment_index)?;
+   }
+   util::fsync_dir(&snapshot_dir)?;
+
+   let manifest = SnapshotManifest {
+      schema_version: base_manifest.schema_version,
+      chunk_row_schema_version: base_manifest.chunk_row_schema_version,
+      snapshot_id: snapshot_id.clone(),
+      parent_snapshot_id: target_base,
+      created_at: created_at.clone(),
+      canonical_root: base_manifest.canonical_root.clone(),
+      store_id: store_id.to_string(),
+      config_fingerprint: config_fingerprint.to_string(),
+      ignore_fingerprint: ignore_fingerprint.to_string(),
+      lease_epoch: lease.lease_epoch(),
+      git: SnapshotGitInfo {
+         head_sha:           base_manifest.git.head_sha.clone(),
+         dirty:              base_manifest.git.dirty,
+         untracked_included: base_manifest.git.untracked_included,
+      },
+      segments,
+      tombstones: Vec::<SnapshotTombstoneRef>::new(),
+      counts: SnapshotCounts {
+         files_indexed:    path_keys.len() as u64,
+         chunks_indexed:   rows_after,
+         tombstones_added: 0,
+      },
+      degraded: base_manifest.degraded,
+      errors: base_manifest.errors.clone(),
+   };
+   snapshot_manager
+      .publish_manifest(&manifest, lease.owner_id(), lease.lease_epoch())
+      .await?;
+
+   // Loading the source meta recorded the configured model; saving it (or a
+   // copy of its files for another store) clears the model mismatch so the
+   // next sync only picks up file changes.
+   let mut meta_store = if same_store {
+      source_meta
+   } else {
+      let mut meta_store = MetaStore::load(store_id)?;
+      meta_store.copy_files_from(&source_meta);
+      meta_store
+   };
+   let duration_ms = start.elapsed().as_millis() as u64;
+   meta_store.set_fingerprints(config_fingerprint.to_string(), ignore_fingerprint.to_string());
+   meta_store.set_snapshot_status(
+      manifest.snapshot_id.clone(),
+      manifest.created_at.clone(),
+      manifest.degraded,
+   );
+   meta_store.record_sync("ok", duration_ms);
+   meta_store.save()?;
+
+   Ok(ReembedResult {
+      source_store_id: source_store_id.to_string(),
+      store_id: store_id.to_string(),
+      base_snapshot_id,
+      new_snapshot_id: snapshot_id,
+      files: path_keys.len() as u64,
+      rows: rows_after,
+      duration_ms,
+   })
+}
+
+/// Streams the visible rows of `base_manifest`'s segments, embeds their stored
+/// text in `batch_size` slices and appends them to `table_name`. Returns the
+/// rows written.
+#[allow(
+   clippy::too_many_arguments,
+   reason = "Private helper split out of `reembed_store`; each argument is one of its locals"
+)]
+async fn build_reembedded_segment(
+   store: &LanceStore,
+   embedder: &dyn Embedder,
+   source_store_id: &str,
+   store_id: &str,
+   table_name: &str,
+   base_manifest: &SnapshotManifest,
+   tombstones: &HashSet<String>,
+   path_keys: &mut HashSet<String>,
+) -> Result<u64> {
+   let batch_size = config::get().batch_size().max(1);
+   let mut rows: u64 = 0;
+
+   for segment in &base_manifest.segments {
+      let table = store.get_table(source_store_id, &segment.table).await?;
+      let mut stream = table
+         .query()
+         .execute()
+         .await
+         .map_err(|e| reembed_error(format!("failed to scan segment {}: {e}", segment.table)))?;
+      while let Some(batch) = stream
+         .try_next()
+         .await
+         .map_err(|e| reembed_error(format!("failed to read segment {}: {e}", segment.table)))?
+      {
+         let (filtered, kept) = filter_batch(&batch, tombstones, path_keys)?;
+         let mut offset = 0;
+         while offset < kept {
+            let slice = filtered.slice(offset, batch_size.min(kept - offset));
+            let texts = LanceStore::batch_texts(&slice)?;
+            let embeddings = embedder.compute_hybrid(&texts).await?;
+            let slice = LanceStore::replace_embeddings(&slice, embeddings)?;
+            offset += slice.num_rows();
+            store
+               .append_record_batch(store_id, table_name, slice)
+               .await?;
+         }
+         rows = rows.saturating_add(kept as u64);
+      }
+   }
+
+   Ok(rows)
+}
+
+fn reembed_error(reason: String) -> Error {
+   Error::Server { op: "reset-embeddings", reason }
+}
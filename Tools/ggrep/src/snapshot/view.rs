@@ -8,7 +8,7 @@ use std::{
 
 use serde::Deserialize;
 
-use crate::{Result, error::Error};
+use crate::{Result, config, error::Error};
 
 use super::{
    manifest::{SnapshotManifest, SnapshotTombstoneRef},
@@ -50,6 +50,11 @@ impl SnapshotView {
       self.tombstones.contains(path_key)
    }
 
+   /// Distinct tombstoned path keys across the snapshot chain.
+   pub fn tombstone_count(&self) -> usize {
+      self.tombstones.len()
+   }
+
    pub fn is_visible(&self, path_key: &str, segment_table: Option<&str>) -> bool {
       if !self.tombstones.contains(path_key) {
          return true;
@@ -65,6 +70,15 @@ impl SnapshotView {
    pub fn segment_tables(&self) -> &[String] {
       &self.segment_tables
    }
+
+   /// Whether search returns anchor chunks. Fast segments hold only anchors,
+   /// so any fast segment keeps them; unrecorded snapshots follow config.
+   pub fn include_anchors(&self) -> bool {
+      self
+         .manifest
+         .has_fast_segments()
+         .unwrap_or(config::get().fast_mode)
+   }
 }
 
 #[derive(Debug, Deserialize)]
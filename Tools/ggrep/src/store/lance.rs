@@ -1,38 +1,50 @@
 //! LanceDB-backed vector storage with Arrow integration.
 
 use std::{
-   collections::{HashMap, HashSet, hash_map::Entry},
+   collections::{BTreeMap, HashMap, HashSet, hash_map::Entry},
    fs,
    path::{Path, PathBuf},
    sync::Arc,
+   time::Instant,
 };
 
 use arrow_array::{
-   Array, FixedSizeListArray, Float32Array, Float64Array, LargeBinaryArray, LargeStringArray,
-   RecordBatch, RecordBatchReader, StringArray, UInt32Array,
+   Array, BinaryArray, FixedSizeListArray, Float32Array, Float64Array, LargeBinaryArray,
+   LargeStringArray, RecordBatch, RecordBatchReader, StringArray, UInt32Array,
+   array::new_null_array,
    builder::{
       BinaryBuilder, Float32Builder, Float64Builder, LargeBinaryBuilder, LargeStringBuilder,
       StringBuilder, UInt32Builder,
    },
 };
 use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
 use lancedb::{
    Connection, Table, connect,
    index::{Index, scalar::FullTextSearchQuery},
-   query::{ExecutableQuery, QueryBase},
+   query::{ExecutableQuery, QueryBase, Select},
 };
 use parking_lot::RwLock;
 
 use crate::{
-   config,
+   Str, config,
+   embed::HybridEmbedding,
    error::Result,
+   grammar::UNKNOWN_LANGUAGE,
    search::colbert::max_sim_quantized,
+   snapshot::SnapshotView,
    store,
-   types::{ChunkType, SearchResponse, SearchResult, SearchStatus, VectorRecord},
+   types::{
+      CandidateCounts, ChunkType, RerankStats, ScoreRange, SearchResponse, SearchResult,
+      SearchStatus, SegmentCandidates, SourceCandidateCounts, VectorRecord, dedup_results_by_chunk,
+   },
    util::probe_store_path,
 };
 
+/// Vector rows below which `create_vector_index` skips the IVF-PQ index; a
+/// brute-force scan is fast enough at that size.
+pub const VECTOR_INDEX_MIN_ROWS: u64 = 1000;
+
 /// Errors that can occur during `LanceDB` operations.
 #[derive(Debug, thiserror::Error)]
 pub enum StoreError {
@@ -69,6 +81,9 @@ pub enum StoreError {
    #[error("failed to add migrated records: {0}")]
    AddMigratedRecords(#[source] lancedb::Error),
 
+   #[error("table {table} lacks required column {column}; re-index with `ggrep index --reset`")]
+   MissingRequiredColumn { table: String, column: String },
+
    #[error("failed to create empty batch: {0}")]
    CreateEmptyBatch(#[source] ArrowError),
 
@@ -146,6 +161,9 @@ pub enum StoreError {
 
    #[error("failed to create vector index: {0}")]
    CreateVectorIndex(#[source] lancedb::Error),
+
+   #[error("failed to list indices: {0}")]
+   ListIndices(#[source] lancedb::Error),
 }
 
 /// Single-use [`RecordBatch`] iterator for `LanceDB` table creation.
@@ -264,12 +282,31 @@ impl LanceStore {
       Ok(table)
    }
 
+   /// Segments are immutable once published (the manifest pins their hash),
+   /// so tables written before a nullable column such as `language` or
+   /// `symbol` existed are left as they are: reads treat the column as null
+   /// and `conform_to_schema` pads rows copied out of them. Only a missing
+   /// required column makes the table unusable.
    async fn check_and_migrate_table(
       conn: &Connection,
       table_name: &str,
       table: &Table,
    ) -> Result<()> {
-      let _ = (conn, table_name, table);
+      let _ = conn;
+      let existing = table
+         .schema()
+         .await
+         .map_err(StoreError::SampleTableForMigration)?;
+      let missing = Self::create_schema()
+         .fields()
+         .iter()
+         .find(|field| !field.is_nullable() && existing.field_with_name(field.name()).is_err())
+         .map(|field| field.name().clone());
+      if let Some(column) = missing {
+         return Err(
+            StoreError::MissingRequiredColumn { table: table_name.to_string(), column }.into(),
+         );
+      }
       Ok(())
    }
 
@@ -298,8 +335,10 @@ impl LanceStore {
          Field::new("colbert", DataType::LargeBinary, true),
          Field::new("colbert_scale", DataType::Float64, true),
          Field::new("chunk_type", DataType::Utf8, true),
+         Field::new("symbol", DataType::Utf8, true),
          Field::new("context_prev", DataType::Utf8, true),
          Field::new("context_next", DataType::Utf8, true),
+         Field::new("language", DataType::Utf8, true),
       ]))
    }
 
@@ -328,8 +367,10 @@ impl LanceStore {
       let colbert_array = LargeBinaryBuilder::new().finish();
       let colbert_scale_array = Float64Builder::new().finish();
       let chunk_type_array = StringBuilder::new().finish();
+      let symbol_array = StringBuilder::new().finish();
       let context_prev_array = StringBuilder::new().finish();
       let context_next_array = StringBuilder::new().finish();
+      let language_array = StringBuilder::new().finish();
 
       Ok(RecordBatch::try_new(schema.clone(), vec![
          Arc::new(row_id_array),
@@ -348,8 +389,10 @@ impl LanceStore {
          Arc::new(colbert_array),
          Arc::new(colbert_scale_array),
          Arc::new(chunk_type_array),
+         Arc::new(symbol_array),
          Arc::new(context_prev_array),
          Arc::new(context_next_array),
+         Arc::new(language_array),
       ])
       .map_err(StoreError::CreateEmptyBatch)?)
    }
@@ -379,8 +422,10 @@ impl LanceStore {
       let mut colbert_builder = LargeBinaryBuilder::new();
       let mut colbert_scale_builder = Float64Builder::new();
       let mut chunk_type_builder = StringBuilder::new();
+      let mut symbol_builder = StringBuilder::new();
       let mut context_prev_builder = StringBuilder::new();
       let mut context_next_builder = StringBuilder::new();
+      let mut language_builder = StringBuilder::new();
 
       let dim = cfg.dense_dim;
       for record in records {
@@ -414,6 +459,8 @@ impl LanceStore {
             chunk_type_builder.append_null();
          }
 
+         symbol_builder.append_option(record.symbol.as_deref());
+
          if let Some(prev) = &record.context_prev {
             context_prev_builder.append_value(prev);
          } else {
@@ -425,6 +472,8 @@ impl LanceStore {
          } else {
             context_next_builder.append_null();
          }
+
+         language_builder.append_option(record.language.as_deref());
       }
 
       let row_id_array = row_id_builder.finish();
@@ -451,8 +500,10 @@ impl LanceStore {
       let colbert_array = colbert_builder.finish();
       let colbert_scale_array = colbert_scale_builder.finish();
       let chunk_type_array = chunk_type_builder.finish();
+      let symbol_array = symbol_builder.finish();
       let context_prev_array = context_prev_builder.finish();
       let context_next_array = context_next_builder.finish();
+      let language_array = language_builder.finish();
 
       Ok(RecordBatch::try_new(schema, vec![
          Arc::new(row_id_array),
@@ -471,12 +522,106 @@ impl LanceStore {
          Arc::new(colbert_array),
          Arc::new(colbert_scale_array),
          Arc::new(chunk_type_array),
+         Arc::new(symbol_array),
          Arc::new(context_prev_array),
          Arc::new(context_next_array),
+         Arc::new(language_array),
       ])
       .map_err(StoreError::CreateRecordBatch)?)
    }
 
+   /// Pads batches read from segments written before a column existed with
+   /// nulls, so they can be appended to tables using the current schema.
+   fn conform_to_schema(batch: RecordBatch) -> Result<RecordBatch> {
+      let schema = Self::create_schema();
+      if batch.schema().fields().len() == schema.fields().len() {
+         return Ok(batch);
+      }
+      let num_rows = batch.num_rows();
+      let columns = schema
+         .fields()
+         .iter()
+         .map(|field| {
+            batch
+               .column_by_name(field.name())
+               .cloned()
+               .unwrap_or_else(|| new_null_array(field.data_type(), num_rows))
+         })
+         .collect();
+      Ok(RecordBatch::try_new(schema, columns).map_err(StoreError::CreateRecordBatch)?)
+   }
+
+   /// Reads the stored chunk text of every row in `batch`, as it was embedded.
+   pub(crate) fn batch_texts(batch: &RecordBatch) -> Result<Vec<Str>> {
+      let column = batch
+         .column_by_name("text")
+         .ok_or(StoreError::ContentColumnTypeMismatch)?;
+      let texts = if let Some(str_array) = column.as_any().downcast_ref::<StringArray>() {
+         str_array
+            .iter()
+            .map(|text| Str::copy_from_str(text.unwrap_or_default()))
+            .collect()
+      } else if let Some(large_str_array) = column.as_any().downcast_ref::<LargeStringArray>() {
+         large_str_array
+            .iter()
+            .map(|text| Str::copy_from_str(text.unwrap_or_default()))
+            .collect()
+      } else {
+         return Err(StoreError::ContentColumnTypeMismatch.into());
+      };
+      Ok(texts)
+   }
+
+   /// Rebuilds `batch` in the current schema with `embeddings` (one per row)
+   /// in place of its `embedding`, `colbert` and `colbert_scale` columns.
+   /// Every other column is kept as stored.
+   pub(crate) fn replace_embeddings(
+      batch: &RecordBatch,
+      embeddings: Vec<HybridEmbedding>,
+   ) -> Result<RecordBatch> {
+      if embeddings.len() != batch.num_rows() {
+         return Err(StoreError::VectorColumnTypeMismatch.into());
+      }
+
+      let dim = config::get().dense_dim;
+      let mut vector_builder = Float32Builder::new();
+      let mut colbert_builder = LargeBinaryBuilder::new();
+      let mut colbert_scale_builder = Float64Builder::new();
+      for embedding in embeddings {
+         if embedding.dense.len() != dim {
+            return Err(StoreError::VectorColumnTypeMismatch.into());
+         }
+         vector_builder.append_slice(&embedding.dense);
+         colbert_builder.append_value(&embedding.colbert);
+         colbert_scale_builder.append_value(embedding.colbert_scale);
+      }
+      let vector_array: Arc<dyn Array> = Arc::new(FixedSizeListArray::new(
+         Arc::new(Field::new("item", DataType::Float32, true)),
+         dim as i32,
+         Arc::new(vector_builder.finish()),
+         None,
+      ));
+      let colbert_array: Arc<dyn Array> = Arc::new(colbert_builder.finish());
+      let colbert_scale_array: Arc<dyn Array> = Arc::new(colbert_scale_builder.finish());
+
+      let schema = Self::create_schema();
+      let num_rows = batch.num_rows();
+      let columns = schema
+         .fields()
+         .iter()
+         .map(|field| match field.name().as_str() {
+            "embedding" => Arc::clone(&vector_array),
+            "colbert" => Arc::clone(&colbert_array),
+            "colbert_scale" => Arc::clone(&colbert_scale_array),
+            name => batch
+               .column_by_name(name)
+               .cloned()
+               .unwrap_or_else(|| new_null_array(field.data_type(), num_rows)),
+         })
+         .collect();
+      Ok(RecordBatch::try_new(schema, columns).map_err(StoreError::CreateRecordBatch)?)
+   }
+
    fn parse_chunk_type(s: &str) -> ChunkType {
       match s {
          "function" => ChunkType::Function,
@@ -488,16 +633,6 @@ impl LanceStore {
          _ => ChunkType::Other,
       }
    }
-
-   fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-      debug_assert_eq!(a.len(), b.len(), "cosine_similarity requires equal-length vectors");
-      let len = a.len().min(b.len());
-      let mut dot = 0.0;
-      for i in 0..len {
-         dot += a[i] * b[i];
-      }
-      dot
-   }
 }
 
 impl Default for LanceStore {
@@ -538,6 +673,7 @@ impl LanceStore {
       if batch.num_rows() == 0 {
          return Ok(());
       }
+      let batch = Self::conform_to_schema(batch)?;
       let table = self.get_table(store_id, table_name).await?;
       table
          .add(RecordBatchOnce::new(batch))
@@ -566,30 +702,76 @@ impl LanceStore {
       Ok(())
    }
 
+   /// How many segment tables `search_segments` searches at once.
+   pub fn segment_search_concurrency() -> usize {
+      config::get().effective_max_open_segments_per_query()
+   }
+
    pub async fn search_segments(&self, params: store::SearchParams<'_>) -> Result<SearchResponse> {
       if params.tables.is_empty() {
          return Ok(SearchResponse {
-            results:    vec![],
-            status:     SearchStatus::Ready,
-            progress:   None,
-            timings_ms: None,
-            limits_hit: vec![],
-            warnings:   vec![],
+            results:     vec![],
+            status:      SearchStatus::Ready,
+            progress:    None,
+            timings_ms:  None,
+            limits_hit:  vec![],
+            warnings:    vec![],
+            embedder:    None,
+            candidates:  None,
+            segments:    vec![],
+            rerank:      None,
+            snapshot_id: None,
+            expansions:  vec![],
+            funnel:      vec![],
          });
       }
 
       let mut combined = SearchResponse {
-         results:    Vec::new(),
-         status:     SearchStatus::Ready,
-         progress:   None,
-         timings_ms: None,
-         limits_hit: Vec::new(),
-         warnings:   Vec::new(),
+         results:     Vec::new(),
+         status:      SearchStatus::Ready,
+         progress:    None,
+         timings_ms:  None,
+         limits_hit:  Vec::new(),
+         warnings:    Vec::new(),
+         embedder:    None,
+         candidates:  None,
+         segments:    Vec::new(),
+         rerank:      None,
+         snapshot_id: None,
+         expansions:  Vec::new(),
+         funnel:      Vec::new(),
       };
 
-      for table_name in params.tables {
-         let table = match self.get_table(params.store_id, table_name).await {
-            Ok(table) => table,
+      let params = &params;
+      let searches = params
+         .tables
+         .iter()
+         .enumerate()
+         .map(|(idx, table_name)| async move {
+            let table = match self.get_table(params.store_id, table_name).await {
+               Ok(table) => table,
+               Err(e) => return Ok((idx, Err(e))),
+            };
+            let response = self.search_table(&table, params, table_name).await?;
+            let vector_indexed = if params.index_stats {
+               has_vector_index(&table).await.ok()
+            } else {
+               None
+            };
+            Ok::<_, crate::error::Error>((idx, Ok((response, vector_indexed))))
+         });
+      let mut outcomes: Vec<_> = futures::stream::iter(searches)
+         .buffer_unordered(Self::segment_search_concurrency())
+         .try_collect()
+         .await?;
+      // Merge in snapshot order so the response does not depend on which
+      // table finished first.
+      outcomes.sort_by_key(|(idx, _)| *idx);
+
+      for (idx, outcome) in outcomes {
+         let table_name = &params.tables[idx];
+         let (response, vector_indexed) = match outcome {
+            Ok(outcome) => outcome,
             Err(e) => {
                combined.warnings.push(crate::types::SearchWarning {
                   code:     "segment_open_failed".to_string(),
@@ -599,12 +781,31 @@ impl LanceStore {
                continue;
             },
          };
-         let response = self.search_table(&table, &params, table_name).await?;
+         combined.segments.push(SegmentCandidates {
+            table: table_name.clone(),
+            candidates: response.results.len() as u64,
+            vector_indexed,
+         });
          combined.results.extend(response.results);
          combined.limits_hit.extend(response.limits_hit);
          combined.warnings.extend(response.warnings);
+         if let Some(counts) = response.candidates {
+            combined
+               .candidates
+               .get_or_insert_with(CandidateCounts::default)
+               .merge(&counts);
+         }
+         if let Some(stats) = response.rerank {
+            combined
+               .rerank
+               .get_or_insert_with(RerankStats::default)
+               .merge(&stats);
+         }
       }
 
+      // Each table dedups its own hits; chunks shared by overlapping
+      // segments are only collapsed here.
+      dedup_results_by_chunk(&mut combined.results);
       Ok(combined)
    }
 
@@ -614,31 +815,22 @@ impl LanceStore {
       params: &store::SearchParams<'_>,
       table_name: &str,
    ) -> Result<SearchResponse> {
-
-      let anchor_filter = if params.include_anchors {
-         "1 = 1"
+      let language = if params.languages.is_empty() {
+         None
       } else {
-         "(kind IS NULL OR kind != 'anchor')"
+         let has_language = table
+            .schema()
+            .await
+            .map_err(StoreError::ExecuteQuery)?
+            .field_with_name("language")
+            .is_ok();
+         params.languages.clause(has_language)
       };
-      let graph_clause = "(path_key LIKE '%.mmd' OR path_key LIKE '%.mermaid')";
-      let doc_clause =
-         "(path_key LIKE '%.md' OR path_key LIKE '%.mdx' OR path_key LIKE '%.txt' OR path_key LIKE \
-          '%.json' OR path_key LIKE '%.yaml' OR path_key LIKE '%.yml' OR path_key LIKE '%.toml')";
-      let non_code_clause = format!("({doc_clause} OR {graph_clause})");
-      let code_clause = format!("NOT {non_code_clause}");
-
-      let mut code_filter = format!("{code_clause} AND {anchor_filter}");
-      let mut doc_filter = format!("{doc_clause} AND {anchor_filter}");
-      let mut graph_filter = format!("{graph_clause} AND {anchor_filter}");
-      let base_filter = if let Some(filter) = params.path_filter {
-         let filter_str = store::escape_path_for_like(filter);
-         let path_clause = format!("path_key LIKE '{filter_str}%'");
-         code_filter = format!("{path_clause} AND {code_clause} AND {anchor_filter}");
-         doc_filter = format!("{path_clause} AND {doc_clause} AND {anchor_filter}");
-         graph_filter = format!("{path_clause} AND {graph_clause} AND {anchor_filter}");
-         Some(format!("{path_clause} AND {anchor_filter}"))
+      let language = language.as_deref();
+      let filters = if params.anchors_only {
+         store::SearchFilters::anchors_only(params.path_filter, language)
       } else {
-         Some(anchor_filter.to_owned())
+         store::SearchFilters::new(params.path_filter, language, params.include_anchors)
       };
 
       let (code_batches, doc_batches, graph_batches): (
@@ -652,7 +844,7 @@ impl LanceStore {
                .nearest_to(params.query_vector)
                .map_err(StoreError::CreateVectorQuery)?
                .limit(params.limit)
-               .only_if(&code_filter)
+               .only_if(&filters.code)
                .execute()
                .await
                .map_err(StoreError::ExecuteCodeSearch)?;
@@ -666,7 +858,7 @@ impl LanceStore {
                .query()
                .nearest_to(params.query_vector)
                .map_err(StoreError::CreateVectorQuery)?
-               .only_if(&doc_filter)
+               .only_if(&filters.doc)
                .limit(params.limit)
                .execute()
                .await
@@ -681,7 +873,7 @@ impl LanceStore {
                .query()
                .nearest_to(params.query_vector)
                .map_err(StoreError::CreateVectorQuery)?
-               .only_if(&graph_filter)
+               .only_if(&filters.graph)
                .limit(params.limit)
                .execute()
                .await
@@ -693,31 +885,54 @@ impl LanceStore {
          },
       )?;
 
-      let fts_query = FullTextSearchQuery::new(params.query_text.to_owned());
-      let mut fts_query_builder = table.query().full_text_search(fts_query);
-
-      if let Some(ref filter) = base_filter {
-         fts_query_builder = fts_query_builder.only_if(filter);
-      }
-
+      let mut fts_warnings = Vec::new();
+      let fts_text = store::sanitize_fts_query(params.query_text);
       let fts_batches: Vec<RecordBatch> =
-         match fts_query_builder.limit(params.limit).execute().await {
-            Ok(stream) => stream.try_collect().await.unwrap_or_default(),
-            Err(_) => vec![],
+         if params.lexical && config::get().enable_fts && !fts_text.is_empty() {
+            let fts_query = FullTextSearchQuery::new(fts_text);
+            let fts_query_builder = table
+               .query()
+               .full_text_search(fts_query)
+               .only_if(&filters.fts)
+               .limit(params.limit);
+            let fts: std::result::Result<Vec<RecordBatch>, lancedb::Error> =
+               async { fts_query_builder.execute().await?.try_collect().await }.await;
+            // Vector hits still stand on their own; surface the lexical miss
+            // instead of dropping it silently.
+            fts.unwrap_or_else(|e| {
+               fts_warnings.push(crate::types::SearchWarning {
+                  code:     "fts_query_failed".to_string(),
+                  message:  format!("full-text search failed on segment {table_name}: {e}"),
+                  path_key: None,
+               });
+               vec![]
+            })
+         } else {
+            vec![]
          };
 
+      // FTS batches come last, so an empty FTS set leaves the source ranges
+      // of the vector batches unchanged.
       let all_batches: Vec<&RecordBatch> = code_batches
          .iter()
          .chain(doc_batches.iter())
          .chain(graph_batches.iter())
          .chain(fts_batches.iter())
          .collect();
+      let vector_end = code_batches.len();
+      let doc_end = vector_end + doc_batches.len();
+      let graph_end = doc_end + graph_batches.len();
+      let source_ends = [vector_end, doc_end, graph_end];
 
       let estimated_capacity = all_batches.iter().map(|b| b.num_rows()).sum();
       let mut candidates: Vec<(usize, usize)> = Vec::with_capacity(estimated_capacity);
       let mut seen_keys: HashSet<(&str, u32)> = HashSet::with_capacity(estimated_capacity);
+      let mut counts = CandidateCounts::default();
 
       for (batch_idx, batch) in all_batches.iter().enumerate() {
+         let source = source_counts(&mut counts, batch_idx, source_ends);
+         source.raw += batch.num_rows() as u64;
+
          let path_col = batch
             .column_by_name("path_key")
             .ok_or(StoreError::MissingPathColumn)?
@@ -744,11 +959,13 @@ impl LanceStore {
                continue;
             }
 
+            source.deduped += 1;
             candidates.push((batch_idx, i));
          }
       }
 
       let mut scored_results = Vec::with_capacity(candidates.len());
+      let dense_metric = config::get().dense_metric;
 
       for (cand_idx, (batch_idx, row_idx)) in candidates.iter().enumerate() {
          let batch = all_batches[*batch_idx];
@@ -806,6 +1023,26 @@ impl LanceStore {
             }
          });
 
+         let symbol = batch.column_by_name("symbol").and_then(|col| {
+            if col.is_null(*row_idx) {
+               None
+            } else {
+               col.as_any()
+                  .downcast_ref::<StringArray>()
+                  .map(|arr| Str::copy_from_str(arr.value(*row_idx)))
+            }
+         });
+
+         let language = batch.column_by_name("language").and_then(|col| {
+            if col.is_null(*row_idx) {
+               None
+            } else {
+               col.as_any()
+                  .downcast_ref::<StringArray>()
+                  .map(|arr| arr.value(*row_idx).to_string())
+            }
+         });
+
          let is_anchor = batch.column_by_name("kind").and_then(|col| {
             if col.is_null(*row_idx) {
                None
@@ -833,10 +1070,13 @@ impl LanceStore {
          let values = vector_floats.values();
          let doc_vector = &values[offset..offset + len];
 
-         let score = Self::cosine_similarity(params.query_vector, doc_vector);
+         let score = store::dense_similarity(params.query_vector, doc_vector, dense_metric);
+         let source = source_counts(&mut counts, *batch_idx, source_ends);
+         ScoreRange::include(&mut source.scores, ScoreRange::of(score));
 
          let mut full_content = String::new();
          let mut context_prev_lines = 0u32;
+         let mut context_next_lines = 0u32;
 
          if let Some(prev_col) = batch.column_by_name("context_prev")
             && !prev_col.is_null(*row_idx)
@@ -851,7 +1091,9 @@ impl LanceStore {
             && !next_col.is_null(*row_idx)
             && let Some(next_str) = next_col.as_any().downcast_ref::<StringArray>()
          {
-            full_content.push_str(next_str.value(*row_idx));
+            let next_content = next_str.value(*row_idx);
+            context_next_lines = next_content.lines().count() as u32;
+            full_content.push_str(next_content);
          }
 
          let adjusted_start_line = start_line.saturating_sub(context_prev_lines);
@@ -865,16 +1107,28 @@ impl LanceStore {
             segment_table: Some(table_name.to_string()),
             start_line: adjusted_start_line,
             num_lines: end_line.saturating_sub(start_line).max(1),
+            end_line: None,
             chunk_type,
+            symbol,
             is_anchor,
+            language,
+            context_before: context_prev_lines,
+            context_after: context_next_lines,
          }));
       }
 
       scored_results.sort_by(|a, b| crate::types::cmp_results_deterministic(&a.1, &b.1));
 
+      let mut rerank = RerankStats {
+         requested: params.rerank,
+         query_colbert: !params.query_colbert.is_empty(),
+         ..RerankStats::default()
+      };
       if params.rerank && !params.query_colbert.is_empty() {
          const RERANK_CAP: usize = 50;
+         let rerank_start = Instant::now();
          let rerank_count = scored_results.len().min(RERANK_CAP);
+         rerank.eligible = rerank_count as u64;
 
          for (cand_idx, result) in scored_results.iter_mut().take(rerank_count) {
             let (batch_idx, row_idx) = candidates[*cand_idx];
@@ -911,11 +1165,14 @@ impl LanceStore {
                      scale,
                      config::get().colbert_dim,
                   );
+                  rerank.scored += 1;
+                  ScoreRange::include(&mut rerank.scores, ScoreRange::of(result.score));
                }
             }
          }
 
          scored_results.sort_by(|a, b| crate::types::cmp_results_deterministic(&a.1, &b.1));
+         rerank.elapsed_us = rerank_start.elapsed().as_micros() as u64;
       }
 
       let mut scored_results: Vec<SearchResult> =
@@ -923,12 +1180,19 @@ impl LanceStore {
       scored_results.truncate(params.limit);
 
       Ok(SearchResponse {
-         results:    scored_results,
-         status:     SearchStatus::Ready,
-         progress:   None,
-         timings_ms: None,
-         limits_hit: vec![],
-         warnings:   vec![],
+         results:     scored_results,
+         status:      SearchStatus::Ready,
+         progress:    None,
+         timings_ms:  None,
+         limits_hit:  vec![],
+         warnings:    fts_warnings,
+         embedder:    None,
+         candidates:  Some(counts),
+         segments:    vec![],
+         rerank:      Some(rerank),
+         snapshot_id: None,
+         expansions:  vec![],
+         funnel:      vec![],
       })
    }
 
@@ -946,7 +1210,12 @@ impl LanceStore {
       Ok(tables.is_empty())
    }
 
+   /// Builds the full-text index for a segment; a no-op when `enable_fts` is
+   /// off.
    pub async fn create_fts_index(&self, store_id: &str, table_name: &str) -> Result<()> {
+      if !config::get().enable_fts {
+         return Ok(());
+      }
       let table = self.get_table(store_id, table_name).await?;
 
       table
@@ -971,7 +1240,7 @@ impl LanceStore {
          .await
          .map_err(StoreError::CountRows)?;
 
-      if vector_rows < 1000 {
+      if vector_rows < VECTOR_INDEX_MIN_ROWS as usize {
          return Ok(());
       }
 
@@ -1008,7 +1277,160 @@ impl LanceStore {
       Ok(store::SegmentMetadata { rows: row_count, size_bytes, sha256 })
    }
 
+   /// Counts rows visible in `snapshot` per stored language. Rows written
+   /// before the `language` column existed are counted as `unknown`.
+   pub async fn language_counts(
+      &self,
+      store_id: &str,
+      snapshot: &SnapshotView,
+   ) -> Result<BTreeMap<String, u64>> {
+      let mut counts = BTreeMap::new();
+      for table_name in snapshot.segment_tables() {
+         let table = self.get_table(store_id, table_name).await?;
+         let has_language = table
+            .schema()
+            .await
+            .map_err(StoreError::ExecuteQuery)?
+            .field_with_name("language")
+            .is_ok();
+         let columns: &[&str] = if has_language {
+            &["path_key", "language"]
+         } else {
+            &["path_key"]
+         };
+         let batches: Vec<RecordBatch> = table
+            .query()
+            .select(Select::columns(columns))
+            .execute()
+            .await
+            .map_err(StoreError::ExecuteQuery)?
+            .try_collect()
+            .await
+            .map_err(StoreError::CollectResults)?;
+
+         for batch in &batches {
+            let path_col = batch
+               .column_by_name("path_key")
+               .ok_or(StoreError::MissingPathColumn)?
+               .as_any()
+               .downcast_ref::<StringArray>()
+               .ok_or(StoreError::PathColumnTypeMismatch)?;
+            let language_col = batch
+               .column_by_name("language")
+               .and_then(|col| col.as_any().downcast_ref::<StringArray>());
+
+            for i in 0..batch.num_rows() {
+               if path_col.is_null(i) || !snapshot.is_visible(path_col.value(i), Some(table_name)) {
+                  continue;
+               }
+               let language = language_col
+                  .filter(|col| !col.is_null(i))
+                  .map_or(UNKNOWN_LANGUAGE, |col| col.value(i));
+               *counts.entry(language.to_string()).or_insert(0) += 1;
+            }
+         }
+      }
+      Ok(counts)
+   }
+
+   /// Returns the `file_hash` stored for each path in `table_name`. Used to
+   /// decide which rows of an interrupted sync can be kept.
+   pub async fn segment_file_hashes(
+      &self,
+      store_id: &str,
+      table_name: &str,
+   ) -> Result<HashMap<String, Vec<u8>>> {
+      let table = self.get_table(store_id, table_name).await?;
+      let batches: Vec<RecordBatch> = table
+         .query()
+         .select(Select::columns(&["path_key", "file_hash"]))
+         .execute()
+         .await
+         .map_err(StoreError::ExecuteQuery)?
+         .try_collect()
+         .await
+         .map_err(StoreError::CollectResults)?;
+
+      let mut hashes = HashMap::new();
+      for batch in &batches {
+         let path_col = batch
+            .column_by_name("path_key")
+            .ok_or(StoreError::MissingPathColumn)?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or(StoreError::PathColumnTypeMismatch)?;
+         let Some(hash_col) = batch
+            .column_by_name("file_hash")
+            .and_then(|col| col.as_any().downcast_ref::<BinaryArray>())
+         else {
+            continue;
+         };
+         for i in 0..batch.num_rows() {
+            if path_col.is_null(i) || hash_col.is_null(i) {
+               continue;
+            }
+            hashes
+               .entry(path_col.value(i).to_string())
+               .or_insert_with(|| hash_col.value(i).to_vec());
+         }
+      }
+      Ok(hashes)
+   }
+
+   /// Deletes every row belonging to `path_keys` from `table_name`.
+   pub async fn delete_paths(
+      &self,
+      store_id: &str,
+      table_name: &str,
+      path_keys: &[String],
+   ) -> Result<()> {
+      if path_keys.is_empty() {
+         return Ok(());
+      }
+      let table = self.get_table(store_id, table_name).await?;
+      let list = path_keys
+         .iter()
+         .map(|key| format!("'{}'", key.replace('\'', "''")))
+         .collect::<Vec<_>>()
+         .join(", ");
+      table
+         .delete(&format!("path_key IN ({list})"))
+         .await
+         .map_err(StoreError::DeleteFiles)?;
+      Ok(())
+   }
+
    pub fn store_path(&self, store_id: &str) -> PathBuf {
       self.data_dir.join(store_id)
    }
 }
+
+/// Whether `table` has an index on `embedding`. Without one, dense retrieval
+/// scans every row of the table.
+async fn has_vector_index(table: &Table) -> Result<bool> {
+   let indices = table
+      .list_indices()
+      .await
+      .map_err(StoreError::ListIndices)?;
+   Ok(indices
+      .iter()
+      .any(|index| index.columns.iter().any(|column| column == "embedding")))
+}
+
+/// Counts for the retrieval source of `all_batches[batch_idx]`, given where
+/// the code, doc and graph batches end; the FTS batches come last.
+fn source_counts(
+   counts: &mut CandidateCounts,
+   batch_idx: usize,
+   [vector_end, doc_end, graph_end]: [usize; 3],
+) -> &mut SourceCandidateCounts {
+   if batch_idx < vector_end {
+      &mut counts.vector
+   } else if batch_idx < doc_end {
+      &mut counts.doc
+   } else if batch_idx < graph_end {
+      &mut counts.graph
+   } else {
+      &mut counts.fts
+   }
+}
@@ -5,6 +5,9 @@ pub(crate) mod lance;
 use std::path::Path;
 
 use ndarray::Array2;
+use serde::Serialize;
+
+use crate::config::DenseMetric;
 
 
 
@@ -35,6 +38,120 @@ pub fn escape_path_for_like(path: &Path) -> String {
       .replace('\'', "''")
 }
 
+/// `--lang` languages resolved for retrieval.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageFilter {
+   /// Language names as chunks record them at index time (after
+   /// `language_overrides`, content and shebang detection).
+   pub languages:  Vec<String>,
+   /// Extensions (no dot) mapping to `languages`, for segments written before
+   /// the `language` column existed.
+   pub extensions: Vec<String>,
+}
+
+impl LanguageFilter {
+   pub const fn is_empty(&self) -> bool {
+      self.languages.is_empty()
+   }
+
+   /// The predicate for a segment; `has_language_column` is false for
+   /// segments that predate the column, which are matched by extension.
+   pub fn clause(&self, has_language_column: bool) -> Option<String> {
+      if self.is_empty() {
+         return None;
+      }
+      if has_language_column {
+         let names = self
+            .languages
+            .iter()
+            .map(|language| format!("'{}'", language.replace('\'', "''")))
+            .collect::<Vec<_>>()
+            .join(", ");
+         return Some(format!("language IN ({names})"));
+      }
+      let ext_clause = self
+         .extensions
+         .iter()
+         .map(|ext| format!("path_key LIKE '%.{}'", escape_path_for_like(Path::new(ext))))
+         .collect::<Vec<_>>()
+         .join(" OR ");
+      Some(if ext_clause.is_empty() {
+         "1 = 0".to_string()
+      } else {
+         format!("({ext_clause})")
+      })
+   }
+}
+
+/// SQL predicates a segment search applies to each retrieval source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchFilters {
+   pub code:  String,
+   pub doc:   String,
+   pub graph: String,
+   /// Applied to full-text search; only scope and anchor clauses.
+   pub fts:   String,
+}
+
+impl SearchFilters {
+   pub fn new(path_filter: Option<&Path>, language: Option<&str>, include_anchors: bool) -> Self {
+      let anchor_filter = if include_anchors {
+         "1 = 1"
+      } else {
+         "(kind IS NULL OR kind != 'anchor')"
+      };
+      Self::with_anchor_filter(path_filter, language, anchor_filter)
+   }
+
+   /// Like [`Self::new`], but every source keeps anchor chunks only.
+   pub fn anchors_only(path_filter: Option<&Path>, language: Option<&str>) -> Self {
+      Self::with_anchor_filter(path_filter, language, "kind = 'anchor'")
+   }
+
+   /// `language` is a [`LanguageFilter::clause`].
+   fn with_anchor_filter(
+      path_filter: Option<&Path>,
+      language: Option<&str>,
+      anchor_filter: &str,
+   ) -> Self {
+      let graph_clause = "(path_key LIKE '%.mmd' OR path_key LIKE '%.mermaid')";
+      let doc_clause = "(path_key LIKE '%.md' OR path_key LIKE '%.mdx' OR path_key LIKE '%.txt' \
+                        OR path_key LIKE '%.json' OR path_key LIKE '%.yaml' OR path_key LIKE \
+                        '%.yml' OR path_key LIKE '%.toml')";
+      let code_clause = format!("NOT ({doc_clause} OR {graph_clause})");
+
+      let mut scope_clauses = Vec::new();
+      if let Some(filter) = path_filter {
+         scope_clauses.push(scope_clause(filter));
+      }
+      if let Some(language) = language {
+         scope_clauses.push(language.to_string());
+      }
+      let scope = if scope_clauses.is_empty() {
+         String::new()
+      } else {
+         format!("{} AND ", scope_clauses.join(" AND "))
+      };
+
+      Self {
+         code:  format!("{scope}{code_clause} AND {anchor_filter}"),
+         doc:   format!("{scope}{doc_clause} AND {anchor_filter}"),
+         graph: format!("{scope}{graph_clause} AND {anchor_filter}"),
+         fts:   format!("{scope}{anchor_filter}"),
+      }
+   }
+}
+
+/// Matches `scope` itself (a file) or anything below it (a directory), but
+/// not siblings sharing a name prefix: `src/f` excludes `src/foobar/`.
+fn scope_clause(scope: &Path) -> String {
+   let exact = escape_path_literal(scope);
+   let exact = exact.trim_end_matches('/');
+   let prefix = escape_path_for_like(scope);
+   let prefix = prefix.trim_end_matches('/');
+   format!("(path_key = '{exact}' OR path_key LIKE '{prefix}/%')")
+}
+
 /// Parameters for vector search queries.
 pub struct SearchParams<'a> {
    pub store_id:        &'a str,
@@ -44,11 +161,64 @@ pub struct SearchParams<'a> {
    pub query_colbert:   &'a Array2<f32>,
    pub limit:           usize,
    pub path_filter:     Option<&'a Path>,
+   /// Restricts hits to these languages; empty means any
+   pub languages:       &'a LanguageFilter,
    pub rerank:          bool,
    pub include_anchors: bool,
+   /// Search anchor chunks only (`--path-query`)
+   pub anchors_only:    bool,
+   /// Also retrieve full-text matches; `false` for pure vector search
+   /// (`--no-fts`)
+   pub lexical:         bool,
+   /// Fill in `SegmentCandidates::vector_indexed` for each segment
+   pub index_stats:     bool,
+}
+
+pub use lance::{LanceStore, VECTOR_INDEX_MIN_ROWS};
+
+/// Dense score of `a` against `b` under `metric`. Cosine of a zero vector is
+/// 0.0.
+pub(crate) fn dense_similarity(a: &[f32], b: &[f32], metric: DenseMetric) -> f32 {
+   debug_assert_eq!(a.len(), b.len(), "dense_similarity requires equal-length vectors");
+   let mut dot = 0.0;
+   let mut norm_a = 0.0;
+   let mut norm_b = 0.0;
+   for (x, y) in a.iter().zip(b) {
+      dot += x * y;
+      norm_a += x * x;
+      norm_b += y * y;
+   }
+   match metric {
+      DenseMetric::Dot => dot,
+      DenseMetric::Cosine => {
+         let norm = (norm_a * norm_b).sqrt();
+         if norm > 0.0 { dot / norm } else { 0.0 }
+      },
+   }
 }
 
-pub use lance::LanceStore;
+/// Reduces a query to plain terms for the full-text index: punctuation the
+/// query parser treats as syntax (`:`, quotes, brackets, `+`/`-`, ...) becomes
+/// whitespace and bare `AND`/`OR`/`NOT` operators are dropped, so a query like
+/// `foo:bar` or `a AND` still matches lexically.
+pub(crate) fn sanitize_fts_query(query: &str) -> String {
+   let plain: String = query
+      .chars()
+      .map(|c| {
+         if c.is_alphanumeric() || c == '_' || c == '.' {
+            c
+         } else {
+            ' '
+         }
+      })
+      .collect();
+   plain
+      .split_whitespace()
+      .map(|term| term.trim_matches('.'))
+      .filter(|term| !term.is_empty() && !matches!(*term, "AND" | "OR" | "NOT"))
+      .collect::<Vec<_>>()
+      .join(" ")
+}
 
 #[derive(Debug, Clone)]
 pub struct SegmentMetadata {
@@ -73,6 +243,83 @@ mod tests {
       assert_eq!(escape_path_literal(path), "foo_bar%baz''qux");
    }
 
+   #[test]
+   fn search_filters_scope_every_source() {
+      let filters =
+         SearchFilters::new(Some(Path::new("src/my_mod")), Some("language IN ('rust')"), false);
+      let scope = "(path_key = 'src/my_mod' OR path_key LIKE 'src/my\\_mod/%') AND language IN \
+                   ('rust') AND ";
+      for filter in [&filters.code, &filters.doc, &filters.graph, &filters.fts] {
+         assert!(filter.starts_with(scope), "{filter}");
+         assert!(filter.ends_with("(kind IS NULL OR kind != 'anchor')"), "{filter}");
+      }
+
+      let unscoped = SearchFilters::new(None, None, true);
+      assert_eq!(unscoped.fts, "1 = 1");
+      assert!(unscoped.code.starts_with("NOT ("));
+   }
+
+   #[test]
+   fn anchors_only_filters_every_source() {
+      let filters = SearchFilters::anchors_only(None, None);
+      for filter in [&filters.code, &filters.doc, &filters.graph, &filters.fts] {
+         assert!(filter.ends_with("kind = 'anchor'"), "{filter}");
+      }
+   }
+
+   #[test]
+   fn language_filter_matches_the_language_column() {
+      let filter = LanguageFilter {
+         languages:  vec!["make".to_string(), "a'b".to_string()],
+         extensions: vec!["mk".to_string(), "my_ext".to_string()],
+      };
+      assert_eq!(filter.clause(true).as_deref(), Some("language IN ('make', 'a''b')"));
+      // Segments without the column fall back to the extensions.
+      assert_eq!(
+         filter.clause(false).as_deref(),
+         Some("(path_key LIKE '%.mk' OR path_key LIKE '%.my\\_ext')")
+      );
+      let extensionless = LanguageFilter { extensions: vec![], ..filter };
+      assert_eq!(extensionless.clause(false).as_deref(), Some("1 = 0"));
+      assert_eq!(LanguageFilter::default().clause(true), None);
+   }
+
+   #[test]
+   fn scope_clause_stops_at_directory_boundary() {
+      assert_eq!(
+         scope_clause(Path::new("src/f")),
+         "(path_key = 'src/f' OR path_key LIKE 'src/f/%')"
+      );
+      assert_eq!(
+         scope_clause(Path::new("src/f/")),
+         "(path_key = 'src/f' OR path_key LIKE 'src/f/%')"
+      );
+   }
+
+   #[test]
+   fn cosine_similarity_is_scale_invariant() {
+      let a = [3.0, 4.0, 0.0];
+      let doubled = [6.0, 8.0, 0.0];
+      let orthogonal = [0.0, 0.0, 2.0];
+      let cosine = |x: &[f32], y: &[f32]| dense_similarity(x, y, DenseMetric::Cosine);
+      assert!((cosine(&a, &a) - 1.0).abs() < 1e-6);
+      assert!((cosine(&a, &doubled) - 1.0).abs() < 1e-6);
+      assert!(cosine(&a, &orthogonal).abs() < 1e-6);
+      assert_eq!(cosine(&a, &[0.0; 3]), 0.0);
+      assert!((dense_similarity(&a, &a, DenseMetric::Dot) - 25.0).abs() < 1e-6);
+   }
+
+   #[test]
+   fn sanitize_fts_query_strips_query_syntax() {
+      assert_eq!(sanitize_fts_query("foo:bar"), "foo bar");
+      assert_eq!(sanitize_fts_query("a AND"), "a");
+      assert_eq!(
+         sanitize_fts_query("\"config.toml\" (load) +parse -x*"),
+         "config.toml load parse x"
+      );
+      assert_eq!(sanitize_fts_query(":: ()"), "");
+   }
+
    #[test]
    fn escape_path_for_like_escapes_specials() {
       let path = Path::new("foo_bar%baz'qux");
@@ -3,7 +3,7 @@
 #[cfg(test)]
 use std::sync::{Mutex, OnceLock};
 use std::{
-   collections::{HashMap, HashSet},
+   collections::{BTreeMap, HashMap, HashSet},
    fs,
    io::Write,
    path::{Path, PathBuf},
@@ -11,6 +11,7 @@ use std::{
    time::Duration,
 };
 
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use indicatif::ProgressBar;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
@@ -32,17 +33,19 @@ use crate::{
    lease::WriterLease,
    meta::{FileHash, MetaStore},
    snapshot::{
-      SnapshotCounts, SnapshotError, SnapshotGitInfo, SnapshotManifest, SnapshotSegmentRef,
-      SnapshotTombstoneRef, SnapshotManager, compute_tombstone_artifact, read_segment_file_index,
-      segment_table_name, write_segment_file_index,
-      manifest::{CHUNK_ROW_SCHEMA_VERSION, MANIFEST_SCHEMA_VERSION},
+      ChunkingMode, SnapshotCounts, SnapshotError, SnapshotGitInfo, SnapshotManifest,
+      SnapshotSegmentRef, SnapshotTombstoneRef, SnapshotManager, StagingTxn,
+      compute_tombstone_artifact, read_active_snapshot_id_at,
+      read_segment_file_index, segment_table_name, write_segment_file_index,
+      manifest::{CHUNK_ROW_SCHEMA_VERSION, GRAMMAR_MISSING_CODE, MANIFEST_SCHEMA_VERSION},
    },
    store::LanceStore,
    types::{PreparedChunk, VectorRecord},
    util,
 };
 
-const CHUNKER_VERSION: &str = "chunker-v2";
+/// Recorded on every chunk row; bumped when chunk boundaries change.
+pub const CHUNKER_VERSION: &str = "chunker-v2";
 const HEAD_HASH_BYTES: usize = 4096;
 const STABLE_READ_RETRIES: usize = 3;
 const STABLE_READ_BACKOFF_MS: u64 = 25;
@@ -126,8 +129,10 @@ fn prepare_chunk(
    start_line: u32,
    end_line: u32,
    chunk_type: Option<crate::types::ChunkType>,
+   symbol: Option<Str>,
    context_prev: Option<Str>,
    context_next: Option<Str>,
+   language: Option<&str>,
 ) -> PreparedChunk {
    let text = preprocess::prepare_for_embedding(&content, path_key);
    let chunk_hash = build_chunk_hash(&text);
@@ -148,11 +153,48 @@ fn prepare_chunk(
       start_line,
       end_line,
       chunk_type,
+      symbol,
       context_prev,
       context_next,
+      language: language.map(str::to_string),
    }
 }
 
+/// Flags chunks whose text ran past an encoder's max length: only their head
+/// is embedded, so matches in the tail will not be found. Logs one line per
+/// file so a large generated file does not flood the log.
+fn warn_truncated_chunks(chunks: &[PreparedChunk], embeddings: &[HybridEmbedding]) {
+   let mut by_file: BTreeMap<&Path, Vec<(u32, u32)>> = BTreeMap::new();
+   for (chunk, embedding) in chunks.iter().zip(embeddings) {
+      if embedding.truncated {
+         by_file
+            .entry(chunk.path_key.as_path())
+            .or_default()
+            .push((chunk.start_line, chunk.end_line));
+      }
+   }
+   for (path, spans) in by_file {
+      let (start, end) = spans[0];
+      tracing::warn!(
+         "{}: {} chunk(s) exceed the encoder max length, first at lines {}-{}; only their head is \
+          embedded",
+         path.display(),
+         spans.len(),
+         start,
+         end
+      );
+   }
+}
+
+/// Whether a failure says something about the file itself: reading or
+/// chunking it fails the same way on every sync. Embedding failures usually
+/// mean the embedder is down, so they never count toward quarantine. A
+/// chunk-cap quarantine lasts only while the cap stays put (see
+/// [`MetaStore::release_chunk_cap_failures`]).
+fn counts_toward_quarantine(code: &str) -> bool {
+   matches!(code, "read_failed" | "chunk_failed" | "chunk_cap_exceeded")
+}
+
 fn write_tombstones(path: &Path, entries: &[TombstoneEntry]) -> Result<()> {
    if let Some(parent) = path.parent() {
       fs::create_dir_all(parent)?;
@@ -166,6 +208,26 @@ fn write_tombstones(path: &Path, entries: &[TombstoneEntry]) -> Result<()> {
    Ok(())
 }
 
+/// Chunking modes recorded by the store's active snapshot; empty when there is
+/// no snapshot yet or its segments predate the recorded mode.
+fn active_chunking_modes(store_id: &str) -> Vec<ChunkingMode> {
+   let Ok(Some(snapshot_id)) = read_active_snapshot_id_at(&config::data_dir().join(store_id))
+   else {
+      return Vec::new();
+   };
+   SnapshotManifest::load(&SnapshotManager::manifest_path_for(store_id, &snapshot_id))
+      .map(|manifest| manifest.chunking_modes())
+      .unwrap_or_default()
+}
+
+fn chunking_label(modes: &[ChunkingMode]) -> String {
+   modes
+      .iter()
+      .map(|mode| mode.as_str())
+      .collect::<Vec<_>>()
+      .join("+")
+}
+
 fn head_hash_from_bytes(bytes: &[u8]) -> FileHash {
    let len = bytes.len().min(HEAD_HASH_BYTES);
    FileHash::sum(&bytes[..len])
@@ -304,7 +366,12 @@ impl<'a, F: FileSystem + Sync> ChangeDetector for FileSystemChangeDetector<'a, F
 
       for file in files {
          match meta_store.get_meta(&file.path_key) {
-            None => add.push(file),
+            None => {
+               let (current_mtime, current_size) = get_mtime_and_size(&file.real_path).await;
+               if !meta_store.is_quarantined(&file.path_key, current_mtime, current_size) {
+                  add.push(file);
+               }
+            },
             Some(meta) => {
                let (current_mtime, current_size) = get_mtime_and_size(&file.real_path).await;
                if meta.mtime != current_mtime || meta.size != current_size {
@@ -358,16 +425,39 @@ pub struct SyncResult {
    pub deleted:   usize,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct SyncOptions {
    pub allow_degraded:     bool,
+   /// Quarantine files that fail to read or chunk (see
+   /// [`MetaStore::record_failure`]) instead of failing the sync or degrading
+   /// the snapshot. Embedding failures are never quarantined.
+   pub quiet_errors:       bool,
    pub embed_max_retries:  usize,
    pub embed_backoff_ms:   u64,
+   /// Explicit chunking mode; `None` keeps the active snapshot's mode, or
+   /// follows `fast_mode` from config for a new store. An explicit mode that
+   /// differs from the active snapshot forces a rebuild.
+   pub chunking:           Option<ChunkingMode>,
+   /// Per-run override of the per-file chunk cap; `None` uses the config
+   /// value. Clamped to the same hard cap as the config value; not part of
+   /// the config fingerprint, so it never forces a rebuild.
+   pub max_chunks_per_file: Option<usize>,
+   /// Allow-list for a single run (`ggrep index --only`): files outside it are
+   /// neither indexed nor deleted, so a later unrestricted sync picks them up.
+   pub only:               Option<GlobSet>,
 }
 
 impl Default for SyncOptions {
    fn default() -> Self {
-      Self { allow_degraded: false, embed_max_retries: 1, embed_backoff_ms: 100 }
+      Self {
+         allow_degraded:    false,
+         quiet_errors:      false,
+         embed_max_retries: 1,
+         embed_backoff_ms:  100,
+         chunking:          None,
+         max_chunks_per_file: None,
+         only:              None,
+      }
    }
 }
 
@@ -387,6 +477,46 @@ impl ChangeSet {
          && self.delete.is_empty()
          && self.rename.is_empty()
    }
+
+   /// Drops every change outside `only`. A rename into the allow-list is
+   /// kept; a rename out of it becomes a delete of the old path.
+   pub fn retain_only(&mut self, only: &GlobSet) {
+      self.add.retain(|file| only.is_match(&file.path_key));
+      self.modify.retain(|file| only.is_match(&file.path_key));
+      self.delete.retain(|path| only.is_match(path));
+      for (from, to) in std::mem::take(&mut self.rename) {
+         if only.is_match(&to) {
+            self.rename.push((from, to));
+         } else if only.is_match(&from) {
+            self.delete.push(from);
+         }
+      }
+   }
+}
+
+/// Builds the `--only` allow-list. A glob without a `/` matches at any depth,
+/// so `*.rs` selects every Rust file.
+pub fn only_matcher(patterns: &[String]) -> Result<Option<GlobSet>> {
+   if patterns.is_empty() {
+      return Ok(None);
+   }
+   let mut builder = GlobSetBuilder::new();
+   for pattern in patterns {
+      let glob = pattern.trim_start_matches("./");
+      let glob = if glob.contains('/') {
+         glob.to_string()
+      } else {
+         format!("**/{glob}")
+      };
+      builder.add(Glob::new(&glob).map_err(|e| Error::Server {
+         op:     "index",
+         reason: format!("invalid_request: invalid --only glob '{pattern}': {e}"),
+      })?);
+   }
+   builder.build().map(Some).map_err(|e| Error::Server {
+      op:     "index",
+      reason: format!("invalid_request: invalid --only globs: {e}"),
+   })
 }
 
 struct PendingEmbed {
@@ -398,6 +528,14 @@ struct PendingEmbed {
    chunks:    Vec<PreparedChunk>,
 }
 
+/// A file whose rows from an interrupted sync still match its content.
+struct SalvagedFile {
+   hash:      FileHash,
+   mtime:     u64,
+   size:      u64,
+   head_hash: FileHash,
+}
+
 #[derive(Debug, Default)]
 struct EmbedBatchOutcome {
    indexed:       usize,
@@ -538,16 +676,49 @@ where
       let model_changed = meta_store.model_mismatch();
       let index_changed = meta_store.index_mismatch();
       let file_batch_size = config::get().sync_file_batch_size.max(1);
-      let fast_mode = config::get().fast_mode;
+      // Without an explicit mode, keep the one the store was built with so
+      // that an implicit sync never mixes modes; `fast_mode` only decides
+      // for a new or mixed store.
+      let active_chunking = active_chunking_modes(store_id);
+      let chunking = match (options.chunking, active_chunking.as_slice()) {
+         (Some(mode), _) => mode,
+         (None, [mode]) => *mode,
+         (None, _) => ChunkingMode::from_fast(config::get().fast_mode),
+      };
+      let fast_mode = chunking == ChunkingMode::Fast;
+      let chunking_changed =
+         options.chunking.is_some() && !active_chunking.is_empty() && active_chunking != [chunking];
+      if !chunking_changed && active_chunking.iter().any(|mode| *mode != chunking) {
+         tracing::warn!(
+            "store {store_id} has {} chunked segments; syncing with {} chunking mixes modes \
+             (rebuild with `ggrep index --reset --{}`)",
+            chunking_label(&active_chunking),
+            chunking.as_str(),
+            chunking.as_str()
+         );
+      }
       let max_file_size = config::get().effective_max_file_size_bytes();
-      let max_chunks_per_file = config::get().effective_max_chunks_per_file();
+      let max_chunks_per_file = options.max_chunks_per_file.map_or_else(
+         || config::get().effective_max_chunks_per_file(),
+         |cap| cap.clamp(1, config::MAX_CHUNKS_PER_FILE_CAP),
+      );
       let max_bytes_per_sync = config::get().effective_max_bytes_per_sync();
       let allow_degraded = options.allow_degraded;
+      let quiet_errors = options.quiet_errors;
+      let quarantine_after = config::get().quarantine_after_failures;
+      // Failures are collected and counted toward quarantine, rather than
+      // aborting the sync at the first one, unless nothing would use them.
+      let collect_failures = allow_degraded || quiet_errors || quarantine_after > 0;
 
       let mut degraded_errors: Vec<SnapshotError> = Vec::new();
       let mut degraded_paths: HashSet<String> = HashSet::new();
+      // Content of files that failed to chunk, so a quarantine holds until
+      // the content itself changes.
+      let mut failed_hashes: HashMap<PathBuf, FileHash> = HashMap::new();
+      // Per language: first affected file and how many were simple-chunked.
+      let mut grammar_fallbacks: BTreeMap<&str, (String, usize)> = BTreeMap::new();
 
-      if (model_changed || index_changed) && !dry_run {
+      if (model_changed || index_changed || chunking_changed) && !dry_run {
          self.store.delete_store(store_id).await?;
          meta_store.reset_for_signature_change();
       }
@@ -559,6 +730,8 @@ where
       }
 
       meta_store.normalize_paths(&root_real);
+      meta_store.prune_failures(&root_real);
+      meta_store.release_chunk_cap_failures(max_chunks_per_file);
       let fingerprints = identity::compute_fingerprints(&root_real)?;
       meta_store.set_fingerprints(
          fingerprints.config_fingerprint.clone(),
@@ -571,7 +744,15 @@ where
          fingerprints.config_fingerprint.clone(),
          fingerprints.ignore_fingerprint.clone(),
       );
-      snapshot_manager.cleanup_staging()?;
+      let resumable = if dry_run {
+         None
+      } else {
+         snapshot_manager
+            .find_resumable_staging(chunking, max_chunks_per_file)
+            .await?
+      };
+      snapshot_manager
+         .cleanup_staging_except(resumable.as_ref().map(|txn| txn.staging_txn_id.as_str()))?;
 
       let mut effective_changeset = if let Some(changeset) = changeset {
          changeset
@@ -579,8 +760,11 @@ where
          let detector = FileSystemChangeDetector::new(&self.file_system);
          detector.detect(&root_real, &meta_store).await?
       };
+      if let Some(only) = &options.only {
+         effective_changeset.retain_only(only);
+      }
 
-      if effective_changeset.is_empty() {
+      if effective_changeset.is_empty() && resumable.is_none() {
          if !dry_run {
             if snapshot_manager.read_active_snapshot_id()?.is_none() {
                let snapshot_id = Uuid::new_v4().to_string();
@@ -628,17 +812,44 @@ where
          return Ok(SyncResult { processed: 0, indexed: 0, skipped: 0, deleted: 0 });
       }
 
-      let snapshot_id = Uuid::new_v4().to_string();
       let created_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
       let parent_snapshot_id = snapshot_manager.read_active_snapshot_id()?;
-      let segment_table = segment_table_name(&snapshot_id, 0);
-      let staging_txn_id = Uuid::new_v4().to_string();
+      // Resuming reuses the interrupted run's snapshot id and segment table so
+      // rows it already inserted are published instead of re-embedded.
+      let (snapshot_id, segment_table, staging_txn_id) = match &resumable {
+         Some(txn) => {
+            (txn.snapshot_id.clone(), txn.segment_table.clone(), txn.staging_txn_id.clone())
+         },
+         None => {
+            let snapshot_id = Uuid::new_v4().to_string();
+            let segment_table = segment_table_name(&snapshot_id, 0);
+            (snapshot_id, segment_table, Uuid::new_v4().to_string())
+         },
+      };
       if !dry_run {
          lease
             .set_staging_txn_id(Some(staging_txn_id.clone()))
             .await?;
-         let _staging_dir = snapshot_manager.create_staging(&staging_txn_id)?;
+         if resumable.is_none() {
+            snapshot_manager.create_staging_txn(&StagingTxn {
+               staging_txn_id:      staging_txn_id.clone(),
+               snapshot_id:         snapshot_id.clone(),
+               segment_table:       segment_table.clone(),
+               parent_snapshot_id:  parent_snapshot_id.clone(),
+               config_fingerprint:  fingerprints.config_fingerprint.clone(),
+               created_at:          created_at.clone(),
+               chunking:            Some(chunking),
+               max_chunks_per_file: Some(max_chunks_per_file),
+            })?;
+         }
       }
+      let mut salvaged = if resumable.is_some() {
+         self
+            .salvage_staging_rows(store_id, &root_real, &segment_table)
+            .await?
+      } else {
+         HashMap::new()
+      };
 
       let mut processed = 0;
       let mut indexed = 0;
@@ -667,6 +878,7 @@ where
                code: code.to_string(),
                message,
                path_key: key,
+               language: None,
             });
          }
       }
@@ -728,7 +940,20 @@ where
       for file in files {
          processed += 1;
 
+         if salvaged.contains_key(&file.path_key) {
+            continue;
+         }
+
          let (current_mtime, current_size) = get_mtime_and_size(&file.real_path).await;
+         if meta_store.is_quarantined(&file.path_key, current_mtime, current_size) {
+            skipped += 1;
+            // Quarantined by a sync that failed before publishing.
+            if !dry_run && meta_store.get_meta(&file.path_key).is_some() {
+               push_tombstone(&file.path_key, "quarantine");
+               meta_store.remove(&file.path_key);
+            }
+            continue;
+         }
          if current_size > max_file_size {
             skipped += 1;
             if !dry_run {
@@ -809,7 +1034,7 @@ where
                   continue;
                }
 
-               if allow_degraded {
+               if collect_failures {
                   let code = if matches!(e, Error::Server { op, .. } if op == "stable_read") {
                      "stable_read_failed"
                   } else {
@@ -858,6 +1083,11 @@ where
 
          let hash = FileHash::sum(&content);
          let size = content.len() as u64;
+         if meta_store.quarantine_holds(&file.path_key, hash, current_mtime, size) {
+            // Touched since it was quarantined, but the content is the same.
+            skipped += 1;
+            continue;
+         }
          let head_hash = head_hash_from_bytes(&content);
          let existing_hash = meta_store.get_hash(file.path_key.as_path());
 
@@ -888,6 +1118,7 @@ where
          let content_str = Str::from_utf8_lossy(&content);
          let path_key_ci = file.path_key_ci.clone();
          let anchor_chunk = create_anchor_chunk(&content_str, &file.path_key);
+         let language = self.chunker.detect_language(&file.path_key, &content_str);
 
          let mut prepared_chunks = Vec::new();
          prepared_chunks.push(prepare_chunk(
@@ -902,20 +1133,23 @@ where
             anchor_chunk.chunk_type,
             None,
             None,
+            None,
+            language,
          ));
 
          if !fast_mode {
             let chunks = match self.chunker.chunk(&content_str, &file.real_path).await {
                Ok(chunks) => chunks,
                Err(e) => {
-                  if allow_degraded {
-                  record_degraded(
-                     &mut degraded_paths,
-                     &mut degraded_errors,
-                     &file.path_key,
-                     "chunk_failed",
-                     format!("failed to chunk {}: {e}", file.real_path.display()),
-                  );
+                  if collect_failures {
+                     record_degraded(
+                        &mut degraded_paths,
+                        &mut degraded_errors,
+                        &file.path_key,
+                        "chunk_failed",
+                        format!("failed to chunk {}: {e}", file.real_path.display()),
+                     );
+                     failed_hashes.insert(file.path_key.clone(), hash);
                      skipped += 1;
                      continue;
                   }
@@ -931,7 +1165,7 @@ where
 
             let total_chunks = chunks.len().saturating_add(1);
             if total_chunks > max_chunks_per_file {
-               if allow_degraded {
+               if collect_failures {
                   record_degraded(
                      &mut degraded_paths,
                      &mut degraded_errors,
@@ -944,6 +1178,7 @@ where
                         max_chunks_per_file
                      ),
                   );
+                  failed_hashes.insert(file.path_key.clone(), hash);
                   skipped += 1;
                   continue;
                }
@@ -961,6 +1196,15 @@ where
                );
             }
 
+            if let Some(language) = language
+               && self.chunker.grammar_missing(language).await
+            {
+               grammar_fallbacks
+                  .entry(language)
+                  .or_insert_with(|| (file.path_key.to_string_lossy().into_owned(), 0))
+                  .1 += 1;
+            }
+
             for (idx, chunk) in chunks.iter().enumerate() {
                let context_prev: Option<Str> = if idx > 0 {
                   Some(chunks[idx - 1].content.clone())
@@ -984,8 +1228,10 @@ where
                   chunk.start_line as u32,
                   chunk.end_line as u32,
                   chunk.chunk_type,
+                  chunk.symbol.clone(),
                   context_prev,
                   context_next,
+                  language,
                );
                prepared_chunks.push(prepared);
             }
@@ -1012,7 +1258,7 @@ where
 
             let batch = std::mem::take(&mut embed_queue);
             let batch_outcome = self
-               .process_embed_batch(store_id, &segment_table, batch, &mut meta_store, options)
+               .process_embed_batch(store_id, &segment_table, batch, &mut meta_store, &options)
                .await?;
             indexed += batch_outcome.indexed;
             since_save += batch_outcome.indexed;
@@ -1051,7 +1297,7 @@ where
 
          let batch = std::mem::take(&mut embed_queue);
          let batch_outcome = self
-            .process_embed_batch(store_id, &segment_table, batch, &mut meta_store, options)
+            .process_embed_batch(store_id, &segment_table, batch, &mut meta_store, &options)
             .await?;
          indexed += batch_outcome.indexed;
          for path in batch_outcome.indexed_paths {
@@ -1069,6 +1315,76 @@ where
          skipped += batch_failed;
       }
 
+      // Salvaged rows only replace files the base snapshot already has; a
+      // file the interrupted run added needs no tombstone.
+      let base_paths = if salvaged.is_empty() {
+         None
+      } else {
+         parent_snapshot_id
+            .as_deref()
+            .map(|parent_id| {
+               snapshot_manager
+                  .snapshot_dir(parent_id)
+                  .join("segment_file_index.jsonl")
+            })
+            .filter(|index| index.exists())
+            .map(|index| read_segment_file_index(&index))
+            .transpose()?
+      };
+      for (path_key, file) in salvaged.drain() {
+         let in_base = match (&base_paths, parent_snapshot_id.is_some()) {
+            (Some(paths), _) => paths.contains_key(path_key.to_string_lossy().as_ref()),
+            // Without an index to check, assume the parent has the file.
+            (None, has_parent) => has_parent,
+         };
+         if in_base {
+            push_tombstone(&path_key, "replace");
+         }
+         indexed_paths.insert(path_key.to_string_lossy().into_owned());
+         meta_store.set_meta(path_key, file.hash, file.mtime, file.size, file.head_hash);
+         indexed += 1;
+      }
+
+      // Files that failed to read or chunk are counted; those quarantined
+      // (all of them with `--quiet-errors`, otherwise the ones that kept
+      // failing) are tombstoned and skipped by later syncs until they change,
+      // so the rest of the snapshot stays healthy.
+      if !dry_run && !degraded_errors.is_empty() {
+         let mut quarantined = 0usize;
+         let mut remaining = Vec::new();
+         for err in std::mem::take(&mut degraded_errors) {
+            if !counts_toward_quarantine(&err.code) {
+               remaining.push(err);
+               continue;
+            }
+            let path_key = PathBuf::from(&err.path_key);
+            let (mtime, size) = get_mtime_and_size(&root_real.join(&path_key)).await;
+            let failures = meta_store.note_failure(
+               path_key.clone(),
+               mtime,
+               size,
+               failed_hashes.get(&path_key).copied(),
+               err.code.clone(),
+               err.message.clone(),
+            );
+            if err.code == "chunk_cap_exceeded" {
+               meta_store.note_chunk_cap(&path_key, max_chunks_per_file);
+            }
+            if quiet_errors || (quarantine_after > 0 && failures >= quarantine_after) {
+               push_tombstone(&path_key, "quarantine");
+               meta_store.remove(&path_key);
+               meta_store.quarantine(&path_key);
+               quarantined += 1;
+            } else {
+               remaining.push(err);
+            }
+         }
+         if quarantined > 0 {
+            tracing::warn!("quarantined {quarantined} file(s) that failed to index");
+         }
+         degraded_errors = remaining;
+      }
+
       if !degraded_errors.is_empty() && !allow_degraded {
          if !dry_run {
             if indexed > 0 {
@@ -1076,11 +1392,20 @@ where
             }
             let _ = lease.set_staging_txn_id(None).await;
             let _ = fs::remove_dir_all(snapshot_manager.staging_path(&staging_txn_id));
+            // Nothing from this run is published, but the failure counts must
+            // survive it for the file to reach quarantine.
+            let mut persisted = MetaStore::load(store_id)?;
+            persisted.copy_failures_from(&meta_store);
+            persisted.save()?;
          }
          return Err(
             Error::Server {
                op:     "sync",
-               reason: format!("failed to index {} file(s)", degraded_errors.len()),
+               reason: format!(
+                  "failed to index {} file(s): {}",
+                  degraded_errors.len(),
+                  degraded_errors[0].message
+               ),
             }
             .into(),
          );
@@ -1101,12 +1426,20 @@ where
 
          let mut segments: Vec<SnapshotSegmentRef> = Vec::new();
          let mut tombstone_refs: Vec<SnapshotTombstoneRef> = Vec::new();
+         // Chunks from earlier snapshots stay simple-chunked until a reset.
+         let mut inherited_grammar_errors: Vec<SnapshotError> = Vec::new();
 
          if let Some(parent_id) = parent_snapshot_id.as_deref() {
             let parent_manifest = SnapshotManifest::load(&snapshot_manager.manifest_path(parent_id))?;
             snapshot_manager.verify_manifest(&parent_manifest).await?;
             segments.extend(parent_manifest.segments);
             tombstone_refs.extend(parent_manifest.tombstones);
+            inherited_grammar_errors.extend(
+               parent_manifest
+                  .errors
+                  .into_iter()
+                  .filter(|error| error.code == GRAMMAR_MISSING_CODE),
+            );
          }
 
          if indexed > 0 {
@@ -1118,6 +1451,7 @@ where
                rows: metadata.rows,
                size_bytes: metadata.size_bytes,
                sha256: metadata.sha256,
+               chunking: Some(chunking),
             });
          }
 
@@ -1234,7 +1568,33 @@ where
          let files_indexed = meta_store.all_paths().count() as u64;
 
          let degraded = allow_degraded && !degraded_errors.is_empty();
-         let errors = if degraded { degraded_errors } else { Vec::new() };
+         let mut errors = if degraded {
+            degraded_errors
+         } else {
+            Vec::new()
+         };
+         let grammar_fallbacks_seen: HashSet<&str> = grammar_fallbacks.keys().copied().collect();
+         for (language, (path_key, files)) in grammar_fallbacks {
+            tracing::warn!(
+               "{files} {language} file(s) indexed without a tree-sitter grammar; run `ggrep \
+                grammars fetch {language}` and `ggrep index --reset` for structural chunks"
+            );
+            errors.push(SnapshotError {
+               code: GRAMMAR_MISSING_CODE.to_string(),
+               message: format!(
+                  "{files} file(s) chunked without the {language} grammar; run `ggrep grammars \
+                   fetch {language}` and `ggrep index --reset` for structural chunks"
+               ),
+               path_key,
+               language: Some(language.to_string()),
+            });
+         }
+         errors.extend(inherited_grammar_errors.into_iter().filter(|error| {
+            error
+               .language
+               .as_deref()
+               .is_some_and(|language| !grammar_fallbacks_seen.contains(language))
+         }));
 
          let manifest = SnapshotManifest {
             schema_version: MANIFEST_SCHEMA_VERSION,
@@ -1287,7 +1647,7 @@ where
    async fn embed_with_retry(
       &self,
       texts: &[Str],
-      options: SyncOptions,
+      options: &SyncOptions,
    ) -> Result<Vec<HybridEmbedding>> {
       let mut attempt = 0usize;
       loop {
@@ -1309,13 +1669,62 @@ where
       }
    }
 
+   /// Checks the rows an interrupted sync left in `segment_table` against the
+   /// files on disk. Rows for files that changed or disappeared since are
+   /// deleted; the rest are returned so the resumed sync can skip them.
+   async fn salvage_staging_rows(
+      &self,
+      store_id: &str,
+      root: &Path,
+      segment_table: &str,
+   ) -> Result<HashMap<PathBuf, SalvagedFile>> {
+      let stored = self
+         .store
+         .segment_file_hashes(store_id, segment_table)
+         .await?;
+      let mut salvaged = HashMap::with_capacity(stored.len());
+      let mut stale = Vec::new();
+
+      for (key, stored_hash) in stored {
+         let path_key = PathBuf::from(&key);
+         let real_path = root.join(&path_key);
+         let Ok(content) = read_file_verified(root, &real_path).await else {
+            stale.push(key);
+            continue;
+         };
+         let hash = FileHash::sum(&content);
+         if FileHash::from_slice(&stored_hash) != Some(hash) {
+            stale.push(key);
+            continue;
+         }
+         let (mtime, size) = get_mtime_and_size(&real_path).await;
+         salvaged.insert(path_key, SalvagedFile {
+            hash,
+            mtime,
+            size,
+            head_hash: head_hash_from_bytes(&content),
+         });
+      }
+
+      self
+         .store
+         .delete_paths(store_id, segment_table, &stale)
+         .await?;
+      tracing::info!(
+         "resuming interrupted sync: {} file(s) salvaged, {} stale",
+         salvaged.len(),
+         stale.len()
+      );
+      Ok(salvaged)
+   }
+
    async fn process_embed_batch(
       &self,
       store_id: &str,
       table_name: &str,
       batch: Vec<PendingEmbed>,
       meta_store: &mut MetaStore,
-      options: SyncOptions,
+      options: &SyncOptions,
    ) -> Result<EmbedBatchOutcome> {
       let mut outcome = EmbedBatchOutcome::default();
       if batch.is_empty() {
@@ -1344,6 +1753,7 @@ where
                   .into(),
                );
             }
+            warn_truncated_chunks(&all_chunks, &embeddings);
             let records: Vec<VectorRecord> = all_chunks
                .into_iter()
                .zip(embeddings.into_iter())
@@ -1361,11 +1771,13 @@ where
                   start_line:    chunk.start_line,
                   end_line:      chunk.end_line,
                   chunk_type:    chunk.chunk_type,
+                  symbol:        chunk.symbol,
                   context_prev:  chunk.context_prev,
                   context_next:  chunk.context_next,
                   vector:        embedding.dense,
                   colbert:       embedding.colbert,
                   colbert_scale: embedding.colbert_scale,
+                  language:      chunk.language,
                })
                .collect();
 
@@ -1413,6 +1825,7 @@ where
                      .into(),
                   );
                }
+               warn_truncated_chunks(&chunks, &embeddings);
                let records: Vec<VectorRecord> = chunks
                   .into_iter()
                   .zip(embeddings.into_iter())
@@ -1430,11 +1843,13 @@ where
                      start_line:    chunk.start_line,
                      end_line:      chunk.end_line,
                      chunk_type:    chunk.chunk_type,
+                     symbol:        chunk.symbol,
                      context_prev:  chunk.context_prev,
                      context_next:  chunk.context_next,
                      vector:        embedding.dense,
                      colbert:       embedding.colbert,
                      colbert_scale: embedding.colbert_scale,
+                     language:      chunk.language,
                   })
                   .collect();
 
@@ -1448,6 +1863,7 @@ where
                   code:     "embed_failed".to_string(),
                   message:  format!("embedding failed: {err}"),
                   path_key: path_key.to_string_lossy().into_owned(),
+                  language: None,
                });
             },
          }
@@ -1490,4 +1906,26 @@ mod tests {
          _ => panic!("expected stable_read failure"),
       }
    }
+
+   #[test]
+   fn retain_only_keeps_changes_inside_the_allow_list() {
+      let only = only_matcher(&["*.rs".to_string()])
+         .expect("globs")
+         .expect("matcher");
+      let mut changeset = ChangeSet {
+         delete: vec![PathBuf::from("src/gone.rs"), PathBuf::from("docs/gone.md")],
+         rename: vec![
+            (PathBuf::from("a.md"), PathBuf::from("src/a.rs")),
+            (PathBuf::from("src/b.rs"), PathBuf::from("b.md")),
+            (PathBuf::from("c.md"), PathBuf::from("d.md")),
+         ],
+         ..ChangeSet::default()
+      };
+      changeset.retain_only(&only);
+
+      assert_eq!(changeset.delete, vec![PathBuf::from("src/gone.rs"), PathBuf::from("src/b.rs")]);
+      assert_eq!(changeset.rename, vec![(PathBuf::from("a.md"), PathBuf::from("src/a.rs"))]);
+      assert!(only_matcher(&[]).expect("empty").is_none());
+      assert!(only_matcher(&["[".to_string()]).is_err());
+   }
 }
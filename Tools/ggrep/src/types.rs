@@ -1,4 +1,8 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+   collections::HashMap,
+   path::{Path, PathBuf},
+   sync::Arc,
+};
 
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -43,6 +47,8 @@ pub struct Chunk {
    pub start_col:   usize,
    pub end_line:    usize,
    pub chunk_type:  Option<ChunkType>,
+   /// Name of the definition the chunk was cut from, if any.
+   pub symbol:      Option<Str>,
    pub context:     ContextVec,
    pub chunk_index: Option<i32>,
    pub is_anchor:   Option<bool>,
@@ -62,6 +68,7 @@ impl Chunk {
          start_col: 0,
          end_line,
          chunk_type: Some(chunk_type),
+         symbol: None,
          context: context.iter().cloned().collect(),
          chunk_index: None,
          is_anchor: Some(false),
@@ -72,6 +79,11 @@ impl Chunk {
       self.start_col = col;
       self
    }
+
+   pub fn with_symbol(mut self, symbol: Option<Str>) -> Self {
+      self.symbol = symbol;
+      self
+   }
 }
 
 /// Chunk prepared for embedding with file hash and identifier
@@ -92,8 +104,12 @@ pub struct PreparedChunk {
    pub start_line:   u32,
    pub end_line:     u32,
    pub chunk_type:   Option<ChunkType>,
+   #[serde(default)]
+   pub symbol:       Option<Str>,
    pub context_prev: Option<Str>,
    pub context_next: Option<Str>,
+   #[serde(default)]
+   pub language:     Option<String>,
 }
 
 /// Chunk with embedding vectors ready for storage in vector database
@@ -112,11 +128,13 @@ pub struct VectorRecord {
    pub start_line:    u32,
    pub end_line:      u32,
    pub chunk_type:    Option<ChunkType>,
+   pub symbol:        Option<Str>,
    pub context_prev:  Option<Str>,
    pub context_next:  Option<Str>,
    pub vector:        Vec<f32>,
    pub colbert:       Vec<u8>,
    pub colbert_scale: f64,
+   pub language:      Option<String>,
 }
 
 /// Individual search result with location and relevance score
@@ -133,8 +151,36 @@ pub struct SearchResult {
    pub segment_table:   Option<String>,
    pub start_line:      u32,
    pub num_lines:       u32,
+   /// `start_line + num_lines`, filled in on daemon responses so clients do
+   /// not derive it themselves.
+   pub end_line:        Option<u32>,
    pub chunk_type:      Option<ChunkType>,
+   /// Enclosing definition name (`None` for rows from older segments).
+   pub symbol:          Option<Str>,
    pub is_anchor:       Option<bool>,
+   /// Language detected at index time (`None` for rows from older segments).
+   pub language:        Option<String>,
+   /// Lines of the previous chunk prepended to `content` as context.
+   pub context_before:  u32,
+   /// Lines of the next chunk appended to `content` as context.
+   pub context_after:   u32,
+}
+
+impl SearchResult {
+   /// The chunk's own text: `content` without the neighbouring-chunk context
+   /// lines counted by `context_before`/`context_after`.
+   pub fn own_content(&self) -> &str {
+      let content = self.content.as_str();
+      let lines: Vec<&str> = content.split_inclusive('\n').collect();
+      let before = (self.context_before as usize).min(lines.len());
+      let after = (self.context_after as usize).min(lines.len() - before);
+      let start: usize = lines[..before].iter().map(|line| line.len()).sum();
+      let end: usize = lines[lines.len() - after..]
+         .iter()
+         .map(|line| line.len())
+         .sum();
+      &content[start..content.len() - end]
+   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -200,6 +246,48 @@ pub fn cmp_results_deterministic(a: &SearchResult, b: &SearchResult) -> std::cmp
    a.num_lines.cmp(&b.num_lines)
 }
 
+/// Identity of the chunk behind a result: its `row_id` when known, otherwise
+/// its location.
+#[derive(PartialEq, Eq, Hash)]
+enum ChunkIdentity<'a> {
+   Row(&'a str),
+   Location(&'a Path, u32, u32),
+}
+
+impl<'a> ChunkIdentity<'a> {
+   fn of(result: &'a SearchResult) -> Self {
+      match result.row_id.as_deref() {
+         Some(row_id) => Self::Row(row_id),
+         None => Self::Location(&result.path, result.start_line, result.num_lines),
+      }
+   }
+}
+
+/// Drops results for a chunk that was already seen, keeping the
+/// highest-scoring copy in its original position. Overlapping segments (e.g. a
+/// base and a delta after compaction) can return the same chunk from more than
+/// one table. Results without a `row_id` are matched by path and line span.
+pub fn dedup_results_by_chunk(results: &mut Vec<SearchResult>) {
+   let mut keep = vec![true; results.len()];
+   let mut best: HashMap<ChunkIdentity<'_>, usize> = HashMap::with_capacity(results.len());
+   for (idx, result) in results.iter().enumerate() {
+      let identity = ChunkIdentity::of(result);
+      match best.get(&identity).copied() {
+         Some(prev) if results[prev].score >= result.score => keep[idx] = false,
+         Some(prev) => {
+            keep[prev] = false;
+            best.insert(identity, idx);
+         },
+         None => {
+            best.insert(identity, idx);
+         },
+      }
+   }
+
+   let mut keep = keep.into_iter();
+   results.retain(|_| keep.next().unwrap_or(true));
+}
+
 pub fn sort_and_dedup_limits(limits: &mut Vec<SearchLimitHit>) {
    limits.sort_by(|a, b| {
       let code_cmp = a.code.cmp(&b.code);
@@ -234,7 +322,9 @@ pub enum SearchStatus {
 ///
 /// Used to tune candidate mixing and ranking for hybrid corpora (code + docs +
 /// diagrams) without requiring changes to how documents are authored.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[derive(
+   Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum SearchMode {
    /// Default behavior (mostly score-sorted results).
@@ -248,13 +338,82 @@ pub enum SearchMode {
    Planning,
    /// Favors debugging/incident triage code paths.
    Debug,
+   /// Favors test files, to find the tests exercising a behavior.
+   Tests,
+   /// Ranks anchor chunks (file-level summaries) only, to find the file that
+   /// plays a described role.
+   Paths,
+}
+
+impl SearchMode {
+   /// Search flag that selects this mode; `None` for the default.
+   pub const fn flag(self) -> Option<&'static str> {
+      match self {
+         Self::Balanced => None,
+         Self::Discovery => Some("-d"),
+         Self::Implementation => Some("-i"),
+         Self::Planning => Some("-p"),
+         Self::Debug => Some("-b"),
+         Self::Tests => Some("-t"),
+         Self::Paths => Some("--path-query"),
+      }
+   }
+}
+
+/// How selected results from the same file are combined when their line
+/// ranges meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanMerge {
+   /// Keep every chunk as ranked, including repeated context lines.
+   Off,
+   /// Merge chunks whose line ranges (context included) overlap.
+   #[default]
+   Overlapping,
+   /// Also merge chunks that end exactly where the next one starts, e.g. a
+   /// function split across consecutive chunks.
+   Adjacent,
+}
+
+/// Per-query settings beyond the query, limits and mode. The CLI, the daemon
+/// request and the engine all carry this one struct, so a new search flag is
+/// a field here rather than another positional argument on each path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryOptions {
+   /// Recency boost window in days; `None` uses the configured default and
+   /// `Some(0)` disables the boost.
+   pub recent_boost_days: Option<u32>,
+   /// How overlapping chunks of the same file are combined.
+   pub span_merge:        SpanMerge,
+   /// Languages to restrict results to (`--lang`); empty means any.
+   pub languages:         Vec<String>,
+   /// Ranked results to skip, after per-file limits (`--offset`).
+   pub offset:            usize,
+   /// Path globs whose results are dropped (`--exclude`).
+   pub excludes:          Vec<String>,
+   /// Drop results repeating a higher-ranked result's content
+   /// (`--dedup-content`).
+   pub dedup_content:     bool,
+   /// Skip full-text retrieval (`--no-fts`).
+   pub no_fts:            bool,
+   /// Look up which segments have a vector index; only `--explain` reports
+   /// it, so other searches skip the extra call per segment.
+   pub index_stats:       bool,
+   /// Regex a result's own lines must match (`--grep`). Applied before
+   /// limits and paging, so pages are filled with matching results.
+   pub grep:              Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct SearchTimings {
    pub admission_ms:     u64,
    pub snapshot_read_ms: u64,
+   /// Query encoding.
+   pub embed_ms:         u64,
+   /// Candidate retrieval across segments, excluding `rerank_ms`.
    pub retrieve_ms:      u64,
+   /// ColBERT reranking of the slowest segment; segments run concurrently.
+   pub rerank_ms:        u64,
    pub rank_ms:          u64,
    pub format_ms:        u64,
 }
@@ -262,15 +421,206 @@ pub struct SearchTimings {
 /// Response from a semantic search query
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResponse {
-   pub results:    Vec<SearchResult>,
-   pub status:     SearchStatus,
-   pub progress:   Option<u8>,
+   pub results:     Vec<SearchResult>,
+   pub status:      SearchStatus,
+   pub progress:    Option<u8>,
    #[serde(default)]
-   pub timings_ms: Option<SearchTimings>,
+   pub timings_ms:  Option<SearchTimings>,
    #[serde(default)]
-   pub limits_hit: Vec<SearchLimitHit>,
+   pub limits_hit:  Vec<SearchLimitHit>,
    #[serde(default)]
-   pub warnings:   Vec<SearchWarning>,
+   pub warnings:    Vec<SearchWarning>,
+   pub embedder:    Option<EmbedderStatus>,
+   pub candidates:  Option<CandidateCounts>,
+   pub segments:    Vec<SegmentCandidates>,
+   pub rerank:      Option<RerankStats>,
+   /// Snapshot the results were read from.
+   pub snapshot_id: Option<String>,
+   /// Synonyms appended to the full-text query.
+   pub expansions:  Vec<QueryExpansion>,
+   /// Candidates left after each filter stage, in the order they ran.
+   pub funnel:      Vec<FilterStage>,
+}
+
+/// One step of the filter funnel shown by `search --explain`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FilterStage {
+   pub stage:     String,
+   /// The active setting, e.g. the `--exclude` globs.
+   pub filter:    Option<String>,
+   /// Candidates this stage dropped; `None` for the first stage, whose
+   /// filters run inside retrieval and never see what they reject.
+   pub removed:   Option<u64>,
+   pub remaining: u64,
+}
+
+impl FilterStage {
+   /// Appends a stage that left `remaining` candidates, counting what it
+   /// removed against the previous stage.
+   pub fn push(funnel: &mut Vec<Self>, stage: &str, filter: Option<String>, remaining: usize) {
+      let remaining = remaining as u64;
+      let removed = funnel
+         .last()
+         .map(|prev| prev.remaining.saturating_sub(remaining));
+      funnel.push(Self { stage: stage.to_string(), filter, removed, remaining });
+   }
+}
+
+/// A query term the synonym dictionary expanded, with the synonyms it added
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueryExpansion {
+   pub term:     String,
+   pub synonyms: Vec<String>,
+}
+
+/// Number of candidates a single segment table contributed to a query
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SegmentCandidates {
+   pub table:          String,
+   pub candidates:     u64,
+   /// Whether the table has a vector index on `embedding`; `None` unless
+   /// [`QueryOptions::index_stats`] asked, or when its index metadata could
+   /// not be read.
+   pub vector_indexed: Option<bool>,
+}
+
+/// Summary of the scores a set of candidates received
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ScoreRange {
+   pub count: u64,
+   pub min:   f32,
+   pub max:   f32,
+   pub mean:  f32,
+}
+
+impl ScoreRange {
+   pub const fn of(score: f32) -> Self {
+      Self { count: 1, min: score, max: score, mean: score }
+   }
+
+   pub fn merge(&mut self, other: &Self) {
+      let count = self.count + other.count;
+      let sum =
+         f64::from(self.mean) * self.count as f64 + f64::from(other.mean) * other.count as f64;
+      self.mean = (sum / count as f64) as f32;
+      self.count = count;
+      self.min = self.min.min(other.min);
+      self.max = self.max.max(other.max);
+   }
+
+   /// Folds `other` into `range`, starting it if empty.
+   pub fn include(range: &mut Option<Self>, other: Self) {
+      match range {
+         Some(range) => range.merge(&other),
+         None => *range = Some(other),
+      }
+   }
+}
+
+/// Candidate rows for one retrieval source, before and after the
+/// `(path, start_line)` dedup
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct SourceCandidateCounts {
+   pub raw:     u64,
+   pub deduped: u64,
+   /// Dense scores of the deduped candidates, before reranking.
+   pub scores:  Option<ScoreRange>,
+}
+
+/// Per-source candidate counts gathered during retrieval; a duplicate is
+/// attributed to the first source that produced the row
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct CandidateCounts {
+   pub vector:               SourceCandidateCounts,
+   pub doc:                  SourceCandidateCounts,
+   pub graph:                SourceCandidateCounts,
+   pub fts:                  SourceCandidateCounts,
+   /// Ranked candidates dropped because the snapshot view hides them
+   /// (tombstoned, or superseded by a newer segment).
+   pub filtered_not_visible: u64,
+}
+
+impl CandidateCounts {
+   pub fn merge(&mut self, other: &Self) {
+      for (dst, src) in [
+         (&mut self.vector, other.vector),
+         (&mut self.doc, other.doc),
+         (&mut self.graph, other.graph),
+         (&mut self.fts, other.fts),
+      ] {
+         dst.raw += src.raw;
+         dst.deduped += src.deduped;
+         if let Some(scores) = src.scores {
+            ScoreRange::include(&mut dst.scores, scores);
+         }
+      }
+      self.filtered_not_visible += other.filtered_not_visible;
+   }
+
+   pub fn raw_total(&self) -> u64 {
+      self.vector.raw + self.doc.raw + self.graph.raw + self.fts.raw
+   }
+
+   pub fn deduped_total(&self) -> u64 {
+      self.vector.deduped + self.doc.deduped + self.graph.deduped + self.fts.deduped
+   }
+}
+
+/// What ColBERT reranking actually scored for a query, summed over segments
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct RerankStats {
+   /// Reranking was requested (no `--no-rerank`).
+   pub requested:     bool,
+   /// The query encoder produced ColBERT token vectors.
+   pub query_colbert: bool,
+   /// Candidates inside the per-segment rerank cap.
+   pub eligible:      u64,
+   /// Candidates whose stored ColBERT vectors were scored.
+   pub scored:        u64,
+   /// Time spent scoring and re-sorting candidates; segments rerank
+   /// concurrently, so this is the slowest segment's time, not a sum.
+   pub elapsed_us:    u64,
+   /// ColBERT scores of the scored candidates.
+   pub scores:        Option<ScoreRange>,
+}
+
+impl RerankStats {
+   pub fn merge(&mut self, other: &Self) {
+      self.requested |= other.requested;
+      self.query_colbert |= other.query_colbert;
+      self.eligible += other.eligible;
+      self.scored += other.scored;
+      self.elapsed_us = self.elapsed_us.max(other.elapsed_us);
+      if let Some(scores) = other.scores {
+         ScoreRange::include(&mut self.scores, scores);
+      }
+   }
+
+   pub const fn reranked(&self) -> bool {
+      self.scored > 0
+   }
+
+   /// Why nothing was reranked, or `None` if at least one candidate was.
+   pub const fn skipped_reason(&self) -> Option<&'static str> {
+      if self.reranked() {
+         None
+      } else if !self.requested {
+         Some("disabled")
+      } else if !self.query_colbert {
+         Some("no_query_colbert")
+      } else if self.eligible == 0 {
+         Some("no_candidates")
+      } else {
+         Some("no_stored_colbert")
+      }
+   }
+}
+
+/// Embedder backend that served a query and whether it was warm beforehand
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EmbedderStatus {
+   pub backend: String,
+   pub ready:   bool,
 }
 
 /// Metadata about a vector store instance
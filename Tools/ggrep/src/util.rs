@@ -19,6 +19,16 @@ use crate::{Result, error::Error};
 /// - is comparable *within a single result set* (not globally calibrated),
 /// - does not always force the top hit to 100%.
 pub fn compute_match_pcts(scores: &[f32]) -> Vec<Option<u8>> {
+   normalize_scores(scores)
+      .into_iter()
+      .map(|norm| norm.map(|n| (n as f64 * 100.0).round().clamp(0.0, 99.0) as u8))
+      .collect()
+}
+
+/// Maps raw relevance scores onto 0.0–1.0: a sigmoid of each score's z-score
+/// within the set, the scale behind [`compute_match_pcts`]. Non-finite scores
+/// map to `None`.
+pub fn normalize_scores(scores: &[f32]) -> Vec<Option<f32>> {
    if scores.is_empty() {
       return Vec::new();
    }
@@ -30,19 +40,16 @@ pub fn compute_match_pcts(scores: &[f32]) -> Vec<Option<u8>> {
       .map(|s| s as f64)
       .collect();
 
-   // Fallback for degenerate cases (0–1 finite scores): show a bounded absolute
-   // scale so the UI can still surface something.
-   if finite.len() < 2 {
-      return scores
+   // Fallback for degenerate cases (0–1 finite scores, or no spread): show a
+   // bounded absolute scale so the UI can still surface something.
+   let absolute = |scores: &[f32]| {
+      scores
          .iter()
-         .map(|&s| {
-            if !s.is_finite() {
-               return None;
-            }
-            let pct = (s as f64 * 100.0).round().clamp(0.0, 99.0) as u8;
-            Some(pct)
-         })
-         .collect();
+         .map(|&s| s.is_finite().then(|| s.clamp(0.0, 1.0)))
+         .collect()
+   };
+   if finite.len() < 2 {
+      return absolute(scores);
    }
 
    let mean = finite.iter().sum::<f64>() / finite.len() as f64;
@@ -57,16 +64,7 @@ pub fn compute_match_pcts(scores: &[f32]) -> Vec<Option<u8>> {
    let std = var.sqrt();
 
    if !std.is_finite() || std <= 1e-9 {
-      return scores
-         .iter()
-         .map(|&s| {
-            if !s.is_finite() {
-               return None;
-            }
-            let pct = (s as f64 * 100.0).round().clamp(0.0, 99.0) as u8;
-            Some(pct)
-         })
-         .collect();
+      return absolute(scores);
    }
 
    scores
@@ -76,9 +74,7 @@ pub fn compute_match_pcts(scores: &[f32]) -> Vec<Option<u8>> {
             return None;
          }
          let z = (s as f64 - mean) / std;
-         let sigmoid = 1.0 / (1.0 + (-z).exp());
-         let pct = (sigmoid * 100.0).round().clamp(0.0, 99.0) as u8;
-         Some(pct)
+         Some((1.0 / (1.0 + (-z).exp())) as f32)
       })
       .collect()
 }
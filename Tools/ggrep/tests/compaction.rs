@@ -9,7 +9,7 @@ use ggrep::{
    file::LocalFileSystem,
    identity,
    search::SearchEngine,
-   snapshot::{SnapshotManager, compact_store, CompactionOptions},
+   snapshot::{CompactionOptions, SnapshotManager, compact_store, tombstone_ratio},
    store::LanceStore,
    sync::SyncEngine,
    types::SearchMode,
@@ -60,7 +60,11 @@ async fn tombstone_prune() {
    let manifest =
       ggrep::snapshot::SnapshotManifest::load(&snapshot_manager.manifest_path(&active_id))
          .expect("manifest");
-   assert!(manifest.tombstones.iter().map(|t| t.count).sum::<u64>() > 0);
+   let tombstones: u64 = manifest.tombstones.iter().map(|t| t.count).sum();
+   assert!(tombstones > 0);
+   // Only keep.rs is live, so the ratio is tombstones per that one file.
+   assert_eq!(manifest.counts.files_indexed, 1);
+   assert_eq!(tombstone_ratio(&manifest), tombstones as f64);
 
    let compaction = compact_store(
       store.clone(),
@@ -0,0 +1,106 @@
+mod support;
+
+use std::sync::Arc;
+
+use ggrep::{
+   cmd::{
+      search::{self, SearchOptions},
+      serve,
+   },
+   config,
+   embed::DummyEmbedder,
+   ipc::{Request, Response},
+   usock,
+};
+use support::indexed_fixture_with;
+use tokio::time;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn daemon_and_in_process_json_match() {
+   // Safe in test harness: the daemon and the in-process engine must embed
+   // like the fixture.
+   unsafe {
+      std::env::set_var("GGREP_DUMMY_EMBEDDER", "1");
+      std::env::set_var("GGREP_TEST_EMBEDDER", "1");
+   }
+
+   // Identical files tie on score, so their order comes from the tiebreak.
+   let body = "pub fn alpha() -> u32 {\n   1\n}\n";
+   let fixture = indexed_fixture_with(
+      "daemon-parity-test",
+      &[
+         ("a.rs", body),
+         ("b.rs", body),
+         ("src/c.rs", body),
+         ("src/nested/d.rs", body),
+         ("notes.md", "# Alpha\n\nalpha notes\n"),
+      ],
+      |dim| Arc::new(DummyEmbedder::new(dim)),
+   )
+   .await;
+   let (root, store_id) = (fixture.root.clone(), fixture.store_id);
+   let fingerprint = fixture.config_fingerprint.clone();
+   let options = SearchOptions { no_rerank: true, ..SearchOptions::default() };
+
+   let local = search::search_json("alpha", &root, store_id, 10, 5, &options, false)
+      .await
+      .expect("in-process search");
+
+   let server_root = root.clone();
+   let server_store = store_id.to_string();
+   let _server = tokio::spawn(async move {
+      let _ = serve::execute(Some(server_root), Some(server_store), false, false, None, None).await;
+   });
+   wait_for_daemon(store_id).await;
+   let remote = search::search_json("alpha", &root, store_id, 10, 5, &options, true).await;
+   let _ = shutdown_daemon(store_id, &fingerprint).await;
+   let remote = remote.expect("daemon search");
+
+   assert_eq!(local["execution"], "in_process");
+   assert_eq!(remote["execution"], "daemon");
+   assert!(local["results"].as_array().is_some_and(|r| r.len() > 1), "{local}");
+   assert_eq!(
+      serde_json::to_string(&stable_fields(remote)).expect("serialize"),
+      serde_json::to_string(&stable_fields(local)).expect("serialize")
+   );
+}
+
+/// Drops the fields that legitimately differ between two runs of the same
+/// query: which path ran it, when, and how long each stage took.
+fn stable_fields(mut output: serde_json::Value) -> serde_json::Value {
+   let fields = output.as_object_mut().expect("json object");
+   for volatile in ["request_id", "execution", "snapshot_age_secs", "timings_ms"] {
+      fields.remove(volatile);
+   }
+   output
+}
+
+async fn wait_for_daemon(store_id: &str) {
+   for _ in 0..50 {
+      if usock::Stream::connect(store_id).await.is_ok() {
+         return;
+      }
+      time::sleep(std::time::Duration::from_millis(50)).await;
+   }
+   panic!("daemon did not start");
+}
+
+async fn shutdown_daemon(store_id: &str, fingerprint: &str) -> ggrep::Result<()> {
+   let mut stream = usock::Stream::connect(store_id).await?;
+   let mut buffer = ggrep::ipc::SocketBuffer::new();
+   let hello = ggrep::ipc::client_hello(
+      store_id,
+      fingerprint,
+      Some(ggrep::ipc::default_client_id("ggrep-test")),
+      ggrep::ipc::default_client_capabilities(),
+   );
+   buffer.send(&mut stream, &hello).await?;
+   let _response: Response = buffer
+      .recv_with_limit(&mut stream, config::get().max_response_bytes)
+      .await?;
+   buffer.send(&mut stream, &Request::Shutdown).await?;
+   let _response: Response = buffer
+      .recv_with_limit(&mut stream, config::get().max_response_bytes)
+      .await?;
+   Ok(())
+}
@@ -0,0 +1,96 @@
+mod support;
+
+use std::{collections::HashSet, path::Path};
+
+use ggrep::types::{QueryOptions, SearchResponse};
+use support::{FixtureSearch, indexed_fixture};
+
+const VENDORED: &str =
+   "pub fn checksum(bytes: &[u8]) -> u32 {\n   bytes.iter().map(|b| u32::from(*b)).sum()\n}\n";
+
+#[tokio::test]
+async fn dedup_content_drops_repeated_chunks() {
+   let fixture = indexed_fixture("dedup-content-test", &[
+      ("vendor/a/checksum.rs", VENDORED),
+      ("vendor/b/checksum.rs", VENDORED),
+      ("src/main.rs", "fn main() {\n   println!(\"{}\", checksum(b\"abc\"));\n}\n"),
+   ])
+   .await;
+
+   let run = async |dedup_content: bool| -> SearchResponse {
+      let options =
+         QueryOptions { recent_boost_days: Some(0), dedup_content, ..QueryOptions::default() };
+      fixture
+         .search("checksum bytes", &FixtureSearch { options, ..FixtureSearch::default() })
+         .await
+   };
+   // Distinct vendored files among the results.
+   let vendored = |response: &SearchResponse| {
+      response
+         .results
+         .iter()
+         .filter(|r| r.path.starts_with(Path::new("vendor")))
+         .map(|r| r.path.clone())
+         .collect::<HashSet<_>>()
+         .len()
+   };
+
+   let response = run(false).await;
+   assert_eq!(vendored(&response), 2, "{:?}", response.results);
+   assert!(
+      !response
+         .limits_hit
+         .iter()
+         .any(|hit| hit.code == "content_dedup")
+   );
+
+   let response = run(true).await;
+   assert_eq!(vendored(&response), 1, "{:?}", response.results);
+   let hit = response
+      .limits_hit
+      .iter()
+      .find(|hit| hit.code == "content_dedup")
+      .expect("content_dedup limit hit");
+   assert!(hit.observed.is_some_and(|observed| observed > hit.limit), "{hit:?}");
+   let stage = response
+      .funnel
+      .iter()
+      .find(|stage| stage.stage == "dedup_content")
+      .expect("dedup_content stage");
+   assert!(stage.removed.is_some_and(|removed| removed > 0), "{stage:?}");
+}
+
+const SHARED_SECTION: &str =
+   "## Retry policy\n\nRequests are retried three times with exponential backoff.\n";
+
+/// A doc with `SHARED_SECTION` between sections unique to `name`.
+fn doc_around_shared(name: &str) -> String {
+   format!("# {name}\n\nAbout the {name}.\n\n{SHARED_SECTION}\n## {name} errors\n\nSee the log.\n")
+}
+
+#[tokio::test]
+async fn dedup_content_ignores_surrounding_context() {
+   // The same section sits between different neighbours in each file, so the
+   // results differ only in their context lines.
+   let (client, server) = (doc_around_shared("Client"), doc_around_shared("Server"));
+   let fixture = indexed_fixture("dedup-content-context-test", &[
+      ("docs/client.md", client.as_str()),
+      ("docs/server.md", server.as_str()),
+   ])
+   .await;
+   let run = async |dedup_content: bool| -> usize {
+      let options =
+         QueryOptions { recent_boost_days: Some(0), dedup_content, ..QueryOptions::default() };
+      let response = fixture
+         .search("retry policy backoff", &FixtureSearch { options, ..FixtureSearch::default() })
+         .await;
+      response
+         .results
+         .iter()
+         .filter(|r| r.own_content().contains("exponential backoff"))
+         .count()
+   };
+
+   assert_eq!(run(false).await, 2);
+   assert_eq!(run(true).await, 1);
+}
@@ -49,7 +49,7 @@ impl FlakyEmbedder {
       if !dense.is_empty() {
          dense[0] = text.as_str().len() as f32;
       }
-      HybridEmbedding { dense, colbert: Vec::new(), colbert_scale: 1.0 }
+      HybridEmbedding { dense, colbert: Vec::new(), colbert_scale: 1.0, truncated: false }
    }
 }
 
@@ -69,17 +69,21 @@ impl Embedder for FlakyEmbedder {
       Ok(texts.iter().map(|t| self.embed_text(t)).collect())
    }
 
-   async fn encode_query(&self, text: &str) -> ggrep::Result<QueryEmbedding> {
+   async fn encode_query(&self, text: &str, _mode: SearchMode) -> ggrep::Result<QueryEmbedding> {
       let mut dense = vec![0.0; self.dense_dim];
       if !dense.is_empty() {
          dense[0] = text.len() as f32;
       }
-      Ok(QueryEmbedding { dense, colbert: Array2::zeros((0, 0)) })
+      Ok(QueryEmbedding { dense, colbert: Array2::zeros((0, 0)), truncated: None })
    }
 
    fn is_ready(&self) -> bool {
       true
    }
+
+   fn backend_name(&self) -> &'static str {
+      "flaky"
+   }
 }
 
 #[tokio::test]
@@ -106,7 +110,12 @@ async fn allow_degraded_publishes_with_errors() {
          root,
          None,
          false,
-         SyncOptions { allow_degraded: true, embed_max_retries: 0, embed_backoff_ms: 0 },
+         SyncOptions {
+            allow_degraded: true,
+            embed_max_retries: 0,
+            embed_backoff_ms: 0,
+            ..SyncOptions::default()
+         },
          &mut (),
       )
       .await
@@ -197,7 +206,12 @@ async fn embed_retry_recovers() {
          root,
          None,
          false,
-         SyncOptions { allow_degraded: false, embed_max_retries: 1, embed_backoff_ms: 0 },
+         SyncOptions {
+            allow_degraded: false,
+            embed_max_retries: 1,
+            embed_backoff_ms: 0,
+            ..SyncOptions::default()
+         },
          &mut (),
       )
       .await
@@ -0,0 +1,107 @@
+mod support;
+
+use std::{collections::HashSet, path::Path};
+
+use ggrep::types::{FilterStage, QueryOptions, SearchResponse, SpanMerge};
+use support::{FixtureSearch, indexed_fixture};
+
+const CHECKSUM: &str =
+   "pub fn checksum(bytes: &[u8]) -> u32 {\n   bytes.iter().map(|b| u32::from(*b)).sum()\n}\n";
+
+const MANY: &str = "pub fn checksum_a(bytes: &[u8]) -> u32 {\n   bytes.len() as u32\n}\n\npub fn \
+                    checksum_b(bytes: &[u8]) -> u32 {\n   bytes.len() as u32 + 1\n}\n\npub fn \
+                    checksum_c(bytes: &[u8]) -> u32 {\n   bytes.len() as u32 + 2\n}\n";
+
+fn stage<'a>(response: &'a SearchResponse, name: &str) -> &'a FilterStage {
+   response
+      .funnel
+      .iter()
+      .find(|stage| stage.stage == name)
+      .unwrap_or_else(|| panic!("{name} stage in {:?}", response.funnel))
+}
+
+fn count_in(response: &SearchResponse, path: &str) -> usize {
+   response
+      .results
+      .iter()
+      .filter(|r| r.path.starts_with(Path::new(path)))
+      .count()
+}
+
+#[tokio::test]
+async fn funnel_counts_each_filter_stage() {
+   let fixture = indexed_fixture("filter-funnel-test", &[
+      ("vendor/checksum.rs", CHECKSUM),
+      ("src/a.rs", CHECKSUM),
+      ("src/b.rs", CHECKSUM),
+      ("src/many.rs", MANY),
+   ])
+   .await;
+   let options = QueryOptions {
+      recent_boost_days: Some(0),
+      span_merge: SpanMerge::Off,
+      ..QueryOptions::default()
+   };
+
+   // Unfiltered and unlimited: every retrieved candidate is returned.
+   let all = fixture
+      .search("checksum bytes", &FixtureSearch {
+         limit: 50,
+         per_file: 50,
+         options: options.clone(),
+         ..FixtureSearch::default()
+      })
+      .await;
+   let names: Vec<_> = all.funnel.iter().map(|s| s.stage.as_str()).collect();
+   assert_eq!(names, ["retrieved", "visible", "limit"]);
+   let retrieved = all.results.len() as u64;
+   assert_eq!(stage(&all, "retrieved").remaining, retrieved);
+   assert!(count_in(&all, "src/many.rs") > 1, "{:?}", all.results);
+
+   let response = fixture
+      .search("checksum bytes", &FixtureSearch {
+         limit: 50,
+         per_file: 1,
+         options: QueryOptions {
+            excludes: vec!["vendor".to_string()],
+            dedup_content: true,
+            ..options
+         },
+         ..FixtureSearch::default()
+      })
+      .await;
+   let names: Vec<_> = response.funnel.iter().map(|s| s.stage.as_str()).collect();
+   assert_eq!(names, ["retrieved", "exclude", "visible", "dedup_content", "limit"]);
+
+   // Each stage's `removed` is the drop from the stage before it.
+   assert_eq!(response.funnel[0].removed, None);
+   for pair in response.funnel.windows(2) {
+      assert_eq!(pair[1].removed, Some(pair[0].remaining - pair[1].remaining), "{pair:?}");
+   }
+
+   let vendored = count_in(&all, "vendor") as u64;
+   let copies = count_in(&all, "src/a.rs") as u64;
+   assert_eq!(stage(&response, "retrieved").remaining, retrieved);
+   assert_eq!(stage(&response, "exclude").removed, Some(vendored));
+   assert_eq!(stage(&response, "visible").removed, Some(0));
+   // `src/a.rs` and `src/b.rs` are identical; one copy of each chunk stays.
+   assert_eq!(stage(&response, "dedup_content").removed, Some(copies));
+   // One result per file: `src/many.rs` loses all but its best chunk.
+   let limit = stage(&response, "limit");
+   assert_eq!(limit.filter.as_deref(), Some("50 per query, 1 per file"));
+   let many_dropped = count_in(&all, "src/many.rs") as u64 - 1;
+   assert!(limit.removed.is_some_and(|removed| removed >= many_dropped), "{limit:?}");
+   let paths: HashSet<_> = response.results.iter().map(|r| r.path.clone()).collect();
+   assert_eq!(limit.remaining, paths.len() as u64, "{:?}", response.results);
+   assert_eq!(paths.len(), response.results.len());
+   assert_eq!(count_in(&response, "vendor"), 0);
+   assert!(
+      response
+         .limits_hit
+         .iter()
+         .any(|hit| hit.code == "per_file_truncated"
+            && hit.path_key.as_deref() == Some("src/many.rs")),
+      "{:?}",
+      response.limits_hit
+   );
+}
@@ -0,0 +1,76 @@
+mod support;
+
+use std::path::Path;
+
+use ggrep::types::{QueryOptions, SearchResponse};
+use support::{FixtureSearch, indexed_fixture};
+
+#[tokio::test]
+async fn fts_failure_keeps_vector_hits_and_warns() {
+   let fixture = indexed_fixture("fts-query-test", &[
+      ("src/alpha.rs", "pub fn alpha() {\n   beta();\n}\n"),
+      ("src/beta.rs", "pub fn beta() -> u32 {\n   42\n}\n"),
+   ])
+   .await;
+
+   let run = async |query: &str, no_fts: bool| -> SearchResponse {
+      let options = QueryOptions { recent_boost_days: Some(0), no_fts, ..QueryOptions::default() };
+      fixture
+         .search(query, &FixtureSearch { per_file: 2, options, ..FixtureSearch::default() })
+         .await
+   };
+   let fts_failed = |response: &SearchResponse| {
+      response
+         .warnings
+         .iter()
+         .any(|w| w.code == "fts_query_failed")
+   };
+
+   // Query syntax is stripped before it reaches the full-text parser.
+   let response = run("alpha:beta AND", false).await;
+   assert!(!response.results.is_empty());
+   assert!(!fts_failed(&response), "{:?}", response.warnings);
+
+   // Without a full-text index the lexical branch errors; vector hits remain.
+   drop_fts_indices(&fixture.store.store_path(fixture.store_id)).await;
+   let response = run("alpha:beta", false).await;
+   assert!(!response.results.is_empty());
+   assert!(fts_failed(&response), "{:?}", response.warnings);
+
+   // Vector-only search never touches the full-text branch.
+   let response = run("alpha:beta", true).await;
+   assert!(!response.results.is_empty());
+   assert!(!fts_failed(&response), "{:?}", response.warnings);
+   let candidates = response.candidates.expect("candidate counts");
+   assert_eq!(candidates.fts.raw, 0);
+   assert!(candidates.fts.scores.is_none());
+   let scores = candidates.vector.scores.expect("vector score range");
+   assert_eq!(scores.count, candidates.vector.deduped);
+   assert!(scores.min <= scores.mean && scores.mean <= scores.max, "{scores:?}");
+
+   // The funnel starts at retrieval and ends at what was returned.
+   let first = response.funnel.first().expect("funnel");
+   assert_eq!(first.stage, "retrieved");
+   assert_eq!(first.removed, None);
+   let last = response.funnel.last().expect("funnel");
+   assert_eq!(last.remaining, response.results.len() as u64);
+}
+
+async fn drop_fts_indices(db_path: &Path) {
+   let conn = lancedb::connect(db_path.to_str().expect("utf-8 path"))
+      .execute()
+      .await
+      .expect("connect");
+   for table_name in conn.table_names().execute().await.expect("tables") {
+      let table = conn
+         .open_table(&table_name)
+         .execute()
+         .await
+         .expect("open table");
+      for index in table.list_indices().await.expect("indices") {
+         if index.columns.iter().any(|column| column == "text") {
+            table.drop_index(&index.name).await.expect("drop index");
+         }
+      }
+   }
+}
@@ -0,0 +1,78 @@
+mod support;
+
+use std::{path::Path, sync::Arc};
+
+use ggrep::{
+   chunker::Chunker,
+   config,
+   embed::Embedder,
+   file::LocalFileSystem,
+   identity,
+   snapshot::{SnapshotManager, SnapshotManifest},
+   store::LanceStore,
+   sync::SyncEngine,
+};
+use support::{TestEmbedder, set_temp_home};
+use tempfile::TempDir;
+
+const STORE_ID: &str = "grammar-missing";
+
+#[tokio::test]
+async fn offline_sync_records_grammar_fallbacks() {
+   let temp_home = TempDir::new().expect("temp home");
+   set_temp_home(&temp_home);
+   // Safe in test harness: with downloads off and an empty grammar cache,
+   // every language falls back to simple chunking.
+   unsafe {
+      std::env::set_var("GGREP_OFFLINE", "true");
+   }
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path();
+   std::fs::write(root.join("main.rs"), "fn main() {}\n").expect("seed file");
+   std::fs::write(root.join("lib.rs"), "pub fn lib() {}\n").expect("seed file");
+
+   config::init_for_root(root);
+   assert!(config::get().offline);
+
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder: Arc<dyn Embedder> = Arc::new(TestEmbedder::new(config::get().dense_dim));
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder, store.clone());
+
+   sync_engine
+      .initial_sync(STORE_ID, root, None, false, &mut ())
+      .await
+      .expect("files without a grammar still index");
+   let manifest = active_manifest(&store, root);
+   assert!(!manifest.degraded, "a missing grammar does not degrade the snapshot");
+   assert!(manifest.counts.chunks_indexed > 0);
+   assert_eq!(manifest.grammar_missing_languages(), ["rust"]);
+   assert_eq!(manifest.errors.len(), 1, "one entry per language: {:?}", manifest.errors);
+   assert!(manifest.errors[0].message.starts_with("2 file(s)"), "{}", manifest.errors[0].message);
+   assert_eq!(manifest.file_errors().count(), 0);
+
+   // A sync that chunks no Rust keeps the warning: the old chunks remain.
+   std::fs::write(root.join("notes.md"), "# Notes\n").expect("notes file");
+   sync_engine
+      .initial_sync(STORE_ID, root, None, false, &mut ())
+      .await
+      .expect("second sync");
+   let manifest = active_manifest(&store, root);
+   assert_eq!(manifest.grammar_missing_languages(), ["rust"]);
+}
+
+fn active_manifest(store: &Arc<LanceStore>, root: &Path) -> SnapshotManifest {
+   let fingerprints = identity::compute_fingerprints(root).expect("fingerprints");
+   let snapshot_manager = SnapshotManager::new(
+      store.clone(),
+      STORE_ID.to_string(),
+      fingerprints.config_fingerprint,
+      fingerprints.ignore_fingerprint,
+   );
+   let active = snapshot_manager
+      .read_active_snapshot_id()
+      .expect("read snapshot")
+      .expect("snapshot id");
+   SnapshotManifest::load(&snapshot_manager.manifest_path(&active)).expect("manifest")
+}
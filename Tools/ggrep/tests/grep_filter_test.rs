@@ -0,0 +1,63 @@
+mod support;
+
+use std::{collections::HashSet, path::PathBuf};
+
+use ggrep::types::{QueryOptions, SearchResponse};
+use support::{FixtureSearch, indexed_fixture};
+
+#[tokio::test]
+async fn grep_filters_before_paging() {
+   let fixture = indexed_fixture("grep-filter-test", &[
+      ("src/alpha.rs", "pub fn alpha_handler() -> u32 {\n   1\n}\n"),
+      ("src/beta.rs", "pub fn beta() -> u32 {\n   2\n}\n"),
+      ("src/gamma.rs", "pub fn gamma_handler() -> u32 {\n   3\n}\n"),
+      ("src/delta.rs", "pub fn delta() -> u32 {\n   4\n}\n"),
+   ])
+   .await;
+
+   let run = async |limit: usize, offset: usize| -> SearchResponse {
+      let options = QueryOptions {
+         recent_boost_days: Some(0),
+         offset,
+         grep: Some("_handler".to_string()),
+         ..QueryOptions::default()
+      };
+      fixture
+         .search("handler function", &FixtureSearch {
+            limit,
+            per_file: 1,
+            options,
+            ..FixtureSearch::default()
+         })
+         .await
+   };
+
+   let all = run(10, 0).await;
+   let paths: HashSet<PathBuf> = all.results.iter().map(|r| r.path.clone()).collect();
+   let expected: HashSet<PathBuf> = ["src/alpha.rs", "src/gamma.rs"]
+      .into_iter()
+      .map(PathBuf::from)
+      .collect();
+   assert_eq!(paths, expected, "{:?}", all.results);
+   let stage = all
+      .funnel
+      .iter()
+      .find(|stage| stage.stage == "grep")
+      .expect("grep stage");
+   assert!(stage.removed.is_some_and(|removed| removed > 0), "{stage:?}");
+
+   // One-result pages walk the matching results only: neither page is short
+   // and together they cover both matches.
+   let first = run(1, 0).await;
+   let second = run(1, 1).await;
+   assert_eq!(first.results.len(), 1, "{:?}", first.results);
+   assert_eq!(second.results.len(), 1, "{:?}", second.results);
+   let paged: HashSet<PathBuf> = first
+      .results
+      .iter()
+      .chain(&second.results)
+      .map(|r| r.path.clone())
+      .collect();
+   assert_eq!(paged, expected);
+   assert!(run(1, 2).await.results.is_empty());
+}
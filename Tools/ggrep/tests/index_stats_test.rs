@@ -0,0 +1,37 @@
+mod support;
+
+use ggrep::types::QueryOptions;
+use support::{FixtureSearch, indexed_fixture};
+
+#[tokio::test]
+async fn vector_index_lookup_only_when_asked() {
+   let fixture = indexed_fixture("index-stats-test", &[
+      ("src/alpha.rs", "pub fn alpha() {\n   beta();\n}\n"),
+      ("src/beta.rs", "pub fn beta() -> u32 {\n   42\n}\n"),
+   ])
+   .await;
+
+   let run = async |index_stats: bool| {
+      let options =
+         QueryOptions { recent_boost_days: Some(0), index_stats, ..QueryOptions::default() };
+      fixture
+         .search("alpha", &FixtureSearch { options, ..FixtureSearch::default() })
+         .await
+   };
+
+   let response = run(false).await;
+   assert!(!response.segments.is_empty());
+   assert!(response.segments.iter().all(|s| s.vector_indexed.is_none()), "{:?}", response.segments);
+
+   // The fixture is far below the row count that gets an IVF-PQ index.
+   let response = run(true).await;
+   assert!(!response.segments.is_empty());
+   assert!(
+      response
+         .segments
+         .iter()
+         .all(|s| s.vector_indexed == Some(false)),
+      "{:?}",
+      response.segments
+   );
+}
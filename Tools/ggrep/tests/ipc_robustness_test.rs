@@ -61,3 +61,26 @@ async fn test_ipc_rejects_oversized_payload() {
       .unwrap_err();
    assert!(err.to_string().contains("message too large"));
 }
+
+#[tokio::test]
+async fn test_ipc_send_with_limit_rejects_before_writing() {
+   let (mut client, _server) = tokio::io::duplex(64);
+   let mut buffer = ggrep::ipc::SocketBuffer::new();
+   let request = ggrep::ipc::Request::Gc { dry_run: true };
+
+   buffer
+      .send_with_limit(&mut client, &request, 1024)
+      .await
+      .unwrap();
+   let sent = buffer.sent_bytes();
+   assert!(sent > 0);
+
+   let err = buffer
+      .send_with_limit(&mut client, &request, sent - 1)
+      .await
+      .unwrap_err();
+   let message = err.to_string();
+   assert!(message.contains("message too large"));
+   assert!(message.contains(&format!("limit of {} bytes", sent - 1)));
+   assert_eq!(buffer.sent_bytes(), sent);
+}
@@ -0,0 +1,42 @@
+mod support;
+
+use std::{collections::BTreeSet, path::PathBuf};
+
+use ggrep::types::QueryOptions;
+use support::{FixtureSearch, indexed_fixture};
+
+#[tokio::test]
+async fn lang_filters_on_the_indexed_language() {
+   let fixture = indexed_fixture("lang-filter-test", &[
+      ("src/lib.rs", "pub fn build_target() -> u32 {\n   1\n}\n"),
+      ("src/LEGACY.RS", "pub fn build_legacy_target() -> u32 {\n   2\n}\n"),
+      ("Makefile", "build:\n\tcargo build --release\n"),
+      ("Dockerfile", "FROM rust:1\nRUN cargo build --release\n"),
+      ("scripts/build.py", "def build_target():\n    return 3\n"),
+   ])
+   .await;
+
+   let paths = async |languages: &[&str]| -> BTreeSet<PathBuf> {
+      let options = QueryOptions {
+         recent_boost_days: Some(0),
+         languages: languages.iter().map(ToString::to_string).collect(),
+         ..QueryOptions::default()
+      };
+      fixture
+         .search("build target", &FixtureSearch { limit: 20, options, ..FixtureSearch::default() })
+         .await
+         .results
+         .into_iter()
+         .map(|r| r.path)
+         .collect()
+   };
+
+   // Extensions match regardless of case, as they do during discovery.
+   let rust = paths(&["Rust"]).await;
+   assert_eq!(rust, BTreeSet::from([PathBuf::from("src/LEGACY.RS"), PathBuf::from("src/lib.rs")]));
+   // Extensionless files match through the language detected at index time.
+   assert_eq!(paths(&["make"]).await, BTreeSet::from([PathBuf::from("Makefile")]));
+   assert_eq!(paths(&["dockerfile"]).await, BTreeSet::from([PathBuf::from("Dockerfile")]));
+   let both = paths(&["python", "make"]).await;
+   assert_eq!(both, BTreeSet::from([PathBuf::from("Makefile"), PathBuf::from("scripts/build.py")]));
+}
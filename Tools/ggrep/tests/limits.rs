@@ -12,7 +12,7 @@ use ggrep::{
    ipc::{Request, Response},
    store::LanceStore,
    sync::SyncEngine,
-   types::SearchMode,
+   types::{QueryOptions, SearchMode},
    usock,
 };
 use support::set_temp_home;
@@ -55,7 +55,7 @@ async fn open_handle_budget() {
    let server_root = root.to_path_buf();
    let server_store = store_id.to_string();
    let _server = tokio::spawn(async move {
-      let _ = serve::execute(Some(server_root), Some(server_store), false).await;
+      let _ = serve::execute(Some(server_root), Some(server_store), false, false, None, None).await;
    });
 
    wait_for_daemon(store_id).await;
@@ -109,17 +109,16 @@ async fn run_query(store_id: String, fingerprint: String, query: String) -> Resp
    assert!(matches!(response, Response::Hello { .. }));
 
    buffer
-      .send(
-         &mut stream,
-         &Request::Search {
-            query,
-            limit: 5,
-            per_file: 5,
-            mode: SearchMode::Balanced,
-            path: None,
-            rerank: false,
-         },
-      )
+      .send(&mut stream, &Request::Search {
+         query,
+         limit: 5,
+         per_file: 5,
+         mode: SearchMode::Balanced,
+         path: None,
+         rerank: false,
+         snapshot_id: None,
+         options: QueryOptions::default(),
+      })
       .await
       .expect("search send");
    buffer
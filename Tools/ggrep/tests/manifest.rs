@@ -1,14 +1,64 @@
 use std::path::PathBuf;
 
-use ggrep::snapshot::SnapshotManifest;
+use ggrep::snapshot::{ChunkingMode, GRAMMAR_MISSING_CODE, SnapshotError, SnapshotManifest};
 
-#[test]
-fn manifest_fixture_parses() {
+fn load_fixture() -> SnapshotManifest {
    let path =
       PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/schemas/manifest.json");
    let data = std::fs::read_to_string(&path).expect("fixture readable");
-   let manifest: SnapshotManifest = serde_json::from_str(&data).expect("valid manifest");
+   serde_json::from_str(&data).expect("valid manifest")
+}
+
+#[test]
+fn manifest_fixture_parses() {
+   let manifest = load_fixture();
    assert_eq!(manifest.schema_version, 1);
    assert_eq!(manifest.chunk_row_schema_version, 1);
    assert!(!manifest.snapshot_id.is_empty());
 }
+
+#[test]
+fn manifest_chunking_mode_detects_mixed_segments() {
+   let mut manifest = load_fixture();
+   assert!(manifest.chunking_modes().is_empty());
+   assert_eq!(manifest.chunking_mode(), None);
+
+   manifest.segments[0].chunking = Some(ChunkingMode::Full);
+   let mut fast = manifest.segments[0].clone();
+   fast.table = "seg_fast".to_string();
+   fast.chunking = Some(ChunkingMode::Fast);
+   assert_eq!(manifest.chunking_mode(), Some(ChunkingMode::Full));
+   assert!(!manifest.mixed_chunking());
+
+   manifest.segments.push(fast);
+   assert_eq!(manifest.chunking_modes(), vec![ChunkingMode::Fast, ChunkingMode::Full]);
+   assert_eq!(manifest.chunking_mode(), None);
+   assert!(manifest.mixed_chunking());
+
+   let raw = serde_json::to_string(&manifest.segments[1]).expect("serialize segment");
+   assert!(raw.contains("\"chunking\":\"fast\""));
+}
+
+#[test]
+fn manifest_separates_grammar_warnings_from_file_errors() {
+   let mut manifest = load_fixture();
+   let error = |code: &str, path_key: &str, language: Option<&str>| SnapshotError {
+      code:     code.to_string(),
+      message:  String::new(),
+      path_key: path_key.to_string(),
+      language: language.map(str::to_string),
+   };
+   manifest.errors = vec![
+      error(GRAMMAR_MISSING_CODE, "src/main.rs", Some("rust")),
+      error("embed_failed", "src/big.rs", None),
+      error(GRAMMAR_MISSING_CODE, "app.py", Some("python")),
+      error(GRAMMAR_MISSING_CODE, "src/lib.rs", Some("rust")),
+   ];
+
+   assert_eq!(manifest.grammar_missing_languages(), ["python", "rust"]);
+   let file_errors: Vec<_> = manifest
+      .file_errors()
+      .map(|e| e.path_key.as_str())
+      .collect();
+   assert_eq!(file_errors, ["src/big.rs"]);
+}
@@ -0,0 +1,37 @@
+mod support;
+
+use std::path::{Path, PathBuf};
+
+use support::{FixtureSearch, indexed_fixture};
+
+#[tokio::test]
+async fn scope_does_not_match_sibling_name_prefix() {
+   let fixture = indexed_fixture("path-scope-test", &[
+      ("src/f/inner.rs", "pub fn alpha_inner() {}\n"),
+      ("src/foobar/sibling.rs", "pub fn alpha_sibling() {}\n"),
+      ("src/f.rs", "pub fn alpha_file() {}\n"),
+   ])
+   .await;
+
+   let paths_in = async |scope: &str| {
+      let search = FixtureSearch { path: Some(Path::new(scope)), ..FixtureSearch::default() };
+      let mut paths: Vec<PathBuf> = fixture
+         .search("alpha", &search)
+         .await
+         .results
+         .into_iter()
+         .map(|r| r.path)
+         .collect();
+      paths.sort();
+      paths.dedup();
+      paths
+   };
+
+   // A directory scope stops at the boundary: `src/f` is not `src/foobar`.
+   assert_eq!(paths_in("src/f").await, vec![PathBuf::from("src/f/inner.rs")]);
+   assert_eq!(paths_in("src/f/").await, vec![PathBuf::from("src/f/inner.rs")]);
+   // A partial directory name matches nothing.
+   assert!(paths_in("src/foo").await.is_empty());
+   // A file scope matches that file exactly.
+   assert_eq!(paths_in("src/f.rs").await, vec![PathBuf::from("src/f.rs")]);
+}
@@ -0,0 +1,125 @@
+mod support;
+
+use std::{
+   fs::File,
+   path::Path,
+   sync::Arc,
+   time::{Duration, SystemTime},
+};
+
+use ggrep::{
+   chunker::Chunker,
+   config,
+   embed::Embedder,
+   file::LocalFileSystem,
+   identity,
+   meta::MetaStore,
+   snapshot::{SnapshotManager, SnapshotManifest},
+   store::LanceStore,
+   sync::{SyncEngine, SyncOptions},
+};
+use support::{TestEmbedder, set_temp_home};
+use tempfile::TempDir;
+
+const STORE_ID: &str = "quarantine-test";
+
+#[tokio::test]
+async fn repeated_chunking_failures_quarantine_file() {
+   let temp_home = TempDir::new().expect("temp home");
+   set_temp_home(&temp_home);
+   // Safe in test harness: quarantine is off by default; set it before the
+   // config is first loaded.
+   unsafe {
+      std::env::set_var("GGREP_QUARANTINE_AFTER_FAILURES", "3");
+   }
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path();
+   std::fs::write(root.join("good.rs"), "fn good() {}\n").expect("good file");
+   // Chunks into more pieces than the cap below allows, on every sync.
+   let bad = format!("fn bad() {{\n{}}}\n", "   step();\n".repeat(400));
+   std::fs::write(root.join("bad.rs"), &bad).expect("bad file");
+
+   config::init_for_root(root);
+   let threshold = config::get().quarantine_after_failures;
+   assert_eq!(threshold, 3);
+
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder: Arc<dyn Embedder> = Arc::new(TestEmbedder::new(config::get().dense_dim));
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder, store.clone());
+   let options = SyncOptions { max_chunks_per_file: Some(3), ..SyncOptions::default() };
+   let sync = async || {
+      sync_engine
+         .initial_sync_with_options(STORE_ID, root, None, false, options.clone(), &mut ())
+         .await
+   };
+
+   for attempt in 1..threshold {
+      let err = sync().await.expect_err("bad.rs should fail the sync");
+      assert!(err.to_string().contains("chunk cap exceeded"), "{err}");
+      let meta = MetaStore::load(STORE_ID).expect("meta");
+      assert_eq!(meta.quarantined().count(), 0, "quarantined after {attempt} failure(s)");
+   }
+
+   sync()
+      .await
+      .expect("sync that reaches the threshold quarantines bad.rs");
+   let meta = MetaStore::load(STORE_ID).expect("meta");
+   let quarantined: Vec<_> = meta.quarantined().collect();
+   assert_eq!(quarantined.len(), 1);
+   assert!(quarantined[0].0.ends_with("bad.rs"));
+   assert_eq!(quarantined[0].1.failures, threshold);
+   assert_eq!(quarantined[0].1.code, "chunk_cap_exceeded");
+   assert!(!active_manifest(&store, root).degraded, "quarantine should keep the snapshot healthy");
+
+   // Touching the file without changing it keeps it quarantined.
+   File::options()
+      .write(true)
+      .open(root.join("bad.rs"))
+      .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(5)))
+      .expect("touch bad file");
+   sync().await.expect("later syncs skip the quarantined file");
+   let meta = MetaStore::load(STORE_ID).expect("meta");
+   assert_eq!(meta.quarantined().count(), 1, "an unchanged file stays quarantined");
+
+   std::fs::write(root.join("bad.rs"), "fn fixed() {}\n").expect("fix bad file");
+   sync().await.expect("changed file is retried");
+   let meta = MetaStore::load(STORE_ID).expect("meta");
+   assert_eq!(meta.quarantined().count(), 0, "indexing the fixed file lifts the quarantine");
+
+   // Over the cap again, it is quarantined again once it keeps failing.
+   std::fs::write(root.join("bad.rs"), &bad).expect("break bad file again");
+   for _ in 1..threshold {
+      sync().await.expect_err("bad.rs should fail the sync");
+   }
+   sync().await.expect("sync that reaches the threshold");
+   let meta = MetaStore::load(STORE_ID).expect("meta");
+   assert_eq!(meta.quarantined().count(), 1);
+
+   // A chunk-cap quarantine lasts only while the cap does: raising it
+   // retries the unchanged file, which now fits.
+   let raised = SyncOptions { max_chunks_per_file: Some(1000), ..SyncOptions::default() };
+   sync_engine
+      .initial_sync_with_options(STORE_ID, root, None, false, raised, &mut ())
+      .await
+      .expect("sync with a raised cap");
+   let meta = MetaStore::load(STORE_ID).expect("meta");
+   assert_eq!(meta.quarantined().count(), 0, "a raised cap lifts the quarantine");
+   assert!(meta.get_meta(Path::new("bad.rs")).is_some(), "bad.rs is indexed");
+}
+
+fn active_manifest(store: &Arc<LanceStore>, root: &Path) -> SnapshotManifest {
+   let fingerprints = identity::compute_fingerprints(root).expect("fingerprints");
+   let snapshot_manager = SnapshotManager::new(
+      store.clone(),
+      STORE_ID.to_string(),
+      fingerprints.config_fingerprint,
+      fingerprints.ignore_fingerprint,
+   );
+   let active = snapshot_manager
+      .read_active_snapshot_id()
+      .expect("read snapshot")
+      .expect("snapshot id");
+   SnapshotManifest::load(&snapshot_manager.manifest_path(&active)).expect("manifest")
+}
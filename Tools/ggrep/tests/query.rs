@@ -1,10 +1,35 @@
 use std::path::PathBuf;
 
 use ggrep::{
-   SearchLimitHit, SearchResult, SearchWarning, Str, sort_and_dedup_limits,
+   SearchLimitHit, SearchResult, SearchWarning, Str, dedup_results_by_chunk, sort_and_dedup_limits,
    sort_and_dedup_warnings, sort_results_deterministic,
 };
 
+fn segment_result(
+   segment: &str,
+   row_id: Option<&str>,
+   start_line: u32,
+   score: f32,
+) -> SearchResult {
+   SearchResult {
+      path: PathBuf::from("src/lib.rs"),
+      content: Str::default(),
+      score,
+      secondary_score: None,
+      row_id: row_id.map(str::to_string),
+      segment_table: Some(segment.to_string()),
+      start_line,
+      num_lines: 1,
+      end_line: None,
+      chunk_type: None,
+      symbol: None,
+      is_anchor: None,
+      language: None,
+      context_before: 0,
+      context_after: 0,
+   }
+}
+
 #[test]
 fn deterministic_ordering_tiebreak() {
    let mut results = vec![
@@ -17,8 +42,13 @@ fn deterministic_ordering_tiebreak() {
          segment_table:   None,
          start_line:      10,
          num_lines:       1,
+         end_line:        None,
          chunk_type:      None,
+         symbol:          None,
          is_anchor:       None,
+         language:        None,
+         context_before:  0,
+         context_after:   0,
       },
       SearchResult {
          path:            PathBuf::from("a.rs"),
@@ -29,8 +59,13 @@ fn deterministic_ordering_tiebreak() {
          segment_table:   None,
          start_line:      5,
          num_lines:       1,
+         end_line:        None,
          chunk_type:      None,
+         symbol:          None,
          is_anchor:       None,
+         language:        None,
+         context_before:  0,
+         context_after:   0,
       },
       SearchResult {
          path:            PathBuf::from("a.rs"),
@@ -41,8 +76,13 @@ fn deterministic_ordering_tiebreak() {
          segment_table:   None,
          start_line:      5,
          num_lines:       1,
+         end_line:        None,
          chunk_type:      None,
+         symbol:          None,
          is_anchor:       None,
+         language:        None,
+         context_before:  0,
+         context_after:   0,
       },
    ];
 
@@ -109,3 +149,35 @@ fn deterministic_limits_and_warnings_ordering() {
    assert_eq!(warnings[1].path_key.as_deref(), Some("a"));
    assert_eq!(warnings[2].code, "warn-b");
 }
+
+#[test]
+fn overlapping_segments_dedup_by_row_id() {
+   // The same chunk stored in a base segment and a post-compaction delta.
+   let mut results = vec![
+      segment_result("seg_base_0", Some("src/lib.rs:c1:0"), 10, 0.7),
+      segment_result("seg_base_0", Some("src/lib.rs:c2:1"), 20, 0.5),
+      segment_result("seg_delta_0", Some("src/lib.rs:c1:0"), 10, 0.9),
+   ];
+
+   dedup_results_by_chunk(&mut results);
+
+   assert_eq!(results.len(), 2);
+   assert_eq!(results[0].row_id.as_deref(), Some("src/lib.rs:c2:1"));
+   assert_eq!(results[1].row_id.as_deref(), Some("src/lib.rs:c1:0"));
+   assert_eq!(results[1].segment_table.as_deref(), Some("seg_delta_0"));
+}
+
+#[test]
+fn overlapping_segments_dedup_rows_without_row_id() {
+   let mut results = vec![
+      segment_result("seg_base_0", None, 10, 0.8),
+      segment_result("seg_delta_0", None, 10, 0.6),
+      segment_result("seg_delta_0", None, 30, 0.4),
+   ];
+
+   dedup_results_by_chunk(&mut results);
+
+   assert_eq!(results.len(), 2);
+   assert_eq!(results[0].segment_table.as_deref(), Some("seg_base_0"));
+   assert_eq!(results[1].start_line, 30);
+}
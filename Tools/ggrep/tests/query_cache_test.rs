@@ -0,0 +1,60 @@
+mod support;
+
+use std::{path::PathBuf, sync::Arc};
+
+use ggrep::{search::SearchEngine, types::SearchMode};
+use support::{FixtureSearch, TestEmbedder, indexed_fixture};
+
+#[tokio::test]
+async fn repeated_query_reuses_its_embedding() {
+   let fixture = indexed_fixture("query-cache-test", &[
+      ("src/retry.rs", "pub fn retry_with_backoff() {\n   sleep(delay);\n}\n"),
+      ("src/parse.rs", "pub fn parse_config() {\n   toml::from_str(raw);\n}\n"),
+   ])
+   .await;
+   let paths = async |search: &FixtureSearch<'_>| -> Vec<PathBuf> {
+      fixture
+         .search("retry backoff", search)
+         .await
+         .results
+         .into_iter()
+         .map(|r| r.path)
+         .collect()
+   };
+
+   let balanced = FixtureSearch::default();
+   let first = paths(&balanced).await;
+   assert_eq!(fixture.engine.query_cache_hits(), 0);
+   let second = paths(&balanced).await;
+   assert_eq!(fixture.engine.query_cache_hits(), 1, "the same query is a hit");
+   assert_eq!(first, second, "a cached embedding ranks the same");
+
+   // Modes embed differently, so another mode is its own entry.
+   let debug = FixtureSearch { mode: SearchMode::Debug, ..FixtureSearch::default() };
+   paths(&debug).await;
+   assert_eq!(fixture.engine.query_cache_hits(), 1);
+   paths(&debug).await;
+   assert_eq!(fixture.engine.query_cache_hits(), 2);
+
+   // A capacity of 0 turns the cache off.
+   let embedder = Arc::new(TestEmbedder::new(ggrep::config::get().dense_dim));
+   let uncached = SearchEngine::with_query_cache(fixture.store.clone(), embedder, 0);
+   for _ in 0..2 {
+      uncached
+         .search_with_options(
+            &fixture.snapshot,
+            fixture.store_id,
+            "retry backoff",
+            10,
+            5,
+            None,
+            false,
+            fixture.snapshot.include_anchors(),
+            SearchMode::Balanced,
+            &balanced.options,
+         )
+         .await
+         .expect("search");
+   }
+   assert_eq!(uncached.query_cache_hits(), 0);
+}
@@ -0,0 +1,111 @@
+mod support;
+
+use std::sync::Arc;
+
+use ggrep::{
+   chunker::Chunker,
+   config,
+   embed::{DummyEmbedder, Embedder},
+   file::LocalFileSystem,
+   identity,
+   meta::MetaStore,
+   search::SearchEngine,
+   snapshot::{SnapshotManager, SnapshotManifest, reembed_store},
+   store::LanceStore,
+   sync::SyncEngine,
+   types::SearchMode,
+};
+use support::set_temp_home;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn reembeds_visible_rows_after_model_change() {
+   let temp_home = TempDir::new().expect("temp home");
+   set_temp_home(&temp_home);
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path();
+   std::fs::write(root.join("keep.rs"), "pub fn keep() {}\n").expect("seed file");
+   std::fs::write(root.join("drop.rs"), "pub fn drop_me() {}\n").expect("seed file");
+
+   config::init_for_root(root);
+
+   let store_id = "reembed-test";
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder: Arc<dyn Embedder> = Arc::new(DummyEmbedder::new(config::get().dense_dim));
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder.clone(), store.clone());
+   sync_engine
+      .initial_sync(store_id, root, None, false, &mut ())
+      .await
+      .expect("initial sync");
+   std::fs::remove_file(root.join("drop.rs")).expect("delete file");
+   sync_engine
+      .initial_sync(store_id, root, None, false, &mut ())
+      .await
+      .expect("sync delete");
+
+   let fingerprints = identity::compute_fingerprints(root).expect("fingerprints");
+   let reembed = async || {
+      reembed_store(
+         store.clone(),
+         embedder.clone(),
+         store_id,
+         store_id,
+         &fingerprints.config_fingerprint,
+         &fingerprints.ignore_fingerprint,
+      )
+      .await
+   };
+
+   // Same model: nothing to re-embed.
+   assert!(reembed().await.is_err());
+
+   // Record an older model, as if the config had switched since indexing.
+   let meta_path = config::meta_dir().join(format!("{store_id}.json"));
+   let mut meta: serde_json::Value =
+      serde_json::from_str(&std::fs::read_to_string(&meta_path).expect("read meta"))
+         .expect("parse meta");
+   meta["model"]["dense_model"] = serde_json::json!("previous-model");
+   std::fs::write(&meta_path, meta.to_string()).expect("write meta");
+   assert!(MetaStore::load(store_id).expect("meta").model_mismatch());
+
+   let result = reembed().await.expect("reembed");
+   assert_eq!(result.files, 1);
+   assert!(result.rows > 0);
+   assert!(!MetaStore::load(store_id).expect("meta").model_mismatch());
+
+   let snapshot_manager = SnapshotManager::new(
+      store.clone(),
+      store_id.to_string(),
+      fingerprints.config_fingerprint.clone(),
+      fingerprints.ignore_fingerprint.clone(),
+   );
+   let manifest = SnapshotManifest::load(&snapshot_manager.manifest_path(&result.new_snapshot_id))
+      .expect("manifest");
+   assert_eq!(manifest.parent_snapshot_id.as_deref(), Some(result.base_snapshot_id.as_str()));
+   assert_eq!(manifest.segments.len(), 1);
+   assert!(manifest.tombstones.is_empty());
+
+   let search_engine = SearchEngine::new(store.clone(), embedder.clone());
+   let snapshot_view = snapshot_manager
+      .open_snapshot_view()
+      .await
+      .expect("snapshot view");
+   let results = search_engine
+      .search_with_mode(
+         &snapshot_view,
+         store_id,
+         "keep",
+         5,
+         5,
+         None,
+         false,
+         config::get().fast_mode,
+         SearchMode::Balanced,
+      )
+      .await
+      .expect("search keep");
+   assert!(!results.results.is_empty());
+   assert!(results.results.iter().all(|r| r.path.ends_with("keep.rs")));
+}
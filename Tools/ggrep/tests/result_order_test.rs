@@ -0,0 +1,136 @@
+mod support;
+
+use ggrep::{
+   cmd::search,
+   types::{QueryOptions, SearchMode, SpanMerge, cmp_results_deterministic},
+};
+use support::{FixtureSearch, indexed_fixture};
+
+#[tokio::test]
+async fn json_results_are_in_rank_order() {
+   // Long enough that the chunker splits it into overlapping chunks.
+   let long = format!("pub fn long() {{\n{}}}\n", "   alpha();\n".repeat(150));
+   let files: Vec<(&str, &str)> = SEED_FILES
+      .iter()
+      .copied()
+      .chain([("src/long.rs", long.as_str())])
+      .collect();
+   let fixture = indexed_fixture("result-order-test", &files).await;
+
+   let run = async |limit: usize, mode: SearchMode, span_merge: SpanMerge, offset: usize| {
+      let options =
+         QueryOptions { recent_boost_days: Some(0), span_merge, offset, ..QueryOptions::default() };
+      fixture
+         .search("alpha", &FixtureSearch { limit, per_file: 2, mode, path: None, options })
+         .await
+         .results
+   };
+
+   for mode in [
+      SearchMode::Balanced,
+      SearchMode::Discovery,
+      SearchMode::Implementation,
+      SearchMode::Planning,
+      SearchMode::Debug,
+      SearchMode::Tests,
+      SearchMode::Paths,
+   ] {
+      let results = run(8, mode, SpanMerge::default(), 0).await;
+      assert!(results.len() > 1, "{mode:?}: expected several results");
+      for pair in results.windows(2) {
+         assert_ne!(
+            cmp_results_deterministic(&pair[0], &pair[1]),
+            std::cmp::Ordering::Greater,
+            "{mode:?}: results out of rank order"
+         );
+      }
+
+      let json = search::results_json(results, &fixture.root).expect("json");
+      let scores: Vec<f64> = json
+         .as_array()
+         .expect("results array")
+         .iter()
+         .map(|r| r["score"].as_f64().expect("score"))
+         .collect();
+      assert!(scores.windows(2).all(|s| s[0] >= s[1]), "{mode:?}: {scores:?}");
+      let norms: Vec<f64> = json
+         .as_array()
+         .expect("results array")
+         .iter()
+         .map(|r| r["norm_score"].as_f64().expect("norm_score"))
+         .collect();
+      assert!(norms.windows(2).all(|n| n[0] >= n[1]), "{mode:?}: {norms:?}");
+      assert!(norms.iter().all(|n| (0.0..=1.0).contains(n)), "{mode:?}: {norms:?}");
+   }
+
+   // A shorter page is a prefix of a longer one: paging never reorders.
+   let keys = |results: Vec<ggrep::types::SearchResult>| {
+      results
+         .into_iter()
+         .map(|r| (r.path, r.start_line))
+         .collect::<Vec<_>>()
+   };
+   let page = keys(run(3, SearchMode::Balanced, SpanMerge::Off, 0).await);
+   let full = keys(run(6, SearchMode::Balanced, SpanMerge::Off, 0).await);
+   assert_eq!(page.len(), 3);
+   assert_eq!(page[..], full[..3]);
+
+   // An offset page continues where the first page stopped.
+   let next = keys(run(3, SearchMode::Balanced, SpanMerge::Off, 3).await);
+   assert_eq!(next[..], full[3..]);
+
+   // With the default span merge, the overlapping chunks of `src/long.rs`
+   // merge before paging, so offset pages still tile the merged ranking.
+   let merged = fixture
+      .search("alpha", &FixtureSearch { limit: 6, per_file: 2, ..FixtureSearch::default() })
+      .await;
+   let merge_stage = merged
+      .funnel
+      .iter()
+      .find(|stage| stage.stage == "merge_spans");
+   assert!(
+      merge_stage
+         .and_then(|stage| stage.removed)
+         .is_some_and(|removed| removed > 0),
+      "{:?}",
+      merged.funnel
+   );
+   let full = keys(merged.results);
+   assert_eq!(full.len(), 6);
+   let page = keys(run(3, SearchMode::Balanced, SpanMerge::default(), 0).await);
+   let next = keys(run(3, SearchMode::Balanced, SpanMerge::default(), 3).await);
+   assert_eq!(page[..], full[..3]);
+   assert_eq!(next[..], full[3..]);
+
+   // Pages end at `max_query_results`: one starting past it is empty and says
+   // why instead of quietly returning an earlier page.
+   let window = ggrep::config::get().max_query_results;
+   let options = QueryOptions { offset: window + 10, ..FixtureSearch::default().options };
+   let past = fixture
+      .search("alpha", &FixtureSearch { limit: 10, options, ..FixtureSearch::default() })
+      .await;
+   assert!(past.results.is_empty());
+   let hit = past
+      .limits_hit
+      .iter()
+      .find(|hit| hit.code == "offset_exceeds_max_results")
+      .expect("offset limit hit");
+   assert_eq!(hit.limit, window as u64);
+   assert_eq!(hit.observed, Some((window + 20) as u64));
+   let first = fixture.search("alpha", &FixtureSearch::default()).await;
+   assert!(
+      first
+         .limits_hit
+         .iter()
+         .all(|hit| hit.code != "offset_exceeds_max_results")
+   );
+}
+
+const SEED_FILES: &[(&str, &str)] = &[
+   ("src/a.rs", "pub fn a() {\n   alpha();\n}\n"),
+   ("src/b.rs", "pub fn b() {\n   alpha();\n   alpha();\n}\n"),
+   ("src/c.rs", "pub fn c() {\n   alpha();\n   alpha();\n   alpha();\n}\n"),
+   ("src/d.rs", "pub fn d() {\n   alpha();\n   alpha();\n   alpha();\n   alpha();\n}\n"),
+   ("docs/alpha.md", "# Alpha\n\nHow alpha works.\n"),
+   ("docs/plan.md", "# Plan\n\nalpha rollout\n"),
+];
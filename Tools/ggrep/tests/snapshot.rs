@@ -1,6 +1,6 @@
 mod support;
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use ggrep::{
    chunker::Chunker,
@@ -8,9 +8,10 @@ use ggrep::{
    embed::Embedder,
    file::LocalFileSystem,
    identity,
-   snapshot::{SnapshotManager, SnapshotManifest},
+   meta::MetaStore,
+   snapshot::{ChunkingMode, SnapshotManager, SnapshotManifest, StagingTxn},
    store::LanceStore,
-   sync::SyncEngine,
+   sync::{SyncEngine, SyncOptions},
 };
 use support::{TestEmbedder, set_temp_home};
 use tempfile::TempDir;
@@ -159,3 +160,216 @@ async fn checksum_verification() {
    let message = format!("{err}");
    assert!(message.contains("checksum mismatch"));
 }
+
+#[tokio::test]
+async fn snapshot_view_by_id_pins_older_snapshot() {
+   let temp_home = TempDir::new().expect("temp home");
+   set_temp_home(&temp_home);
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path();
+   std::fs::write(root.join("main.rs"), "fn main() {}\n").expect("seed file");
+
+   config::init_for_root(root);
+
+   let store_id = "snapshot-by-id";
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder: Arc<dyn Embedder> = Arc::new(TestEmbedder::new(config::get().dense_dim));
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder, store.clone());
+
+   sync_engine
+      .initial_sync(store_id, root, None, false, &mut ())
+      .await
+      .expect("initial sync");
+
+   let fingerprints = identity::compute_fingerprints(root).expect("fingerprints");
+   let snapshot_manager = SnapshotManager::new(
+      store.clone(),
+      store_id.to_string(),
+      fingerprints.config_fingerprint,
+      fingerprints.ignore_fingerprint,
+   );
+   let first_id = snapshot_manager
+      .read_active_snapshot_id()
+      .expect("active snapshot id")
+      .expect("active snapshot");
+
+   std::fs::write(root.join("lib.rs"), "pub fn lib() {}\n").expect("second file");
+   sync_engine
+      .initial_sync(store_id, root, None, false, &mut ())
+      .await
+      .expect("second sync");
+   let active_id = snapshot_manager
+      .read_active_snapshot_id()
+      .expect("active snapshot id")
+      .expect("active snapshot");
+   assert_ne!(active_id, first_id);
+
+   let view = snapshot_manager
+      .open_snapshot_view_by_id(&first_id)
+      .await
+      .expect("pinned view");
+   assert_eq!(view.snapshot_id, first_id);
+
+   let err = snapshot_manager
+      .open_snapshot_view_by_id(&Uuid::new_v4().to_string())
+      .await
+      .expect_err("unknown snapshot rejected");
+   assert!(format!("{err}").contains("no longer exists"));
+}
+
+#[tokio::test]
+async fn implicit_sync_keeps_active_chunking_mode() {
+   let temp_home = TempDir::new().expect("temp home");
+   set_temp_home(&temp_home);
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path();
+   std::fs::write(root.join("main.rs"), "fn main() {}\n").expect("seed file");
+
+   config::init_for_root(root);
+   assert!(!config::get().fast_mode);
+
+   let store_id = "implicit-chunking";
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder: Arc<dyn Embedder> = Arc::new(TestEmbedder::new(config::get().dense_dim));
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder, store.clone());
+
+   let fast = SyncOptions { chunking: Some(ChunkingMode::Fast), ..SyncOptions::default() };
+   sync_engine
+      .initial_sync_with_options(store_id, root, None, false, fast, &mut ())
+      .await
+      .expect("fast sync");
+
+   // A sync without `--fast`/`--full`, e.g. from the watcher, follows the
+   // store rather than `fast_mode` from config.
+   std::fs::write(root.join("lib.rs"), "pub fn lib() {}\n").expect("second file");
+   sync_engine
+      .initial_sync(store_id, root, None, false, &mut ())
+      .await
+      .expect("implicit sync");
+
+   let fingerprints = identity::compute_fingerprints(root).expect("fingerprints");
+   let snapshot_manager = SnapshotManager::new(
+      store.clone(),
+      store_id.to_string(),
+      fingerprints.config_fingerprint,
+      fingerprints.ignore_fingerprint,
+   );
+   let active = snapshot_manager
+      .read_active_snapshot_id()
+      .expect("active snapshot id")
+      .expect("active snapshot");
+   let manifest =
+      SnapshotManifest::load(&snapshot_manager.manifest_path(&active)).expect("manifest");
+   assert_eq!(manifest.chunking_modes(), vec![ChunkingMode::Fast]);
+}
+
+#[tokio::test]
+async fn staging_resumes_only_with_matching_chunking() {
+   let temp_home = TempDir::new().expect("temp home");
+   set_temp_home(&temp_home);
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path();
+   std::fs::write(root.join("main.rs"), "fn main() {}\n").expect("seed file");
+
+   config::init_for_root(root);
+
+   let store_id = "staging-resume-chunking";
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder: Arc<dyn Embedder> = Arc::new(TestEmbedder::new(config::get().dense_dim));
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder, store.clone());
+
+   sync_engine
+      .initial_sync(store_id, root, None, false, &mut ())
+      .await
+      .expect("initial sync");
+
+   let fingerprints = identity::compute_fingerprints(root).expect("fingerprints");
+   let snapshot_manager = SnapshotManager::new(
+      store.clone(),
+      store_id.to_string(),
+      fingerprints.config_fingerprint.clone(),
+      fingerprints.ignore_fingerprint,
+   );
+   let active = snapshot_manager
+      .read_active_snapshot_id()
+      .expect("active snapshot id")
+      .expect("active snapshot");
+   let manifest =
+      SnapshotManifest::load(&snapshot_manager.manifest_path(&active)).expect("manifest");
+
+   // Reuse a table that exists so only the chunking settings decide.
+   let txn = StagingTxn {
+      staging_txn_id:      format!("staging-{}", Uuid::new_v4()),
+      snapshot_id:         Uuid::new_v4().to_string(),
+      segment_table:       manifest.segments[0].table.clone(),
+      parent_snapshot_id:  Some(active),
+      config_fingerprint:  fingerprints.config_fingerprint,
+      created_at:          chrono::Utc::now().to_rfc3339(),
+      chunking:            Some(ChunkingMode::Full),
+      max_chunks_per_file: Some(64),
+   };
+   snapshot_manager
+      .create_staging_txn(&txn)
+      .expect("create staging txn");
+
+   let resumed = snapshot_manager
+      .find_resumable_staging(ChunkingMode::Full, 64)
+      .await
+      .expect("find staging");
+   assert_eq!(resumed, Some(txn));
+   let other_mode = snapshot_manager
+      .find_resumable_staging(ChunkingMode::Fast, 64)
+      .await
+      .expect("find staging");
+   assert_eq!(other_mode, None);
+   let other_cap = snapshot_manager
+      .find_resumable_staging(ChunkingMode::Full, 32)
+      .await
+      .expect("find staging");
+   assert_eq!(other_cap, None);
+}
+
+#[tokio::test]
+async fn raised_chunk_cap_indexes_previously_skipped_file() {
+   let temp_home = TempDir::new().expect("temp home");
+   set_temp_home(&temp_home);
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path();
+   std::fs::write(root.join("main.rs"), "fn main() {}\n").expect("seed file");
+   let big = format!("fn big() {{\n{}}}\n", "   step();\n".repeat(400));
+   std::fs::write(root.join("big.rs"), big).expect("big file");
+
+   config::init_for_root(root);
+
+   let store_id = "raised-chunk-cap";
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder: Arc<dyn Embedder> = Arc::new(TestEmbedder::new(config::get().dense_dim));
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder, store.clone());
+   let sync = async |cap: usize| {
+      let options = SyncOptions {
+         allow_degraded: true,
+         max_chunks_per_file: Some(cap),
+         ..SyncOptions::default()
+      };
+      sync_engine
+         .initial_sync_with_options(store_id, root, None, false, options, &mut ())
+         .await
+         .expect("sync");
+      MetaStore::load(store_id).expect("meta")
+   };
+
+   let meta = sync(3).await;
+   assert!(meta.get_meta(Path::new("main.rs")).is_some());
+   assert!(meta.get_meta(Path::new("big.rs")).is_none(), "over the cap: skipped");
+
+   let meta = sync(1000).await;
+   assert!(meta.get_meta(Path::new("big.rs")).is_some(), "a raised cap indexes it");
+}
@@ -1,10 +1,25 @@
 #![allow(dead_code)]
 
+use std::{
+   path::{Path, PathBuf},
+   sync::Arc,
+};
+
 use ggrep::{
    Str,
-   embed::{Embedder, HybridEmbedding, QueryEmbedding},
+   chunker::Chunker,
+   config,
+   embed::{Embedder, EmbedderDescription, HybridEmbedding, QueryEmbedding},
+   file::LocalFileSystem,
+   identity,
+   search::SearchEngine,
+   snapshot::{SnapshotManager, SnapshotView},
+   store::LanceStore,
+   sync::SyncEngine,
+   types::{QueryOptions, SearchMode, SearchResponse},
 };
 use ndarray::Array2;
+use tempfile::TempDir;
 
 pub struct TestEmbedder {
    dense_dim: usize,
@@ -25,22 +40,41 @@ impl Embedder for TestEmbedder {
          if !dense.is_empty() {
             dense[0] = text.as_str().len() as f32;
          }
-         out.push(HybridEmbedding { dense, colbert: Vec::new(), colbert_scale: 1.0 });
+         out.push(HybridEmbedding {
+            dense,
+            colbert: Vec::new(),
+            colbert_scale: 1.0,
+            truncated: false,
+         });
       }
       Ok(out)
    }
 
-   async fn encode_query(&self, text: &str) -> ggrep::Result<QueryEmbedding> {
+   async fn encode_query(&self, text: &str, _mode: SearchMode) -> ggrep::Result<QueryEmbedding> {
       let mut dense = vec![0.0; self.dense_dim];
       if !dense.is_empty() {
          dense[0] = text.len() as f32;
       }
-      Ok(QueryEmbedding { dense, colbert: Array2::zeros((0, 0)) })
+      Ok(QueryEmbedding { dense, colbert: Array2::zeros((0, 0)), truncated: None })
    }
 
    fn is_ready(&self) -> bool {
       true
    }
+
+   fn backend_name(&self) -> &'static str {
+      "test"
+   }
+
+   fn describe(&self) -> EmbedderDescription {
+      EmbedderDescription {
+         backend:       self.backend_name(),
+         dense_model:   None,
+         colbert_model: None,
+         dense_dim:     self.dense_dim,
+         ready:         true,
+      }
+   }
 }
 
 pub fn set_temp_home(dir: &tempfile::TempDir) {
@@ -49,3 +83,117 @@ pub fn set_temp_home(dir: &tempfile::TempDir) {
       std::env::set_var("HOME", dir.path());
    }
 }
+
+/// A temp repo, indexed once, with an engine over its active snapshot.
+pub struct Fixture {
+   pub root:               PathBuf,
+   pub store_id:           &'static str,
+   pub config_fingerprint: String,
+   pub store:              Arc<LanceStore>,
+   pub snapshot:           SnapshotView,
+   pub engine:             SearchEngine,
+   _home:                  TempDir,
+   _repo:                  TempDir,
+}
+
+/// One engine query against a [`Fixture`]; the default searches the whole
+/// repo in balanced mode with the recency boost off.
+pub struct FixtureSearch<'a> {
+   pub limit:    usize,
+   pub per_file: usize,
+   pub mode:     SearchMode,
+   pub path:     Option<&'a Path>,
+   pub options:  QueryOptions,
+}
+
+impl Default for FixtureSearch<'_> {
+   fn default() -> Self {
+      Self {
+         limit:    10,
+         per_file: 5,
+         mode:     SearchMode::Balanced,
+         path:     None,
+         options:  QueryOptions { recent_boost_days: Some(0), ..QueryOptions::default() },
+      }
+   }
+}
+
+/// Writes `files` (repo-relative path, contents) into a fresh repo under a
+/// fresh `HOME` and indexes it with [`TestEmbedder`].
+pub async fn indexed_fixture(store_id: &'static str, files: &[(&str, &str)]) -> Fixture {
+   indexed_fixture_with(store_id, files, |dim| Arc::new(TestEmbedder::new(dim))).await
+}
+
+/// Like [`indexed_fixture`], embedding with the embedder `make` builds for
+/// the configured dense dimension.
+pub async fn indexed_fixture_with(
+   store_id: &'static str,
+   files: &[(&str, &str)],
+   make: impl FnOnce(usize) -> Arc<dyn Embedder>,
+) -> Fixture {
+   let home = TempDir::new().expect("temp home");
+   set_temp_home(&home);
+
+   let repo = TempDir::new().expect("temp repo");
+   let root = repo.path().canonicalize().expect("canonical root");
+   for (name, body) in files {
+      let path = root.join(name);
+      std::fs::create_dir_all(path.parent().expect("parent")).expect("seed dir");
+      std::fs::write(path, body).expect("seed file");
+   }
+   config::init_for_root(&root);
+
+   let store = Arc::new(LanceStore::new().expect("store"));
+   let embedder = make(config::get().dense_dim);
+   let sync_engine =
+      SyncEngine::new(LocalFileSystem::new(), Chunker::default(), embedder.clone(), store.clone());
+   sync_engine
+      .initial_sync(store_id, &root, None, false, &mut ())
+      .await
+      .expect("initial sync");
+
+   let fingerprints = identity::compute_fingerprints(&root).expect("fingerprints");
+   let snapshot = SnapshotManager::new(
+      store.clone(),
+      store_id.to_string(),
+      fingerprints.config_fingerprint.clone(),
+      fingerprints.ignore_fingerprint,
+   )
+   .open_snapshot_view()
+   .await
+   .expect("snapshot view");
+   let engine = SearchEngine::new(store.clone(), embedder);
+
+   Fixture {
+      root,
+      store_id,
+      config_fingerprint: fingerprints.config_fingerprint,
+      store,
+      snapshot,
+      engine,
+      _home: home,
+      _repo: repo,
+   }
+}
+
+impl Fixture {
+   /// Runs `query` through the engine against the indexed snapshot.
+   pub async fn search(&self, query: &str, search: &FixtureSearch<'_>) -> SearchResponse {
+      self
+         .engine
+         .search_with_options(
+            &self.snapshot,
+            self.store_id,
+            query,
+            search.limit,
+            search.per_file,
+            search.path,
+            false,
+            self.snapshot.include_anchors(),
+            search.mode,
+            &search.options,
+         )
+         .await
+         .expect("search")
+   }
+}
@@ -0,0 +1,71 @@
+mod support;
+
+use ggrep::cmd::verify_store;
+use serde_json::Value;
+use support::indexed_fixture;
+
+#[tokio::test]
+async fn verify_store_flags_a_missing_segment() {
+   let fixture = indexed_fixture("verify-store-test", &[
+      ("src/main.rs", "fn main() {\n   run();\n}\n"),
+      ("src/run.rs", "pub fn run() {}\n"),
+   ])
+   .await;
+   let store_id = || Some(fixture.store_id.to_string());
+   let report = async || -> Value {
+      let report = verify_store::verify(Some(fixture.root.clone()), store_id())
+         .await
+         .expect("verify");
+      serde_json::to_value(report).expect("report json")
+   };
+   let error_codes = |report: &Value| -> Vec<String> {
+      report["findings"]
+         .as_array()
+         .expect("findings")
+         .iter()
+         .filter(|f| f["severity"] == "error")
+         .map(|f| f["code"].as_str().expect("code").to_string())
+         .collect()
+   };
+
+   let healthy = report().await;
+   assert_eq!(healthy["schema_version"], 1);
+   assert_eq!(healthy["store_id"], fixture.store_id);
+   assert_eq!(healthy["ok"], true, "{healthy}");
+   assert_eq!(healthy["errors"], 0);
+   assert!(error_codes(&healthy).is_empty());
+   verify_store::execute(Some(fixture.root.clone()), true, store_id())
+      .await
+      .expect("a healthy store exits 0");
+
+   // Drop the snapshot's segment out from under its manifest.
+   let table = &fixture.snapshot.segment_tables()[0];
+   fixture
+      .store
+      .drop_table(fixture.store_id, table)
+      .await
+      .expect("drop segment");
+
+   let corrupted = report().await;
+   assert_eq!(corrupted["ok"], false);
+   assert!(corrupted["errors"].as_u64().expect("errors") >= 1);
+   assert!(error_codes(&corrupted).contains(&"segment_missing".to_string()), "{corrupted}");
+   let missing = corrupted["findings"]
+      .as_array()
+      .expect("findings")
+      .iter()
+      .find(|f| f["code"] == "segment_missing")
+      .expect("segment_missing finding");
+   assert_eq!(missing["remedy"], "repair");
+   assert!(
+      missing["message"]
+         .as_str()
+         .expect("message")
+         .contains(table.as_str())
+   );
+
+   let err = verify_store::execute(Some(fixture.root.clone()), true, store_id())
+      .await
+      .expect_err("a corrupted store fails");
+   assert_eq!(err.exit_code(), 1);
+}